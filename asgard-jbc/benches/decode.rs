@@ -0,0 +1,136 @@
+//! Compares decoding into the owned [`ClassFile`] with the borrowed
+//! [`ClassFileRef`].
+//!
+//! The class files are read from the directory given by `ASGARD_BENCH_CLASSES`,
+//! e.g. an extracted JDK, and are generated otherwise.
+
+use std::{io::Cursor, path::Path};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use asgard_jbc::{
+    class::{borrowed::ClassFileRef, builder::ClassBuilder, ClassFile, MethodAccessFlags},
+    code::Instruction,
+    Decode, Encode, Result,
+};
+
+fn class_files() -> Vec<Vec<u8>> {
+    match std::env::var_os("ASGARD_BENCH_CLASSES") {
+        Some(path) => {
+            let mut class_files = Vec::new();
+            read_dir(Path::new(&path), &mut class_files);
+            class_files
+        }
+        None => (0..64).map(|index| generate(index).unwrap()).collect(),
+    }
+}
+
+fn read_dir(path: &Path, class_files: &mut Vec<Vec<u8>>) {
+    for entry in std::fs::read_dir(path).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            read_dir(&path, class_files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "class")
+        {
+            class_files.push(std::fs::read(path).unwrap());
+        }
+    }
+}
+
+fn generate(index: usize) -> Result<Vec<u8>> {
+    let name = format!("bench/Class{index}");
+    let mut class = ClassBuilder::new(name.as_str());
+    for method in 0..32 {
+        class.method(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            format!("method{method}"),
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            &[],
+            |code, constant_pool| {
+                let value = constant_pool.string(&format!("{name}.method{method}"))?;
+                let concat = constant_pool.method_ref(
+                    "java/lang/String",
+                    "concat",
+                    "(Ljava/lang/String;)Ljava/lang/String;",
+                )?;
+                code.instruction(Instruction::Aload0)
+                    .instruction(Instruction::LdcW(value))
+                    .instruction(Instruction::Invokevirtual(concat))
+                    .instruction(Instruction::Areturn);
+                Ok(())
+            },
+        )?;
+    }
+    let mut output = Cursor::new(Vec::new());
+    class.build()?.encode(&mut output)?;
+    Ok(output.into_inner())
+}
+
+fn decode(c: &mut Criterion) {
+    let class_files = class_files();
+    let bytes = class_files.iter().map(Vec::len).sum::<usize>();
+
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            class_files
+                .iter()
+                .map(|class_file| ClassFile::decode(&mut class_file.as_slice()).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            class_files
+                .iter()
+                .map(|class_file| ClassFileRef::decode(&mut class_file.as_slice()).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+/// Decodes the classes and resolves the names and descriptors of their methods,
+/// as done when scanning a class path for bindings.
+fn scan(c: &mut Criterion) {
+    let class_files = class_files();
+    let bytes = class_files.iter().map(Vec::len).sum::<usize>();
+
+    let mut group = c.benchmark_group("scan");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            let mut methods = 0;
+            for class_file in &class_files {
+                let class_file = ClassFile::decode(&mut class_file.as_slice()).unwrap();
+                for method in &class_file.methods {
+                    method.name(&class_file.constant_pool).unwrap();
+                    method.descriptor(&class_file.constant_pool).unwrap();
+                    methods += 1;
+                }
+            }
+            methods
+        })
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            let mut methods = 0;
+            for class_file in &class_files {
+                let class_file = ClassFileRef::decode(&mut class_file.as_slice()).unwrap();
+                for method in &class_file.methods {
+                    method.name(&class_file.constant_pool).unwrap();
+                    method.descriptor(&class_file.constant_pool).unwrap();
+                    methods += 1;
+                }
+            }
+            methods
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decode, scan);
+criterion_main!(benches);
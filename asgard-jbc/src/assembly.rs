@@ -0,0 +1,2731 @@
+//! A textual assembly format for class files, which can be written by hand
+//! and parsed back into class files.
+//!
+//! The format is line-based, each line holding a directive, a label or an
+//! instruction. A `{` at the end of a line opens a block, which is closed by a
+//! line holding only `}`, and `//` starts a comment. Names and strings are
+//! written as is, or quoted with Java escapes if they contain whitespace or
+//! could be taken for something else.
+//!
+//! ```text
+//! .version 52 0
+//! .class public super Hello
+//! .super java/lang/Object
+//!
+//! .method public static main ([Ljava/lang/String;)V {
+//!   .code stack 2 locals 1 {
+//!       getstatic Field java/lang/System out Ljava/io/PrintStream;
+//!       ldc String "Hello, world!"
+//!       invokevirtual Method java/io/PrintStream println (Ljava/lang/String;)V
+//!     L0:
+//!       return
+//!     .linenumbertable {
+//!       L0 3
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Constant pool entries are written by their kind and contents, e.g.
+//! `Class java/lang/Object`, and allocated in order of their first use, with
+//! the constants of `ldc` first as it can only refer to the first 255
+//! entries. They can also be referred to by index, e.g. `#7`, as defined by
+//! lines like `.const #7 = Class #8`. The [`Printer`] only prints the
+//! constant pool when requested, which reproduces class files byte for byte,
+//! otherwise just the order of the constant pool is lost.
+//!
+//! Attributes are written as directives named after the attribute in
+//! lowercase, e.g. `.sourcefile Hello.java`. Unknown attributes, and those
+//! which would not be encoded the same, are written as hexadecimal bytes by
+//! `.attribute`, whose constant pool indices stay intact only together with
+//! the constant pool.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{Display, Write},
+    io::Cursor,
+};
+
+use bitflags::Flags;
+
+use crate::{
+    class::{
+        annotation::{
+            Annotation, ElementValue, ElementValuePair, LocalvarTarget, TargetInfo, TypeAnnotation,
+            TypePath, TypePathEntry, TypePathKind,
+        },
+        attribute::{
+            self, AnnotationDefaultAttribute, AnnotationsAttribute, Attribute, BootstrapMethod,
+            BootstrapMethodsAttribute, CodeAttribute, ConstantValueAttribute,
+            EnclosingMethodAttribute, ExceptionTableEntry, ExceptionsAttribute, Exports,
+            InnerClass, InnerClassesAttribute, LineNumber, LineNumberTableAttribute, LocalVariable,
+            LocalVariableTableAttribute, LocalVariableType, LocalVariableTypeTableAttribute,
+            MethodParameter, MethodParametersAttribute, ModuleAttribute, ModuleMainClassAttribute,
+            ModulePackagesAttribute, NestHostAttribute, NestMembersAttribute, Opens,
+            ParameterAnnotationsAttribute, PermittedSubclassesAttribute, Provides, RecordAttribute,
+            RecordComponentInfo, Requires, SignatureAttribute, SourceDebugExtensionAttribute,
+            SourceFileAttribute, StackMapFrame, StackMapTableAttribute, TypeAnnotationsAttribute,
+            VerificationTypeInfo,
+        },
+        builder::ConstantPoolBuilder,
+        AttributeInfo, ClassFile, ConstantLookup, ConstantPool, ConstantPoolInfo, FieldInfo,
+        MethodInfo,
+    },
+    code::{self, ArrayType, Instruction},
+    disassembler::{
+        frame_offsets, quote, quote_java, reference_kind_name, referenced_offsets,
+        target_type_name, Labels,
+    },
+    mutf8::JavaString,
+    Error, Result,
+};
+
+/// Prints a class file as assembly, see [`Printer`].
+pub fn print(class_file: &ClassFile) -> Result<String> {
+    Printer::new().print(class_file)
+}
+
+/// Parses a class file from its assembly.
+pub fn parse(input: &str) -> Result<ClassFile> {
+    let nodes = lines(input)?;
+    let mut parser = Parser {
+        constant_pool: ConstantPoolBuilder::try_from(constant_pool(&nodes)?)?,
+    };
+    parser.allocate_ldc(&nodes)?;
+    parser.class_file(&nodes, input.lines().count())
+}
+
+/// Prints class files as assembly.
+#[derive(Debug, Clone, Default)]
+pub struct Printer {
+    constant_pool: bool,
+}
+
+impl Printer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prints the constant pool and refers to its entries by index, which
+    /// keeps the class file byte for byte when parsed.
+    pub fn constant_pool(&mut self, constant_pool: bool) -> &mut Self {
+        self.constant_pool = constant_pool;
+        self
+    }
+
+    pub fn print(&self, class_file: &ClassFile) -> Result<String> {
+        let mut names = HashMap::new();
+        if self.constant_pool {
+            for (index, info) in class_file.constant_pool.iter() {
+                if let ConstantPoolInfo::Utf8(value) = info {
+                    if let Some(value) = value.as_str() {
+                        names.entry(value).or_insert(index);
+                    }
+                }
+            }
+        }
+        let mut output = Output {
+            constants: Constants {
+                constant_pool: &class_file.constant_pool,
+                indices: self.constant_pool,
+            },
+            names,
+            output: String::new(),
+        };
+        output.class_file(class_file)?;
+        Ok(output.output)
+    }
+}
+
+/// Refers to constant pool entries, either by index or by their contents.
+#[derive(Clone, Copy)]
+struct Constants<'a> {
+    constant_pool: &'a ConstantPool,
+    indices: bool,
+}
+
+impl Constants<'_> {
+    fn utf8(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        Ok(java_word(self.constant_pool.java_string(index)?))
+    }
+
+    fn class(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        Ok(word(self.constant_pool.class_name(index)?))
+    }
+
+    fn module(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        Ok(word(self.constant_pool.module_name(index)?))
+    }
+
+    fn package(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        Ok(word(self.constant_pool.package_name(index)?))
+    }
+
+    fn name_and_type(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        let (name, descriptor) = self.constant_pool.name_and_type(index)?;
+        Ok(format!("{} {}", word(name), word(descriptor)))
+    }
+
+    fn integer(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        Ok(self.constant_pool.integer(index)?.to_string())
+    }
+
+    fn long(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        Ok(self.constant_pool.long(index)?.to_string())
+    }
+
+    fn float(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        Ok(float(self.constant_pool.float(index)?))
+    }
+
+    fn double(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        Ok(double(self.constant_pool.double(index)?))
+    }
+
+    fn constant(&self, index: u16) -> Result<String> {
+        if self.indices {
+            return Ok(format!("#{index}"));
+        }
+        let info = self
+            .constant_pool
+            .get(index)
+            .ok_or(Error::InvalidConstantPoolIndex(index))?;
+        self.info(info)
+    }
+
+    fn info(&self, info: &ConstantPoolInfo) -> Result<String> {
+        Ok(match info {
+            ConstantPoolInfo::Utf8(value) => format!("Utf8 {}", java_word(value)),
+            ConstantPoolInfo::Integer(value) => format!("Integer {value}"),
+            ConstantPoolInfo::Float(value) => format!("Float {}", float(*value)),
+            ConstantPoolInfo::Long(value) => format!("Long {value}"),
+            ConstantPoolInfo::Double(value) => format!("Double {}", double(*value)),
+            ConstantPoolInfo::Class { name_index } => format!("Class {}", self.utf8(*name_index)?),
+            ConstantPoolInfo::String { string_index } => {
+                format!("String {}", self.utf8(*string_index)?)
+            }
+            ConstantPoolInfo::Fieldref {
+                class_index,
+                name_and_type_index,
+            } => format!(
+                "Field {} {}",
+                self.class(*class_index)?,
+                self.name_and_type(*name_and_type_index)?
+            ),
+            ConstantPoolInfo::Methodref {
+                class_index,
+                name_and_type_index,
+            } => format!(
+                "Method {} {}",
+                self.class(*class_index)?,
+                self.name_and_type(*name_and_type_index)?
+            ),
+            ConstantPoolInfo::InterfaceMethodref {
+                class_index,
+                name_and_type_index,
+            } => format!(
+                "InterfaceMethod {} {}",
+                self.class(*class_index)?,
+                self.name_and_type(*name_and_type_index)?
+            ),
+            ConstantPoolInfo::NameAndType {
+                name_index,
+                descriptor_index,
+            } => format!(
+                "NameAndType {} {}",
+                self.utf8(*name_index)?,
+                self.utf8(*descriptor_index)?
+            ),
+            ConstantPoolInfo::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => format!(
+                "MethodHandle {} {}",
+                reference_kind_name(*reference_kind),
+                self.constant(*reference_index)?
+            ),
+            ConstantPoolInfo::MethodType { descriptor_index } => {
+                format!("MethodType {}", self.utf8(*descriptor_index)?)
+            }
+            ConstantPoolInfo::Dynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => format!(
+                "Dynamic {bootstrap_method_attr_index} {}",
+                self.name_and_type(*name_and_type_index)?
+            ),
+            ConstantPoolInfo::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => format!(
+                "InvokeDynamic {bootstrap_method_attr_index} {}",
+                self.name_and_type(*name_and_type_index)?
+            ),
+            ConstantPoolInfo::Module { name_index } => {
+                format!("Module {}", self.utf8(*name_index)?)
+            }
+            ConstantPoolInfo::Package { name_index } => {
+                format!("Package {}", self.utf8(*name_index)?)
+            }
+        })
+    }
+}
+
+struct Output<'a> {
+    constants: Constants<'a>,
+    /// The first `Utf8` entry of each value, which is the one attribute names
+    /// are allocated to when parsed.
+    names: HashMap<&'a str, u16>,
+    output: String,
+}
+
+impl Output<'_> {
+    fn line(&mut self, indent: usize, line: impl Display) {
+        // writing to a string never fails
+        let _ = writeln!(self.output, "{:indent$}{line}", "", indent = indent * 2);
+    }
+
+    /// Prints a line followed by a block of lines, if there are any.
+    fn block(&mut self, indent: usize, line: impl Display, lines: Vec<String>) {
+        if lines.is_empty() {
+            self.line(indent, line);
+            return;
+        }
+        self.line(indent, format!("{line} {{"));
+        for line in lines {
+            self.line(indent + 1, line);
+        }
+        self.line(indent, "}");
+    }
+
+    fn class_file(&mut self, class_file: &ClassFile) -> Result<()> {
+        let constants = self.constants;
+        self.line(
+            0,
+            format!(
+                ".version {} {}",
+                class_file.major_version, class_file.minor_version
+            ),
+        );
+        let mut header = vec![".class".to_owned()];
+        header.extend(flags(&class_file.access_flags));
+        header.push(constants.class(class_file.this_class)?);
+        self.line(0, header.join(" "));
+        if class_file.super_class != 0 {
+            self.line(
+                0,
+                format!(".super {}", constants.class(class_file.super_class)?),
+            );
+        }
+        for interface in &class_file.interfaces {
+            self.line(0, format!(".implements {}", constants.class(*interface)?));
+        }
+
+        if constants.indices {
+            self.line(0, "");
+            for (index, info) in constants.constant_pool.iter() {
+                self.line(0, format!(".const #{index} = {}", constants.info(info)?));
+            }
+        }
+
+        for field in &class_file.fields {
+            self.line(0, "");
+            let mut header = vec![".field".to_owned()];
+            header.extend(flags(&field.access_flags));
+            header.push(constants.utf8(field.name_index)?);
+            header.push(constants.utf8(field.descriptor_index)?);
+            self.member(0, header.join(" "), &field.attributes)?;
+        }
+        for method in &class_file.methods {
+            self.line(0, "");
+            let mut header = vec![".method".to_owned()];
+            header.extend(flags(&method.access_flags));
+            header.push(constants.utf8(method.name_index)?);
+            header.push(constants.utf8(method.descriptor_index)?);
+            self.member(0, header.join(" "), &method.attributes)?;
+        }
+        if !class_file.attributes.is_empty() {
+            self.line(0, "");
+            self.attributes(0, &class_file.attributes, &Labels::default())?;
+        }
+        Ok(())
+    }
+
+    /// Prints the declaration of a field, method or record component,
+    /// followed by its attributes.
+    fn member(
+        &mut self,
+        indent: usize,
+        header: String,
+        attributes: &[AttributeInfo],
+    ) -> Result<()> {
+        if attributes.is_empty() {
+            self.line(indent, header);
+            return Ok(());
+        }
+        self.line(indent, format!("{header} {{"));
+        self.attributes(indent + 1, attributes, &Labels::default())?;
+        self.line(indent, "}");
+        Ok(())
+    }
+
+    fn attributes(
+        &mut self,
+        indent: usize,
+        attributes: &[AttributeInfo],
+        labels: &Labels,
+    ) -> Result<()> {
+        for info in attributes {
+            let printed = match self.decode(info) {
+                Some(attribute) => self.attribute(indent, &attribute, labels)?,
+                None => false,
+            };
+            if !printed {
+                self.raw_attribute(indent, info)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes a standard attribute, unless it would be encoded differently
+    /// when parsed.
+    fn decode(&self, info: &AttributeInfo) -> Option<Attribute> {
+        let name = self
+            .constants
+            .constant_pool
+            .utf8(info.attribute_name_index)
+            .ok()?;
+        if self.constants.indices && self.names.get(name) != Some(&info.attribute_name_index) {
+            return None;
+        }
+        let attribute = attribute::decode_standard(name, &info.info).ok()??;
+        (attribute.info().ok()? == info.info).then_some(attribute)
+    }
+
+    fn raw_attribute(&mut self, indent: usize, info: &AttributeInfo) -> Result<()> {
+        let name = self.constants.utf8(info.attribute_name_index)?;
+        let lines = info
+            .info
+            .chunks(32)
+            .map(|chunk| chunk.iter().map(|byte| format!("{byte:02x}")).collect())
+            .collect();
+        self.block(indent, format!(".attribute {name}"), lines);
+        Ok(())
+    }
+
+    /// Prints a standard attribute as directive, returns `false` if it has to
+    /// be printed as raw attribute instead.
+    fn attribute(&mut self, indent: usize, attribute: &Attribute, labels: &Labels) -> Result<bool> {
+        let constants = self.constants;
+        let directive = format!(".{}", attribute.name().to_lowercase());
+        match attribute {
+            Attribute::ConstantValue(attribute) => {
+                let value = constants.constant(attribute.constantvalue_index)?;
+                self.line(indent, format!("{directive} {value}"));
+            }
+            Attribute::Code(attribute) => return self.code(indent, attribute),
+            Attribute::StackMapTable(attribute) => {
+                let mut lines = vec![];
+                for (offset, frame) in frame_offsets(attribute) {
+                    let label = labels.name(offset);
+                    lines.push(match frame {
+                        StackMapFrame::Same { .. } => format!("{label} same"),
+                        StackMapFrame::SameLocals1StackItem { stack, .. } => format!(
+                            "{label} same_locals_1_stack_item {}",
+                            self.verification_type(stack, labels)?
+                        ),
+                        StackMapFrame::SameLocals1StackItemExtended { stack, .. } => format!(
+                            "{label} same_locals_1_stack_item_extended {}",
+                            self.verification_type(stack, labels)?
+                        ),
+                        StackMapFrame::Chop { k, .. } => format!("{label} chop {k}"),
+                        StackMapFrame::SameExtended { .. } => format!("{label} same_extended"),
+                        StackMapFrame::Append { locals, .. } => format!(
+                            "{label} append {}",
+                            self.verification_types(locals, labels)?
+                        ),
+                        StackMapFrame::Full { locals, stack, .. } => format!(
+                            "{label} full locals {} stack {}",
+                            self.verification_types(locals, labels)?,
+                            self.verification_types(stack, labels)?
+                        )
+                        .trim_end()
+                        .replace("  ", " "),
+                    });
+                }
+                self.block(indent, directive, lines);
+            }
+            Attribute::Exceptions(ExceptionsAttribute(classes))
+            | Attribute::NestMembers(NestMembersAttribute(classes))
+            | Attribute::PermittedSubclasses(PermittedSubclassesAttribute(classes)) => {
+                let lines = classes
+                    .iter()
+                    .map(|class| constants.class(*class))
+                    .collect::<Result<_>>()?;
+                self.block(indent, directive, lines);
+            }
+            Attribute::InnerClasses(attribute) => {
+                let mut lines = vec![];
+                for inner_class in &attribute.0 {
+                    let mut line = vec![constants.class(inner_class.inner_class_info_index)?];
+                    if inner_class.outer_class_info_index != 0 {
+                        line.push("outer".to_owned());
+                        line.push(constants.class(inner_class.outer_class_info_index)?);
+                    }
+                    if inner_class.inner_name_index != 0 {
+                        line.push("name".to_owned());
+                        line.push(constants.utf8(inner_class.inner_name_index)?);
+                    }
+                    line.extend(flags(&inner_class.inner_class_access_flags));
+                    lines.push(line.join(" "));
+                }
+                self.block(indent, directive, lines);
+            }
+            Attribute::EnclosingMethod(attribute) => {
+                let mut line = format!("{directive} {}", constants.class(attribute.class_index)?);
+                if attribute.method_index != 0 {
+                    line.push(' ');
+                    line.push_str(&constants.name_and_type(attribute.method_index)?);
+                }
+                self.line(indent, line);
+            }
+            Attribute::Synthetic | Attribute::Deprecated => self.line(indent, directive),
+            Attribute::Signature(attribute) => {
+                let signature = constants.utf8(attribute.signature_index)?;
+                self.line(indent, format!("{directive} {signature}"));
+            }
+            Attribute::SourceFile(attribute) => {
+                let source_file = constants.utf8(attribute.sourcefile_index)?;
+                self.line(indent, format!("{directive} {source_file}"));
+            }
+            Attribute::SourceDebugExtension(attribute) => {
+                let Ok(debug_extension) = std::str::from_utf8(&attribute.0) else {
+                    return Ok(false);
+                };
+                self.line(indent, format!("{directive} {}", quote(debug_extension)));
+            }
+            Attribute::LineNumberTable(attribute) => {
+                let lines = attribute
+                    .0
+                    .iter()
+                    .map(|line| {
+                        format!("{} {}", labels.name(line.start_pc as u32), line.line_number)
+                    })
+                    .collect();
+                self.block(indent, directive, lines);
+            }
+            Attribute::LocalVariableTable(attribute) => {
+                let mut lines = vec![];
+                for local_variable in &attribute.0 {
+                    let start = local_variable.start_pc as u32;
+                    lines.push(format!(
+                        "{} {} {} {} {}",
+                        local_variable.index,
+                        constants.utf8(local_variable.name_index)?,
+                        constants.utf8(local_variable.descriptor_index)?,
+                        labels.name(start),
+                        labels.name(start + local_variable.length as u32)
+                    ));
+                }
+                self.block(indent, directive, lines);
+            }
+            Attribute::LocalVariableTypeTable(attribute) => {
+                let mut lines = vec![];
+                for local_variable in &attribute.0 {
+                    let start = local_variable.start_pc as u32;
+                    lines.push(format!(
+                        "{} {} {} {} {}",
+                        local_variable.index,
+                        constants.utf8(local_variable.name_index)?,
+                        constants.utf8(local_variable.signature_index)?,
+                        labels.name(start),
+                        labels.name(start + local_variable.length as u32)
+                    ));
+                }
+                self.block(indent, directive, lines);
+            }
+            Attribute::RuntimeVisibleAnnotations(attribute)
+            | Attribute::RuntimeInvisibleAnnotations(attribute) => {
+                if attribute.0.is_empty() {
+                    self.line(indent, directive);
+                } else {
+                    self.line(indent, format!("{directive} {{"));
+                    for annotation in &attribute.0 {
+                        self.annotation(indent + 1, String::new(), annotation)?;
+                    }
+                    self.line(indent, "}");
+                }
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(attribute)
+            | Attribute::RuntimeInvisibleParameterAnnotations(attribute) => {
+                if attribute.0.is_empty() {
+                    self.line(indent, directive);
+                } else {
+                    self.line(indent, format!("{directive} {{"));
+                    for annotations in &attribute.0 {
+                        if annotations.is_empty() {
+                            self.line(indent + 1, "parameter");
+                            continue;
+                        }
+                        self.line(indent + 1, "parameter {");
+                        for annotation in annotations {
+                            self.annotation(indent + 2, String::new(), annotation)?;
+                        }
+                        self.line(indent + 1, "}");
+                    }
+                    self.line(indent, "}");
+                }
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(attribute)
+            | Attribute::RuntimeInvisibleTypeAnnotations(attribute) => {
+                // type paths only have a syntax for type argument indices of
+                // type arguments
+                let stray_index = attribute.0.iter().any(|type_annotation| {
+                    type_annotation.target_path.0.iter().any(|entry| {
+                        entry.type_path_kind != TypePathKind::TypeArgument
+                            && entry.type_argument_index != 0
+                    })
+                });
+                if stray_index {
+                    return Ok(false);
+                }
+                if attribute.0.is_empty() {
+                    self.line(indent, directive);
+                } else {
+                    self.line(indent, format!("{directive} {{"));
+                    for type_annotation in &attribute.0 {
+                        self.type_annotation(indent + 1, type_annotation, labels)?;
+                    }
+                    self.line(indent, "}");
+                }
+            }
+            Attribute::AnnotationDefault(attribute) => {
+                self.element_value(indent, format!("{directive} "), &attribute.0)?;
+            }
+            Attribute::BootstrapMethods(attribute) => {
+                if attribute.0.is_empty() {
+                    self.line(indent, directive);
+                } else {
+                    self.line(indent, format!("{directive} {{"));
+                    for bootstrap_method in &attribute.0 {
+                        let arguments = bootstrap_method
+                            .bootstrap_arguments
+                            .iter()
+                            .map(|argument| constants.constant(*argument))
+                            .collect::<Result<_>>()?;
+                        let method = constants.constant(bootstrap_method.bootstrap_method_ref)?;
+                        self.block(indent + 1, format!("method {method}"), arguments);
+                    }
+                    self.line(indent, "}");
+                }
+            }
+            Attribute::MethodParameters(attribute) => {
+                let mut lines = vec![];
+                for parameter in &attribute.0 {
+                    let mut line = vec!["parameter".to_owned()];
+                    if parameter.name_index != 0 {
+                        line.push("name".to_owned());
+                        line.push(constants.utf8(parameter.name_index)?);
+                    }
+                    line.extend(flags(&parameter.access_flags));
+                    lines.push(line.join(" "));
+                }
+                self.block(indent, directive, lines);
+            }
+            Attribute::Module(attribute) => {
+                let mut header = vec![directive, constants.module(attribute.module_name_index)?];
+                header.extend(flags(&attribute.module_flags));
+                if attribute.module_version_index != 0 {
+                    header.push("version".to_owned());
+                    header.push(constants.utf8(attribute.module_version_index)?);
+                }
+                let mut lines = vec![];
+                for requires in &attribute.requires {
+                    let mut line = vec![
+                        "requires".to_owned(),
+                        constants.module(requires.requires_index)?,
+                    ];
+                    line.extend(flags(&requires.requires_flags));
+                    if requires.requires_version_index != 0 {
+                        line.push("version".to_owned());
+                        line.push(constants.utf8(requires.requires_version_index)?);
+                    }
+                    lines.push(line.join(" "));
+                }
+                for exports in &attribute.exports {
+                    let mut line = vec![
+                        "exports".to_owned(),
+                        constants.package(exports.exports_index)?,
+                    ];
+                    line.extend(flags(&exports.exports_flags));
+                    if !exports.exports_to_index.is_empty() {
+                        line.push("to".to_owned());
+                        for module in &exports.exports_to_index {
+                            line.push(constants.module(*module)?);
+                        }
+                    }
+                    lines.push(line.join(" "));
+                }
+                for opens in &attribute.opens {
+                    let mut line = vec!["opens".to_owned(), constants.package(opens.opens_index)?];
+                    line.extend(flags(&opens.opens_flags));
+                    if !opens.opens_to_index.is_empty() {
+                        line.push("to".to_owned());
+                        for module in &opens.opens_to_index {
+                            line.push(constants.module(*module)?);
+                        }
+                    }
+                    lines.push(line.join(" "));
+                }
+                for uses in &attribute.uses_index {
+                    lines.push(format!("uses {}", constants.class(*uses)?));
+                }
+                for provides in &attribute.provides {
+                    let mut line = vec![
+                        "provides".to_owned(),
+                        constants.class(provides.provides_index)?,
+                        "with".to_owned(),
+                    ];
+                    for class in &provides.provides_with_index {
+                        line.push(constants.class(*class)?);
+                    }
+                    lines.push(line.join(" "));
+                }
+                self.block(indent, header.join(" "), lines);
+            }
+            Attribute::ModulePackages(attribute) => {
+                let lines = attribute
+                    .0
+                    .iter()
+                    .map(|package| constants.package(*package))
+                    .collect::<Result<_>>()?;
+                self.block(indent, directive, lines);
+            }
+            Attribute::ModuleMainClass(attribute) => {
+                let main_class = constants.class(attribute.main_class_index)?;
+                self.line(indent, format!("{directive} {main_class}"));
+            }
+            Attribute::NestHost(attribute) => {
+                let host_class = constants.class(attribute.host_class_index)?;
+                self.line(indent, format!("{directive} {host_class}"));
+            }
+            Attribute::Record(attribute) => {
+                if attribute.0.is_empty() {
+                    self.line(indent, directive);
+                } else {
+                    self.line(indent, format!("{directive} {{"));
+                    for component in &attribute.0 {
+                        let header = format!(
+                            "component {} {}",
+                            constants.utf8(component.name_index)?,
+                            constants.utf8(component.descriptor_index)?
+                        );
+                        self.member(indent + 1, header, &component.attributes)?;
+                    }
+                    self.line(indent, "}");
+                }
+            }
+            Attribute::Custom(_) | Attribute::Unknown { .. } => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Prints the code of a method, returns `false` if the instructions would
+    /// be encoded differently when parsed.
+    fn code(&mut self, indent: usize, code: &CodeAttribute) -> Result<bool> {
+        let Ok(instructions) = code.instructions() else {
+            return Ok(false);
+        };
+        let length = code.code.len() as u32;
+        let mut encoded = Cursor::new(Vec::new());
+        for (offset, instruction) in &instructions {
+            instruction.encode(&mut encoded, *offset)?;
+        }
+        if encoded.into_inner() != code.code {
+            return Ok(false);
+        }
+
+        // only instruction boundaries can be labeled, other offsets are
+        // written as is
+        let attributes = code
+            .attributes
+            .iter()
+            .filter_map(|attribute| self.decode(attribute))
+            .collect::<Vec<_>>();
+        let boundaries = instructions
+            .iter()
+            .map(|(offset, _)| *offset)
+            .chain([length])
+            .collect::<BTreeSet<_>>();
+        let labels = Labels(
+            referenced_offsets(&instructions, code, &attributes, true)
+                .into_iter()
+                .filter(|offset| boundaries.contains(offset))
+                .enumerate()
+                .map(|(index, offset)| (offset, index))
+                .collect(),
+        );
+
+        self.line(
+            indent,
+            format!(
+                ".code stack {} locals {} {{",
+                code.max_stack, code.max_locals
+            ),
+        );
+        let mut placed = labels.0.iter().peekable();
+        for (offset, instruction) in &instructions {
+            while let Some((_, index)) = placed.next_if(|(label, _)| *label <= offset) {
+                self.line(indent + 1, format!("L{index}:"));
+            }
+            let (line, lines) = self.instruction(*offset, instruction, &labels)?;
+            if instruction.opcode() == 170 || instruction.opcode() == 171 {
+                self.line(indent + 2, format!("{line} {{"));
+                for line in lines {
+                    self.line(indent + 3, line);
+                }
+                self.line(indent + 2, "}");
+            } else {
+                self.line(indent + 2, line);
+            }
+        }
+        for (_, index) in placed {
+            self.line(indent + 1, format!("L{index}:"));
+        }
+        for entry in &code.exception_table {
+            let catch_type = if entry.catch_type == 0 {
+                "any".to_owned()
+            } else {
+                self.constants.class(entry.catch_type)?
+            };
+            self.line(
+                indent + 1,
+                format!(
+                    ".catch {} {} {} {catch_type}",
+                    labels.name(entry.start_pc as u32),
+                    labels.name(entry.end_pc as u32),
+                    labels.name(entry.handler_pc as u32)
+                ),
+            );
+        }
+        self.attributes(indent + 1, &code.attributes, &labels)?;
+        self.line(indent, "}");
+        Ok(true)
+    }
+
+    /// Returns the line of an instruction, and the lines of the block of
+    /// switches.
+    fn instruction(
+        &self,
+        offset: u32,
+        instruction: &Instruction,
+        labels: &Labels,
+    ) -> Result<(String, Vec<String>)> {
+        let constants = self.constants;
+        let mnemonic = instruction.mnemonic();
+        let target = |delta: i32| labels.name(offset.wrapping_add_signed(delta));
+        let line = match instruction {
+            Instruction::Bipush(value) => format!("{mnemonic} {value}"),
+            Instruction::Sipush(value) => format!("{mnemonic} {value}"),
+            Instruction::Ldc(index) => {
+                let index = *index as u16;
+                self.commented(format!("{mnemonic} {}", constants.constant(index)?), index)
+            }
+            Instruction::LdcW(index)
+            | Instruction::Ldc2W(index)
+            | Instruction::Getstatic(index)
+            | Instruction::Putstatic(index)
+            | Instruction::Getfield(index)
+            | Instruction::Putfield(index)
+            | Instruction::Invokevirtual(index)
+            | Instruction::Invokespecial(index)
+            | Instruction::Invokestatic(index)
+            | Instruction::Invokedynamic(index) => self.commented(
+                format!("{mnemonic} {}", constants.constant(*index)?),
+                *index,
+            ),
+            Instruction::Invokeinterface { index, count } => self.commented(
+                format!("{mnemonic} {} {count}", constants.constant(*index)?),
+                *index,
+            ),
+            Instruction::New(index)
+            | Instruction::Anewarray(index)
+            | Instruction::Checkcast(index)
+            | Instruction::Instanceof(index) => {
+                self.commented(format!("{mnemonic} {}", constants.class(*index)?), *index)
+            }
+            Instruction::Multianewarray { index, dimensions } => self.commented(
+                format!("{mnemonic} {} {dimensions}", constants.class(*index)?),
+                *index,
+            ),
+            Instruction::Iload(index)
+            | Instruction::Lload(index)
+            | Instruction::Fload(index)
+            | Instruction::Dload(index)
+            | Instruction::Aload(index)
+            | Instruction::Istore(index)
+            | Instruction::Lstore(index)
+            | Instruction::Fstore(index)
+            | Instruction::Dstore(index)
+            | Instruction::Astore(index)
+            | Instruction::Ret(index) => format!("{mnemonic} {index}"),
+            Instruction::Iinc { index, value } => format!("{mnemonic} {index} {value}"),
+            Instruction::Wide(instruction) => {
+                let (line, _) = self.instruction(offset, instruction, labels)?;
+                format!("{mnemonic} {line}")
+            }
+            Instruction::Ifeq(delta)
+            | Instruction::Ifne(delta)
+            | Instruction::Iflt(delta)
+            | Instruction::Ifge(delta)
+            | Instruction::Ifgt(delta)
+            | Instruction::Ifle(delta)
+            | Instruction::IfIcmpeq(delta)
+            | Instruction::IfIcmpne(delta)
+            | Instruction::IfIcmplt(delta)
+            | Instruction::IfIcmpge(delta)
+            | Instruction::IfIcmpgt(delta)
+            | Instruction::IfIcmple(delta)
+            | Instruction::IfAcmpeq(delta)
+            | Instruction::IfAcmpne(delta)
+            | Instruction::Goto(delta)
+            | Instruction::Jsr(delta)
+            | Instruction::Ifnull(delta)
+            | Instruction::Ifnonnull(delta) => format!("{mnemonic} {}", target(*delta as i32)),
+            Instruction::GotoW(delta) | Instruction::JsrW(delta) => {
+                format!("{mnemonic} {}", target(*delta))
+            }
+            Instruction::Newarray(array_type) => format!("{mnemonic} {}", array_type.name()),
+            Instruction::Tableswitch {
+                default,
+                low,
+                offsets,
+            } => {
+                let lines = offsets
+                    .iter()
+                    .map(|delta| target(*delta))
+                    .chain([format!("default {}", target(*default))])
+                    .collect();
+                return Ok((format!("{mnemonic} {low}"), lines));
+            }
+            Instruction::Lookupswitch { default, pairs } => {
+                let lines = pairs
+                    .iter()
+                    .map(|(key, delta)| format!("{key} {}", target(*delta)))
+                    .chain([format!("default {}", target(*default))])
+                    .collect();
+                return Ok((mnemonic.to_owned(), lines));
+            }
+            _ => mnemonic.to_owned(),
+        };
+        Ok((line, vec![]))
+    }
+
+    /// Appends the contents of the referenced constant as comment, if the
+    /// constant pool is printed.
+    fn commented(&self, line: String, index: u16) -> String {
+        if !self.constants.indices {
+            return line;
+        }
+        let constants = Constants {
+            indices: false,
+            ..self.constants
+        };
+        match constants.constant(index) {
+            Ok(constant) => format!("{line} // {constant}"),
+            Err(_) => line,
+        }
+    }
+
+    fn verification_type(
+        &self,
+        verification_type: &VerificationTypeInfo,
+        labels: &Labels,
+    ) -> Result<String> {
+        Ok(match verification_type {
+            VerificationTypeInfo::Top => "top".to_owned(),
+            VerificationTypeInfo::Integer => "int".to_owned(),
+            VerificationTypeInfo::Float => "float".to_owned(),
+            VerificationTypeInfo::Double => "double".to_owned(),
+            VerificationTypeInfo::Long => "long".to_owned(),
+            VerificationTypeInfo::Null => "null".to_owned(),
+            VerificationTypeInfo::UninitializedThis => "uninitialized_this".to_owned(),
+            VerificationTypeInfo::Object { cpool_index } => {
+                format!("object {}", self.constants.class(*cpool_index)?)
+            }
+            VerificationTypeInfo::Uninitialized { offset } => {
+                format!("uninitialized {}", labels.name(*offset as u32))
+            }
+        })
+    }
+
+    fn verification_types(
+        &self,
+        verification_types: &[VerificationTypeInfo],
+        labels: &Labels,
+    ) -> Result<String> {
+        Ok(verification_types
+            .iter()
+            .map(|verification_type| self.verification_type(verification_type, labels))
+            .collect::<Result<Vec<_>>>()?
+            .join(" "))
+    }
+
+    fn annotation(&mut self, indent: usize, prefix: String, annotation: &Annotation) -> Result<()> {
+        let header = format!(
+            "{prefix}annotation {}",
+            self.constants.utf8(annotation.type_index)?
+        );
+        self.element_value_pairs(indent, header, &annotation.element_value_pairs)
+    }
+
+    fn type_annotation(
+        &mut self,
+        indent: usize,
+        type_annotation: &TypeAnnotation,
+        labels: &Labels,
+    ) -> Result<()> {
+        let target_info = &type_annotation.target_info;
+        let mut header = vec![target_type_name(target_info.target_type())];
+        match target_info {
+            TargetInfo::ClassTypeParameter {
+                type_parameter_index,
+            }
+            | TargetInfo::MethodTypeParameter {
+                type_parameter_index,
+            } => header.push(type_parameter_index.to_string()),
+            TargetInfo::ClassExtends { supertype_index } => {
+                header.push(supertype_index.to_string())
+            }
+            TargetInfo::ClassTypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            }
+            | TargetInfo::MethodTypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            } => {
+                header.push(type_parameter_index.to_string());
+                header.push(bound_index.to_string());
+            }
+            TargetInfo::Field | TargetInfo::MethodReturn | TargetInfo::MethodReceiver => {}
+            TargetInfo::MethodFormalParameter {
+                formal_parameter_index,
+            } => header.push(formal_parameter_index.to_string()),
+            TargetInfo::Throws { throws_type_index } => header.push(throws_type_index.to_string()),
+            TargetInfo::LocalVariable(targets) | TargetInfo::ResourceVariable(targets) => {
+                header.push(targets.len().to_string());
+                for target in targets {
+                    let start = target.start_pc as u32;
+                    header.push(labels.name(start));
+                    header.push(labels.name(start + target.length as u32));
+                    header.push(target.index.to_string());
+                }
+            }
+            TargetInfo::ExceptionParameter {
+                exception_table_index,
+            } => header.push(exception_table_index.to_string()),
+            TargetInfo::Instanceof { offset }
+            | TargetInfo::New { offset }
+            | TargetInfo::ConstructorReference { offset }
+            | TargetInfo::MethodReference { offset } => header.push(labels.name(*offset as u32)),
+            TargetInfo::Cast {
+                offset,
+                type_argument_index,
+            }
+            | TargetInfo::ConstructorInvocationTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | TargetInfo::MethodInvocationTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | TargetInfo::ConstructorReferenceTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | TargetInfo::MethodReferenceTypeArgument {
+                offset,
+                type_argument_index,
+            } => {
+                header.push(labels.name(*offset as u32));
+                header.push(type_argument_index.to_string());
+            }
+        }
+        if !type_annotation.target_path.0.is_empty() {
+            header.push("path".to_owned());
+            header.push(
+                type_annotation
+                    .target_path
+                    .0
+                    .iter()
+                    .map(|entry| match entry.type_path_kind {
+                        TypePathKind::Array => "[".to_owned(),
+                        TypePathKind::Nested => ".".to_owned(),
+                        TypePathKind::WildcardBound => "*".to_owned(),
+                        TypePathKind::TypeArgument => format!("{};", entry.type_argument_index),
+                    })
+                    .collect(),
+            );
+        }
+        header.push(self.constants.utf8(type_annotation.type_index)?);
+        self.element_value_pairs(
+            indent,
+            header.join(" "),
+            &type_annotation.element_value_pairs,
+        )
+    }
+
+    fn element_value_pairs(
+        &mut self,
+        indent: usize,
+        header: String,
+        element_value_pairs: &[ElementValuePair],
+    ) -> Result<()> {
+        if element_value_pairs.is_empty() {
+            self.line(indent, header);
+            return Ok(());
+        }
+        self.line(indent, format!("{header} {{"));
+        for pair in element_value_pairs {
+            let name = self.constants.utf8(pair.element_name_index)?;
+            self.element_value(indent + 1, format!("{name} "), &pair.value)?;
+        }
+        self.line(indent, "}");
+        Ok(())
+    }
+
+    fn element_value(&mut self, indent: usize, prefix: String, value: &ElementValue) -> Result<()> {
+        let constants = self.constants;
+        let line = match value {
+            ElementValue::Byte(index) => format!("{prefix}byte {}", constants.integer(*index)?),
+            ElementValue::Char(index) => format!("{prefix}char {}", constants.integer(*index)?),
+            ElementValue::Double(index) => {
+                format!("{prefix}double {}", constants.double(*index)?)
+            }
+            ElementValue::Float(index) => format!("{prefix}float {}", constants.float(*index)?),
+            ElementValue::Int(index) => format!("{prefix}int {}", constants.integer(*index)?),
+            ElementValue::Long(index) => format!("{prefix}long {}", constants.long(*index)?),
+            ElementValue::Short(index) => format!("{prefix}short {}", constants.integer(*index)?),
+            ElementValue::Boolean(index) => {
+                format!("{prefix}boolean {}", constants.integer(*index)?)
+            }
+            ElementValue::String(index) => format!("{prefix}string {}", constants.utf8(*index)?),
+            ElementValue::Enum {
+                type_name_index,
+                const_name_index,
+            } => format!(
+                "{prefix}enum {} {}",
+                constants.utf8(*type_name_index)?,
+                constants.utf8(*const_name_index)?
+            ),
+            ElementValue::Class(index) => format!("{prefix}class {}", constants.utf8(*index)?),
+            ElementValue::Annotation(annotation) => {
+                return self.annotation(indent, prefix, annotation);
+            }
+            ElementValue::Array(values) => {
+                if values.is_empty() {
+                    format!("{prefix}array")
+                } else {
+                    self.line(indent, format!("{prefix}array {{"));
+                    for value in values {
+                        self.element_value(indent + 1, String::new(), value)?;
+                    }
+                    "}".to_owned()
+                }
+            }
+        };
+        self.line(indent, line);
+        Ok(())
+    }
+}
+
+/// Words which have a meaning where names can be written as well.
+const RESERVED: [&str; 4] = ["{", "}", "any", "path"];
+
+/// Returns a name or string as is, or quoted if it would be read differently.
+fn word(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value.starts_with(['#', '"'])
+        && !value.contains("//")
+        && !value
+            .chars()
+            .any(|char| char.is_whitespace() || char.is_control())
+        && !RESERVED.contains(&value);
+    if plain {
+        value.to_owned()
+    } else {
+        quote(value)
+    }
+}
+
+/// Returns a Java string like [`word`], escaping unpaired surrogates.
+fn java_word(value: &JavaString) -> String {
+    match value.as_str() {
+        Some(value) => word(value),
+        None => quote_java(value),
+    }
+}
+
+/// Returns the float as decimal number, or its bits if it is NaN as the
+/// payload would get lost otherwise.
+fn float(value: f32) -> String {
+    if value.is_nan() {
+        format!("{:#010x}", value.to_bits())
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// Returns the double as decimal number, or its bits if it is NaN.
+fn double(value: f64) -> String {
+    if value.is_nan() {
+        format!("{:#018x}", value.to_bits())
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// Returns the lowercase names of the set flags, and the unknown ones as
+/// hexadecimal number.
+fn flags<F: Flags<Bits = u16>>(flags: &F) -> Vec<String> {
+    let mut known = 0;
+    let mut names = vec![];
+    for (name, flag) in flags.iter_names() {
+        known |= flag.bits();
+        names.push(name.to_lowercase());
+    }
+    let unknown = flags.bits() & !known;
+    if unknown != 0 {
+        names.push(format!("{unknown:#06x}"));
+    }
+    names
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    /// A quoted string, which is never taken for a keyword.
+    String(JavaString),
+}
+
+impl Token {
+    /// Returns the text of the token, unless it has unpaired surrogates.
+    fn text(&self) -> Option<&str> {
+        match self {
+            Self::Word(text) => Some(text),
+            Self::String(text) => text.as_str(),
+        }
+    }
+
+    fn is(&self, keyword: &str) -> bool {
+        matches!(self, Self::Word(word) if word == keyword)
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Word(text) => f.write_str(text),
+            Self::String(text) => Display::fmt(text, f),
+        }
+    }
+}
+
+/// A line together with the block it opens.
+struct Node {
+    line: usize,
+    tokens: Vec<Token>,
+    block: Option<Vec<Node>>,
+}
+
+fn error(line: usize, reason: impl Into<String>) -> Error {
+    Error::InvalidAssembly {
+        line,
+        reason: reason.into(),
+    }
+}
+
+/// Splits the input into lines of tokens, nested by their blocks.
+fn lines(input: &str) -> Result<Vec<Node>> {
+    let mut nodes = vec![];
+    let mut open: Vec<Node> = vec![];
+    for (index, text) in input.lines().enumerate() {
+        let line = index + 1;
+        let mut tokens = tokens(line, text)?;
+        if tokens.is_empty() {
+            continue;
+        }
+        if tokens.len() == 1 && tokens[0].is("}") {
+            let node = open.pop().ok_or_else(|| error(line, "unexpected `}`"))?;
+            match open.last_mut() {
+                Some(parent) => parent.block.get_or_insert_with(Vec::new).push(node),
+                None => nodes.push(node),
+            }
+            continue;
+        }
+        if tokens.last().is_some_and(|token| token.is("{")) {
+            tokens.pop();
+            if tokens.is_empty() {
+                return Err(error(line, "unexpected `{`"));
+            }
+            open.push(Node {
+                line,
+                tokens,
+                block: Some(vec![]),
+            });
+            continue;
+        }
+        let node = Node {
+            line,
+            tokens,
+            block: None,
+        };
+        match open.last_mut() {
+            Some(parent) => parent.block.get_or_insert_with(Vec::new).push(node),
+            None => nodes.push(node),
+        }
+    }
+    match open.first() {
+        Some(node) => Err(error(node.line, "unclosed block")),
+        None => Ok(nodes),
+    }
+}
+
+fn tokens(line: usize, text: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|char| char.is_whitespace()).is_some() {}
+        if chars.next_if_eq(&'"').is_some() {
+            // escapes can produce unpaired surrogates
+            let mut value = JavaString::default();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => value.push(match chars.next() {
+                        Some('"') => '"' as u32,
+                        Some('\\') => '\\' as u32,
+                        Some('n') => '\n' as u32,
+                        Some('r') => '\r' as u32,
+                        Some('t') => '\t' as u32,
+                        Some('u') => {
+                            let code = (0..4).filter_map(|_| chars.next()).collect::<String>();
+                            u32::from_str_radix(&code, 16)
+                                .ok()
+                                .filter(|_| code.len() == 4)
+                                .ok_or_else(|| error(line, format!("invalid escape `\\u{code}`")))?
+                        }
+                        _ => return Err(error(line, "invalid escape")),
+                    }),
+                    Some(char) => value.push(char as u32),
+                    None => return Err(error(line, "unterminated string")),
+                }
+            }
+            tokens.push(Token::String(value));
+            continue;
+        }
+        let mut word = String::new();
+        while let Some(char) = chars.next_if(|char| !char.is_whitespace()) {
+            word.push(char);
+        }
+        if word.is_empty() {
+            break;
+        }
+        // the rest of the line is a comment
+        if let Some(comment) = word.find("//") {
+            word.truncate(comment);
+            if !word.is_empty() {
+                tokens.push(Token::Word(word));
+            }
+            break;
+        }
+        tokens.push(Token::Word(word));
+    }
+    Ok(tokens)
+}
+
+/// The tokens of a line and the block it opens, which have to be consumed
+/// entirely.
+struct Tokens<'a> {
+    line: usize,
+    tokens: &'a [Token],
+    block: Option<&'a [Node]>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(node: &'a Node) -> Self {
+        Self {
+            line: node.line,
+            tokens: &node.tokens,
+            block: node.block.as_deref(),
+        }
+    }
+
+    fn error(&self, reason: impl Into<String>) -> Error {
+        error(self.line, reason)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.first()
+    }
+
+    fn next(&mut self) -> Result<&'a Token> {
+        let (token, tokens) = self
+            .tokens
+            .split_first()
+            .ok_or_else(|| self.error("unexpected end of line"))?;
+        self.tokens = tokens;
+        Ok(token)
+    }
+
+    fn text(&mut self) -> Result<&'a str> {
+        self.next()?
+            .text()
+            .ok_or_else(|| self.error("unexpected unpaired surrogate"))
+    }
+
+    /// Consumes a name or string, which may contain unpaired surrogates.
+    fn java_string(&mut self) -> Result<JavaString> {
+        Ok(match self.next()? {
+            Token::Word(word) => word.as_str().into(),
+            Token::String(value) => value.clone(),
+        })
+    }
+
+    /// Consumes the next token if it is the given keyword.
+    fn next_if(&mut self, keyword: &str) -> bool {
+        let next = self.peek().is_some_and(|token| token.is(keyword));
+        if next {
+            self.tokens = &self.tokens[1..];
+        }
+        next
+    }
+
+    fn expect(&mut self, keyword: &str) -> Result<()> {
+        if self.next_if(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{keyword}`")))
+        }
+    }
+
+    /// Returns the lines of the block, which is empty if there is none.
+    fn block(&mut self) -> &'a [Node] {
+        self.block.take().unwrap_or_default()
+    }
+
+    fn end(&self) -> Result<()> {
+        if let Some(token) = self.peek() {
+            return Err(self.error(format!("unexpected `{token}`")));
+        }
+        if self.block.is_some() {
+            return Err(self.error("unexpected block"));
+        }
+        Ok(())
+    }
+
+    fn number<T: TryFrom<i64>>(&mut self) -> Result<T> {
+        let text = self.text()?;
+        let value = match text.strip_prefix("0x") {
+            Some(digits) => i64::from_str_radix(digits, 16).ok(),
+            None => text.parse().ok(),
+        };
+        value
+            .and_then(|value| T::try_from(value).ok())
+            .ok_or_else(|| self.error(format!("invalid number `{text}`")))
+    }
+
+    fn float(&mut self) -> Result<f32> {
+        let text = self.text()?;
+        match text.strip_prefix("0x") {
+            Some(bits) => u32::from_str_radix(bits, 16).ok().map(f32::from_bits),
+            None => text.parse().ok(),
+        }
+        .ok_or_else(|| self.error(format!("invalid float `{text}`")))
+    }
+
+    fn double(&mut self) -> Result<f64> {
+        let text = self.text()?;
+        match text.strip_prefix("0x") {
+            Some(bits) => u64::from_str_radix(bits, 16).ok().map(f64::from_bits),
+            None => text.parse().ok(),
+        }
+        .ok_or_else(|| self.error(format!("invalid double `{text}`")))
+    }
+
+    /// Consumes a constant pool index like `#7`, if the next token is one.
+    fn index(&mut self) -> Result<Option<u16>> {
+        let Some(Token::Word(word)) = self.peek() else {
+            return Ok(None);
+        };
+        let Some(index) = word.strip_prefix('#') else {
+            return Ok(None);
+        };
+        let index = index
+            .parse()
+            .map_err(|_| self.error(format!("invalid constant pool index `{word}`")))?;
+        self.tokens = &self.tokens[1..];
+        Ok(Some(index))
+    }
+
+    /// Consumes flags until the next token is not a flag.
+    fn flags<F: Flags<Bits = u16>>(&mut self) -> F {
+        let mut flags = F::empty();
+        while let Some(flag) = self.peek().and_then(flag::<F>) {
+            flags.insert(flag);
+            self.tokens = &self.tokens[1..];
+        }
+        flags
+    }
+
+    /// Consumes flags up to the last `count` tokens.
+    fn flags_before<F: Flags<Bits = u16>>(&mut self, count: usize) -> Result<F> {
+        let mut flags = F::empty();
+        while self.tokens.len() > count {
+            let token = self.next()?;
+            let flag =
+                flag::<F>(token).ok_or_else(|| self.error(format!("unknown flag `{token}`")))?;
+            flags.insert(flag);
+        }
+        Ok(flags)
+    }
+
+    /// Consumes a label, or an offset like `@12`.
+    fn offset(&mut self, labels: &HashMap<&str, u32>) -> Result<u32> {
+        let label = self.text()?;
+        let offset = match label.strip_prefix('@') {
+            Some(offset) => offset.parse().ok(),
+            None => labels.get(label).copied(),
+        };
+        offset.ok_or_else(|| self.error(format!("undefined label `{label}`")))
+    }
+
+    fn offset16(&mut self, labels: &HashMap<&str, u32>) -> Result<u16> {
+        let offset = self.offset(labels)?;
+        u16::try_from(offset).map_err(|_| self.error(format!("offset {offset} out of range")))
+    }
+
+    /// Consumes the labels of a range, returning its start and length.
+    fn range(&mut self, labels: &HashMap<&str, u32>) -> Result<(u16, u16)> {
+        let start = self.offset16(labels)?;
+        let end = self.offset(labels)?;
+        let length = end
+            .checked_sub(start as u32)
+            .and_then(|length| u16::try_from(length).ok())
+            .ok_or_else(|| self.error(format!("invalid range from {start} to {end}")))?;
+        Ok((start, length))
+    }
+}
+
+/// Parses a flag by its lowercase name, or unknown flags as hexadecimal
+/// number.
+fn flag<F: Flags<Bits = u16>>(token: &Token) -> Option<F> {
+    let Token::Word(word) = token else {
+        return None;
+    };
+    match word.strip_prefix("0x") {
+        Some(bits) => u16::from_str_radix(bits, 16).ok().map(F::from_bits_retain),
+        None => F::from_name(&word.to_uppercase()),
+    }
+}
+
+/// Builds the constant pool defined by `.const`, whose entries have to be
+/// numbered consecutively.
+fn constant_pool(nodes: &[Node]) -> Result<ConstantPool> {
+    let mut parser = Parser {
+        constant_pool: ConstantPoolBuilder::new(),
+    };
+    let mut entries = BTreeMap::new();
+    for node in nodes {
+        let mut t = Tokens::new(node);
+        if !t.next_if(".const") {
+            continue;
+        }
+        let index = t
+            .index()?
+            .ok_or_else(|| t.error("expected a constant pool index"))?;
+        t.expect("=")?;
+        let info = parser.constant_info(&mut t, true)?;
+        t.end()?;
+        if entries.insert(index, (node.line, info)).is_some() {
+            return Err(t.error(format!("constant pool entry #{index} is already defined")));
+        }
+    }
+
+    let mut constant_pool = ConstantPool::new();
+    for (index, (line, info)) in entries {
+        let expected = constant_pool.len() as u32 + 1;
+        if index as u32 != expected {
+            return Err(error(
+                line,
+                format!(
+                    "constant pool entry #{index} does not follow #{}",
+                    expected - 1
+                ),
+            ));
+        }
+        constant_pool.push(info);
+    }
+    Ok(constant_pool)
+}
+
+/// An instruction whose branch targets are resolved once all labels are
+/// placed.
+enum Pending<'a> {
+    Instruction(Instruction),
+    Branch {
+        opcode: u8,
+        target: &'a str,
+    },
+    Tableswitch {
+        low: i32,
+        targets: Vec<&'a str>,
+        default: &'a str,
+    },
+    Lookupswitch {
+        pairs: Vec<(i32, &'a str)>,
+        default: &'a str,
+    },
+}
+
+impl Pending<'_> {
+    /// Resolves the instruction at `offset`, `target` returning the offset of
+    /// a label.
+    fn resolve(
+        &self,
+        line: usize,
+        offset: u32,
+        target: impl Fn(&str) -> Result<u32>,
+    ) -> Result<Instruction> {
+        let delta = |label: &str| Ok(target(label)?.wrapping_sub(offset) as i32);
+        Ok(match self {
+            Self::Instruction(instruction) => instruction.clone(),
+            Self::Branch { opcode, target } => {
+                let delta = delta(target)?;
+                let mut bytes = vec![*opcode];
+                if matches!(opcode, 200 | 201) {
+                    bytes.extend(delta.to_be_bytes());
+                } else {
+                    let delta = i16::try_from(delta)
+                        .map_err(|_| error(line, format!("branch to `{target}` out of range")))?;
+                    bytes.extend(delta.to_be_bytes());
+                }
+                Instruction::decode(&mut bytes.as_slice(), offset)?
+            }
+            Self::Tableswitch {
+                low,
+                targets,
+                default,
+            } => Instruction::Tableswitch {
+                default: delta(default)?,
+                low: *low,
+                offsets: targets
+                    .iter()
+                    .map(|target| delta(target))
+                    .collect::<Result<_>>()?,
+            },
+            Self::Lookupswitch { pairs, default } => Instruction::Lookupswitch {
+                default: delta(default)?,
+                pairs: pairs
+                    .iter()
+                    .map(|(key, target)| Ok((*key, delta(target)?)))
+                    .collect::<Result<_>>()?,
+            },
+        })
+    }
+}
+
+struct Parser {
+    constant_pool: ConstantPoolBuilder,
+}
+
+impl Parser {
+    /// Allocates the constants of all `ldc` instructions up front, as they
+    /// have to be among the first 255 entries.
+    fn allocate_ldc(&mut self, nodes: &[Node]) -> Result<()> {
+        for node in nodes {
+            let mut t = Tokens::new(node);
+            if t.next_if(".code") {
+                for node in t.block() {
+                    let mut t = Tokens::new(node);
+                    if t.next_if("ldc") {
+                        self.constant(&mut t)?;
+                    }
+                }
+            } else if let Some(nodes) = &node.block {
+                self.allocate_ldc(nodes)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn class_file(&mut self, nodes: &[Node], end: usize) -> Result<ClassFile> {
+        let mut version = None;
+        let mut class = None;
+        let mut super_class = 0;
+        let mut interfaces = vec![];
+        let mut fields = vec![];
+        let mut methods = vec![];
+        let mut attributes = vec![];
+        for node in nodes {
+            let mut t = Tokens::new(node);
+            let directive = t.peek().and_then(Token::text).unwrap_or_default();
+            match directive {
+                ".version" => {
+                    t.next()?;
+                    if version.is_some() {
+                        return Err(t.error("duplicate `.version`"));
+                    }
+                    version = Some((t.number()?, t.number()?));
+                }
+                ".class" => {
+                    t.next()?;
+                    if class.is_some() {
+                        return Err(t.error("duplicate `.class`"));
+                    }
+                    let access_flags = t.flags_before(1)?;
+                    class = Some((access_flags, self.class(&mut t)?));
+                }
+                ".super" => {
+                    t.next()?;
+                    super_class = self.class(&mut t)?;
+                }
+                ".implements" => {
+                    t.next()?;
+                    interfaces.push(self.class(&mut t)?);
+                }
+                ".const" => continue,
+                ".field" => {
+                    t.next()?;
+                    fields.push(FieldInfo {
+                        access_flags: t.flags_before(2)?,
+                        name_index: self.utf8(&mut t)?,
+                        descriptor_index: self.utf8(&mut t)?,
+                        attributes: self.attributes(&mut t, &HashMap::new())?,
+                    });
+                }
+                ".method" => {
+                    t.next()?;
+                    methods.push(MethodInfo {
+                        access_flags: t.flags_before(2)?,
+                        name_index: self.utf8(&mut t)?,
+                        descriptor_index: self.utf8(&mut t)?,
+                        attributes: self.attributes(&mut t, &HashMap::new())?,
+                    });
+                }
+                _ => {
+                    attributes.push(self.attribute(node, &HashMap::new())?);
+                    continue;
+                }
+            }
+            t.end()?;
+        }
+
+        let (major_version, minor_version) =
+            version.ok_or_else(|| error(end, "missing `.version`"))?;
+        let (access_flags, this_class) = class.ok_or_else(|| error(end, "missing `.class`"))?;
+        Ok(ClassFile {
+            minor_version,
+            major_version,
+            constant_pool: std::mem::take(&mut self.constant_pool).build(),
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+
+    fn utf8(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => {
+                let value = t.java_string()?;
+                self.constant_pool.push(ConstantPoolInfo::Utf8(value))
+            }
+        }
+    }
+
+    fn class(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => self.constant_pool.class(t.text()?),
+        }
+    }
+
+    fn module(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => self.constant_pool.module(t.text()?),
+        }
+    }
+
+    fn package(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => self.constant_pool.package(t.text()?),
+        }
+    }
+
+    fn name_and_type(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => {
+                let name = t.text()?;
+                let descriptor = t.text()?;
+                self.constant_pool.name_and_type(name, descriptor)
+            }
+        }
+    }
+
+    fn integer(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => {
+                let value = t.number()?;
+                self.constant_pool.integer(value)
+            }
+        }
+    }
+
+    fn long(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => {
+                let value = t.number()?;
+                self.constant_pool.long(value)
+            }
+        }
+    }
+
+    fn float(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => {
+                let value = t.float()?;
+                self.constant_pool.float(value)
+            }
+        }
+    }
+
+    fn double(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => {
+                let value = t.double()?;
+                self.constant_pool.double(value)
+            }
+        }
+    }
+
+    fn constant(&mut self, t: &mut Tokens) -> Result<u16> {
+        match t.index()? {
+            Some(index) => Ok(index),
+            None => {
+                let info = self.constant_info(t, false)?;
+                self.constant_pool.push(info)
+            }
+        }
+    }
+
+    /// Parses the kind and contents of a constant, which has to refer to
+    /// other entries by index if it is `raw`.
+    fn constant_info(&mut self, t: &mut Tokens, raw: bool) -> Result<ConstantPoolInfo> {
+        let kind = t.text()?;
+        Ok(match kind {
+            "Utf8" => ConstantPoolInfo::Utf8(t.java_string()?),
+            "Integer" => ConstantPoolInfo::Integer(t.number()?),
+            "Float" => ConstantPoolInfo::Float(t.float()?),
+            "Long" => ConstantPoolInfo::Long(t.number()?),
+            "Double" => ConstantPoolInfo::Double(t.double()?),
+            "Class" => ConstantPoolInfo::Class {
+                name_index: self.reference(t, raw, Self::utf8)?,
+            },
+            "String" => ConstantPoolInfo::String {
+                string_index: self.reference(t, raw, Self::utf8)?,
+            },
+            "Field" => ConstantPoolInfo::Fieldref {
+                class_index: self.reference(t, raw, Self::class)?,
+                name_and_type_index: self.reference(t, raw, Self::name_and_type)?,
+            },
+            "Method" => ConstantPoolInfo::Methodref {
+                class_index: self.reference(t, raw, Self::class)?,
+                name_and_type_index: self.reference(t, raw, Self::name_and_type)?,
+            },
+            "InterfaceMethod" => ConstantPoolInfo::InterfaceMethodref {
+                class_index: self.reference(t, raw, Self::class)?,
+                name_and_type_index: self.reference(t, raw, Self::name_and_type)?,
+            },
+            "NameAndType" => ConstantPoolInfo::NameAndType {
+                name_index: self.reference(t, raw, Self::utf8)?,
+                descriptor_index: self.reference(t, raw, Self::utf8)?,
+            },
+            "MethodHandle" => {
+                let name = t.text()?;
+                ConstantPoolInfo::MethodHandle {
+                    reference_kind: (0..=u8::MAX)
+                        .find(|reference_kind| reference_kind_name(*reference_kind) == name)
+                        .ok_or_else(|| t.error(format!("unknown reference kind `{name}`")))?,
+                    reference_index: self.reference(t, raw, Self::constant)?,
+                }
+            }
+            "MethodType" => ConstantPoolInfo::MethodType {
+                descriptor_index: self.reference(t, raw, Self::utf8)?,
+            },
+            "Dynamic" => ConstantPoolInfo::Dynamic {
+                bootstrap_method_attr_index: t.number()?,
+                name_and_type_index: self.reference(t, raw, Self::name_and_type)?,
+            },
+            "InvokeDynamic" => ConstantPoolInfo::InvokeDynamic {
+                bootstrap_method_attr_index: t.number()?,
+                name_and_type_index: self.reference(t, raw, Self::name_and_type)?,
+            },
+            "Module" => ConstantPoolInfo::Module {
+                name_index: self.reference(t, raw, Self::utf8)?,
+            },
+            "Package" => ConstantPoolInfo::Package {
+                name_index: self.reference(t, raw, Self::utf8)?,
+            },
+            _ => return Err(t.error(format!("unknown constant `{kind}`"))),
+        })
+    }
+
+    fn reference(
+        &mut self,
+        t: &mut Tokens,
+        raw: bool,
+        parse: fn(&mut Self, &mut Tokens) -> Result<u16>,
+    ) -> Result<u16> {
+        if raw {
+            t.index()?
+                .ok_or_else(|| t.error("expected a constant pool index"))
+        } else {
+            parse(self, t)
+        }
+    }
+
+    /// Parses each line of the block opened by the current line.
+    fn lines<T>(
+        &mut self,
+        t: &mut Tokens,
+        mut parse: impl FnMut(&mut Self, &mut Tokens) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        t.block()
+            .iter()
+            .map(|node| {
+                let mut t = Tokens::new(node);
+                let value = parse(self, &mut t)?;
+                t.end()?;
+                Ok(value)
+            })
+            .collect()
+    }
+
+    fn attributes(
+        &mut self,
+        t: &mut Tokens,
+        labels: &HashMap<&str, u32>,
+    ) -> Result<Vec<AttributeInfo>> {
+        t.block()
+            .iter()
+            .map(|node| self.attribute(node, labels))
+            .collect()
+    }
+
+    fn attribute(&mut self, node: &Node, labels: &HashMap<&str, u32>) -> Result<AttributeInfo> {
+        let mut t = Tokens::new(node);
+        let directive = t.text()?;
+        let attribute = match directive {
+            ".attribute" => {
+                let attribute_name_index = self.utf8(&mut t)?;
+                let mut info = vec![];
+                for node in t.block() {
+                    for token in &node.tokens {
+                        let bytes = token.to_string();
+                        if bytes.len() % 2 != 0 {
+                            return Err(error(node.line, format!("invalid bytes `{bytes}`")));
+                        }
+                        for index in (0..bytes.len()).step_by(2) {
+                            info.push(
+                                bytes
+                                    .get(index..index + 2)
+                                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                                    .ok_or_else(|| {
+                                        error(node.line, format!("invalid bytes `{bytes}`"))
+                                    })?,
+                            );
+                        }
+                    }
+                }
+                t.end()?;
+                return Ok(AttributeInfo {
+                    attribute_name_index,
+                    info,
+                });
+            }
+            ".constantvalue" => Attribute::ConstantValue(ConstantValueAttribute {
+                constantvalue_index: self.constant(&mut t)?,
+            }),
+            ".code" => Attribute::Code(self.code(&mut t)?),
+            ".stackmaptable" => Attribute::StackMapTable(self.stack_map_table(&mut t, labels)?),
+            ".exceptions" => {
+                Attribute::Exceptions(ExceptionsAttribute(self.lines(&mut t, Self::class)?))
+            }
+            ".innerclasses" => Attribute::InnerClasses(InnerClassesAttribute(self.lines(
+                &mut t,
+                |parser, t| {
+                    Ok(InnerClass {
+                        inner_class_info_index: parser.class(t)?,
+                        outer_class_info_index: if t.next_if("outer") {
+                            parser.class(t)?
+                        } else {
+                            0
+                        },
+                        inner_name_index: if t.next_if("name") {
+                            parser.utf8(t)?
+                        } else {
+                            0
+                        },
+                        inner_class_access_flags: t.flags(),
+                    })
+                },
+            )?)),
+            ".enclosingmethod" => Attribute::EnclosingMethod(EnclosingMethodAttribute {
+                class_index: self.class(&mut t)?,
+                method_index: if t.is_empty() {
+                    0
+                } else {
+                    self.name_and_type(&mut t)?
+                },
+            }),
+            ".synthetic" => Attribute::Synthetic,
+            ".signature" => Attribute::Signature(SignatureAttribute {
+                signature_index: self.utf8(&mut t)?,
+            }),
+            ".sourcefile" => Attribute::SourceFile(SourceFileAttribute {
+                sourcefile_index: self.utf8(&mut t)?,
+            }),
+            ".sourcedebugextension" => Attribute::SourceDebugExtension(
+                SourceDebugExtensionAttribute(t.text()?.as_bytes().to_vec()),
+            ),
+            ".linenumbertable" => Attribute::LineNumberTable(LineNumberTableAttribute(
+                self.lines(&mut t, |_, t| {
+                    Ok(LineNumber {
+                        start_pc: t.offset16(labels)?,
+                        line_number: t.number()?,
+                    })
+                })?,
+            )),
+            ".localvariabletable" => Attribute::LocalVariableTable(LocalVariableTableAttribute(
+                self.lines(&mut t, |parser, t| {
+                    let index = t.number()?;
+                    let name_index = parser.utf8(t)?;
+                    let descriptor_index = parser.utf8(t)?;
+                    let (start_pc, length) = t.range(labels)?;
+                    Ok(LocalVariable {
+                        start_pc,
+                        length,
+                        name_index,
+                        descriptor_index,
+                        index,
+                    })
+                })?,
+            )),
+            ".localvariabletypetable" => Attribute::LocalVariableTypeTable(
+                LocalVariableTypeTableAttribute(self.lines(&mut t, |parser, t| {
+                    let index = t.number()?;
+                    let name_index = parser.utf8(t)?;
+                    let signature_index = parser.utf8(t)?;
+                    let (start_pc, length) = t.range(labels)?;
+                    Ok(LocalVariableType {
+                        start_pc,
+                        length,
+                        name_index,
+                        signature_index,
+                        index,
+                    })
+                })?),
+            ),
+            ".deprecated" => Attribute::Deprecated,
+            ".runtimevisibleannotations" => {
+                Attribute::RuntimeVisibleAnnotations(self.annotations(&mut t)?)
+            }
+            ".runtimeinvisibleannotations" => {
+                Attribute::RuntimeInvisibleAnnotations(self.annotations(&mut t)?)
+            }
+            ".runtimevisibleparameterannotations" => {
+                Attribute::RuntimeVisibleParameterAnnotations(self.parameter_annotations(&mut t)?)
+            }
+            ".runtimeinvisibleparameterannotations" => {
+                Attribute::RuntimeInvisibleParameterAnnotations(self.parameter_annotations(&mut t)?)
+            }
+            ".runtimevisibletypeannotations" => {
+                Attribute::RuntimeVisibleTypeAnnotations(self.type_annotations(&mut t, labels)?)
+            }
+            ".runtimeinvisibletypeannotations" => {
+                Attribute::RuntimeInvisibleTypeAnnotations(self.type_annotations(&mut t, labels)?)
+            }
+            ".annotationdefault" => Attribute::AnnotationDefault(AnnotationDefaultAttribute(
+                self.element_value(&mut t)?,
+            )),
+            ".bootstrapmethods" => Attribute::BootstrapMethods(BootstrapMethodsAttribute(
+                self.lines(&mut t, |parser, t| {
+                    t.expect("method")?;
+                    Ok(BootstrapMethod {
+                        bootstrap_method_ref: parser.constant(t)?,
+                        bootstrap_arguments: parser.lines(t, Self::constant)?,
+                    })
+                })?,
+            )),
+            ".methodparameters" => Attribute::MethodParameters(MethodParametersAttribute(
+                self.lines(&mut t, |parser, t| {
+                    t.expect("parameter")?;
+                    Ok(MethodParameter {
+                        name_index: if t.next_if("name") {
+                            parser.utf8(t)?
+                        } else {
+                            0
+                        },
+                        access_flags: t.flags(),
+                    })
+                })?,
+            )),
+            ".module" => Attribute::Module(self.module_attribute(&mut t)?),
+            ".modulepackages" => Attribute::ModulePackages(ModulePackagesAttribute(
+                self.lines(&mut t, Self::package)?,
+            )),
+            ".modulemainclass" => Attribute::ModuleMainClass(ModuleMainClassAttribute {
+                main_class_index: self.class(&mut t)?,
+            }),
+            ".nesthost" => Attribute::NestHost(NestHostAttribute {
+                host_class_index: self.class(&mut t)?,
+            }),
+            ".nestmembers" => {
+                Attribute::NestMembers(NestMembersAttribute(self.lines(&mut t, Self::class)?))
+            }
+            ".record" => Attribute::Record(RecordAttribute(self.lines(&mut t, |parser, t| {
+                t.expect("component")?;
+                Ok(RecordComponentInfo {
+                    name_index: parser.utf8(t)?,
+                    descriptor_index: parser.utf8(t)?,
+                    attributes: parser.attributes(t, &HashMap::new())?,
+                })
+            })?)),
+            ".permittedsubclasses" => Attribute::PermittedSubclasses(PermittedSubclassesAttribute(
+                self.lines(&mut t, Self::class)?,
+            )),
+            _ => return Err(t.error(format!("unknown directive `{directive}`"))),
+        };
+        t.end()?;
+        self.constant_pool.attribute(&attribute)
+    }
+
+    fn code(&mut self, t: &mut Tokens) -> Result<CodeAttribute> {
+        t.expect("stack")?;
+        let max_stack = t.number()?;
+        t.expect("locals")?;
+        let max_locals = t.number()?;
+
+        // places the labels, branches are assumed to be in range until all
+        // offsets are known
+        let mut labels = HashMap::new();
+        let mut instructions = vec![];
+        let mut directives = vec![];
+        let mut offset = 0u32;
+        for node in t.block() {
+            let mut t = Tokens::new(node);
+            let first = t.text()?;
+            if first.starts_with('.') {
+                directives.push(node);
+                continue;
+            }
+            if let Some(label) = first.strip_suffix(':') {
+                t.end()?;
+                if labels.insert(label, offset).is_some() {
+                    return Err(t.error(format!("label `{label}` is already placed")));
+                }
+                continue;
+            }
+            let pending = self.instruction(first, &mut t)?;
+            t.end()?;
+            let length = pending
+                .resolve(node.line, offset, |_| Ok(offset))?
+                .length(offset);
+            instructions.push((node.line, offset, pending));
+            offset = offset
+                .checked_add(length)
+                .ok_or_else(|| t.error("code too large"))?;
+        }
+
+        let mut code = Cursor::new(Vec::new());
+        for (line, offset, pending) in &instructions {
+            let instruction = pending.resolve(*line, *offset, |label| {
+                let offset = match label.strip_prefix('@') {
+                    Some(offset) => offset.parse().ok(),
+                    None => labels.get(label).copied(),
+                };
+                offset.ok_or_else(|| error(*line, format!("undefined label `{label}`")))
+            })?;
+            instruction.encode(&mut code, *offset)?;
+        }
+
+        let mut exception_table = vec![];
+        let mut attributes = vec![];
+        for node in directives {
+            let mut t = Tokens::new(node);
+            if !t.next_if(".catch") {
+                attributes.push(self.attribute(node, &labels)?);
+                continue;
+            }
+            exception_table.push(ExceptionTableEntry {
+                start_pc: t.offset16(&labels)?,
+                end_pc: t.offset16(&labels)?,
+                handler_pc: t.offset16(&labels)?,
+                catch_type: if t.next_if("any") {
+                    0
+                } else {
+                    self.class(&mut t)?
+                },
+            });
+            t.end()?;
+        }
+        Ok(CodeAttribute {
+            max_stack,
+            max_locals,
+            code: code.into_inner(),
+            exception_table,
+            attributes,
+        })
+    }
+
+    fn instruction<'a>(&mut self, mnemonic: &str, t: &mut Tokens<'a>) -> Result<Pending<'a>> {
+        let wide = mnemonic == "wide";
+        let mnemonic = if wide { t.text()? } else { mnemonic };
+        let opcode = code::opcode(mnemonic)
+            .filter(|opcode| *opcode != 196)
+            .ok_or_else(|| t.error(format!("unknown instruction `{mnemonic}`")))?;
+        if wide && !matches!(opcode, 21..=25 | 54..=58 | 132 | 169) {
+            return Err(t.error(format!("`{mnemonic}` cannot be wide")));
+        }
+
+        let mut bytes = vec![opcode];
+        match opcode {
+            16 => bytes.extend(t.number::<i8>()?.to_be_bytes()),
+            17 => bytes.extend(t.number::<i16>()?.to_be_bytes()),
+            18 => {
+                let index = self.constant(t)?;
+                bytes.push(u8::try_from(index).map_err(|_| {
+                    t.error(format!(
+                        "constant pool index {index} out of range for `ldc`"
+                    ))
+                })?);
+            }
+            19 | 20 | 178..=184 => bytes.extend(self.constant(t)?.to_be_bytes()),
+            185 => {
+                bytes.extend(self.constant(t)?.to_be_bytes());
+                bytes.extend([t.number()?, 0]);
+            }
+            186 => {
+                bytes.extend(self.constant(t)?.to_be_bytes());
+                bytes.extend([0, 0]);
+            }
+            187 | 189 | 192 | 193 => bytes.extend(self.class(t)?.to_be_bytes()),
+            197 => {
+                bytes.extend(self.class(t)?.to_be_bytes());
+                bytes.push(t.number()?);
+            }
+            21..=25 | 54..=58 | 169 => {
+                let index = t.number::<u16>()?;
+                match u8::try_from(index) {
+                    Ok(index) if !wide => bytes.push(index),
+                    _ => {
+                        bytes.insert(0, 196);
+                        bytes.extend(index.to_be_bytes());
+                    }
+                }
+            }
+            132 => {
+                let index = t.number::<u16>()?;
+                let value = t.number::<i16>()?;
+                match (u8::try_from(index), i8::try_from(value)) {
+                    (Ok(index), Ok(value)) if !wide => {
+                        bytes.push(index);
+                        bytes.extend(value.to_be_bytes());
+                    }
+                    _ => {
+                        bytes.insert(0, 196);
+                        bytes.extend(index.to_be_bytes());
+                        bytes.extend(value.to_be_bytes());
+                    }
+                }
+            }
+            188 => {
+                let name = t.text()?;
+                bytes.push(
+                    (4..=11)
+                        .find(|atype| {
+                            ArrayType::from_atype(*atype)
+                                .is_some_and(|array_type| array_type.name() == name)
+                        })
+                        .ok_or_else(|| t.error(format!("unknown array type `{name}`")))?,
+                );
+            }
+            153..=168 | 198..=201 => {
+                return Ok(Pending::Branch {
+                    opcode,
+                    target: t.text()?,
+                })
+            }
+            170 => {
+                let low = t.number()?;
+                let mut targets = vec![];
+                let mut default = None;
+                for node in t.block() {
+                    let mut t = Tokens::new(node);
+                    if t.next_if("default") {
+                        default = Some(t.text()?);
+                    } else {
+                        targets.push(t.text()?);
+                    }
+                    t.end()?;
+                }
+                return Ok(Pending::Tableswitch {
+                    low,
+                    targets,
+                    default: default.ok_or_else(|| t.error("missing `default`"))?,
+                });
+            }
+            171 => {
+                let mut pairs = vec![];
+                let mut default = None;
+                for node in t.block() {
+                    let mut t = Tokens::new(node);
+                    if t.next_if("default") {
+                        default = Some(t.text()?);
+                    } else {
+                        pairs.push((t.number()?, t.text()?));
+                    }
+                    t.end()?;
+                }
+                return Ok(Pending::Lookupswitch {
+                    pairs,
+                    default: default.ok_or_else(|| t.error("missing `default`"))?,
+                });
+            }
+            _ => {}
+        }
+        Ok(Pending::Instruction(Instruction::decode(
+            &mut bytes.as_slice(),
+            0,
+        )?))
+    }
+
+    fn stack_map_table(
+        &mut self,
+        t: &mut Tokens,
+        labels: &HashMap<&str, u32>,
+    ) -> Result<StackMapTableAttribute> {
+        let mut previous = None;
+        let frames = self.lines(t, |parser, t| {
+            let offset = t.offset(labels)?;
+            let offset_delta = match previous {
+                Some(previous) => offset.checked_sub(previous + 1),
+                None => Some(offset),
+            }
+            .and_then(|offset_delta| u16::try_from(offset_delta).ok())
+            .ok_or_else(|| t.error("stack map frames have to be in ascending order"))?;
+            previous = Some(offset);
+
+            let kind = t.text()?;
+            let frame = match kind {
+                "same" => StackMapFrame::Same { offset_delta },
+                "same_locals_1_stack_item" => StackMapFrame::SameLocals1StackItem {
+                    offset_delta,
+                    stack: parser.verification_type(t, labels)?,
+                },
+                "same_locals_1_stack_item_extended" => {
+                    StackMapFrame::SameLocals1StackItemExtended {
+                        offset_delta,
+                        stack: parser.verification_type(t, labels)?,
+                    }
+                }
+                "chop" => {
+                    let k = t.number()?;
+                    if !(1..=3).contains(&k) {
+                        return Err(t.error("chop frames remove 1 to 3 locals"));
+                    }
+                    StackMapFrame::Chop { k, offset_delta }
+                }
+                "same_extended" => StackMapFrame::SameExtended { offset_delta },
+                "append" => {
+                    let mut locals = vec![];
+                    while !t.is_empty() {
+                        locals.push(parser.verification_type(t, labels)?);
+                    }
+                    if !(1..=3).contains(&locals.len()) {
+                        return Err(t.error("append frames add 1 to 3 locals"));
+                    }
+                    StackMapFrame::Append {
+                        offset_delta,
+                        locals,
+                    }
+                }
+                "full" => {
+                    t.expect("locals")?;
+                    let mut locals = vec![];
+                    while !t.is_empty() && !t.peek().is_some_and(|token| token.is("stack")) {
+                        locals.push(parser.verification_type(t, labels)?);
+                    }
+                    t.expect("stack")?;
+                    let mut stack = vec![];
+                    while !t.is_empty() {
+                        stack.push(parser.verification_type(t, labels)?);
+                    }
+                    StackMapFrame::Full {
+                        offset_delta,
+                        locals,
+                        stack,
+                    }
+                }
+                _ => return Err(t.error(format!("unknown stack map frame `{kind}`"))),
+            };
+            if offset_delta > 63
+                && matches!(
+                    frame,
+                    StackMapFrame::Same { .. } | StackMapFrame::SameLocals1StackItem { .. }
+                )
+            {
+                return Err(t.error(format!(
+                    "offset delta {offset_delta} too large for `{kind}`"
+                )));
+            }
+            Ok(frame)
+        })?;
+        Ok(StackMapTableAttribute(frames))
+    }
+
+    fn verification_type(
+        &mut self,
+        t: &mut Tokens,
+        labels: &HashMap<&str, u32>,
+    ) -> Result<VerificationTypeInfo> {
+        let kind = t.text()?;
+        Ok(match kind {
+            "top" => VerificationTypeInfo::Top,
+            "int" => VerificationTypeInfo::Integer,
+            "float" => VerificationTypeInfo::Float,
+            "double" => VerificationTypeInfo::Double,
+            "long" => VerificationTypeInfo::Long,
+            "null" => VerificationTypeInfo::Null,
+            "uninitialized_this" => VerificationTypeInfo::UninitializedThis,
+            "object" => VerificationTypeInfo::Object {
+                cpool_index: self.class(t)?,
+            },
+            "uninitialized" => VerificationTypeInfo::Uninitialized {
+                offset: t.offset16(labels)?,
+            },
+            _ => return Err(t.error(format!("unknown verification type `{kind}`"))),
+        })
+    }
+
+    fn annotations(&mut self, t: &mut Tokens) -> Result<AnnotationsAttribute> {
+        Ok(AnnotationsAttribute(self.lines(t, |parser, t| {
+            t.expect("annotation")?;
+            parser.annotation(t)
+        })?))
+    }
+
+    fn parameter_annotations(&mut self, t: &mut Tokens) -> Result<ParameterAnnotationsAttribute> {
+        Ok(ParameterAnnotationsAttribute(self.lines(
+            t,
+            |parser, t| {
+                t.expect("parameter")?;
+                Ok(parser.annotations(t)?.0)
+            },
+        )?))
+    }
+
+    fn type_annotations(
+        &mut self,
+        t: &mut Tokens,
+        labels: &HashMap<&str, u32>,
+    ) -> Result<TypeAnnotationsAttribute> {
+        Ok(TypeAnnotationsAttribute(
+            self.lines(t, |parser, t| parser.type_annotation(t, labels))?,
+        ))
+    }
+
+    /// Parses an annotation following its `annotation` keyword.
+    fn annotation(&mut self, t: &mut Tokens) -> Result<Annotation> {
+        Ok(Annotation {
+            type_index: self.utf8(t)?,
+            element_value_pairs: self.element_value_pairs(t)?,
+        })
+    }
+
+    fn type_annotation(
+        &mut self,
+        t: &mut Tokens,
+        labels: &HashMap<&str, u32>,
+    ) -> Result<TypeAnnotation> {
+        let name = t.text()?;
+        let target_type = (0..=u8::MAX)
+            .find(|target_type| target_type_name(*target_type) == name)
+            .ok_or_else(|| t.error(format!("unknown target type `{name}`")))?;
+        let locals = |t: &mut Tokens| -> Result<Vec<LocalvarTarget>> {
+            let count = t.number::<u16>()?;
+            let mut targets = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let (start_pc, length) = t.range(labels)?;
+                targets.push(LocalvarTarget {
+                    start_pc,
+                    length,
+                    index: t.number()?,
+                });
+            }
+            Ok(targets)
+        };
+        let target_info = match target_type {
+            0x00 => TargetInfo::ClassTypeParameter {
+                type_parameter_index: t.number()?,
+            },
+            0x01 => TargetInfo::MethodTypeParameter {
+                type_parameter_index: t.number()?,
+            },
+            0x10 => TargetInfo::ClassExtends {
+                supertype_index: t.number()?,
+            },
+            0x11 => TargetInfo::ClassTypeParameterBound {
+                type_parameter_index: t.number()?,
+                bound_index: t.number()?,
+            },
+            0x12 => TargetInfo::MethodTypeParameterBound {
+                type_parameter_index: t.number()?,
+                bound_index: t.number()?,
+            },
+            0x13 => TargetInfo::Field,
+            0x14 => TargetInfo::MethodReturn,
+            0x15 => TargetInfo::MethodReceiver,
+            0x16 => TargetInfo::MethodFormalParameter {
+                formal_parameter_index: t.number()?,
+            },
+            0x17 => TargetInfo::Throws {
+                throws_type_index: t.number()?,
+            },
+            0x40 => TargetInfo::LocalVariable(locals(t)?),
+            0x41 => TargetInfo::ResourceVariable(locals(t)?),
+            0x42 => TargetInfo::ExceptionParameter {
+                exception_table_index: t.number()?,
+            },
+            0x43 => TargetInfo::Instanceof {
+                offset: t.offset16(labels)?,
+            },
+            0x44 => TargetInfo::New {
+                offset: t.offset16(labels)?,
+            },
+            0x45 => TargetInfo::ConstructorReference {
+                offset: t.offset16(labels)?,
+            },
+            0x46 => TargetInfo::MethodReference {
+                offset: t.offset16(labels)?,
+            },
+            0x47 => TargetInfo::Cast {
+                offset: t.offset16(labels)?,
+                type_argument_index: t.number()?,
+            },
+            0x48 => TargetInfo::ConstructorInvocationTypeArgument {
+                offset: t.offset16(labels)?,
+                type_argument_index: t.number()?,
+            },
+            0x49 => TargetInfo::MethodInvocationTypeArgument {
+                offset: t.offset16(labels)?,
+                type_argument_index: t.number()?,
+            },
+            0x4A => TargetInfo::ConstructorReferenceTypeArgument {
+                offset: t.offset16(labels)?,
+                type_argument_index: t.number()?,
+            },
+            0x4B => TargetInfo::MethodReferenceTypeArgument {
+                offset: t.offset16(labels)?,
+                type_argument_index: t.number()?,
+            },
+            _ => return Err(t.error(format!("unknown target type `{name}`"))),
+        };
+        let target_path = if t.next_if("path") {
+            type_path(t)?
+        } else {
+            TypePath::default()
+        };
+        Ok(TypeAnnotation {
+            target_info,
+            target_path,
+            type_index: self.utf8(t)?,
+            element_value_pairs: self.element_value_pairs(t)?,
+        })
+    }
+
+    fn element_value_pairs(&mut self, t: &mut Tokens) -> Result<Vec<ElementValuePair>> {
+        self.lines(t, |parser, t| {
+            Ok(ElementValuePair {
+                element_name_index: parser.utf8(t)?,
+                value: parser.element_value(t)?,
+            })
+        })
+    }
+
+    fn element_value(&mut self, t: &mut Tokens) -> Result<ElementValue> {
+        let tag = t.text()?;
+        Ok(match tag {
+            "byte" => ElementValue::Byte(self.integer(t)?),
+            "char" => ElementValue::Char(self.integer(t)?),
+            "double" => ElementValue::Double(self.double(t)?),
+            "float" => ElementValue::Float(self.float(t)?),
+            "int" => ElementValue::Int(self.integer(t)?),
+            "long" => ElementValue::Long(self.long(t)?),
+            "short" => ElementValue::Short(self.integer(t)?),
+            "boolean" => ElementValue::Boolean(self.integer(t)?),
+            "string" => ElementValue::String(self.utf8(t)?),
+            "enum" => ElementValue::Enum {
+                type_name_index: self.utf8(t)?,
+                const_name_index: self.utf8(t)?,
+            },
+            "class" => ElementValue::Class(self.utf8(t)?),
+            "annotation" => ElementValue::Annotation(self.annotation(t)?),
+            "array" => ElementValue::Array(self.lines(t, Self::element_value)?),
+            _ => return Err(t.error(format!("unknown element value `{tag}`"))),
+        })
+    }
+
+    fn module_attribute(&mut self, t: &mut Tokens) -> Result<ModuleAttribute> {
+        let mut module = ModuleAttribute {
+            module_name_index: self.module(t)?,
+            module_flags: t.flags(),
+            module_version_index: if t.next_if("version") {
+                self.utf8(t)?
+            } else {
+                0
+            },
+            requires: vec![],
+            exports: vec![],
+            opens: vec![],
+            uses_index: vec![],
+            provides: vec![],
+        };
+        for node in t.block() {
+            let mut t = Tokens::new(node);
+            let directive = t.text()?;
+            match directive {
+                "requires" => module.requires.push(Requires {
+                    requires_index: self.module(&mut t)?,
+                    requires_flags: t.flags(),
+                    requires_version_index: if t.next_if("version") {
+                        self.utf8(&mut t)?
+                    } else {
+                        0
+                    },
+                }),
+                "exports" => module.exports.push(Exports {
+                    exports_index: self.package(&mut t)?,
+                    exports_flags: t.flags(),
+                    exports_to_index: self.modules_to(&mut t)?,
+                }),
+                "opens" => module.opens.push(Opens {
+                    opens_index: self.package(&mut t)?,
+                    opens_flags: t.flags(),
+                    opens_to_index: self.modules_to(&mut t)?,
+                }),
+                "uses" => module.uses_index.push(self.class(&mut t)?),
+                "provides" => {
+                    let provides_index = self.class(&mut t)?;
+                    t.expect("with")?;
+                    let mut provides_with_index = vec![];
+                    while !t.is_empty() {
+                        provides_with_index.push(self.class(&mut t)?);
+                    }
+                    module.provides.push(Provides {
+                        provides_index,
+                        provides_with_index,
+                    });
+                }
+                _ => return Err(t.error(format!("unknown module directive `{directive}`"))),
+            }
+            t.end()?;
+        }
+        Ok(module)
+    }
+
+    /// Parses the modules an `exports` or `opens` is qualified to.
+    fn modules_to(&mut self, t: &mut Tokens) -> Result<Vec<u16>> {
+        let mut modules = vec![];
+        if t.next_if("to") {
+            while !t.is_empty() {
+                modules.push(self.module(t)?);
+            }
+        }
+        Ok(modules)
+    }
+}
+
+/// Parses a type path like `[.*0;`, with one character per entry, and type
+/// arguments as index followed by `;`.
+fn type_path(t: &mut Tokens) -> Result<TypePath> {
+    let text = t.text()?;
+    let mut path = vec![];
+    let mut rest = text;
+    while let Some(char) = rest.chars().next() {
+        let entry = match char {
+            '[' => TypePathEntry::new(TypePathKind::Array),
+            '.' => TypePathEntry::new(TypePathKind::Nested),
+            '*' => TypePathEntry::new(TypePathKind::WildcardBound),
+            _ => {
+                let (index, next) = rest
+                    .split_once(';')
+                    .ok_or_else(|| t.error(format!("invalid type path `{text}`")))?;
+                let index = index
+                    .parse()
+                    .map_err(|_| t.error(format!("invalid type path `{text}`")))?;
+                rest = next;
+                path.push(TypePathEntry::type_argument(index));
+                continue;
+            }
+        };
+        rest = &rest[1..];
+        path.push(entry);
+    }
+    Ok(TypePath(path))
+}
@@ -1,16 +1,33 @@
-use std::io::Read;
+use std::io::{Read, Seek, Write};
 
 use bitflags::bitflags;
 
-use crate::{Decode, Result};
+use crate::{
+    class::{
+        annotation::{Annotation, ElementValue},
+        attribute::{
+            AnnotationDefaultAttribute, AnnotationsAttribute, CodeAttribute,
+            ParameterAnnotationsAttribute,
+        },
+    },
+    count, decode_nested, decode_with,
+    descriptor::{FieldType, MethodDescriptor},
+    mutf8::{self, JavaString},
+    split_off, Decode, Encode, Error, Result,
+};
 
+pub mod annotation;
 pub mod attribute;
+pub mod borrowed;
+pub mod builder;
+pub mod module;
+pub mod visitor;
 
 #[derive(Debug)]
 pub struct ClassFile {
     pub minor_version: u16,
     pub major_version: u16,
-    pub constant_pool: Vec<ConstantPoolInfo>,
+    pub constant_pool: ConstantPool,
     pub access_flags: ClassAccessFlags,
     pub this_class: u16,
     pub super_class: u16,
@@ -22,15 +39,22 @@ pub struct ClassFile {
 
 impl Decode for ClassFile {
     fn decode(input: &mut &[u8]) -> Result<Self> {
-        let _magic = u32::decode(input)?; // 0xCAFEBABE
+        let begin = *input;
+        decode_with(begin, input, |input| Self::decode_class(begin, input))
+    }
+}
+
+impl ClassFile {
+    fn decode_class(begin: &[u8], input: &mut &[u8]) -> Result<Self> {
+        let magic = u32::decode(input)?;
+        if magic != 0xCAFEBABE {
+            return Err(Error::BadMagic(magic));
+        }
         let minor_version = Decode::decode(input)?;
         let major_version = Decode::decode(input)?;
-        let constant_pool_count = u16::decode(input)? - 1;
-        let mut constant_pool = Vec::with_capacity(constant_pool_count as usize);
-        for _ in 0..constant_pool_count {
-            constant_pool.push(ConstantPoolInfo::decode(input)?);
-        }
-        let access_flags = AccessFlags::from_bits(Decode::decode(input)?).unwrap();
+        let constant_pool = decode_nested(begin, input)?;
+        // reserved bits are to be ignored, but are kept for round-tripping
+        let access_flags = ClassAccessFlags::from_bits_retain(Decode::decode(input)?);
         let this_class = Decode::decode(input)?;
         let super_class = Decode::decode(input)?;
         let interfaces_count = u16::decode(input)?;
@@ -42,17 +66,17 @@ impl Decode for ClassFile {
         let fields_count = u16::decode(input)?;
         let mut fields = Vec::with_capacity(fields_count as usize);
         for _ in 0..fields_count {
-            fields.push(Decode::decode(input)?);
+            fields.push(decode_nested(begin, input)?);
         }
         let methods_count = u16::decode(input)?;
         let mut methods = Vec::with_capacity(methods_count as usize);
         for _ in 0..methods_count {
-            methods.push(Decode::decode(input)?);
+            methods.push(decode_nested(begin, input)?);
         }
         let attributes_count = u16::decode(input)?;
         let mut attributes = Vec::with_capacity(attributes_count as usize);
         for _ in 0..attributes_count {
-            attributes.push(Decode::decode(input)?);
+            attributes.push(decode_nested(begin, input)?);
         }
         Ok(Self {
             minor_version,
@@ -69,9 +93,307 @@ impl Decode for ClassFile {
     }
 }
 
-#[derive(Debug)]
+impl ClassFile {
+    /// Returns the internal name of the class, e.g. `java/lang/String`.
+    pub fn name(&self) -> Result<&str> {
+        self.constant_pool.class_name(self.this_class)
+    }
+
+    /// Returns the internal name of the super class, or `None` for
+    /// `java/lang/Object` and `module-info`.
+    pub fn super_name(&self) -> Result<Option<&str>> {
+        if self.super_class == 0 {
+            return Ok(None);
+        }
+        self.constant_pool.class_name(self.super_class).map(Some)
+    }
+
+    pub fn interface_names(&self) -> Result<Vec<&str>> {
+        self.interfaces
+            .iter()
+            .map(|interface| self.constant_pool.class_name(*interface))
+            .collect()
+    }
+
+    /// Returns the visible and invisible annotations of the class.
+    pub fn annotations(&self) -> Result<Vec<Annotation>> {
+        annotations(&self.attributes, &self.constant_pool)
+    }
+
+    /// Returns the annotation of the class with the given type descriptor,
+    /// e.g. `Ljava/lang/Deprecated;`.
+    pub fn annotation(&self, type_descriptor: &str) -> Result<Option<Annotation>> {
+        annotation(&self.attributes, &self.constant_pool, type_descriptor)
+    }
+}
+
+impl Encode for ClassFile {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        0xCAFEBABEu32.encode(output)?;
+        self.minor_version.encode(output)?;
+        self.major_version.encode(output)?;
+        self.constant_pool.encode(output)?;
+        self.access_flags.bits().encode(output)?;
+        self.this_class.encode(output)?;
+        self.super_class.encode(output)?;
+        count(self.interfaces.len(), "interfaces")?.encode(output)?;
+        for interface in &self.interfaces {
+            interface.encode(output)?;
+        }
+        count(self.fields.len(), "fields")?.encode(output)?;
+        for field in &self.fields {
+            field.encode(output)?;
+        }
+        count(self.methods.len(), "methods")?.encode(output)?;
+        for method in &self.methods {
+            method.encode(output)?;
+        }
+        count(self.attributes.len(), "attributes")?.encode(output)?;
+        for attribute in &self.attributes {
+            attribute.encode(output)?;
+        }
+        Ok(())
+    }
+}
+
+/// The constant pool of a class file, indexed as specified by the JVMS: valid
+/// indices start at 1, and `Long` and `Double` entries take up two indices,
+/// the second of which is unusable.
+#[derive(Debug, Clone, Default)]
+pub struct ConstantPool(Vec<Option<ConstantPoolInfo>>);
+
+impl ConstantPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of indices in use, including unusable ones. This is
+    /// one less than the `constant_pool_count` of the class file.
+    pub fn len(&self) -> u16 {
+        self.0.len() as u16
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Appends an entry and returns its index, reserving the following index
+    /// for `Long` and `Double` entries.
+    pub fn push(&mut self, info: ConstantPoolInfo) -> u16 {
+        let index = self.len() + 1;
+        let wide = matches!(
+            info,
+            ConstantPoolInfo::Long(_) | ConstantPoolInfo::Double(_)
+        );
+        self.0.push(Some(info));
+        if wide {
+            self.0.push(None);
+        }
+        index
+    }
+
+    /// Returns the entry at `index`, or `None` if the index is 0, out of range
+    /// or the unusable index following a `Long` or `Double` entry.
+    pub fn get(&self, index: u16) -> Option<&ConstantPoolInfo> {
+        self.0.get(index.checked_sub(1)? as usize)?.as_ref()
+    }
+
+    /// Returns an iterator over all usable entries with their indices.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &ConstantPoolInfo)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, info)| Some((index as u16 + 1, info.as_ref()?)))
+    }
+
+    fn try_get(&self, index: u16) -> Result<&ConstantPoolInfo> {
+        self.get(index)
+            .ok_or(Error::InvalidConstantPoolIndex(index))
+    }
+
+    /// Returns a `Utf8` entry, which unlike [`Self::utf8`] can contain
+    /// unpaired surrogates.
+    pub fn java_string(&self, index: u16) -> Result<&JavaString> {
+        match self.try_get(index)? {
+            ConstantPoolInfo::Utf8(value) => Ok(value),
+            _ => Err(Error::UnexpectedConstant {
+                index,
+                expected: "Utf8",
+            }),
+        }
+    }
+
+    pub fn utf8(&self, index: u16) -> Result<&str> {
+        match self.try_get(index)? {
+            ConstantPoolInfo::Utf8(value) => value.as_str().ok_or(Error::UnpairedSurrogate(index)),
+            _ => Err(Error::UnexpectedConstant {
+                index,
+                expected: "Utf8",
+            }),
+        }
+    }
+}
+
+impl ConstantLookup for ConstantPool {
+    fn entry(&self, index: u16) -> Result<Option<&ConstantPoolInfo>> {
+        self.try_get(index).map(Some)
+    }
+
+    fn utf8(&self, index: u16) -> Result<&str> {
+        ConstantPool::utf8(self, index)
+    }
+}
+
+/// Typed lookups of constant pool entries, shared by [`ConstantPool`] and
+/// [`ConstantPoolRef`](borrowed::ConstantPoolRef).
+pub trait ConstantLookup {
+    /// Returns the entry at `index`, or `None` if it is a `Utf8` entry which
+    /// is only accessible through [`Self::utf8`].
+    fn entry(&self, index: u16) -> Result<Option<&ConstantPoolInfo>>;
+
+    fn utf8(&self, index: u16) -> Result<&str>;
+
+    fn integer(&self, index: u16) -> Result<i32> {
+        lookup(self, index, "Integer", |info| match info {
+            ConstantPoolInfo::Integer(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    fn float(&self, index: u16) -> Result<f32> {
+        lookup(self, index, "Float", |info| match info {
+            ConstantPoolInfo::Float(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    fn long(&self, index: u16) -> Result<i64> {
+        lookup(self, index, "Long", |info| match info {
+            ConstantPoolInfo::Long(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    fn double(&self, index: u16) -> Result<f64> {
+        lookup(self, index, "Double", |info| match info {
+            ConstantPoolInfo::Double(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// Resolves a `Class` entry to its internal name.
+    fn class_name(&self, index: u16) -> Result<&str> {
+        let name_index = lookup(self, index, "Class", |info| match info {
+            ConstantPoolInfo::Class { name_index } => Some(*name_index),
+            _ => None,
+        })?;
+        self.utf8(name_index)
+    }
+
+    /// Resolves a `String` entry to its value.
+    fn string(&self, index: u16) -> Result<&str> {
+        let string_index = lookup(self, index, "String", |info| match info {
+            ConstantPoolInfo::String { string_index } => Some(*string_index),
+            _ => None,
+        })?;
+        self.utf8(string_index)
+    }
+
+    /// Resolves a `NameAndType` entry to its name and descriptor.
+    fn name_and_type(&self, index: u16) -> Result<(&str, &str)> {
+        let (name_index, descriptor_index) =
+            lookup(self, index, "NameAndType", |info| match info {
+                ConstantPoolInfo::NameAndType {
+                    name_index,
+                    descriptor_index,
+                } => Some((*name_index, *descriptor_index)),
+                _ => None,
+            })?;
+        Ok((self.utf8(name_index)?, self.utf8(descriptor_index)?))
+    }
+
+    /// Resolves a `Fieldref`, `Methodref` or `InterfaceMethodref` entry to its
+    /// class name, name and descriptor.
+    fn member_ref(&self, index: u16) -> Result<(&str, &str, &str)> {
+        let expected = "Fieldref, Methodref or InterfaceMethodref";
+        let (class_index, name_and_type_index) =
+            lookup(self, index, expected, |info| match info {
+                ConstantPoolInfo::Fieldref {
+                    class_index,
+                    name_and_type_index,
+                }
+                | ConstantPoolInfo::Methodref {
+                    class_index,
+                    name_and_type_index,
+                }
+                | ConstantPoolInfo::InterfaceMethodref {
+                    class_index,
+                    name_and_type_index,
+                } => Some((*class_index, *name_and_type_index)),
+                _ => None,
+            })?;
+        let (name, descriptor) = self.name_and_type(name_and_type_index)?;
+        Ok((self.class_name(class_index)?, name, descriptor))
+    }
+
+    /// Resolves a `Module` entry to its name.
+    fn module_name(&self, index: u16) -> Result<&str> {
+        let name_index = lookup(self, index, "Module", |info| match info {
+            ConstantPoolInfo::Module { name_index } => Some(*name_index),
+            _ => None,
+        })?;
+        self.utf8(name_index)
+    }
+
+    /// Resolves a `Package` entry to its internal name.
+    fn package_name(&self, index: u16) -> Result<&str> {
+        let name_index = lookup(self, index, "Package", |info| match info {
+            ConstantPoolInfo::Package { name_index } => Some(*name_index),
+            _ => None,
+        })?;
+        self.utf8(name_index)
+    }
+}
+
+/// Extracts a value from the entry at `index`, failing if `extract` returns
+/// `None` for it.
+fn lookup<C: ConstantLookup + ?Sized, T>(
+    constants: &C,
+    index: u16,
+    expected: &'static str,
+    extract: impl FnOnce(&ConstantPoolInfo) -> Option<T>,
+) -> Result<T> {
+    constants
+        .entry(index)?
+        .and_then(extract)
+        .ok_or(Error::UnexpectedConstant { index, expected })
+}
+
+impl Decode for ConstantPool {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let constant_pool_count = u16::decode(input)?.saturating_sub(1);
+        let mut constant_pool = Self(Vec::with_capacity(constant_pool_count as usize));
+        while constant_pool.len() < constant_pool_count {
+            constant_pool.push(decode_nested(begin, input)?);
+        }
+        Ok(constant_pool)
+    }
+}
+
+impl Encode for ConstantPool {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        (self.len() + 1).encode(output)?;
+        for (_, constant_pool_info) in self.iter() {
+            constant_pool_info.encode(output)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum ConstantPoolInfo {
-    Utf8(String),
+    Utf8(JavaString),
     Integer(i32),
     Float(f32),
     Long(i64),
@@ -121,6 +443,38 @@ pub enum ConstantPoolInfo {
     },
 }
 
+/// The kind of a `MethodHandle` constant, which determines the bytecode
+/// behavior of the handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    GetField = 1,
+    GetStatic = 2,
+    PutField = 3,
+    PutStatic = 4,
+    InvokeVirtual = 5,
+    InvokeStatic = 6,
+    InvokeSpecial = 7,
+    NewInvokeSpecial = 8,
+    InvokeInterface = 9,
+}
+
+impl ReferenceKind {
+    pub fn from_reference_kind(reference_kind: u8) -> Option<Self> {
+        Some(match reference_kind {
+            1 => Self::GetField,
+            2 => Self::GetStatic,
+            3 => Self::PutField,
+            4 => Self::PutStatic,
+            5 => Self::InvokeVirtual,
+            6 => Self::InvokeStatic,
+            7 => Self::InvokeSpecial,
+            8 => Self::NewInvokeSpecial,
+            9 => Self::InvokeInterface,
+            _ => return None,
+        })
+    }
+}
+
 impl Decode for ConstantPoolInfo {
     fn decode(input: &mut &[u8]) -> Result<Self> {
         let tag = u8::decode(input)?;
@@ -129,7 +483,9 @@ impl Decode for ConstantPoolInfo {
                 let length = u16::decode(input)?;
                 let mut bytes = vec![0; length as usize];
                 input.read_exact(&mut bytes)?;
-                ConstantPoolInfo::Utf8(String::from_utf8(bytes).unwrap())
+                ConstantPoolInfo::Utf8(
+                    mutf8::decode_java(&bytes).map_err(|error| error.offset_by(3))?,
+                )
             }
             3 => ConstantPoolInfo::Integer(Decode::decode(input)?),
             4 => ConstantPoolInfo::Float(Decode::decode(input)?),
@@ -178,19 +534,128 @@ impl Decode for ConstantPoolInfo {
             20 => ConstantPoolInfo::Package {
                 name_index: Decode::decode(input)?,
             },
-            _ => todo!(),
+            _ => return Err(Error::UnknownConstantTag { tag, offset: 0 }),
         };
         Ok(info)
     }
 }
 
+impl Encode for ConstantPoolInfo {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        match self {
+            ConstantPoolInfo::Utf8(value) => {
+                let bytes = mutf8::encode_java(value);
+                1u8.encode(output)?;
+                u16::try_from(bytes.len())
+                    .map_err(|_| Error::Utf8TooLong(bytes.len()))?
+                    .encode(output)?;
+                output.write_all(&bytes)?;
+            }
+            ConstantPoolInfo::Integer(value) => {
+                3u8.encode(output)?;
+                value.encode(output)?;
+            }
+            ConstantPoolInfo::Float(value) => {
+                4u8.encode(output)?;
+                value.encode(output)?;
+            }
+            ConstantPoolInfo::Long(value) => {
+                5u8.encode(output)?;
+                value.encode(output)?;
+            }
+            ConstantPoolInfo::Double(value) => {
+                6u8.encode(output)?;
+                value.encode(output)?;
+            }
+            ConstantPoolInfo::Class { name_index } => {
+                7u8.encode(output)?;
+                name_index.encode(output)?;
+            }
+            ConstantPoolInfo::String { string_index } => {
+                8u8.encode(output)?;
+                string_index.encode(output)?;
+            }
+            ConstantPoolInfo::Fieldref {
+                class_index,
+                name_and_type_index,
+            } => {
+                9u8.encode(output)?;
+                class_index.encode(output)?;
+                name_and_type_index.encode(output)?;
+            }
+            ConstantPoolInfo::Methodref {
+                class_index,
+                name_and_type_index,
+            } => {
+                10u8.encode(output)?;
+                class_index.encode(output)?;
+                name_and_type_index.encode(output)?;
+            }
+            ConstantPoolInfo::InterfaceMethodref {
+                class_index,
+                name_and_type_index,
+            } => {
+                11u8.encode(output)?;
+                class_index.encode(output)?;
+                name_and_type_index.encode(output)?;
+            }
+            ConstantPoolInfo::NameAndType {
+                name_index,
+                descriptor_index,
+            } => {
+                12u8.encode(output)?;
+                name_index.encode(output)?;
+                descriptor_index.encode(output)?;
+            }
+            ConstantPoolInfo::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => {
+                15u8.encode(output)?;
+                reference_kind.encode(output)?;
+                reference_index.encode(output)?;
+            }
+            ConstantPoolInfo::MethodType { descriptor_index } => {
+                16u8.encode(output)?;
+                descriptor_index.encode(output)?;
+            }
+            ConstantPoolInfo::Dynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => {
+                17u8.encode(output)?;
+                bootstrap_method_attr_index.encode(output)?;
+                name_and_type_index.encode(output)?;
+            }
+            ConstantPoolInfo::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => {
+                18u8.encode(output)?;
+                bootstrap_method_attr_index.encode(output)?;
+                name_and_type_index.encode(output)?;
+            }
+            ConstantPoolInfo::Module { name_index } => {
+                19u8.encode(output)?;
+                name_index.encode(output)?;
+            }
+            ConstantPoolInfo::Package { name_index } => {
+                20u8.encode(output)?;
+                name_index.encode(output)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct ClassAccessFlags: u16 {
         const PUBLIC = 0x0001;
         const FINAL = 0x0010;
         const SUPER = 0x0020;
         const INTERFACE = 0x0200;
+        const ABSTRACT = 0x0400;
         const SYNTHETIC = 0x1000;
         const ANNOTATION = 0x2000;
         const ENUM = 0x4000;
@@ -208,13 +673,14 @@ pub struct FieldInfo {
 
 impl Decode for FieldInfo {
     fn decode(input: &mut &[u8]) -> Result<Self> {
-        let access_flags = FieldAccessFlags::from_bits(Decode::decode(input)?).unwrap();
+        let begin = *input;
+        let access_flags = FieldAccessFlags::from_bits_retain(Decode::decode(input)?);
         let name_index = Decode::decode(input)?;
         let descriptor_index = Decode::decode(input)?;
         let attributes_count = u16::decode(input)?;
         let mut attributes = Vec::with_capacity(attributes_count as usize);
         for _ in 0..attributes_count {
-            attributes.push(Decode::decode(input)?);
+            attributes.push(decode_nested(begin, input)?);
         }
         Ok(Self {
             access_flags,
@@ -225,8 +691,45 @@ impl Decode for FieldInfo {
     }
 }
 
+impl Encode for FieldInfo {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.access_flags.bits().encode(output)?;
+        self.name_index.encode(output)?;
+        self.descriptor_index.encode(output)?;
+        count(self.attributes.len(), "attributes")?.encode(output)?;
+        for attribute in &self.attributes {
+            attribute.encode(output)?;
+        }
+        Ok(())
+    }
+}
+
+impl FieldInfo {
+    pub fn name<'a>(&self, constant_pool: &'a ConstantPool) -> Result<&'a str> {
+        constant_pool.utf8(self.name_index)
+    }
+
+    pub fn field_type(&self, constant_pool: &ConstantPool) -> Result<FieldType> {
+        constant_pool.utf8(self.descriptor_index)?.parse()
+    }
+
+    /// Returns the visible and invisible annotations of the field.
+    pub fn annotations(&self, constant_pool: &ConstantPool) -> Result<Vec<Annotation>> {
+        annotations(&self.attributes, constant_pool)
+    }
+
+    /// Returns the annotation of the field with the given type descriptor.
+    pub fn annotation(
+        &self,
+        constant_pool: &ConstantPool,
+        type_descriptor: &str,
+    ) -> Result<Option<Annotation>> {
+        annotation(&self.attributes, constant_pool, type_descriptor)
+    }
+}
+
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct FieldAccessFlags: u16 {
         const PUBLIC = 0x0001;
         const PRIVATE = 0x0002;
@@ -250,13 +753,14 @@ pub struct MethodInfo {
 
 impl Decode for MethodInfo {
     fn decode(input: &mut &[u8]) -> Result<Self> {
-        let access_flags = MethodAccessFlags::from_bits(Decode::decode(input)?).unwrap();
+        let begin = *input;
+        let access_flags = MethodAccessFlags::from_bits_retain(Decode::decode(input)?);
         let name_index = Decode::decode(input)?;
         let descriptor_index = Decode::decode(input)?;
         let attributes_count = u16::decode(input)?;
         let mut attributes = Vec::with_capacity(attributes_count as usize);
         for _ in 0..attributes_count {
-            attributes.push(Decode::decode(input)?);
+            attributes.push(decode_nested(begin, input)?);
         }
         Ok(Self {
             access_flags,
@@ -267,8 +771,123 @@ impl Decode for MethodInfo {
     }
 }
 
+impl Encode for MethodInfo {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.access_flags.bits().encode(output)?;
+        self.name_index.encode(output)?;
+        self.descriptor_index.encode(output)?;
+        count(self.attributes.len(), "attributes")?.encode(output)?;
+        for attribute in &self.attributes {
+            attribute.encode(output)?;
+        }
+        Ok(())
+    }
+}
+
+impl MethodInfo {
+    pub fn name<'a>(&self, constant_pool: &'a ConstantPool) -> Result<&'a str> {
+        constant_pool.utf8(self.name_index)
+    }
+
+    pub fn descriptor(&self, constant_pool: &ConstantPool) -> Result<MethodDescriptor> {
+        constant_pool.utf8(self.descriptor_index)?.parse()
+    }
+
+    /// Returns the decoded `Code` attribute, or `None` if the method has none,
+    /// as is the case for abstract and native methods.
+    pub fn code(&self, constant_pool: &ConstantPool) -> Result<Option<CodeAttribute>> {
+        for attribute in &self.attributes {
+            if constant_pool.utf8(attribute.attribute_name_index)? == "Code" {
+                return CodeAttribute::decode(&mut attribute.info.as_slice()).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the visible and invisible annotations of the method.
+    pub fn annotations(&self, constant_pool: &ConstantPool) -> Result<Vec<Annotation>> {
+        annotations(&self.attributes, constant_pool)
+    }
+
+    /// Returns the annotation of the method with the given type descriptor.
+    pub fn annotation(
+        &self,
+        constant_pool: &ConstantPool,
+        type_descriptor: &str,
+    ) -> Result<Option<Annotation>> {
+        annotation(&self.attributes, constant_pool, type_descriptor)
+    }
+
+    /// Returns the visible and invisible annotations of each formal
+    /// parameter.
+    pub fn parameter_annotations(
+        &self,
+        constant_pool: &ConstantPool,
+    ) -> Result<Vec<Vec<Annotation>>> {
+        let mut parameter_annotations: Vec<Vec<Annotation>> = vec![];
+        for attribute in &self.attributes {
+            if matches!(
+                constant_pool.utf8(attribute.attribute_name_index)?,
+                "RuntimeVisibleParameterAnnotations" | "RuntimeInvisibleParameterAnnotations"
+            ) {
+                let attribute =
+                    ParameterAnnotationsAttribute::decode(&mut attribute.info.as_slice())?;
+                if parameter_annotations.len() < attribute.0.len() {
+                    parameter_annotations.resize_with(attribute.0.len(), Vec::new);
+                }
+                for (parameter, annotations) in attribute.0.into_iter().enumerate() {
+                    parameter_annotations[parameter].extend(annotations);
+                }
+            }
+        }
+        Ok(parameter_annotations)
+    }
+
+    /// Returns the default value of an annotation interface element.
+    pub fn annotation_default(&self, constant_pool: &ConstantPool) -> Result<Option<ElementValue>> {
+        for attribute in &self.attributes {
+            if constant_pool.utf8(attribute.attribute_name_index)? == "AnnotationDefault" {
+                return AnnotationDefaultAttribute::decode(&mut attribute.info.as_slice())
+                    .map(|attribute| Some(attribute.0));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Decodes the annotations of the `RuntimeVisibleAnnotations` and
+/// `RuntimeInvisibleAnnotations` attributes.
+fn annotations(
+    attributes: &[AttributeInfo],
+    constant_pool: &ConstantPool,
+) -> Result<Vec<Annotation>> {
+    let mut annotations = vec![];
+    for attribute in attributes {
+        if matches!(
+            constant_pool.utf8(attribute.attribute_name_index)?,
+            "RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations"
+        ) {
+            annotations.extend(AnnotationsAttribute::decode(&mut attribute.info.as_slice())?.0);
+        }
+    }
+    Ok(annotations)
+}
+
+fn annotation(
+    attributes: &[AttributeInfo],
+    constant_pool: &ConstantPool,
+    type_descriptor: &str,
+) -> Result<Option<Annotation>> {
+    for annotation in annotations(attributes, constant_pool)? {
+        if annotation.type_descriptor(constant_pool)? == type_descriptor {
+            return Ok(Some(annotation));
+        }
+    }
+    Ok(None)
+}
+
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct MethodAccessFlags: u16 {
         const PUBLIC = 0x0001;
         const PRIVATE = 0x0002;
@@ -285,7 +904,7 @@ bitflags! {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AttributeInfo {
     pub attribute_name_index: u16,
     pub info: Vec<u8>,
@@ -295,13 +914,19 @@ impl Decode for AttributeInfo {
     fn decode(input: &mut &[u8]) -> Result<Self> {
         let attribute_name_index = Decode::decode(input)?;
         let attribute_length = u32::decode(input)?;
-        let mut info = Vec::with_capacity(attribute_length as usize);
-        for _ in 0..attribute_length {
-            info.push(Decode::decode(input)?);
-        }
+        let info = split_off(input, attribute_length as usize)?.to_vec();
         Ok(Self {
             attribute_name_index,
             info,
         })
     }
 }
+
+impl Encode for AttributeInfo {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.attribute_name_index.encode(output)?;
+        (self.info.len() as u32).encode(output)?;
+        output.write_all(&self.info)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,700 @@
+use std::io::{Seek, Write};
+
+use crate::{
+    class::{
+        attribute::{decode_table, encode_table},
+        ConstantLookup, ConstantPool,
+    },
+    decode_nested, decode_with, Decode, Encode, Error, Result,
+};
+
+/// The maximum depth of element values nested in arrays and annotations,
+/// which bounds the recursion of decoding.
+pub const MAX_ELEMENT_VALUE_DEPTH: usize = 255;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// The constant pool index of the field descriptor of the annotation
+    /// interface, e.g. `Ljava/lang/Deprecated;`.
+    pub type_index: u16,
+    pub element_value_pairs: Vec<ElementValuePair>,
+}
+
+impl Annotation {
+    pub fn type_descriptor<'a>(&self, constant_pool: &'a ConstantPool) -> Result<&'a str> {
+        constant_pool.utf8(self.type_index)
+    }
+
+    /// Returns the value of the element with the given name, or `None` if it
+    /// is not present, in which case the default of the annotation interface
+    /// applies.
+    pub fn value<'a>(
+        &'a self,
+        constant_pool: &'a ConstantPool,
+        name: &str,
+    ) -> Result<Option<Value<'a>>> {
+        for pair in &self.element_value_pairs {
+            if constant_pool.utf8(pair.element_name_index)? == name {
+                return pair.value.resolve(constant_pool).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Annotation {
+    /// Decodes an annotation which is the value of an element at `depth`.
+    fn decode_at(input: &mut &[u8], depth: usize) -> Result<Self> {
+        let begin = *input;
+        let type_index = Decode::decode(input)?;
+        let length = u16::decode(input)?;
+        let mut element_value_pairs = Vec::with_capacity(length as usize);
+        for _ in 0..length {
+            element_value_pairs.push(decode_with(begin, input, |input| {
+                ElementValuePair::decode_at(input, depth)
+            })?);
+        }
+        Ok(Self {
+            type_index,
+            element_value_pairs,
+        })
+    }
+}
+
+impl Decode for Annotation {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Self::decode_at(input, 0)
+    }
+}
+
+impl Encode for Annotation {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.type_index.encode(output)?;
+        encode_table(&self.element_value_pairs, output)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementValuePair {
+    pub element_name_index: u16,
+    pub value: ElementValue,
+}
+
+impl ElementValuePair {
+    fn decode_at(input: &mut &[u8], depth: usize) -> Result<Self> {
+        let begin = *input;
+        let element_name_index = Decode::decode(input)?;
+        let value = decode_with(begin, input, |input| ElementValue::decode_at(input, depth))?;
+        Ok(Self {
+            element_name_index,
+            value,
+        })
+    }
+}
+
+impl Decode for ElementValuePair {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Self::decode_at(input, 0)
+    }
+}
+
+impl Encode for ElementValuePair {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.element_name_index.encode(output)?;
+        self.value.encode(output)?;
+        Ok(())
+    }
+}
+
+/// The value of an annotation element, constants are given by their constant
+/// pool index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElementValue {
+    Byte(u16),
+    Char(u16),
+    Double(u16),
+    Float(u16),
+    Int(u16),
+    Long(u16),
+    Short(u16),
+    Boolean(u16),
+    String(u16),
+    Enum {
+        type_name_index: u16,
+        const_name_index: u16,
+    },
+    /// The constant pool index of the return descriptor of the class, e.g.
+    /// `Ljava/lang/Object;` or `V`.
+    Class(u16),
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+impl ElementValue {
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Byte(_) => b'B',
+            Self::Char(_) => b'C',
+            Self::Double(_) => b'D',
+            Self::Float(_) => b'F',
+            Self::Int(_) => b'I',
+            Self::Long(_) => b'J',
+            Self::Short(_) => b'S',
+            Self::Boolean(_) => b'Z',
+            Self::String(_) => b's',
+            Self::Enum { .. } => b'e',
+            Self::Class(_) => b'c',
+            Self::Annotation(_) => b'@',
+            Self::Array(_) => b'[',
+        }
+    }
+
+    /// Resolves the constant pool entries of the value.
+    pub fn resolve<'a>(&'a self, constant_pool: &'a ConstantPool) -> Result<Value<'a>> {
+        Ok(match self {
+            Self::Byte(index) => Value::Byte(constant_pool.integer(*index)? as i8),
+            Self::Char(index) => Value::Char(constant_pool.integer(*index)? as u16),
+            Self::Double(index) => Value::Double(constant_pool.double(*index)?),
+            Self::Float(index) => Value::Float(constant_pool.float(*index)?),
+            Self::Int(index) => Value::Int(constant_pool.integer(*index)?),
+            Self::Long(index) => Value::Long(constant_pool.long(*index)?),
+            Self::Short(index) => Value::Short(constant_pool.integer(*index)? as i16),
+            Self::Boolean(index) => Value::Boolean(constant_pool.integer(*index)? != 0),
+            Self::String(index) => Value::String(constant_pool.utf8(*index)?),
+            Self::Enum {
+                type_name_index,
+                const_name_index,
+            } => Value::Enum {
+                type_descriptor: constant_pool.utf8(*type_name_index)?,
+                name: constant_pool.utf8(*const_name_index)?,
+            },
+            Self::Class(index) => Value::Class(constant_pool.utf8(*index)?),
+            Self::Annotation(annotation) => Value::Annotation(annotation),
+            Self::Array(values) => Value::Array(
+                values
+                    .iter()
+                    .map(|value| value.resolve(constant_pool))
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+}
+
+impl ElementValue {
+    /// Decodes a value nested in `depth` arrays and annotations, failing
+    /// beyond [`MAX_ELEMENT_VALUE_DEPTH`].
+    fn decode_at(input: &mut &[u8], depth: usize) -> Result<Self> {
+        if depth > MAX_ELEMENT_VALUE_DEPTH {
+            return Err(Error::ElementValueTooDeep { offset: 0 });
+        }
+        let begin = *input;
+        let tag = u8::decode(input)?;
+        Ok(match tag {
+            b'B' => Self::Byte(Decode::decode(input)?),
+            b'C' => Self::Char(Decode::decode(input)?),
+            b'D' => Self::Double(Decode::decode(input)?),
+            b'F' => Self::Float(Decode::decode(input)?),
+            b'I' => Self::Int(Decode::decode(input)?),
+            b'J' => Self::Long(Decode::decode(input)?),
+            b'S' => Self::Short(Decode::decode(input)?),
+            b'Z' => Self::Boolean(Decode::decode(input)?),
+            b's' => Self::String(Decode::decode(input)?),
+            b'e' => {
+                let type_name_index = Decode::decode(input)?;
+                let const_name_index = Decode::decode(input)?;
+                Self::Enum {
+                    type_name_index,
+                    const_name_index,
+                }
+            }
+            b'c' => Self::Class(Decode::decode(input)?),
+            b'@' => Self::Annotation(decode_with(begin, input, |input| {
+                Annotation::decode_at(input, depth + 1)
+            })?),
+            b'[' => {
+                let length = u16::decode(input)?;
+                let mut values = Vec::with_capacity(length as usize);
+                for _ in 0..length {
+                    values.push(decode_with(begin, input, |input| {
+                        Self::decode_at(input, depth + 1)
+                    })?);
+                }
+                Self::Array(values)
+            }
+            _ => return Err(Error::UnknownElementValueTag { tag, offset: 0 }),
+        })
+    }
+}
+
+impl Decode for ElementValue {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Self::decode_at(input, 0)
+    }
+}
+
+impl Encode for ElementValue {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.tag().encode(output)?;
+        match self {
+            Self::Byte(index)
+            | Self::Char(index)
+            | Self::Double(index)
+            | Self::Float(index)
+            | Self::Int(index)
+            | Self::Long(index)
+            | Self::Short(index)
+            | Self::Boolean(index)
+            | Self::String(index)
+            | Self::Class(index) => index.encode(output)?,
+            Self::Enum {
+                type_name_index,
+                const_name_index,
+            } => {
+                type_name_index.encode(output)?;
+                const_name_index.encode(output)?;
+            }
+            Self::Annotation(annotation) => annotation.encode(output)?,
+            Self::Array(values) => encode_table(values, output)?,
+        }
+        Ok(())
+    }
+}
+
+/// An [`ElementValue`] with its constant pool entries resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Byte(i8),
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(&'a str),
+    Enum {
+        type_descriptor: &'a str,
+        name: &'a str,
+    },
+    Class(&'a str),
+    Annotation(&'a Annotation),
+    Array(Vec<Value<'a>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeAnnotation {
+    pub target_info: TargetInfo,
+    pub target_path: TypePath,
+    pub type_index: u16,
+    pub element_value_pairs: Vec<ElementValuePair>,
+}
+
+impl Decode for TypeAnnotation {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let target_info = decode_nested(begin, input)?;
+        let target_path = decode_nested(begin, input)?;
+        let type_index = Decode::decode(input)?;
+        let element_value_pairs = decode_table(begin, input)?;
+        Ok(Self {
+            target_info,
+            target_path,
+            type_index,
+            element_value_pairs,
+        })
+    }
+}
+
+impl Encode for TypeAnnotation {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.target_info.encode(output)?;
+        self.target_path.encode(output)?;
+        self.type_index.encode(output)?;
+        encode_table(&self.element_value_pairs, output)?;
+        Ok(())
+    }
+}
+
+/// The annotated type within a declaration or expression, one variant per
+/// target type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetInfo {
+    ClassTypeParameter {
+        type_parameter_index: u8,
+    },
+    MethodTypeParameter {
+        type_parameter_index: u8,
+    },
+    /// 65535 for the superclass, otherwise the index into the interfaces.
+    ClassExtends {
+        supertype_index: u16,
+    },
+    ClassTypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8,
+    },
+    MethodTypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8,
+    },
+    Field,
+    MethodReturn,
+    MethodReceiver,
+    MethodFormalParameter {
+        formal_parameter_index: u8,
+    },
+    Throws {
+        throws_type_index: u16,
+    },
+    LocalVariable(Vec<LocalvarTarget>),
+    ResourceVariable(Vec<LocalvarTarget>),
+    ExceptionParameter {
+        exception_table_index: u16,
+    },
+    Instanceof {
+        offset: u16,
+    },
+    New {
+        offset: u16,
+    },
+    ConstructorReference {
+        offset: u16,
+    },
+    MethodReference {
+        offset: u16,
+    },
+    Cast {
+        offset: u16,
+        type_argument_index: u8,
+    },
+    ConstructorInvocationTypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+    MethodInvocationTypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+    ConstructorReferenceTypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+    MethodReferenceTypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+}
+
+impl TargetInfo {
+    /// Returns the kind of target, which determines the layout of the target
+    /// info.
+    pub fn target_type(&self) -> u8 {
+        match self {
+            Self::ClassTypeParameter { .. } => 0x00,
+            Self::MethodTypeParameter { .. } => 0x01,
+            Self::ClassExtends { .. } => 0x10,
+            Self::ClassTypeParameterBound { .. } => 0x11,
+            Self::MethodTypeParameterBound { .. } => 0x12,
+            Self::Field => 0x13,
+            Self::MethodReturn => 0x14,
+            Self::MethodReceiver => 0x15,
+            Self::MethodFormalParameter { .. } => 0x16,
+            Self::Throws { .. } => 0x17,
+            Self::LocalVariable(_) => 0x40,
+            Self::ResourceVariable(_) => 0x41,
+            Self::ExceptionParameter { .. } => 0x42,
+            Self::Instanceof { .. } => 0x43,
+            Self::New { .. } => 0x44,
+            Self::ConstructorReference { .. } => 0x45,
+            Self::MethodReference { .. } => 0x46,
+            Self::Cast { .. } => 0x47,
+            Self::ConstructorInvocationTypeArgument { .. } => 0x48,
+            Self::MethodInvocationTypeArgument { .. } => 0x49,
+            Self::ConstructorReferenceTypeArgument { .. } => 0x4A,
+            Self::MethodReferenceTypeArgument { .. } => 0x4B,
+        }
+    }
+
+    /// Returns the offset of the expression in the code, if this targets one.
+    pub fn offset(&self) -> Option<u16> {
+        match *self {
+            Self::Instanceof { offset }
+            | Self::New { offset }
+            | Self::ConstructorReference { offset }
+            | Self::MethodReference { offset }
+            | Self::Cast { offset, .. }
+            | Self::ConstructorInvocationTypeArgument { offset, .. }
+            | Self::MethodInvocationTypeArgument { offset, .. }
+            | Self::ConstructorReferenceTypeArgument { offset, .. }
+            | Self::MethodReferenceTypeArgument { offset, .. } => Some(offset),
+            _ => None,
+        }
+    }
+}
+
+impl Decode for TargetInfo {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let target_type = u8::decode(input)?;
+        Ok(match target_type {
+            0x00 => Self::ClassTypeParameter {
+                type_parameter_index: Decode::decode(input)?,
+            },
+            0x01 => Self::MethodTypeParameter {
+                type_parameter_index: Decode::decode(input)?,
+            },
+            0x10 => Self::ClassExtends {
+                supertype_index: Decode::decode(input)?,
+            },
+            0x11 => Self::ClassTypeParameterBound {
+                type_parameter_index: Decode::decode(input)?,
+                bound_index: Decode::decode(input)?,
+            },
+            0x12 => Self::MethodTypeParameterBound {
+                type_parameter_index: Decode::decode(input)?,
+                bound_index: Decode::decode(input)?,
+            },
+            0x13 => Self::Field,
+            0x14 => Self::MethodReturn,
+            0x15 => Self::MethodReceiver,
+            0x16 => Self::MethodFormalParameter {
+                formal_parameter_index: Decode::decode(input)?,
+            },
+            0x17 => Self::Throws {
+                throws_type_index: Decode::decode(input)?,
+            },
+            0x40 => Self::LocalVariable(decode_table(begin, input)?),
+            0x41 => Self::ResourceVariable(decode_table(begin, input)?),
+            0x42 => Self::ExceptionParameter {
+                exception_table_index: Decode::decode(input)?,
+            },
+            0x43 => Self::Instanceof {
+                offset: Decode::decode(input)?,
+            },
+            0x44 => Self::New {
+                offset: Decode::decode(input)?,
+            },
+            0x45 => Self::ConstructorReference {
+                offset: Decode::decode(input)?,
+            },
+            0x46 => Self::MethodReference {
+                offset: Decode::decode(input)?,
+            },
+            0x47 => Self::Cast {
+                offset: Decode::decode(input)?,
+                type_argument_index: Decode::decode(input)?,
+            },
+            0x48 => Self::ConstructorInvocationTypeArgument {
+                offset: Decode::decode(input)?,
+                type_argument_index: Decode::decode(input)?,
+            },
+            0x49 => Self::MethodInvocationTypeArgument {
+                offset: Decode::decode(input)?,
+                type_argument_index: Decode::decode(input)?,
+            },
+            0x4A => Self::ConstructorReferenceTypeArgument {
+                offset: Decode::decode(input)?,
+                type_argument_index: Decode::decode(input)?,
+            },
+            0x4B => Self::MethodReferenceTypeArgument {
+                offset: Decode::decode(input)?,
+                type_argument_index: Decode::decode(input)?,
+            },
+            _ => {
+                return Err(Error::UnknownTargetType {
+                    target_type,
+                    offset: 0,
+                })
+            }
+        })
+    }
+}
+
+impl Encode for TargetInfo {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.target_type().encode(output)?;
+        match self {
+            Self::ClassTypeParameter {
+                type_parameter_index,
+            }
+            | Self::MethodTypeParameter {
+                type_parameter_index,
+            } => type_parameter_index.encode(output)?,
+            Self::ClassExtends { supertype_index } => supertype_index.encode(output)?,
+            Self::ClassTypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            }
+            | Self::MethodTypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            } => {
+                type_parameter_index.encode(output)?;
+                bound_index.encode(output)?;
+            }
+            Self::Field | Self::MethodReturn | Self::MethodReceiver => {}
+            Self::MethodFormalParameter {
+                formal_parameter_index,
+            } => formal_parameter_index.encode(output)?,
+            Self::Throws { throws_type_index } => throws_type_index.encode(output)?,
+            Self::LocalVariable(table) | Self::ResourceVariable(table) => {
+                encode_table(table, output)?
+            }
+            Self::ExceptionParameter {
+                exception_table_index,
+            } => exception_table_index.encode(output)?,
+            Self::Instanceof { offset }
+            | Self::New { offset }
+            | Self::ConstructorReference { offset }
+            | Self::MethodReference { offset } => offset.encode(output)?,
+            Self::Cast {
+                offset,
+                type_argument_index,
+            }
+            | Self::ConstructorInvocationTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | Self::MethodInvocationTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | Self::ConstructorReferenceTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | Self::MethodReferenceTypeArgument {
+                offset,
+                type_argument_index,
+            } => {
+                offset.encode(output)?;
+                type_argument_index.encode(output)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalvarTarget {
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16,
+}
+
+impl Decode for LocalvarTarget {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let start_pc = Decode::decode(input)?;
+        let length = Decode::decode(input)?;
+        let index = Decode::decode(input)?;
+        Ok(Self {
+            start_pc,
+            length,
+            index,
+        })
+    }
+}
+
+impl Encode for LocalvarTarget {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.start_pc.encode(output)?;
+        self.length.encode(output)?;
+        self.index.encode(output)?;
+        Ok(())
+    }
+}
+
+/// The path to the annotated part of a type, e.g. a type argument or the
+/// component type of an array.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypePath(pub Vec<TypePathEntry>);
+
+impl Decode for TypePath {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let path_length = u8::decode(input)?;
+        let mut path = Vec::with_capacity(path_length as usize);
+        for _ in 0..path_length {
+            path.push(decode_nested(begin, input)?);
+        }
+        Ok(Self(path))
+    }
+}
+
+impl Encode for TypePath {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        (self.0.len() as u8).encode(output)?;
+        for entry in &self.0 {
+            entry.encode(output)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypePathKind {
+    /// Deeper in an array type.
+    Array,
+    /// Deeper in a nested type.
+    Nested,
+    /// On the bound of a wildcard type argument.
+    WildcardBound,
+    /// On a type argument of a parameterized type.
+    TypeArgument,
+}
+
+/// A step of a [`TypePath`]. The type argument index is only meaningful for
+/// [`TypePathKind::TypeArgument`] and 0 otherwise, but kept as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypePathEntry {
+    pub type_path_kind: TypePathKind,
+    pub type_argument_index: u8,
+}
+
+impl TypePathEntry {
+    pub fn new(type_path_kind: TypePathKind) -> Self {
+        Self {
+            type_path_kind,
+            type_argument_index: 0,
+        }
+    }
+
+    pub fn type_argument(type_argument_index: u8) -> Self {
+        Self {
+            type_path_kind: TypePathKind::TypeArgument,
+            type_argument_index,
+        }
+    }
+}
+
+impl Decode for TypePathEntry {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let type_path_kind = u8::decode(input)?;
+        let type_argument_index = u8::decode(input)?;
+        let type_path_kind = match type_path_kind {
+            0 => TypePathKind::Array,
+            1 => TypePathKind::Nested,
+            2 => TypePathKind::WildcardBound,
+            3 => TypePathKind::TypeArgument,
+            _ => {
+                return Err(Error::UnknownTypePathKind {
+                    type_path_kind,
+                    offset: 0,
+                })
+            }
+        };
+        Ok(Self {
+            type_path_kind,
+            type_argument_index,
+        })
+    }
+}
+
+impl Encode for TypePathEntry {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        (self.type_path_kind as u8).encode(output)?;
+        self.type_argument_index.encode(output)?;
+        Ok(())
+    }
+}
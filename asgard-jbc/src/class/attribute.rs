@@ -1,20 +1,422 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt,
+    io::{Cursor, Seek, Write},
+};
+
 use bitflags::bitflags;
 
-use crate::{Decode, Result};
+use crate::{
+    class::{
+        annotation::{Annotation, ElementValue, TypeAnnotation},
+        AttributeInfo, ConstantPool, ConstantPoolInfo,
+    },
+    code::{self, Instruction},
+    decode_nested, split_off, Decode, Encode, Error, Result,
+};
+
+/// A decoded attribute.
+///
+/// Attributes which are not specified by the JVMS are either decoded by a
+/// decoder registered with an [`AttributeRegistry`], or kept as is.
+#[derive(Debug)]
+pub enum Attribute {
+    ConstantValue(ConstantValueAttribute),
+    Code(CodeAttribute),
+    StackMapTable(StackMapTableAttribute),
+    Exceptions(ExceptionsAttribute),
+    InnerClasses(InnerClassesAttribute),
+    EnclosingMethod(EnclosingMethodAttribute),
+    Synthetic,
+    Signature(SignatureAttribute),
+    SourceFile(SourceFileAttribute),
+    SourceDebugExtension(SourceDebugExtensionAttribute),
+    LineNumberTable(LineNumberTableAttribute),
+    LocalVariableTable(LocalVariableTableAttribute),
+    LocalVariableTypeTable(LocalVariableTypeTableAttribute),
+    Deprecated,
+    RuntimeVisibleAnnotations(AnnotationsAttribute),
+    RuntimeInvisibleAnnotations(AnnotationsAttribute),
+    RuntimeVisibleParameterAnnotations(ParameterAnnotationsAttribute),
+    RuntimeInvisibleParameterAnnotations(ParameterAnnotationsAttribute),
+    RuntimeVisibleTypeAnnotations(TypeAnnotationsAttribute),
+    RuntimeInvisibleTypeAnnotations(TypeAnnotationsAttribute),
+    AnnotationDefault(AnnotationDefaultAttribute),
+    BootstrapMethods(BootstrapMethodsAttribute),
+    MethodParameters(MethodParametersAttribute),
+    Module(ModuleAttribute),
+    ModulePackages(ModulePackagesAttribute),
+    ModuleMainClass(ModuleMainClassAttribute),
+    NestHost(NestHostAttribute),
+    NestMembers(NestMembersAttribute),
+    Record(RecordAttribute),
+    PermittedSubclasses(PermittedSubclassesAttribute),
+    Custom(Box<dyn CustomAttribute>),
+    Unknown { name: String, info: Vec<u8> },
+}
+
+impl Attribute {
+    /// Decodes the standard attributes, all others are returned as
+    /// [`Attribute::Unknown`].
+    pub fn decode(attribute: &AttributeInfo, constant_pool: &ConstantPool) -> Result<Self> {
+        AttributeRegistry::new().decode(attribute, constant_pool)
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::ConstantValue(_) => "ConstantValue",
+            Self::Code(_) => "Code",
+            Self::StackMapTable(_) => "StackMapTable",
+            Self::Exceptions(_) => "Exceptions",
+            Self::InnerClasses(_) => "InnerClasses",
+            Self::EnclosingMethod(_) => "EnclosingMethod",
+            Self::Synthetic => "Synthetic",
+            Self::Signature(_) => "Signature",
+            Self::SourceFile(_) => "SourceFile",
+            Self::SourceDebugExtension(_) => "SourceDebugExtension",
+            Self::LineNumberTable(_) => "LineNumberTable",
+            Self::LocalVariableTable(_) => "LocalVariableTable",
+            Self::LocalVariableTypeTable(_) => "LocalVariableTypeTable",
+            Self::Deprecated => "Deprecated",
+            Self::RuntimeVisibleAnnotations(_) => "RuntimeVisibleAnnotations",
+            Self::RuntimeInvisibleAnnotations(_) => "RuntimeInvisibleAnnotations",
+            Self::RuntimeVisibleParameterAnnotations(_) => "RuntimeVisibleParameterAnnotations",
+            Self::RuntimeInvisibleParameterAnnotations(_) => "RuntimeInvisibleParameterAnnotations",
+            Self::RuntimeVisibleTypeAnnotations(_) => "RuntimeVisibleTypeAnnotations",
+            Self::RuntimeInvisibleTypeAnnotations(_) => "RuntimeInvisibleTypeAnnotations",
+            Self::AnnotationDefault(_) => "AnnotationDefault",
+            Self::BootstrapMethods(_) => "BootstrapMethods",
+            Self::MethodParameters(_) => "MethodParameters",
+            Self::Module(_) => "Module",
+            Self::ModulePackages(_) => "ModulePackages",
+            Self::ModuleMainClass(_) => "ModuleMainClass",
+            Self::NestHost(_) => "NestHost",
+            Self::NestMembers(_) => "NestMembers",
+            Self::Record(_) => "Record",
+            Self::PermittedSubclasses(_) => "PermittedSubclasses",
+            Self::Custom(attribute) => attribute.name(),
+            Self::Unknown { name, .. } => name,
+        }
+    }
+
+    /// Encodes the contents of the attribute, excluding name and length.
+    pub fn info(&self) -> Result<Vec<u8>> {
+        let mut output = Cursor::new(Vec::new());
+        match self {
+            Self::ConstantValue(attribute) => attribute.encode(&mut output)?,
+            Self::Code(attribute) => attribute.encode(&mut output)?,
+            Self::StackMapTable(attribute) => attribute.encode(&mut output)?,
+            Self::Exceptions(attribute) => attribute.encode(&mut output)?,
+            Self::InnerClasses(attribute) => attribute.encode(&mut output)?,
+            Self::EnclosingMethod(attribute) => attribute.encode(&mut output)?,
+            Self::Synthetic | Self::Deprecated => {}
+            Self::Signature(attribute) => attribute.encode(&mut output)?,
+            Self::SourceFile(attribute) => attribute.encode(&mut output)?,
+            Self::SourceDebugExtension(attribute) => attribute.encode(&mut output)?,
+            Self::LineNumberTable(attribute) => attribute.encode(&mut output)?,
+            Self::LocalVariableTable(attribute) => attribute.encode(&mut output)?,
+            Self::LocalVariableTypeTable(attribute) => attribute.encode(&mut output)?,
+            Self::RuntimeVisibleAnnotations(attribute) => attribute.encode(&mut output)?,
+            Self::RuntimeInvisibleAnnotations(attribute) => attribute.encode(&mut output)?,
+            Self::RuntimeVisibleParameterAnnotations(attribute) => attribute.encode(&mut output)?,
+            Self::RuntimeInvisibleParameterAnnotations(attribute) => {
+                attribute.encode(&mut output)?
+            }
+            Self::RuntimeVisibleTypeAnnotations(attribute) => attribute.encode(&mut output)?,
+            Self::RuntimeInvisibleTypeAnnotations(attribute) => attribute.encode(&mut output)?,
+            Self::AnnotationDefault(attribute) => attribute.encode(&mut output)?,
+            Self::BootstrapMethods(attribute) => attribute.encode(&mut output)?,
+            Self::MethodParameters(attribute) => attribute.encode(&mut output)?,
+            Self::Module(attribute) => attribute.encode(&mut output)?,
+            Self::ModulePackages(attribute) => attribute.encode(&mut output)?,
+            Self::ModuleMainClass(attribute) => attribute.encode(&mut output)?,
+            Self::NestHost(attribute) => attribute.encode(&mut output)?,
+            Self::NestMembers(attribute) => attribute.encode(&mut output)?,
+            Self::Record(attribute) => attribute.encode(&mut output)?,
+            Self::PermittedSubclasses(attribute) => attribute.encode(&mut output)?,
+            Self::Custom(attribute) => return attribute.info(),
+            Self::Unknown { info, .. } => return Ok(info.clone()),
+        }
+        Ok(output.into_inner())
+    }
+
+    /// Encodes the attribute, reusing the `Utf8` entry of its name if the
+    /// constant pool already contains one, or appending it otherwise.
+    pub fn to_info(&self, constant_pool: &mut ConstantPool) -> Result<AttributeInfo> {
+        let name = self.name();
+        let existing_index = constant_pool
+            .iter()
+            .find(|(_, info)| matches!(info, ConstantPoolInfo::Utf8(value) if value == name))
+            .map(|(index, _)| index);
+        let attribute_name_index = match existing_index {
+            Some(index) => index,
+            None => constant_pool.push(ConstantPoolInfo::Utf8(name.into())),
+        };
+        Ok(AttributeInfo {
+            attribute_name_index,
+            info: self.info()?,
+        })
+    }
+}
+
+/// An attribute which is not specified by the JVMS.
+pub trait CustomAttribute: Any + fmt::Debug {
+    fn name(&self) -> &str;
+
+    /// Encodes the contents of the attribute, excluding name and length.
+    fn info(&self) -> Result<Vec<u8>>;
+}
+
+impl dyn CustomAttribute {
+    pub fn downcast_ref<T: CustomAttribute>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+}
+
+type CustomAttributeDecoder = Box<dyn Fn(&[u8], &ConstantPool) -> Result<Box<dyn CustomAttribute>>>;
+
+/// Decodes attributes by name, the standard attributes are always known, and
+/// decoders for custom attributes can be registered.
+#[derive(Default)]
+pub struct AttributeRegistry {
+    decoders: HashMap<String, CustomAttributeDecoder>,
+}
+
+impl AttributeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a decoder for the attribute with the given name, which takes
+    /// precedence over the decoding of standard attributes.
+    pub fn register<T: CustomAttribute>(
+        &mut self,
+        name: impl Into<String>,
+        decoder: impl Fn(&[u8], &ConstantPool) -> Result<T> + 'static,
+    ) -> &mut Self {
+        self.decoders.insert(
+            name.into(),
+            Box::new(move |info, constant_pool| {
+                Ok(Box::new(decoder(info, constant_pool)?) as Box<dyn CustomAttribute>)
+            }),
+        );
+        self
+    }
+
+    pub fn decode(
+        &self,
+        attribute: &AttributeInfo,
+        constant_pool: &ConstantPool,
+    ) -> Result<Attribute> {
+        let name = constant_pool.utf8(attribute.attribute_name_index)?;
+        if let Some(decoder) = self.decoders.get(name) {
+            return decoder(&attribute.info, constant_pool).map(Attribute::Custom);
+        }
+        Ok(
+            decode_standard(name, &attribute.info)?.unwrap_or_else(|| Attribute::Unknown {
+                name: name.to_owned(),
+                info: attribute.info.clone(),
+            }),
+        )
+    }
+}
+
+/// Decodes the body of a standard attribute, or returns `None` if `name` is not
+/// the name of a standard attribute.
+pub(crate) fn decode_standard(name: &str, info: &[u8]) -> Result<Option<Attribute>> {
+    let mut input = info;
+    let input = &mut input;
+    let decoded = match name {
+        "ConstantValue" => Attribute::ConstantValue(decode_nested(info, input)?),
+        "Code" => Attribute::Code(decode_nested(info, input)?),
+        "StackMapTable" => Attribute::StackMapTable(decode_nested(info, input)?),
+        "Exceptions" => Attribute::Exceptions(decode_nested(info, input)?),
+        "InnerClasses" => Attribute::InnerClasses(decode_nested(info, input)?),
+        "EnclosingMethod" => Attribute::EnclosingMethod(decode_nested(info, input)?),
+        "Synthetic" => Attribute::Synthetic,
+        "Signature" => Attribute::Signature(decode_nested(info, input)?),
+        "SourceFile" => Attribute::SourceFile(decode_nested(info, input)?),
+        "SourceDebugExtension" => Attribute::SourceDebugExtension(decode_nested(info, input)?),
+        "LineNumberTable" => Attribute::LineNumberTable(decode_nested(info, input)?),
+        "LocalVariableTable" => Attribute::LocalVariableTable(decode_nested(info, input)?),
+        "LocalVariableTypeTable" => Attribute::LocalVariableTypeTable(decode_nested(info, input)?),
+        "Deprecated" => Attribute::Deprecated,
+        "RuntimeVisibleAnnotations" => {
+            Attribute::RuntimeVisibleAnnotations(decode_nested(info, input)?)
+        }
+        "RuntimeInvisibleAnnotations" => {
+            Attribute::RuntimeInvisibleAnnotations(decode_nested(info, input)?)
+        }
+        "RuntimeVisibleParameterAnnotations" => {
+            Attribute::RuntimeVisibleParameterAnnotations(decode_nested(info, input)?)
+        }
+        "RuntimeInvisibleParameterAnnotations" => {
+            Attribute::RuntimeInvisibleParameterAnnotations(decode_nested(info, input)?)
+        }
+        "RuntimeVisibleTypeAnnotations" => {
+            Attribute::RuntimeVisibleTypeAnnotations(decode_nested(info, input)?)
+        }
+        "RuntimeInvisibleTypeAnnotations" => {
+            Attribute::RuntimeInvisibleTypeAnnotations(decode_nested(info, input)?)
+        }
+        "AnnotationDefault" => Attribute::AnnotationDefault(decode_nested(info, input)?),
+        "BootstrapMethods" => Attribute::BootstrapMethods(decode_nested(info, input)?),
+        "MethodParameters" => Attribute::MethodParameters(decode_nested(info, input)?),
+        "Module" => Attribute::Module(decode_nested(info, input)?),
+        "ModulePackages" => Attribute::ModulePackages(decode_nested(info, input)?),
+        "ModuleMainClass" => Attribute::ModuleMainClass(decode_nested(info, input)?),
+        "NestHost" => Attribute::NestHost(decode_nested(info, input)?),
+        "NestMembers" => Attribute::NestMembers(decode_nested(info, input)?),
+        "Record" => Attribute::Record(decode_nested(info, input)?),
+        "PermittedSubclasses" => Attribute::PermittedSubclasses(decode_nested(info, input)?),
+        _ => return Ok(None),
+    };
+    if !input.is_empty() {
+        return Err(Error::InvalidAttributeLength(name.to_owned()));
+    }
+    Ok(Some(decoded))
+}
+
+/// Decodes a table preceded by its `u16` length.
+pub(crate) fn decode_table<T: Decode>(begin: &[u8], input: &mut &[u8]) -> Result<Vec<T>> {
+    let length = u16::decode(input)?;
+    let mut table = Vec::with_capacity(length as usize);
+    for _ in 0..length {
+        table.push(decode_nested(begin, input)?);
+    }
+    Ok(table)
+}
+
+/// Encodes a table preceded by its `u16` length.
+pub(crate) fn encode_table<T: Encode>(table: &[T], output: &mut (impl Write + Seek)) -> Result<()> {
+    (table.len() as u16).encode(output)?;
+    for entry in table {
+        entry.encode(output)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct CodeAttribute {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: Vec<u8>,
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+impl Decode for CodeAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let max_stack = Decode::decode(input)?;
+        let max_locals = Decode::decode(input)?;
+        let code_length = u32::decode(input)?;
+        let code = split_off(input, code_length as usize)?.to_vec();
+        let exception_table_length = u16::decode(input)?;
+        let mut exception_table = Vec::with_capacity(exception_table_length as usize);
+        for _ in 0..exception_table_length {
+            exception_table.push(decode_nested(begin, input)?);
+        }
+        let attributes_count = u16::decode(input)?;
+        let mut attributes = Vec::with_capacity(attributes_count as usize);
+        for _ in 0..attributes_count {
+            attributes.push(decode_nested(begin, input)?);
+        }
+        Ok(Self {
+            max_stack,
+            max_locals,
+            code,
+            exception_table,
+            attributes,
+        })
+    }
+}
+
+impl CodeAttribute {
+    /// Decodes the instructions of the code together with their offsets.
+    pub fn instructions(&self) -> Result<Vec<(u32, Instruction)>> {
+        code::decode(&self.code)
+    }
+}
+
+impl Encode for CodeAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.max_stack.encode(output)?;
+        self.max_locals.encode(output)?;
+        (self.code.len() as u32).encode(output)?;
+        output.write_all(&self.code)?;
+        (self.exception_table.len() as u16).encode(output)?;
+        for exception in &self.exception_table {
+            exception.encode(output)?;
+        }
+        (self.attributes.len() as u16).encode(output)?;
+        for attribute in &self.attributes {
+            attribute.encode(output)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExceptionTableEntry {
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: u16,
+}
+
+impl Decode for ExceptionTableEntry {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let start_pc = Decode::decode(input)?;
+        let end_pc = Decode::decode(input)?;
+        let handler_pc = Decode::decode(input)?;
+        let catch_type = Decode::decode(input)?;
+        Ok(Self {
+            start_pc,
+            end_pc,
+            handler_pc,
+            catch_type,
+        })
+    }
+}
+
+impl Encode for ExceptionTableEntry {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.start_pc.encode(output)?;
+        self.end_pc.encode(output)?;
+        self.handler_pc.encode(output)?;
+        self.catch_type.encode(output)?;
+        Ok(())
+    }
+}
 
+#[derive(Debug)]
 pub struct MethodParametersAttribute(pub Vec<MethodParameter>);
 
 impl Decode for MethodParametersAttribute {
     fn decode(input: &mut &[u8]) -> Result<Self> {
-        let parameters_count = u16::decode(input)?;
+        let begin = *input;
+        let parameters_count = u8::decode(input)?;
         let mut parameters = Vec::with_capacity(parameters_count as usize);
         for _ in 0..parameters_count {
-            parameters.push(Decode::decode(input)?);
+            parameters.push(decode_nested(begin, input)?);
         }
         Ok(Self(parameters))
     }
 }
 
+impl Encode for MethodParametersAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        (self.0.len() as u8).encode(output)?;
+        for parameter in &self.0 {
+            parameter.encode(output)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
 pub struct MethodParameter {
     pub name_index: u16,
     pub access_flags: MethodParameterAccessFlags,
@@ -23,7 +425,7 @@ pub struct MethodParameter {
 impl Decode for MethodParameter {
     fn decode(input: &mut &[u8]) -> Result<Self> {
         let name_index = Decode::decode(input)?;
-        let access_flags = MethodParameterAccessFlags::from_bits(u16::decode(input)?).unwrap();
+        let access_flags = MethodParameterAccessFlags::from_bits_retain(Decode::decode(input)?);
         Ok(Self {
             name_index,
             access_flags,
@@ -31,6 +433,14 @@ impl Decode for MethodParameter {
     }
 }
 
+impl Encode for MethodParameter {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.name_index.encode(output)?;
+        self.access_flags.bits().encode(output)?;
+        Ok(())
+    }
+}
+
 bitflags! {
     #[derive(Debug)]
     pub struct MethodParameterAccessFlags: u16 {
@@ -39,3 +449,1073 @@ bitflags! {
         const MANDATED = 0x8000;
     }
 }
+
+#[derive(Debug)]
+pub struct StackMapTableAttribute(pub Vec<StackMapFrame>);
+
+impl Decode for StackMapTableAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let number_of_entries = u16::decode(input)?;
+        let mut entries = Vec::with_capacity(number_of_entries as usize);
+        for _ in 0..number_of_entries {
+            entries.push(decode_nested(begin, input)?);
+        }
+        Ok(Self(entries))
+    }
+}
+
+impl Encode for StackMapTableAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        (self.0.len() as u16).encode(output)?;
+        for entry in &self.0 {
+            entry.encode(output)?;
+        }
+        Ok(())
+    }
+}
+
+/// A stack map frame, the offset of which is given by the offset delta to
+/// the previous frame plus one, or the offset delta itself for the first
+/// frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StackMapFrame {
+    /// Same locals as the previous frame and an empty stack, offset delta is
+    /// less than 64.
+    Same {
+        offset_delta: u16,
+    },
+    /// Same locals as the previous frame and a single stack item, offset delta
+    /// is less than 64.
+    SameLocals1StackItem {
+        offset_delta: u16,
+        stack: VerificationTypeInfo,
+    },
+    SameLocals1StackItemExtended {
+        offset_delta: u16,
+        stack: VerificationTypeInfo,
+    },
+    /// The last `k` (1 to 3) locals of the previous frame are absent and the
+    /// stack is empty.
+    Chop {
+        k: u8,
+        offset_delta: u16,
+    },
+    SameExtended {
+        offset_delta: u16,
+    },
+    /// Up to three locals are appended to those of the previous frame and the
+    /// stack is empty.
+    Append {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+    },
+    Full {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+        stack: Vec<VerificationTypeInfo>,
+    },
+}
+
+impl StackMapFrame {
+    pub fn offset_delta(&self) -> u16 {
+        match self {
+            Self::Same { offset_delta }
+            | Self::SameLocals1StackItem { offset_delta, .. }
+            | Self::SameLocals1StackItemExtended { offset_delta, .. }
+            | Self::Chop { offset_delta, .. }
+            | Self::SameExtended { offset_delta }
+            | Self::Append { offset_delta, .. }
+            | Self::Full { offset_delta, .. } => *offset_delta,
+        }
+    }
+}
+
+impl Decode for StackMapFrame {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let frame_type = u8::decode(input)?;
+        Ok(match frame_type {
+            0..=63 => Self::Same {
+                offset_delta: frame_type as u16,
+            },
+            64..=127 => Self::SameLocals1StackItem {
+                offset_delta: frame_type as u16 - 64,
+                stack: decode_nested(begin, input)?,
+            },
+            247 => Self::SameLocals1StackItemExtended {
+                offset_delta: Decode::decode(input)?,
+                stack: decode_nested(begin, input)?,
+            },
+            248..=250 => Self::Chop {
+                k: 251 - frame_type,
+                offset_delta: Decode::decode(input)?,
+            },
+            251 => Self::SameExtended {
+                offset_delta: Decode::decode(input)?,
+            },
+            252..=254 => {
+                let offset_delta = Decode::decode(input)?;
+                let mut locals = Vec::with_capacity(frame_type as usize - 251);
+                for _ in 251..frame_type {
+                    locals.push(decode_nested(begin, input)?);
+                }
+                Self::Append {
+                    offset_delta,
+                    locals,
+                }
+            }
+            255 => {
+                let offset_delta = Decode::decode(input)?;
+                let number_of_locals = u16::decode(input)?;
+                let mut locals = Vec::with_capacity(number_of_locals as usize);
+                for _ in 0..number_of_locals {
+                    locals.push(decode_nested(begin, input)?);
+                }
+                let number_of_stack_items = u16::decode(input)?;
+                let mut stack = Vec::with_capacity(number_of_stack_items as usize);
+                for _ in 0..number_of_stack_items {
+                    stack.push(decode_nested(begin, input)?);
+                }
+                Self::Full {
+                    offset_delta,
+                    locals,
+                    stack,
+                }
+            }
+            _ => {
+                return Err(Error::UnknownFrameType {
+                    frame_type,
+                    offset: 0,
+                })
+            }
+        })
+    }
+}
+
+impl Encode for StackMapFrame {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        match self {
+            Self::Same { offset_delta } => {
+                if *offset_delta >= 64 {
+                    return Err(Error::InvalidFrame("offset delta of same frame exceeds 63"));
+                }
+                (*offset_delta as u8).encode(output)?
+            }
+            Self::SameLocals1StackItem {
+                offset_delta,
+                stack,
+            } => {
+                if *offset_delta >= 64 {
+                    return Err(Error::InvalidFrame(
+                        "offset delta of same locals 1 stack item frame exceeds 63",
+                    ));
+                }
+                (64 + *offset_delta as u8).encode(output)?;
+                stack.encode(output)?;
+            }
+            Self::SameLocals1StackItemExtended {
+                offset_delta,
+                stack,
+            } => {
+                247u8.encode(output)?;
+                offset_delta.encode(output)?;
+                stack.encode(output)?;
+            }
+            Self::Chop { k, offset_delta } => {
+                if !(1..=3).contains(k) {
+                    return Err(Error::InvalidFrame("chop frame removes 1 to 3 locals"));
+                }
+                (251 - k).encode(output)?;
+                offset_delta.encode(output)?;
+            }
+            Self::SameExtended { offset_delta } => {
+                251u8.encode(output)?;
+                offset_delta.encode(output)?;
+            }
+            Self::Append {
+                offset_delta,
+                locals,
+            } => {
+                if !(1..=3).contains(&locals.len()) {
+                    return Err(Error::InvalidFrame("append frame adds 1 to 3 locals"));
+                }
+                (251 + locals.len() as u8).encode(output)?;
+                offset_delta.encode(output)?;
+                for local in locals {
+                    local.encode(output)?;
+                }
+            }
+            Self::Full {
+                offset_delta,
+                locals,
+                stack,
+            } => {
+                255u8.encode(output)?;
+                offset_delta.encode(output)?;
+                (locals.len() as u16).encode(output)?;
+                for local in locals {
+                    local.encode(output)?;
+                }
+                (stack.len() as u16).encode(output)?;
+                for stack in stack {
+                    stack.encode(output)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationTypeInfo {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object { cpool_index: u16 },
+    Uninitialized { offset: u16 },
+}
+
+impl Decode for VerificationTypeInfo {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let tag = u8::decode(input)?;
+        Ok(match tag {
+            0 => Self::Top,
+            1 => Self::Integer,
+            2 => Self::Float,
+            3 => Self::Double,
+            4 => Self::Long,
+            5 => Self::Null,
+            6 => Self::UninitializedThis,
+            7 => Self::Object {
+                cpool_index: Decode::decode(input)?,
+            },
+            8 => Self::Uninitialized {
+                offset: Decode::decode(input)?,
+            },
+            _ => return Err(Error::UnknownVerificationType { tag, offset: 0 }),
+        })
+    }
+}
+
+impl Encode for VerificationTypeInfo {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        match self {
+            Self::Top => 0u8.encode(output)?,
+            Self::Integer => 1u8.encode(output)?,
+            Self::Float => 2u8.encode(output)?,
+            Self::Double => 3u8.encode(output)?,
+            Self::Long => 4u8.encode(output)?,
+            Self::Null => 5u8.encode(output)?,
+            Self::UninitializedThis => 6u8.encode(output)?,
+            Self::Object { cpool_index } => {
+                7u8.encode(output)?;
+                cpool_index.encode(output)?;
+            }
+            Self::Uninitialized { offset } => {
+                8u8.encode(output)?;
+                offset.encode(output)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ConstantValueAttribute {
+    pub constantvalue_index: u16,
+}
+
+impl Decode for ConstantValueAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            constantvalue_index: Decode::decode(input)?,
+        })
+    }
+}
+
+impl Encode for ConstantValueAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.constantvalue_index.encode(output)
+    }
+}
+
+/// The constant pool indices of the `Class` entries of the checked exceptions
+/// a method may throw.
+#[derive(Debug)]
+pub struct ExceptionsAttribute(pub Vec<u16>);
+
+impl Decode for ExceptionsAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for ExceptionsAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct InnerClassesAttribute(pub Vec<InnerClass>);
+
+impl Decode for InnerClassesAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for InnerClassesAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct InnerClass {
+    pub inner_class_info_index: u16,
+    /// 0 for top-level, local and anonymous classes.
+    pub outer_class_info_index: u16,
+    /// 0 for anonymous classes.
+    pub inner_name_index: u16,
+    pub inner_class_access_flags: InnerClassAccessFlags,
+}
+
+impl Decode for InnerClass {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let inner_class_info_index = Decode::decode(input)?;
+        let outer_class_info_index = Decode::decode(input)?;
+        let inner_name_index = Decode::decode(input)?;
+        let inner_class_access_flags =
+            InnerClassAccessFlags::from_bits_retain(Decode::decode(input)?);
+        Ok(Self {
+            inner_class_info_index,
+            outer_class_info_index,
+            inner_name_index,
+            inner_class_access_flags,
+        })
+    }
+}
+
+impl Encode for InnerClass {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.inner_class_info_index.encode(output)?;
+        self.outer_class_info_index.encode(output)?;
+        self.inner_name_index.encode(output)?;
+        self.inner_class_access_flags.bits().encode(output)?;
+        Ok(())
+    }
+}
+
+bitflags! {
+    #[derive(Debug)]
+    pub struct InnerClassAccessFlags: u16 {
+        const PUBLIC = 0x0001;
+        const PRIVATE = 0x0002;
+        const PROTECTED = 0x0004;
+        const STATIC = 0x0008;
+        const FINAL = 0x0010;
+        const INTERFACE = 0x0200;
+        const ABSTRACT = 0x0400;
+        const SYNTHETIC = 0x1000;
+        const ANNOTATION = 0x2000;
+        const ENUM = 0x4000;
+    }
+}
+
+#[derive(Debug)]
+pub struct EnclosingMethodAttribute {
+    pub class_index: u16,
+    /// 0 if the class is not enclosed by a method or constructor.
+    pub method_index: u16,
+}
+
+impl Decode for EnclosingMethodAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let class_index = Decode::decode(input)?;
+        let method_index = Decode::decode(input)?;
+        Ok(Self {
+            class_index,
+            method_index,
+        })
+    }
+}
+
+impl Encode for EnclosingMethodAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.class_index.encode(output)?;
+        self.method_index.encode(output)?;
+        Ok(())
+    }
+}
+
+/// The generic signature, which can be parsed with the types of
+/// [`crate::signature`].
+#[derive(Debug)]
+pub struct SignatureAttribute {
+    pub signature_index: u16,
+}
+
+impl Decode for SignatureAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            signature_index: Decode::decode(input)?,
+        })
+    }
+}
+
+impl Encode for SignatureAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.signature_index.encode(output)
+    }
+}
+
+#[derive(Debug)]
+pub struct SourceFileAttribute {
+    pub sourcefile_index: u16,
+}
+
+impl Decode for SourceFileAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            sourcefile_index: Decode::decode(input)?,
+        })
+    }
+}
+
+impl Encode for SourceFileAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.sourcefile_index.encode(output)
+    }
+}
+
+/// Extended debugging information, which has no meaning to the JVM and is
+/// kept as is.
+#[derive(Debug)]
+pub struct SourceDebugExtensionAttribute(pub Vec<u8>);
+
+impl Decode for SourceDebugExtensionAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let debug_extension = input.to_vec();
+        *input = &input[input.len()..];
+        Ok(Self(debug_extension))
+    }
+}
+
+impl Encode for SourceDebugExtensionAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        output.write_all(&self.0)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct LineNumberTableAttribute(pub Vec<LineNumber>);
+
+impl Decode for LineNumberTableAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for LineNumberTableAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct LineNumber {
+    pub start_pc: u16,
+    pub line_number: u16,
+}
+
+impl Decode for LineNumber {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let start_pc = Decode::decode(input)?;
+        let line_number = Decode::decode(input)?;
+        Ok(Self {
+            start_pc,
+            line_number,
+        })
+    }
+}
+
+impl Encode for LineNumber {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.start_pc.encode(output)?;
+        self.line_number.encode(output)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalVariableTableAttribute(pub Vec<LocalVariable>);
+
+impl Decode for LocalVariableTableAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for LocalVariableTableAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalVariable {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub index: u16,
+}
+
+impl Decode for LocalVariable {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let start_pc = Decode::decode(input)?;
+        let length = Decode::decode(input)?;
+        let name_index = Decode::decode(input)?;
+        let descriptor_index = Decode::decode(input)?;
+        let index = Decode::decode(input)?;
+        Ok(Self {
+            start_pc,
+            length,
+            name_index,
+            descriptor_index,
+            index,
+        })
+    }
+}
+
+impl Encode for LocalVariable {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.start_pc.encode(output)?;
+        self.length.encode(output)?;
+        self.name_index.encode(output)?;
+        self.descriptor_index.encode(output)?;
+        self.index.encode(output)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalVariableTypeTableAttribute(pub Vec<LocalVariableType>);
+
+impl Decode for LocalVariableTypeTableAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for LocalVariableTypeTableAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalVariableType {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub signature_index: u16,
+    pub index: u16,
+}
+
+impl Decode for LocalVariableType {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let start_pc = Decode::decode(input)?;
+        let length = Decode::decode(input)?;
+        let name_index = Decode::decode(input)?;
+        let signature_index = Decode::decode(input)?;
+        let index = Decode::decode(input)?;
+        Ok(Self {
+            start_pc,
+            length,
+            name_index,
+            signature_index,
+            index,
+        })
+    }
+}
+
+impl Encode for LocalVariableType {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.start_pc.encode(output)?;
+        self.length.encode(output)?;
+        self.name_index.encode(output)?;
+        self.signature_index.encode(output)?;
+        self.index.encode(output)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct AnnotationsAttribute(pub Vec<Annotation>);
+
+impl Decode for AnnotationsAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for AnnotationsAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+/// The annotations of each formal parameter of a method, which may be fewer
+/// than in the method descriptor as compilers can omit synthetic parameters.
+#[derive(Debug)]
+pub struct ParameterAnnotationsAttribute(pub Vec<Vec<Annotation>>);
+
+impl Decode for ParameterAnnotationsAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let num_parameters = u8::decode(input)?;
+        let mut parameter_annotations = Vec::with_capacity(num_parameters as usize);
+        for _ in 0..num_parameters {
+            parameter_annotations.push(decode_table(begin, input)?);
+        }
+        Ok(Self(parameter_annotations))
+    }
+}
+
+impl Encode for ParameterAnnotationsAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        (self.0.len() as u8).encode(output)?;
+        for annotations in &self.0 {
+            encode_table(annotations, output)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TypeAnnotationsAttribute(pub Vec<TypeAnnotation>);
+
+impl Decode for TypeAnnotationsAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for TypeAnnotationsAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+/// The default value of an annotation interface element.
+#[derive(Debug)]
+pub struct AnnotationDefaultAttribute(pub ElementValue);
+
+impl Decode for AnnotationDefaultAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Decode::decode(input).map(Self)
+    }
+}
+
+impl Encode for AnnotationDefaultAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.0.encode(output)
+    }
+}
+
+#[derive(Debug)]
+pub struct BootstrapMethodsAttribute(pub Vec<BootstrapMethod>);
+
+impl Decode for BootstrapMethodsAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for BootstrapMethodsAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct BootstrapMethod {
+    pub bootstrap_method_ref: u16,
+    pub bootstrap_arguments: Vec<u16>,
+}
+
+impl Decode for BootstrapMethod {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let bootstrap_method_ref = Decode::decode(input)?;
+        let bootstrap_arguments = decode_table(begin, input)?;
+        Ok(Self {
+            bootstrap_method_ref,
+            bootstrap_arguments,
+        })
+    }
+}
+
+impl Encode for BootstrapMethod {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.bootstrap_method_ref.encode(output)?;
+        encode_table(&self.bootstrap_arguments, output)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ModuleAttribute {
+    pub module_name_index: u16,
+    pub module_flags: ModuleFlags,
+    /// 0 if the version is unknown.
+    pub module_version_index: u16,
+    pub requires: Vec<Requires>,
+    pub exports: Vec<Exports>,
+    pub opens: Vec<Opens>,
+    pub uses_index: Vec<u16>,
+    pub provides: Vec<Provides>,
+}
+
+impl Decode for ModuleAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let module_name_index = Decode::decode(input)?;
+        let module_flags = ModuleFlags::from_bits_retain(Decode::decode(input)?);
+        let module_version_index = Decode::decode(input)?;
+        let requires = decode_table(begin, input)?;
+        let exports = decode_table(begin, input)?;
+        let opens = decode_table(begin, input)?;
+        let uses_index = decode_table(begin, input)?;
+        let provides = decode_table(begin, input)?;
+        Ok(Self {
+            module_name_index,
+            module_flags,
+            module_version_index,
+            requires,
+            exports,
+            opens,
+            uses_index,
+            provides,
+        })
+    }
+}
+
+impl Encode for ModuleAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.module_name_index.encode(output)?;
+        self.module_flags.bits().encode(output)?;
+        self.module_version_index.encode(output)?;
+        encode_table(&self.requires, output)?;
+        encode_table(&self.exports, output)?;
+        encode_table(&self.opens, output)?;
+        encode_table(&self.uses_index, output)?;
+        encode_table(&self.provides, output)?;
+        Ok(())
+    }
+}
+
+bitflags! {
+    #[derive(Debug)]
+    pub struct ModuleFlags: u16 {
+        const OPEN = 0x0020;
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+#[derive(Debug)]
+pub struct Requires {
+    pub requires_index: u16,
+    pub requires_flags: RequiresFlags,
+    /// 0 if the version is unknown.
+    pub requires_version_index: u16,
+}
+
+impl Decode for Requires {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let requires_index = Decode::decode(input)?;
+        let requires_flags = RequiresFlags::from_bits_retain(Decode::decode(input)?);
+        let requires_version_index = Decode::decode(input)?;
+        Ok(Self {
+            requires_index,
+            requires_flags,
+            requires_version_index,
+        })
+    }
+}
+
+impl Encode for Requires {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.requires_index.encode(output)?;
+        self.requires_flags.bits().encode(output)?;
+        self.requires_version_index.encode(output)?;
+        Ok(())
+    }
+}
+
+bitflags! {
+    #[derive(Debug)]
+    pub struct RequiresFlags: u16 {
+        const TRANSITIVE = 0x0020;
+        const STATIC_PHASE = 0x0040;
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+#[derive(Debug)]
+pub struct Exports {
+    pub exports_index: u16,
+    pub exports_flags: ExportsFlags,
+    /// The modules the package is exported to, empty if it is exported to all.
+    pub exports_to_index: Vec<u16>,
+}
+
+impl Decode for Exports {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let exports_index = Decode::decode(input)?;
+        let exports_flags = ExportsFlags::from_bits_retain(Decode::decode(input)?);
+        let exports_to_index = decode_table(begin, input)?;
+        Ok(Self {
+            exports_index,
+            exports_flags,
+            exports_to_index,
+        })
+    }
+}
+
+impl Encode for Exports {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.exports_index.encode(output)?;
+        self.exports_flags.bits().encode(output)?;
+        encode_table(&self.exports_to_index, output)?;
+        Ok(())
+    }
+}
+
+bitflags! {
+    #[derive(Debug)]
+    pub struct ExportsFlags: u16 {
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+#[derive(Debug)]
+pub struct Opens {
+    pub opens_index: u16,
+    pub opens_flags: OpensFlags,
+    /// The modules the package is opened to, empty if it is opened to all.
+    pub opens_to_index: Vec<u16>,
+}
+
+impl Decode for Opens {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let opens_index = Decode::decode(input)?;
+        let opens_flags = OpensFlags::from_bits_retain(Decode::decode(input)?);
+        let opens_to_index = decode_table(begin, input)?;
+        Ok(Self {
+            opens_index,
+            opens_flags,
+            opens_to_index,
+        })
+    }
+}
+
+impl Encode for Opens {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.opens_index.encode(output)?;
+        self.opens_flags.bits().encode(output)?;
+        encode_table(&self.opens_to_index, output)?;
+        Ok(())
+    }
+}
+
+bitflags! {
+    #[derive(Debug)]
+    pub struct OpensFlags: u16 {
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+#[derive(Debug)]
+pub struct Provides {
+    pub provides_index: u16,
+    pub provides_with_index: Vec<u16>,
+}
+
+impl Decode for Provides {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let provides_index = Decode::decode(input)?;
+        let provides_with_index = decode_table(begin, input)?;
+        Ok(Self {
+            provides_index,
+            provides_with_index,
+        })
+    }
+}
+
+impl Encode for Provides {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.provides_index.encode(output)?;
+        encode_table(&self.provides_with_index, output)?;
+        Ok(())
+    }
+}
+
+/// The constant pool indices of the `Package` entries of all packages of a
+/// module.
+#[derive(Debug)]
+pub struct ModulePackagesAttribute(pub Vec<u16>);
+
+impl Decode for ModulePackagesAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for ModulePackagesAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct ModuleMainClassAttribute {
+    pub main_class_index: u16,
+}
+
+impl Decode for ModuleMainClassAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            main_class_index: Decode::decode(input)?,
+        })
+    }
+}
+
+impl Encode for ModuleMainClassAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.main_class_index.encode(output)
+    }
+}
+
+#[derive(Debug)]
+pub struct NestHostAttribute {
+    pub host_class_index: u16,
+}
+
+impl Decode for NestHostAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            host_class_index: Decode::decode(input)?,
+        })
+    }
+}
+
+impl Encode for NestHostAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.host_class_index.encode(output)
+    }
+}
+
+#[derive(Debug)]
+pub struct NestMembersAttribute(pub Vec<u16>);
+
+impl Decode for NestMembersAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for NestMembersAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct RecordAttribute(pub Vec<RecordComponentInfo>);
+
+impl Decode for RecordAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for RecordAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
+
+#[derive(Debug)]
+pub struct RecordComponentInfo {
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+impl Decode for RecordComponentInfo {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        let name_index = Decode::decode(input)?;
+        let descriptor_index = Decode::decode(input)?;
+        let attributes = decode_table(begin, input)?;
+        Ok(Self {
+            name_index,
+            descriptor_index,
+            attributes,
+        })
+    }
+}
+
+impl Encode for RecordComponentInfo {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        self.name_index.encode(output)?;
+        self.descriptor_index.encode(output)?;
+        encode_table(&self.attributes, output)?;
+        Ok(())
+    }
+}
+
+/// The constant pool indices of the `Class` entries of the classes which may
+/// directly extend or implement a sealed class.
+#[derive(Debug)]
+pub struct PermittedSubclassesAttribute(pub Vec<u16>);
+
+impl Decode for PermittedSubclassesAttribute {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        let begin = *input;
+        decode_table(begin, input).map(Self)
+    }
+}
+
+impl Encode for PermittedSubclassesAttribute {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        encode_table(&self.0, output)
+    }
+}
@@ -0,0 +1,415 @@
+//! A borrowed representation of class files, which references the input for
+//! strings and attribute bodies instead of copying them, and only decodes
+//! attributes on request.
+//!
+//! This is meant for scanning many class files, where most of the attributes
+//! are never looked at. [`ClassFileRef::to_class_file`] converts to the owned
+//! representation when a class has to be modified.
+
+use std::borrow::Cow;
+
+use crate::{
+    class::{
+        attribute::{self, Attribute, CodeAttribute},
+        AttributeInfo, ClassAccessFlags, ClassFile, ConstantLookup, ConstantPool, ConstantPoolInfo,
+        FieldAccessFlags, FieldInfo, MethodAccessFlags, MethodInfo,
+    },
+    decode_nested, decode_with,
+    descriptor::{FieldType, MethodDescriptor},
+    mutf8, split_off, Decode, Error, Result,
+};
+
+#[derive(Debug)]
+pub struct ClassFileRef<'a> {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub constant_pool: ConstantPoolRef<'a>,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<FieldInfoRef<'a>>,
+    pub methods: Vec<MethodInfoRef<'a>>,
+    pub attributes: Vec<AttributeInfoRef<'a>>,
+}
+
+impl<'a> ClassFileRef<'a> {
+    pub fn decode(input: &mut &'a [u8]) -> Result<Self> {
+        let begin = *input;
+        decode_with(begin, input, |input| Self::decode_class(begin, input))
+    }
+
+    fn decode_class(begin: &'a [u8], input: &mut &'a [u8]) -> Result<Self> {
+        let magic = u32::decode(input)?;
+        if magic != 0xCAFEBABE {
+            return Err(Error::BadMagic(magic));
+        }
+        let minor_version = Decode::decode(input)?;
+        let major_version = Decode::decode(input)?;
+        let constant_pool = decode_with(begin, input, ConstantPoolRef::decode)?;
+        let access_flags = ClassAccessFlags::from_bits_retain(Decode::decode(input)?);
+        let this_class = Decode::decode(input)?;
+        let super_class = Decode::decode(input)?;
+        let interfaces_count = u16::decode(input)?;
+        let mut interfaces: Vec<u16> = Vec::with_capacity(interfaces_count as usize);
+        for _ in 0..interfaces_count {
+            interfaces.push(Decode::decode(input)?);
+        }
+
+        let fields_count = u16::decode(input)?;
+        let mut fields = Vec::with_capacity(fields_count as usize);
+        for _ in 0..fields_count {
+            fields.push(decode_with(begin, input, FieldInfoRef::decode)?);
+        }
+        let methods_count = u16::decode(input)?;
+        let mut methods = Vec::with_capacity(methods_count as usize);
+        for _ in 0..methods_count {
+            methods.push(decode_with(begin, input, MethodInfoRef::decode)?);
+        }
+        let attributes = decode_attributes(begin, input)?;
+        Ok(Self {
+            minor_version,
+            major_version,
+            constant_pool,
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+
+    /// Returns the internal name of the class, e.g. `java/lang/String`.
+    pub fn name(&self) -> Result<&str> {
+        self.constant_pool.class_name(self.this_class)
+    }
+
+    /// Returns the internal name of the super class, or `None` for
+    /// `java/lang/Object` and `module-info`.
+    pub fn super_name(&self) -> Result<Option<&str>> {
+        if self.super_class == 0 {
+            return Ok(None);
+        }
+        self.constant_pool.class_name(self.super_class).map(Some)
+    }
+
+    pub fn interface_names(&self) -> Result<Vec<&str>> {
+        self.interfaces
+            .iter()
+            .map(|interface| self.constant_pool.class_name(*interface))
+            .collect()
+    }
+
+    /// Returns the first attribute with the given name.
+    pub fn attribute(&self, name: &str) -> Result<Option<&AttributeInfoRef<'a>>> {
+        find_attribute(&self.attributes, &self.constant_pool, name)
+    }
+
+    /// Copies the class file into its owned representation.
+    pub fn to_class_file(&self) -> ClassFile {
+        ClassFile {
+            minor_version: self.minor_version,
+            major_version: self.major_version,
+            constant_pool: self.constant_pool.to_constant_pool(),
+            access_flags: self.access_flags,
+            this_class: self.this_class,
+            super_class: self.super_class,
+            interfaces: self.interfaces.clone(),
+            fields: self
+                .fields
+                .iter()
+                .map(FieldInfoRef::to_field_info)
+                .collect(),
+            methods: self
+                .methods
+                .iter()
+                .map(MethodInfoRef::to_method_info)
+                .collect(),
+            attributes: to_attribute_infos(&self.attributes),
+        }
+    }
+}
+
+/// The borrowed counterpart of [`ConstantPool`]. `Utf8` entries borrow from
+/// the input unless their modified UTF-8 encoding differs from UTF-8, and
+/// those with unpaired surrogates are kept as [`mutf8::JavaString`].
+#[derive(Debug, Clone, Default)]
+pub struct ConstantPoolRef<'a>(Vec<Option<ConstantPoolInfoRef<'a>>>);
+
+#[derive(Debug, Clone)]
+enum ConstantPoolInfoRef<'a> {
+    Utf8(Cow<'a, str>),
+    Other(ConstantPoolInfo),
+}
+
+impl<'a> ConstantPoolRef<'a> {
+    fn decode(input: &mut &'a [u8]) -> Result<Self> {
+        let begin = *input;
+        let constant_pool_count = u16::decode(input)?.saturating_sub(1);
+        let mut entries = Vec::with_capacity(constant_pool_count as usize);
+        while entries.len() < constant_pool_count as usize {
+            let offset = begin.len() - input.len();
+            let info = if input.first() == Some(&1) {
+                *input = &input[1..];
+                let bytes = u16::decode(input)
+                    .and_then(|length| split_off(input, length as usize))
+                    .map_err(|error| error.offset_by(offset))?;
+                let value =
+                    match mutf8::decode_wtf8(bytes).map_err(|error| error.offset_by(offset + 3))? {
+                        Cow::Borrowed(value) => std::str::from_utf8(value).ok().map(Cow::Borrowed),
+                        Cow::Owned(value) => String::from_utf8(value).ok().map(Cow::Owned),
+                    };
+                match value {
+                    Some(value) => ConstantPoolInfoRef::Utf8(value),
+                    None => ConstantPoolInfoRef::Other(ConstantPoolInfo::Utf8(mutf8::decode_java(
+                        bytes,
+                    )?)),
+                }
+            } else {
+                ConstantPoolInfoRef::Other(decode_nested(begin, input)?)
+            };
+            let wide = matches!(
+                info,
+                ConstantPoolInfoRef::Other(ConstantPoolInfo::Long(_) | ConstantPoolInfo::Double(_))
+            );
+            entries.push(Some(info));
+            if wide {
+                entries.push(None);
+            }
+        }
+        Ok(Self(entries))
+    }
+}
+
+impl ConstantPoolRef<'_> {
+    /// Returns the number of indices in use, including unusable ones.
+    pub fn len(&self) -> u16 {
+        self.0.len() as u16
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn try_get(&self, index: u16) -> Result<&ConstantPoolInfoRef<'_>> {
+        index
+            .checked_sub(1)
+            .and_then(|index| self.0.get(index as usize)?.as_ref())
+            .ok_or(Error::InvalidConstantPoolIndex(index))
+    }
+
+    pub fn utf8(&self, index: u16) -> Result<&str> {
+        match self.try_get(index)? {
+            ConstantPoolInfoRef::Utf8(value) => Ok(value),
+            ConstantPoolInfoRef::Other(ConstantPoolInfo::Utf8(_)) => {
+                Err(Error::UnpairedSurrogate(index))
+            }
+            ConstantPoolInfoRef::Other(_) => Err(Error::UnexpectedConstant {
+                index,
+                expected: "Utf8",
+            }),
+        }
+    }
+
+    /// Copies the constant pool into its owned representation.
+    pub fn to_constant_pool(&self) -> ConstantPool {
+        let mut constant_pool = ConstantPool::new();
+        for info in self.0.iter().flatten() {
+            constant_pool.push(match info {
+                ConstantPoolInfoRef::Utf8(value) => ConstantPoolInfo::Utf8(value.as_ref().into()),
+                ConstantPoolInfoRef::Other(info) => info.clone(),
+            });
+        }
+        constant_pool
+    }
+}
+
+impl ConstantLookup for ConstantPoolRef<'_> {
+    fn entry(&self, index: u16) -> Result<Option<&ConstantPoolInfo>> {
+        Ok(match self.try_get(index)? {
+            ConstantPoolInfoRef::Utf8(_) => None,
+            ConstantPoolInfoRef::Other(info) => Some(info),
+        })
+    }
+
+    fn utf8(&self, index: u16) -> Result<&str> {
+        ConstantPoolRef::utf8(self, index)
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldInfoRef<'a> {
+    pub access_flags: FieldAccessFlags,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<AttributeInfoRef<'a>>,
+}
+
+impl<'a> FieldInfoRef<'a> {
+    fn decode(input: &mut &'a [u8]) -> Result<Self> {
+        let begin = *input;
+        Ok(Self {
+            access_flags: FieldAccessFlags::from_bits_retain(Decode::decode(input)?),
+            name_index: Decode::decode(input)?,
+            descriptor_index: Decode::decode(input)?,
+            attributes: decode_attributes(begin, input)?,
+        })
+    }
+
+    pub fn name<'p>(&self, constant_pool: &'p ConstantPoolRef) -> Result<&'p str> {
+        constant_pool.utf8(self.name_index)
+    }
+
+    pub fn field_type(&self, constant_pool: &ConstantPoolRef) -> Result<FieldType> {
+        constant_pool.utf8(self.descriptor_index)?.parse()
+    }
+
+    /// Returns the first attribute with the given name.
+    pub fn attribute(
+        &self,
+        constant_pool: &ConstantPoolRef,
+        name: &str,
+    ) -> Result<Option<&AttributeInfoRef<'a>>> {
+        find_attribute(&self.attributes, constant_pool, name)
+    }
+
+    pub fn to_field_info(&self) -> FieldInfo {
+        FieldInfo {
+            access_flags: self.access_flags,
+            name_index: self.name_index,
+            descriptor_index: self.descriptor_index,
+            attributes: to_attribute_infos(&self.attributes),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MethodInfoRef<'a> {
+    pub access_flags: MethodAccessFlags,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<AttributeInfoRef<'a>>,
+}
+
+impl<'a> MethodInfoRef<'a> {
+    fn decode(input: &mut &'a [u8]) -> Result<Self> {
+        let begin = *input;
+        Ok(Self {
+            access_flags: MethodAccessFlags::from_bits_retain(Decode::decode(input)?),
+            name_index: Decode::decode(input)?,
+            descriptor_index: Decode::decode(input)?,
+            attributes: decode_attributes(begin, input)?,
+        })
+    }
+
+    pub fn name<'p>(&self, constant_pool: &'p ConstantPoolRef) -> Result<&'p str> {
+        constant_pool.utf8(self.name_index)
+    }
+
+    pub fn descriptor(&self, constant_pool: &ConstantPoolRef) -> Result<MethodDescriptor> {
+        constant_pool.utf8(self.descriptor_index)?.parse()
+    }
+
+    /// Returns the first attribute with the given name.
+    pub fn attribute(
+        &self,
+        constant_pool: &ConstantPoolRef,
+        name: &str,
+    ) -> Result<Option<&AttributeInfoRef<'a>>> {
+        find_attribute(&self.attributes, constant_pool, name)
+    }
+
+    /// Returns the decoded `Code` attribute, or `None` if the method has none.
+    pub fn code(&self, constant_pool: &ConstantPoolRef) -> Result<Option<CodeAttribute>> {
+        self.attribute(constant_pool, "Code")?
+            .map(AttributeInfoRef::decode)
+            .transpose()
+    }
+
+    pub fn to_method_info(&self) -> MethodInfo {
+        MethodInfo {
+            access_flags: self.access_flags,
+            name_index: self.name_index,
+            descriptor_index: self.descriptor_index,
+            attributes: to_attribute_infos(&self.attributes),
+        }
+    }
+}
+
+/// An attribute whose body has not been decoded yet.
+#[derive(Debug, Clone, Copy)]
+pub struct AttributeInfoRef<'a> {
+    pub attribute_name_index: u16,
+    pub info: &'a [u8],
+}
+
+impl<'a> AttributeInfoRef<'a> {
+    fn decode_info(input: &mut &'a [u8]) -> Result<Self> {
+        let attribute_name_index = Decode::decode(input)?;
+        let attribute_length = u32::decode(input)?;
+        Ok(Self {
+            attribute_name_index,
+            info: split_off(input, attribute_length as usize)?,
+        })
+    }
+
+    pub fn name<'p>(&self, constant_pool: &'p ConstantPoolRef) -> Result<&'p str> {
+        constant_pool.utf8(self.attribute_name_index)
+    }
+
+    /// Decodes the body as `T`, which is expected to be the type matching the
+    /// name of the attribute.
+    pub fn decode<T: Decode>(&self) -> Result<T> {
+        T::decode(&mut &*self.info)
+    }
+
+    /// Decodes the body as one of the standard attributes, or as
+    /// [`Attribute::Unknown`] otherwise.
+    pub fn attribute(&self, constant_pool: &ConstantPoolRef) -> Result<Attribute> {
+        let name = self.name(constant_pool)?;
+        Ok(
+            attribute::decode_standard(name, self.info)?.unwrap_or_else(|| Attribute::Unknown {
+                name: name.to_owned(),
+                info: self.info.to_vec(),
+            }),
+        )
+    }
+
+    pub fn to_attribute_info(&self) -> AttributeInfo {
+        AttributeInfo {
+            attribute_name_index: self.attribute_name_index,
+            info: self.info.to_vec(),
+        }
+    }
+}
+
+fn decode_attributes<'a>(begin: &[u8], input: &mut &'a [u8]) -> Result<Vec<AttributeInfoRef<'a>>> {
+    let attributes_count = u16::decode(input)?;
+    let mut attributes = Vec::with_capacity(attributes_count as usize);
+    for _ in 0..attributes_count {
+        attributes.push(decode_with(begin, input, AttributeInfoRef::decode_info)?);
+    }
+    Ok(attributes)
+}
+
+fn find_attribute<'r, 'a>(
+    attributes: &'r [AttributeInfoRef<'a>],
+    constant_pool: &ConstantPoolRef,
+    name: &str,
+) -> Result<Option<&'r AttributeInfoRef<'a>>> {
+    for attribute in attributes {
+        if attribute.name(constant_pool)? == name {
+            return Ok(Some(attribute));
+        }
+    }
+    Ok(None)
+}
+
+fn to_attribute_infos(attributes: &[AttributeInfoRef]) -> Vec<AttributeInfo> {
+    attributes
+        .iter()
+        .map(AttributeInfoRef::to_attribute_info)
+        .collect()
+}
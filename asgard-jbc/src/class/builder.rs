@@ -0,0 +1,503 @@
+use std::{collections::HashMap, io::Cursor};
+
+use crate::{
+    class::{
+        attribute::{Attribute, CodeAttribute},
+        AttributeInfo, ClassAccessFlags, ClassFile, ConstantPool, ConstantPoolInfo,
+        FieldAccessFlags, FieldInfo, MethodAccessFlags, MethodInfo, ReferenceKind,
+    },
+    code::{
+        builder::{AssembledCode, CodeBuilder},
+        frame::{self, ClassHierarchy},
+    },
+    mutf8, Encode, Error, Result,
+};
+
+/// Builds a class file, allocating its constant pool entries and computing
+/// `max_stack`, `max_locals` and the stack map frames of its methods.
+///
+/// Methods with unreachable code are rejected, see [`frame::analyze`].
+pub struct ClassBuilder {
+    minor_version: u16,
+    major_version: u16,
+    access_flags: ClassAccessFlags,
+    name: String,
+    super_class: Option<String>,
+    interfaces: Vec<String>,
+    fields: Vec<Field>,
+    methods: Vec<Method>,
+    attributes: Vec<(String, Vec<u8>)>,
+    hierarchy: Box<dyn ClassHierarchy>,
+    constant_pool: ConstantPoolBuilder,
+}
+
+impl ClassBuilder {
+    /// Starts a public class extending `java/lang/Object` with the version of
+    /// Java 8.
+    ///
+    /// Without a [`ClassHierarchy`], `java/lang/Object` is taken as the common
+    /// super class of any two classes when merging frames.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            minor_version: 0,
+            major_version: 52,
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            name: name.into(),
+            super_class: Some("java/lang/Object".to_owned()),
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+            hierarchy: Box::new(|_: &str, _: &str| "java/lang/Object".to_owned()),
+            constant_pool: ConstantPoolBuilder::new(),
+        }
+    }
+
+    pub fn version(&mut self, major_version: u16, minor_version: u16) -> &mut Self {
+        self.major_version = major_version;
+        self.minor_version = minor_version;
+        self
+    }
+
+    pub fn access_flags(&mut self, access_flags: ClassAccessFlags) -> &mut Self {
+        self.access_flags = access_flags;
+        self
+    }
+
+    /// Sets the super class, `None` only being valid for `java/lang/Object`
+    /// and `module-info`.
+    pub fn super_class(&mut self, name: Option<&str>) -> &mut Self {
+        self.super_class = name.map(str::to_owned);
+        self
+    }
+
+    pub fn interface(&mut self, name: impl Into<String>) -> &mut Self {
+        self.interfaces.push(name.into());
+        self
+    }
+
+    /// Returns the constant pool, to allocate the entries referenced by
+    /// attributes.
+    pub fn constant_pool(&mut self) -> &mut ConstantPoolBuilder {
+        &mut self.constant_pool
+    }
+
+    pub fn hierarchy(&mut self, hierarchy: impl ClassHierarchy + 'static) -> &mut Self {
+        self.hierarchy = Box::new(hierarchy);
+        self
+    }
+
+    /// Adds a field with `attributes`, such as its `ConstantValue`,
+    /// `Signature` or annotations, the constant pool entries of which are
+    /// allocated with [`Self::constant_pool`].
+    pub fn field(
+        &mut self,
+        access_flags: FieldAccessFlags,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+        attributes: &[Attribute],
+    ) -> Result<&mut Self> {
+        self.fields.push(Field {
+            access_flags,
+            name: name.into(),
+            descriptor: descriptor.into(),
+            attributes: encode_attributes(attributes)?,
+        });
+        Ok(self)
+    }
+
+    /// Adds a method, the code of which is assembled by `code`, which can
+    /// allocate the constant pool entries its instructions reference.
+    ///
+    /// `attributes` follow the `Code` attribute, such as `Exceptions`,
+    /// `Signature` or annotations.
+    pub fn method(
+        &mut self,
+        access_flags: MethodAccessFlags,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+        attributes: &[Attribute],
+        code: impl FnOnce(&mut CodeBuilder, &mut ConstantPoolBuilder) -> Result<()>,
+    ) -> Result<&mut Self> {
+        let mut code_builder = CodeBuilder::new();
+        code(&mut code_builder, &mut self.constant_pool)?;
+        self.methods.push(Method {
+            access_flags,
+            name: name.into(),
+            descriptor: descriptor.into(),
+            code: Some(code_builder.build()?),
+            attributes: encode_attributes(attributes)?,
+        });
+        Ok(self)
+    }
+
+    /// Adds a method without code, as is the case for abstract and native
+    /// methods.
+    pub fn declare_method(
+        &mut self,
+        access_flags: MethodAccessFlags,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+        attributes: &[Attribute],
+    ) -> Result<&mut Self> {
+        self.methods.push(Method {
+            access_flags,
+            name: name.into(),
+            descriptor: descriptor.into(),
+            code: None,
+            attributes: encode_attributes(attributes)?,
+        });
+        Ok(self)
+    }
+
+    /// Adds an attribute to the class.
+    pub fn attribute(&mut self, attribute: &Attribute) -> Result<&mut Self> {
+        self.attributes
+            .push((attribute.name().to_owned(), attribute.info()?));
+        Ok(self)
+    }
+
+    pub fn build(&self) -> Result<ClassFile> {
+        let mut constant_pool = self.constant_pool.clone();
+        let this_class = constant_pool.class(&self.name)?;
+        let super_class = match &self.super_class {
+            Some(super_class) => constant_pool.class(super_class)?,
+            None => 0,
+        };
+        let interfaces = self
+            .interfaces
+            .iter()
+            .map(|interface| constant_pool.class(interface))
+            .collect::<Result<_>>()?;
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| {
+                Ok(FieldInfo {
+                    access_flags: field.access_flags,
+                    name_index: constant_pool.utf8(&field.name)?,
+                    descriptor_index: constant_pool.utf8(&field.descriptor)?,
+                    attributes: attribute_infos(&mut constant_pool, &field.attributes)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let mut methods = Vec::with_capacity(self.methods.len());
+        for Method {
+            access_flags,
+            name,
+            descriptor,
+            code: assembled_code,
+            attributes,
+        } in &self.methods
+        {
+            let mut method = MethodInfo {
+                access_flags: *access_flags,
+                name_index: constant_pool.utf8(name)?,
+                descriptor_index: constant_pool.utf8(descriptor)?,
+                attributes: vec![],
+            };
+            if let Some(assembled_code) = assembled_code {
+                let mut code = CodeAttribute {
+                    max_stack: 0,
+                    max_locals: 0,
+                    code: assembled_code.code.clone(),
+                    exception_table: assembled_code.exception_table.clone(),
+                    attributes: vec![],
+                };
+                let analysis = frame::analyze(
+                    &self.name,
+                    &method,
+                    &code,
+                    constant_pool.constant_pool(),
+                    &*self.hierarchy,
+                )?;
+                code.max_stack = analysis.max_stack;
+                code.max_locals = analysis.max_locals;
+                // stack map frames were introduced with Java 6
+                if self.major_version >= 50 && !analysis.frames.is_empty() {
+                    let stack_map_table =
+                        analysis.stack_map_table(|name| constant_pool.class(name))?;
+                    code.attributes
+                        .push(constant_pool.attribute(&Attribute::StackMapTable(stack_map_table))?);
+                }
+                method
+                    .attributes
+                    .push(constant_pool.attribute(&Attribute::Code(code))?);
+            }
+            method
+                .attributes
+                .extend(attribute_infos(&mut constant_pool, attributes)?);
+            methods.push(method);
+        }
+        let attributes = attribute_infos(&mut constant_pool, &self.attributes)?;
+
+        Ok(ClassFile {
+            minor_version: self.minor_version,
+            major_version: self.major_version,
+            constant_pool: constant_pool.build(),
+            access_flags: self.access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+}
+
+struct Field {
+    access_flags: FieldAccessFlags,
+    name: String,
+    descriptor: String,
+    attributes: Vec<(String, Vec<u8>)>,
+}
+
+struct Method {
+    access_flags: MethodAccessFlags,
+    name: String,
+    descriptor: String,
+    code: Option<AssembledCode>,
+    attributes: Vec<(String, Vec<u8>)>,
+}
+
+/// Encodes attributes, the `Utf8` entries of their names are allocated when
+/// building.
+fn encode_attributes(attributes: &[Attribute]) -> Result<Vec<(String, Vec<u8>)>> {
+    attributes
+        .iter()
+        .map(|attribute| Ok((attribute.name().to_owned(), attribute.info()?)))
+        .collect()
+}
+
+fn attribute_infos(
+    constant_pool: &mut ConstantPoolBuilder,
+    attributes: &[(String, Vec<u8>)],
+) -> Result<Vec<AttributeInfo>> {
+    attributes
+        .iter()
+        .map(|(name, info)| {
+            Ok(AttributeInfo {
+                attribute_name_index: constant_pool.utf8(name)?,
+                info: info.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Allocates constant pool entries, reusing equal entries.
+///
+/// Entries are compared by their encoding, therefore floating-point constants
+/// are only reused if their bits are equal, distinguishing `0.0` from `-0.0`
+/// and keeping NaN payloads.
+#[derive(Debug, Clone, Default)]
+pub struct ConstantPoolBuilder {
+    constant_pool: ConstantPool,
+    indices: HashMap<Vec<u8>, u16>,
+}
+
+impl ConstantPoolBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn constant_pool(&self) -> &ConstantPool {
+        &self.constant_pool
+    }
+
+    pub fn build(self) -> ConstantPool {
+        self.constant_pool
+    }
+
+    /// Returns the index of an entry equal to `info`, appending it if there
+    /// is none.
+    pub fn push(&mut self, info: ConstantPoolInfo) -> Result<u16> {
+        let key = key(&info)?;
+        if let Some(index) = self.indices.get(&key) {
+            return Ok(*index);
+        }
+
+        // constant_pool_count is a u16 and one more than the number of used
+        // indices, Long and Double entries take up two of them
+        let length = match info {
+            ConstantPoolInfo::Long(_) | ConstantPoolInfo::Double(_) => 2,
+            _ => 1,
+        };
+        if self.constant_pool.len() as u32 + length > u16::MAX as u32 - 1 {
+            return Err(Error::ConstantPoolTooLarge);
+        }
+        let index = self.constant_pool.push(info);
+        self.indices.insert(key, index);
+        Ok(index)
+    }
+
+    /// Allocates a `Utf8` entry, failing if its modified UTF-8 encoding
+    /// exceeds 65535 bytes.
+    pub fn utf8(&mut self, value: &str) -> Result<u16> {
+        let length = mutf8::encode(value).len();
+        if length > u16::MAX as usize {
+            return Err(Error::Utf8TooLong(length));
+        }
+        self.push(ConstantPoolInfo::Utf8(value.into()))
+    }
+
+    pub fn integer(&mut self, value: i32) -> Result<u16> {
+        self.push(ConstantPoolInfo::Integer(value))
+    }
+
+    pub fn float(&mut self, value: f32) -> Result<u16> {
+        self.push(ConstantPoolInfo::Float(value))
+    }
+
+    pub fn long(&mut self, value: i64) -> Result<u16> {
+        self.push(ConstantPoolInfo::Long(value))
+    }
+
+    pub fn double(&mut self, value: f64) -> Result<u16> {
+        self.push(ConstantPoolInfo::Double(value))
+    }
+
+    /// Allocates a `Class` entry, `name` being an internal name or an array
+    /// descriptor.
+    pub fn class(&mut self, name: &str) -> Result<u16> {
+        let name_index = self.utf8(name)?;
+        self.push(ConstantPoolInfo::Class { name_index })
+    }
+
+    pub fn string(&mut self, value: &str) -> Result<u16> {
+        let string_index = self.utf8(value)?;
+        self.push(ConstantPoolInfo::String { string_index })
+    }
+
+    pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> Result<u16> {
+        let name_index = self.utf8(name)?;
+        let descriptor_index = self.utf8(descriptor)?;
+        self.push(ConstantPoolInfo::NameAndType {
+            name_index,
+            descriptor_index,
+        })
+    }
+
+    pub fn field_ref(&mut self, owner: &str, name: &str, descriptor: &str) -> Result<u16> {
+        let class_index = self.class(owner)?;
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        self.push(ConstantPoolInfo::Fieldref {
+            class_index,
+            name_and_type_index,
+        })
+    }
+
+    pub fn method_ref(&mut self, owner: &str, name: &str, descriptor: &str) -> Result<u16> {
+        let class_index = self.class(owner)?;
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        self.push(ConstantPoolInfo::Methodref {
+            class_index,
+            name_and_type_index,
+        })
+    }
+
+    pub fn interface_method_ref(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<u16> {
+        let class_index = self.class(owner)?;
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        self.push(ConstantPoolInfo::InterfaceMethodref {
+            class_index,
+            name_and_type_index,
+        })
+    }
+
+    /// Allocates a `MethodHandle` entry, `reference_index` being the index of
+    /// a `Fieldref`, `Methodref` or `InterfaceMethodref` entry as required by
+    /// the kind.
+    pub fn method_handle(
+        &mut self,
+        reference_kind: ReferenceKind,
+        reference_index: u16,
+    ) -> Result<u16> {
+        self.push(ConstantPoolInfo::MethodHandle {
+            reference_kind: reference_kind as u8,
+            reference_index,
+        })
+    }
+
+    pub fn method_type(&mut self, descriptor: &str) -> Result<u16> {
+        let descriptor_index = self.utf8(descriptor)?;
+        self.push(ConstantPoolInfo::MethodType { descriptor_index })
+    }
+
+    /// Allocates a `Dynamic` entry, `bootstrap_method_attr_index` being the
+    /// index into the `BootstrapMethods` attribute.
+    pub fn dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<u16> {
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        self.push(ConstantPoolInfo::Dynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        })
+    }
+
+    /// Allocates an `InvokeDynamic` entry, `bootstrap_method_attr_index`
+    /// being the index into the `BootstrapMethods` attribute.
+    pub fn invoke_dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<u16> {
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        self.push(ConstantPoolInfo::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        })
+    }
+
+    pub fn module(&mut self, name: &str) -> Result<u16> {
+        let name_index = self.utf8(name)?;
+        self.push(ConstantPoolInfo::Module { name_index })
+    }
+
+    pub fn package(&mut self, name: &str) -> Result<u16> {
+        let name_index = self.utf8(name)?;
+        self.push(ConstantPoolInfo::Package { name_index })
+    }
+
+    /// Encodes an attribute, allocating the `Utf8` entry of its name.
+    pub fn attribute(&mut self, attribute: &Attribute) -> Result<AttributeInfo> {
+        Ok(AttributeInfo {
+            attribute_name_index: self.utf8(attribute.name())?,
+            info: attribute.info()?,
+        })
+    }
+}
+
+/// Seeds the builder with the entries of an existing constant pool, which
+/// keep their indices, new entries are appended.
+impl TryFrom<ConstantPool> for ConstantPoolBuilder {
+    type Error = Error;
+
+    fn try_from(constant_pool: ConstantPool) -> Result<Self> {
+        let mut indices = HashMap::new();
+        for (index, info) in constant_pool.iter() {
+            // the first of duplicate entries is reused
+            indices.entry(key(info)?).or_insert(index);
+        }
+        Ok(Self {
+            constant_pool,
+            indices,
+        })
+    }
+}
+
+fn key(info: &ConstantPoolInfo) -> Result<Vec<u8>> {
+    let mut output = Cursor::new(Vec::new());
+    info.encode(&mut output)?;
+    Ok(output.into_inner())
+}
@@ -0,0 +1,190 @@
+use crate::{
+    class::{
+        attribute::{
+            ExportsFlags, ModuleAttribute, ModuleFlags, ModuleMainClassAttribute,
+            ModulePackagesAttribute, OpensFlags, RequiresFlags,
+        },
+        ClassFile, ConstantLookup, ConstantPool,
+    },
+    Decode, Error, Result,
+};
+
+/// The declaration of a module as given by a `module-info.class`, with all
+/// constant pool references resolved.
+///
+/// Package and class names are in internal form, e.g. `java/lang` and
+/// `java/lang/Object`.
+#[derive(Debug)]
+pub struct ModuleDescriptor {
+    pub name: String,
+    pub flags: ModuleFlags,
+    pub version: Option<String>,
+    pub requires: Vec<ModuleRequires>,
+    pub exports: Vec<ModuleExports>,
+    pub opens: Vec<ModuleOpens>,
+    pub uses: Vec<String>,
+    pub provides: Vec<ModuleProvides>,
+    /// All packages of the module, empty if the `ModulePackages` attribute is
+    /// absent.
+    pub packages: Vec<String>,
+    pub main_class: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ModuleRequires {
+    pub name: String,
+    pub flags: RequiresFlags,
+    pub version: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ModuleExports {
+    pub package: String,
+    pub flags: ExportsFlags,
+    /// The modules the package is exported to, empty if it is exported to all.
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ModuleOpens {
+    pub package: String,
+    pub flags: OpensFlags,
+    /// The modules the package is opened to, empty if it is opened to all.
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ModuleProvides {
+    pub service: String,
+    pub providers: Vec<String>,
+}
+
+impl ModuleDescriptor {
+    pub fn from_class_file(class_file: &ClassFile) -> Result<Self> {
+        let constant_pool = &class_file.constant_pool;
+        let mut module = None;
+        let mut packages = vec![];
+        let mut main_class = None;
+        for attribute in &class_file.attributes {
+            let mut info = attribute.info.as_slice();
+            match constant_pool.utf8(attribute.attribute_name_index)? {
+                "Module" => module = Some(ModuleAttribute::decode(&mut info)?),
+                "ModulePackages" => {
+                    packages = ModulePackagesAttribute::decode(&mut info)?
+                        .0
+                        .iter()
+                        .map(|index| constant_pool.package_name(*index).map(str::to_owned))
+                        .collect::<Result<_>>()?
+                }
+                "ModuleMainClass" => {
+                    let index = ModuleMainClassAttribute::decode(&mut info)?.main_class_index;
+                    main_class = Some(constant_pool.class_name(index)?.to_owned())
+                }
+                _ => {}
+            }
+        }
+        let module = module.ok_or(Error::MissingAttribute("Module"))?;
+
+        let module_names = |indices: &[u16]| {
+            indices
+                .iter()
+                .map(|index| constant_pool.module_name(*index).map(str::to_owned))
+                .collect::<Result<Vec<_>>>()
+        };
+        let class_names = |indices: &[u16]| {
+            indices
+                .iter()
+                .map(|index| constant_pool.class_name(*index).map(str::to_owned))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            name: constant_pool
+                .module_name(module.module_name_index)?
+                .to_owned(),
+            flags: module.module_flags,
+            version: version(constant_pool, module.module_version_index)?,
+            requires: module
+                .requires
+                .into_iter()
+                .map(|requires| {
+                    Ok(ModuleRequires {
+                        name: constant_pool
+                            .module_name(requires.requires_index)?
+                            .to_owned(),
+                        flags: requires.requires_flags,
+                        version: version(constant_pool, requires.requires_version_index)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            exports: module
+                .exports
+                .into_iter()
+                .map(|exports| {
+                    Ok(ModuleExports {
+                        package: constant_pool
+                            .package_name(exports.exports_index)?
+                            .to_owned(),
+                        flags: exports.exports_flags,
+                        targets: module_names(&exports.exports_to_index)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            opens: module
+                .opens
+                .into_iter()
+                .map(|opens| {
+                    Ok(ModuleOpens {
+                        package: constant_pool.package_name(opens.opens_index)?.to_owned(),
+                        flags: opens.opens_flags,
+                        targets: module_names(&opens.opens_to_index)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            uses: class_names(&module.uses_index)?,
+            provides: module
+                .provides
+                .into_iter()
+                .map(|provides| {
+                    Ok(ModuleProvides {
+                        service: constant_pool
+                            .class_name(provides.provides_index)?
+                            .to_owned(),
+                        providers: class_names(&provides.provides_with_index)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            packages,
+            main_class,
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.flags.contains(ModuleFlags::OPEN)
+    }
+
+    /// Returns whether the package is exported to all modules.
+    pub fn is_exported(&self, package: &str) -> bool {
+        self.exports
+            .iter()
+            .any(|exports| exports.package == package && exports.targets.is_empty())
+    }
+
+    /// Returns whether the package is exported to the given module, either
+    /// unqualified or qualified.
+    pub fn is_exported_to(&self, package: &str, module: &str) -> bool {
+        self.exports.iter().any(|exports| {
+            exports.package == package
+                && (exports.targets.is_empty()
+                    || exports.targets.iter().any(|target| target == module))
+        })
+    }
+}
+
+fn version(constant_pool: &ConstantPool, index: u16) -> Result<Option<String>> {
+    if index == 0 {
+        return Ok(None);
+    }
+    constant_pool
+        .utf8(index)
+        .map(|version| Some(version.to_owned()))
+}
@@ -0,0 +1,1023 @@
+//! Streaming access to class files, for transformations which only touch a
+//! few parts of a class.
+//!
+//! A [`ClassReader`] drives a [`ClassVisitor`], which is usually a chain of
+//! adapters ending in a [`ClassWriter`]. All visitors of a chain share the
+//! constant pool of the read class, which is passed along with every call: the
+//! constant pool indices of the read class stay valid, and adapters can add
+//! the entries they reference. Calls which are not implemented by a visitor
+//! are forwarded to its delegate, if any.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    class::{
+        attribute::{
+            Attribute, CodeAttribute, LineNumber, LineNumberTableAttribute, LocalVariable,
+            LocalVariableTableAttribute, LocalVariableType, LocalVariableTypeTableAttribute,
+        },
+        borrowed::{AttributeInfoRef, ClassFileRef, MethodInfoRef},
+        builder::ConstantPoolBuilder,
+        AttributeInfo, ClassAccessFlags, ClassFile, ConstantLookup, FieldAccessFlags, FieldInfo,
+        MethodAccessFlags, MethodInfo,
+    },
+    code::{
+        builder::{CodeBuilder, Jump, Label},
+        frame::{self, ClassHierarchy},
+        Instruction,
+    },
+    Decode, Error, Result,
+};
+
+/// The part of a class file preceding its fields.
+#[derive(Debug, Clone)]
+pub struct ClassHeader {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+}
+
+/// Visits a class in the order of the class file: [`visit`](Self::visit),
+/// the fields, the methods, the attributes and [`visit_end`](Self::visit_end).
+pub trait ClassVisitor {
+    /// Returns the visitor calls are forwarded to by default.
+    fn delegate(&mut self) -> Option<&mut dyn ClassVisitor> {
+        None
+    }
+
+    fn visit(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        header: ClassHeader,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit(constant_pool, header),
+            None => Ok(()),
+        }
+    }
+
+    /// Visits a field, returning the visitor of its attributes or `None` to
+    /// drop it.
+    fn visit_field(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        access_flags: FieldAccessFlags,
+        name_index: u16,
+        descriptor_index: u16,
+    ) -> Result<Option<Box<dyn FieldVisitor + '_>>> {
+        match self.delegate() {
+            Some(delegate) => {
+                delegate.visit_field(constant_pool, access_flags, name_index, descriptor_index)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Visits a method, returning the visitor of its attributes and code or
+    /// `None` to drop it.
+    fn visit_method(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        access_flags: MethodAccessFlags,
+        name_index: u16,
+        descriptor_index: u16,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        match self.delegate() {
+            Some(delegate) => {
+                delegate.visit_method(constant_pool, access_flags, name_index, descriptor_index)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn visit_attribute(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        attribute: AttributeInfo,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_attribute(constant_pool, attribute),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_end(&mut self, constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_end(constant_pool),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Visits the attributes of a field.
+pub trait FieldVisitor {
+    /// Returns the visitor calls are forwarded to by default.
+    fn delegate(&mut self) -> Option<&mut dyn FieldVisitor> {
+        None
+    }
+
+    fn visit_attribute(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        attribute: AttributeInfo,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_attribute(constant_pool, attribute),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_end(&mut self, constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_end(constant_pool),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Visits the attributes and code of a method.
+///
+/// The `Code` attribute is visited as its parts, starting with
+/// [`visit_code`](Self::visit_code): the instructions with the labels placed
+/// between them, followed by the exception handlers, local variables and the
+/// remaining attributes of the code. Branches reference labels instead of
+/// offsets, therefore instructions can be added and removed freely. The
+/// `StackMapTable` attribute is not visited, it is recomputed by the
+/// [`ClassWriter`].
+pub trait MethodVisitor {
+    /// Returns the visitor calls are forwarded to by default.
+    fn delegate(&mut self) -> Option<&mut dyn MethodVisitor> {
+        None
+    }
+
+    /// Offers the attributes of the method to be taken as is, returning
+    /// whether they have been, in which case only [`visit_end`](Self::visit_end)
+    /// follows.
+    ///
+    /// This is not forwarded to the delegate, as that would bypass the
+    /// visitor, therefore methods are only copied if they are not adapted.
+    fn visit_unchanged(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        _attributes: &[AttributeInfo],
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn visit_attribute(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        attribute: AttributeInfo,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_attribute(constant_pool, attribute),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts the code of the method, at the position of the `Code`
+    /// attribute among the attributes of the method.
+    fn visit_code(&mut self, constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_code(constant_pool),
+            None => Ok(()),
+        }
+    }
+
+    /// Allocates a label, which is done by the end of the chain. Without a
+    /// delegate, the label comes from a [`CodeBuilder`] of its own, which
+    /// suits visitors that do not write code.
+    fn new_label(&mut self) -> Label {
+        match self.delegate() {
+            Some(delegate) => delegate.new_label(),
+            None => CodeBuilder::new().new_label(),
+        }
+    }
+
+    fn visit_label(&mut self, constant_pool: &mut ConstantPoolBuilder, label: Label) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_label(constant_pool, label),
+            None => Ok(()),
+        }
+    }
+
+    /// Visits an instruction which is not a jump or switch, as these are
+    /// visited with [`visit_jump`](Self::visit_jump),
+    /// [`visit_tableswitch`](Self::visit_tableswitch) and
+    /// [`visit_lookupswitch`](Self::visit_lookupswitch).
+    fn visit_instruction(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        instruction: Instruction,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_instruction(constant_pool, instruction),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_jump(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        jump: Jump,
+        target: Label,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_jump(constant_pool, jump, target),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_tableswitch(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        default: Label,
+        low: i32,
+        targets: Vec<Label>,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_tableswitch(constant_pool, default, low, targets),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_lookupswitch(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        default: Label,
+        pairs: Vec<(i32, Label)>,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_lookupswitch(constant_pool, default, pairs),
+            None => Ok(()),
+        }
+    }
+
+    /// Visits an exception handler for the code from `start` (inclusive) to
+    /// `end` (exclusive), `catch_type` being the constant pool index of the
+    /// caught class or 0 for any.
+    fn visit_exception_handler(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_type: u16,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => {
+                delegate.visit_exception_handler(constant_pool, start, end, handler, catch_type)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn visit_line_number(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        line_number: u16,
+        start: Label,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_line_number(constant_pool, line_number, start),
+            None => Ok(()),
+        }
+    }
+
+    /// Visits an entry of the `LocalVariableTable` attribute.
+    fn visit_local_variable(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        start: Label,
+        end: Label,
+        name_index: u16,
+        descriptor_index: u16,
+        index: u16,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_local_variable(
+                constant_pool,
+                start,
+                end,
+                name_index,
+                descriptor_index,
+                index,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Visits an entry of the `LocalVariableTypeTable` attribute.
+    fn visit_local_variable_type(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        start: Label,
+        end: Label,
+        name_index: u16,
+        signature_index: u16,
+        index: u16,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_local_variable_type(
+                constant_pool,
+                start,
+                end,
+                name_index,
+                signature_index,
+                index,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Visits any other attribute of the code. Offsets within the attribute
+    /// are not adjusted if the code changes.
+    fn visit_code_attribute(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        attribute: AttributeInfo,
+    ) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_code_attribute(constant_pool, attribute),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_end(&mut self, constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        match self.delegate() {
+            Some(delegate) => delegate.visit_end(constant_pool),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads a class file and drives visitors with it.
+pub struct ClassReader<'a> {
+    class_file: ClassFileRef<'a>,
+}
+
+impl<'a> ClassReader<'a> {
+    pub fn new(mut input: &'a [u8]) -> Result<Self> {
+        Ok(Self {
+            class_file: ClassFileRef::decode(&mut input)?,
+        })
+    }
+
+    pub fn class_file(&self) -> &ClassFileRef<'a> {
+        &self.class_file
+    }
+
+    /// Visits the class, starting with its constant pool in the shared
+    /// constant pool.
+    pub fn accept(&self, visitor: &mut dyn ClassVisitor) -> Result<()> {
+        let class_file = &self.class_file;
+        let constant_pool =
+            &mut ConstantPoolBuilder::try_from(class_file.constant_pool.to_constant_pool())?;
+        visitor.visit(
+            constant_pool,
+            ClassHeader {
+                minor_version: class_file.minor_version,
+                major_version: class_file.major_version,
+                access_flags: class_file.access_flags,
+                this_class: class_file.this_class,
+                super_class: class_file.super_class,
+                interfaces: class_file.interfaces.clone(),
+            },
+        )?;
+        for field in &class_file.fields {
+            if let Some(mut field_visitor) = visitor.visit_field(
+                constant_pool,
+                field.access_flags,
+                field.name_index,
+                field.descriptor_index,
+            )? {
+                for attribute in &field.attributes {
+                    field_visitor.visit_attribute(constant_pool, attribute.to_attribute_info())?;
+                }
+                field_visitor.visit_end(constant_pool)?;
+            }
+        }
+        for method in &class_file.methods {
+            if let Some(mut method_visitor) = visitor.visit_method(
+                constant_pool,
+                method.access_flags,
+                method.name_index,
+                method.descriptor_index,
+            )? {
+                self.accept_method(method, &mut *method_visitor, constant_pool)?;
+            }
+        }
+        for attribute in &class_file.attributes {
+            visitor.visit_attribute(constant_pool, attribute.to_attribute_info())?;
+        }
+        visitor.visit_end(constant_pool)
+    }
+
+    fn accept_method(
+        &self,
+        method: &MethodInfoRef,
+        visitor: &mut dyn MethodVisitor,
+        constant_pool: &mut ConstantPoolBuilder,
+    ) -> Result<()> {
+        let attributes = method
+            .attributes
+            .iter()
+            .map(AttributeInfoRef::to_attribute_info)
+            .collect::<Vec<_>>();
+        if !visitor.visit_unchanged(constant_pool, &attributes)? {
+            for (attribute, info) in method.attributes.iter().zip(attributes) {
+                if attribute.name(&self.class_file.constant_pool)? == "Code" {
+                    self.accept_code(attribute.decode()?, visitor, constant_pool)?;
+                } else {
+                    visitor.visit_attribute(constant_pool, info)?;
+                }
+            }
+        }
+        visitor.visit_end(constant_pool)
+    }
+
+    fn accept_code(
+        &self,
+        mut code: CodeAttribute,
+        visitor: &mut dyn MethodVisitor,
+        constant_pool: &mut ConstantPoolBuilder,
+    ) -> Result<()> {
+        let instructions = code.instructions()?;
+        let mut line_numbers = vec![];
+        let mut local_variables = vec![];
+        let mut local_variable_types = vec![];
+        let mut attributes = vec![];
+        for attribute in std::mem::take(&mut code.attributes) {
+            let input = &mut attribute.info.as_slice();
+            match self
+                .class_file
+                .constant_pool
+                .utf8(attribute.attribute_name_index)?
+            {
+                "LineNumberTable" => {
+                    line_numbers.extend(LineNumberTableAttribute::decode(input)?.0);
+                }
+                "LocalVariableTable" => {
+                    local_variables.extend(LocalVariableTableAttribute::decode(input)?.0);
+                }
+                "LocalVariableTypeTable" => {
+                    local_variable_types.extend(LocalVariableTypeTableAttribute::decode(input)?.0);
+                }
+                // recomputed by the writer
+                "StackMapTable" => {}
+                _ => attributes.push(attribute),
+            }
+        }
+
+        // every offset referenced by the code or its attributes gets a label
+        let mut offsets = BTreeSet::new();
+        let target = |offset: u32, branch: i32| offset.wrapping_add_signed(branch);
+        for (offset, instruction) in &instructions {
+            if let Some((_, branch)) = Jump::from_instruction(instruction) {
+                offsets.insert(target(*offset, branch));
+            }
+            match instruction {
+                Instruction::Tableswitch {
+                    default,
+                    offsets: branches,
+                    ..
+                } => {
+                    offsets.insert(target(*offset, *default));
+                    offsets.extend(branches.iter().map(|branch| target(*offset, *branch)));
+                }
+                Instruction::Lookupswitch { default, pairs } => {
+                    offsets.insert(target(*offset, *default));
+                    offsets.extend(pairs.iter().map(|(_, branch)| target(*offset, *branch)));
+                }
+                _ => {}
+            }
+        }
+        for entry in &code.exception_table {
+            offsets.extend([entry.start_pc, entry.end_pc, entry.handler_pc].map(u32::from));
+        }
+        offsets.extend(
+            line_numbers
+                .iter()
+                .map(|line_number| line_number.start_pc as u32),
+        );
+        for (start_pc, length) in local_variables
+            .iter()
+            .map(|local_variable| (local_variable.start_pc, local_variable.length))
+            .chain(
+                local_variable_types
+                    .iter()
+                    .map(|local_variable| (local_variable.start_pc, local_variable.length)),
+            )
+        {
+            offsets.extend([start_pc as u32, start_pc as u32 + length as u32]);
+        }
+
+        visitor.visit_code(constant_pool)?;
+        let labels = offsets
+            .into_iter()
+            .map(|offset| (offset, visitor.new_label()))
+            .collect::<BTreeMap<_, _>>();
+        let mut line_numbers_by_offset = BTreeMap::<u32, Vec<u16>>::new();
+        for line_number in &line_numbers {
+            line_numbers_by_offset
+                .entry(line_number.start_pc as u32)
+                .or_default()
+                .push(line_number.line_number);
+        }
+        for (offset, instruction) in instructions {
+            if let Some(label) = labels.get(&offset) {
+                visitor.visit_label(constant_pool, *label)?;
+                for line_number in line_numbers_by_offset.get(&offset).into_iter().flatten() {
+                    visitor.visit_line_number(constant_pool, *line_number, *label)?;
+                }
+            }
+            let label = |branch: i32| labels[&target(offset, branch)];
+            if let Some((jump, branch)) = Jump::from_instruction(&instruction) {
+                visitor.visit_jump(constant_pool, jump, label(branch))?;
+                continue;
+            }
+            match instruction {
+                Instruction::Tableswitch {
+                    default,
+                    low,
+                    offsets,
+                } => visitor.visit_tableswitch(
+                    constant_pool,
+                    label(default),
+                    low,
+                    offsets.into_iter().map(label).collect(),
+                )?,
+                Instruction::Lookupswitch { default, pairs } => visitor.visit_lookupswitch(
+                    constant_pool,
+                    label(default),
+                    pairs
+                        .into_iter()
+                        .map(|(key, branch)| (key, label(branch)))
+                        .collect(),
+                )?,
+                instruction => visitor.visit_instruction(constant_pool, instruction)?,
+            }
+        }
+        if let Some(label) = labels.get(&(code.code.len() as u32)) {
+            visitor.visit_label(constant_pool, *label)?;
+        }
+
+        let label = |offset: u16| labels[&(offset as u32)];
+        for entry in &code.exception_table {
+            visitor.visit_exception_handler(
+                constant_pool,
+                label(entry.start_pc),
+                label(entry.end_pc),
+                label(entry.handler_pc),
+                entry.catch_type,
+            )?;
+        }
+        for local_variable in local_variables {
+            visitor.visit_local_variable(
+                constant_pool,
+                label(local_variable.start_pc),
+                labels[&(local_variable.start_pc as u32 + local_variable.length as u32)],
+                local_variable.name_index,
+                local_variable.descriptor_index,
+                local_variable.index,
+            )?;
+        }
+        for local_variable in local_variable_types {
+            visitor.visit_local_variable_type(
+                constant_pool,
+                label(local_variable.start_pc),
+                labels[&(local_variable.start_pc as u32 + local_variable.length as u32)],
+                local_variable.name_index,
+                local_variable.signature_index,
+                local_variable.index,
+            )?;
+        }
+        for attribute in attributes {
+            visitor.visit_code_attribute(constant_pool, attribute)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the visited class, ending a chain of visitors.
+///
+/// Methods offered unchanged are taken as is, the code of other methods is
+/// assembled with a [`CodeBuilder`], computing `max_stack`, `max_locals` and
+/// the stack map frames like the
+/// [`ClassBuilder`](crate::class::builder::ClassBuilder), which rejects
+/// unreachable code.
+pub struct ClassWriter {
+    hierarchy: Box<dyn ClassHierarchy>,
+    header: Option<ClassHeader>,
+    name: String,
+    fields: Vec<FieldInfo>,
+    methods: Vec<MethodInfo>,
+    attributes: Vec<AttributeInfo>,
+    class_file: Option<ClassFile>,
+}
+
+impl Default for ClassWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClassWriter {
+    /// Without a [`ClassHierarchy`], `java/lang/Object` is taken as the common
+    /// super class of any two classes when merging frames.
+    pub fn new() -> Self {
+        Self {
+            hierarchy: Box::new(|_: &str, _: &str| "java/lang/Object".to_owned()),
+            header: None,
+            name: String::new(),
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+            class_file: None,
+        }
+    }
+
+    pub fn hierarchy(&mut self, hierarchy: impl ClassHierarchy + 'static) -> &mut Self {
+        self.hierarchy = Box::new(hierarchy);
+        self
+    }
+
+    /// Returns the written class file, or `None` if the end of a class has not
+    /// been visited.
+    pub fn into_class_file(self) -> Option<ClassFile> {
+        self.class_file
+    }
+}
+
+impl ClassVisitor for ClassWriter {
+    fn visit(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        header: ClassHeader,
+    ) -> Result<()> {
+        self.name = constant_pool
+            .constant_pool()
+            .class_name(header.this_class)?
+            .to_owned();
+        self.header = Some(header);
+        Ok(())
+    }
+
+    fn visit_field(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        access_flags: FieldAccessFlags,
+        name_index: u16,
+        descriptor_index: u16,
+    ) -> Result<Option<Box<dyn FieldVisitor + '_>>> {
+        Ok(Some(Box::new(FieldWriter {
+            fields: &mut self.fields,
+            field: FieldInfo {
+                access_flags,
+                name_index,
+                descriptor_index,
+                attributes: vec![],
+            },
+        })))
+    }
+
+    fn visit_method(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        access_flags: MethodAccessFlags,
+        name_index: u16,
+        descriptor_index: u16,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        Ok(Some(Box::new(MethodWriter {
+            writer: self,
+            method: MethodInfo {
+                access_flags,
+                name_index,
+                descriptor_index,
+                attributes: vec![],
+            },
+            code_position: None,
+            code: CodeBuilder::new(),
+            line_numbers: vec![],
+            local_variables: vec![],
+            local_variable_types: vec![],
+            code_attributes: vec![],
+        })))
+    }
+
+    fn visit_attribute(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        attribute: AttributeInfo,
+    ) -> Result<()> {
+        self.attributes.push(attribute);
+        Ok(())
+    }
+
+    fn visit_end(&mut self, constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        if let Some(header) = self.header.take() {
+            self.class_file = Some(ClassFile {
+                minor_version: header.minor_version,
+                major_version: header.major_version,
+                constant_pool: constant_pool.constant_pool().clone(),
+                access_flags: header.access_flags,
+                this_class: header.this_class,
+                super_class: header.super_class,
+                interfaces: header.interfaces,
+                fields: std::mem::take(&mut self.fields),
+                methods: std::mem::take(&mut self.methods),
+                attributes: std::mem::take(&mut self.attributes),
+            });
+        }
+        Ok(())
+    }
+}
+
+struct FieldWriter<'a> {
+    fields: &'a mut Vec<FieldInfo>,
+    field: FieldInfo,
+}
+
+impl FieldVisitor for FieldWriter<'_> {
+    fn visit_attribute(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        attribute: AttributeInfo,
+    ) -> Result<()> {
+        self.field.attributes.push(attribute);
+        Ok(())
+    }
+
+    fn visit_end(&mut self, _constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        self.fields.push(FieldInfo {
+            attributes: std::mem::take(&mut self.field.attributes),
+            ..self.field
+        });
+        Ok(())
+    }
+}
+
+struct MethodWriter<'a> {
+    writer: &'a mut ClassWriter,
+    method: MethodInfo,
+    /// Index of the `Code` attribute among the attributes of the method, if
+    /// the code has been visited.
+    code_position: Option<usize>,
+    code: CodeBuilder,
+    line_numbers: Vec<(u16, Label)>,
+    local_variables: Vec<(Label, Label, u16, u16, u16)>,
+    local_variable_types: Vec<(Label, Label, u16, u16, u16)>,
+    code_attributes: Vec<AttributeInfo>,
+}
+
+impl MethodWriter<'_> {
+    fn assemble(&mut self, constant_pool: &mut ConstantPoolBuilder) -> Result<CodeAttribute> {
+        let assembled_code = self.code.build()?;
+        let offset = |label: Label| -> Result<u16> {
+            assembled_code
+                .offset(label)
+                .map(|offset| offset as u16)
+                .ok_or(Error::UnplacedLabel)
+        };
+        let mut code = CodeAttribute {
+            max_stack: 0,
+            max_locals: 0,
+            code: assembled_code.code.clone(),
+            exception_table: assembled_code.exception_table.clone(),
+            attributes: vec![],
+        };
+        let analysis = frame::analyze(
+            &self.writer.name,
+            &self.method,
+            &code,
+            constant_pool.constant_pool(),
+            &*self.writer.hierarchy,
+        )?;
+        code.max_stack = analysis.max_stack;
+        code.max_locals = analysis.max_locals;
+
+        if !self.line_numbers.is_empty() {
+            let line_numbers = self
+                .line_numbers
+                .iter()
+                .map(|(line_number, start)| {
+                    Ok(LineNumber {
+                        start_pc: offset(*start)?,
+                        line_number: *line_number,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            code.attributes
+                .push(constant_pool.attribute(&Attribute::LineNumberTable(
+                    LineNumberTableAttribute(line_numbers),
+                ))?);
+        }
+        if !self.local_variables.is_empty() {
+            let local_variables = self
+                .local_variables
+                .iter()
+                .map(|(start, end, name_index, descriptor_index, index)| {
+                    Ok(LocalVariable {
+                        start_pc: offset(*start)?,
+                        length: offset(*end)? - offset(*start)?,
+                        name_index: *name_index,
+                        descriptor_index: *descriptor_index,
+                        index: *index,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            code.attributes
+                .push(constant_pool.attribute(&Attribute::LocalVariableTable(
+                    LocalVariableTableAttribute(local_variables),
+                ))?);
+        }
+        if !self.local_variable_types.is_empty() {
+            let local_variable_types = self
+                .local_variable_types
+                .iter()
+                .map(|(start, end, name_index, signature_index, index)| {
+                    Ok(LocalVariableType {
+                        start_pc: offset(*start)?,
+                        length: offset(*end)? - offset(*start)?,
+                        name_index: *name_index,
+                        signature_index: *signature_index,
+                        index: *index,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            code.attributes
+                .push(constant_pool.attribute(&Attribute::LocalVariableTypeTable(
+                    LocalVariableTypeTableAttribute(local_variable_types),
+                ))?);
+        }
+        // stack map frames were introduced with Java 6
+        let major_version = self
+            .writer
+            .header
+            .as_ref()
+            .map_or(0, |header| header.major_version);
+        if major_version >= 50 && !analysis.frames.is_empty() {
+            let stack_map_table = analysis.stack_map_table(|name| constant_pool.class(name))?;
+            code.attributes
+                .push(constant_pool.attribute(&Attribute::StackMapTable(stack_map_table))?);
+        }
+        code.attributes
+            .append(&mut std::mem::take(&mut self.code_attributes));
+        Ok(code)
+    }
+}
+
+impl MethodVisitor for MethodWriter<'_> {
+    fn visit_unchanged(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        attributes: &[AttributeInfo],
+    ) -> Result<bool> {
+        self.method.attributes = attributes.to_vec();
+        Ok(true)
+    }
+
+    fn visit_attribute(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        attribute: AttributeInfo,
+    ) -> Result<()> {
+        self.method.attributes.push(attribute);
+        Ok(())
+    }
+
+    fn visit_code(&mut self, _constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        self.code_position = Some(self.method.attributes.len());
+        Ok(())
+    }
+
+    fn new_label(&mut self) -> Label {
+        self.code.new_label()
+    }
+
+    fn visit_label(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        label: Label,
+    ) -> Result<()> {
+        self.code.place_label(label);
+        Ok(())
+    }
+
+    fn visit_instruction(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        instruction: Instruction,
+    ) -> Result<()> {
+        self.code.instruction(instruction);
+        Ok(())
+    }
+
+    fn visit_jump(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        jump: Jump,
+        target: Label,
+    ) -> Result<()> {
+        self.code.jump(jump, target);
+        Ok(())
+    }
+
+    fn visit_tableswitch(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        default: Label,
+        low: i32,
+        targets: Vec<Label>,
+    ) -> Result<()> {
+        self.code.tableswitch(default, low, targets);
+        Ok(())
+    }
+
+    fn visit_lookupswitch(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        default: Label,
+        pairs: Vec<(i32, Label)>,
+    ) -> Result<()> {
+        self.code.lookupswitch(default, pairs);
+        Ok(())
+    }
+
+    fn visit_exception_handler(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_type: u16,
+    ) -> Result<()> {
+        self.code.exception_handler(start, end, handler, catch_type);
+        Ok(())
+    }
+
+    fn visit_line_number(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        line_number: u16,
+        start: Label,
+    ) -> Result<()> {
+        self.line_numbers.push((line_number, start));
+        Ok(())
+    }
+
+    fn visit_local_variable(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        start: Label,
+        end: Label,
+        name_index: u16,
+        descriptor_index: u16,
+        index: u16,
+    ) -> Result<()> {
+        self.local_variables
+            .push((start, end, name_index, descriptor_index, index));
+        Ok(())
+    }
+
+    fn visit_local_variable_type(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        start: Label,
+        end: Label,
+        name_index: u16,
+        signature_index: u16,
+        index: u16,
+    ) -> Result<()> {
+        self.local_variable_types
+            .push((start, end, name_index, signature_index, index));
+        Ok(())
+    }
+
+    fn visit_code_attribute(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        attribute: AttributeInfo,
+    ) -> Result<()> {
+        self.code_attributes.push(attribute);
+        Ok(())
+    }
+
+    fn visit_end(&mut self, constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        if let Some(code_position) = self.code_position {
+            let code = self.assemble(constant_pool)?;
+            self.method.attributes.insert(
+                code_position,
+                constant_pool.attribute(&Attribute::Code(code))?,
+            );
+        }
+        self.writer.methods.push(MethodInfo {
+            attributes: std::mem::take(&mut self.method.attributes),
+            ..self.method
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+//! Lookup of classes by their internal name across directories, jar files and
+//! the runtime image of the JDK.
+
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs::File,
+    io::{ErrorKind, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(feature = "jar")]
+use crate::jar::Jar;
+use crate::{class::ClassFile, jimage::JImage, Decode, Error, Result};
+
+/// The entry of a [`ClassPath`] a class has been found in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassSource {
+    Directory(PathBuf),
+    #[cfg(feature = "jar")]
+    Jar(PathBuf),
+    JImage {
+        path: PathBuf,
+        module: String,
+    },
+}
+
+enum Entry {
+    Directory(PathBuf),
+    #[cfg(feature = "jar")]
+    Jar(PathBuf, Box<Jar<File>>),
+    JImage(PathBuf, Box<JImage<File>>),
+}
+
+impl Entry {
+    fn read(&mut self, name: &str) -> Result<Option<(Vec<u8>, ClassSource)>> {
+        match self {
+            Self::Directory(path) => match std::fs::read(path.join(format!("{name}.class"))) {
+                Ok(data) => Ok(Some((data, ClassSource::Directory(path.clone())))),
+                Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+                Err(error) => Err(error.into()),
+            },
+            #[cfg(feature = "jar")]
+            Self::Jar(path, jar) => Ok(jar
+                .entry(&format!("{name}.class"))?
+                .map(|entry| (entry.data().to_vec(), ClassSource::Jar(path.clone())))),
+            Self::JImage(path, jimage) => {
+                let package = name.rsplit_once('/').map_or("", |(package, _)| package);
+                let Some(module) = jimage.package_module(package)? else {
+                    return Ok(None);
+                };
+                Ok(jimage
+                    .resource(&format!("/{module}/{name}.class"))?
+                    .map(|data| {
+                        let source = ClassSource::JImage {
+                            path: path.clone(),
+                            module,
+                        };
+                        (data, source)
+                    }))
+            }
+        }
+    }
+}
+
+/// An ordered list of directories, jar files and jimages classes are looked up
+/// in, the first entry containing a class providing it.
+///
+/// Classes are only read when looked up, and are cached afterwards, as is
+/// their absence.
+#[derive(Default)]
+pub struct ClassPath {
+    entries: Vec<Entry>,
+    release: Option<u16>,
+    cache: HashMap<String, Option<(Arc<ClassFile>, ClassSource)>>,
+}
+
+impl ClassPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a class path as passed to `java -cp`, separated by `:` or `;`
+    /// depending on the platform, skipping entries which do not exist.
+    pub fn parse(class_path: impl AsRef<OsStr>) -> Result<Self> {
+        let mut this = Self::new();
+        for path in std::env::split_paths(&class_path) {
+            this.push(path)?;
+        }
+        Ok(this)
+    }
+
+    /// Appends a directory, or a jimage or jar file depending on the magic of
+    /// the file, skipping it if it does not exist as `java -cp` does.
+    pub fn push(&mut self, path: impl Into<PathBuf>) -> Result<&mut Self> {
+        let path = path.into();
+        if path.is_dir() {
+            return Ok(self.push_directory(path));
+        }
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(self),
+            Err(error) => return Err(error.into()),
+        };
+        let mut magic = vec![];
+        file.take(4).read_to_end(&mut magic)?;
+        let magic: [u8; 4] = magic
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::TruncatedInput {
+                offset: magic.len(),
+            })?;
+        if matches!(u32::from_le_bytes(magic), 0xCAFEDADA | 0xDADAFECA) {
+            return self.push_jimage(path);
+        }
+        #[cfg(feature = "jar")]
+        return self.push_jar(path);
+        #[cfg(not(feature = "jar"))]
+        Err(std::io::Error::new(ErrorKind::Unsupported, "jar files require the jar feature").into())
+    }
+
+    /// Appends a directory containing class files in directories named after
+    /// their packages.
+    pub fn push_directory(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.entries.push(Entry::Directory(path.into()));
+        self.cache.retain(|_, class| class.is_some());
+        self
+    }
+
+    /// Appends a jar file, which is opened right away.
+    #[cfg(feature = "jar")]
+    pub fn push_jar(&mut self, path: impl Into<PathBuf>) -> Result<&mut Self> {
+        let path = path.into();
+        let mut jar = Jar::open(&path)?;
+        if let Some(release) = self.release {
+            jar.release(release)?;
+        }
+        self.entries.push(Entry::Jar(path, Box::new(jar)));
+        self.cache.retain(|_, class| class.is_some());
+        Ok(self)
+    }
+
+    /// Appends a jimage, which is opened right away.
+    pub fn push_jimage(&mut self, path: impl Into<PathBuf>) -> Result<&mut Self> {
+        let path = path.into();
+        let jimage = JImage::open(&path)?;
+        self.entries.push(Entry::JImage(path, Box::new(jimage)));
+        self.cache.retain(|_, class| class.is_some());
+        Ok(self)
+    }
+
+    /// Appends the runtime image of the JDK or JRE installed at the given
+    /// path, `lib/modules`, as present from Java 9 on.
+    pub fn push_java_home(&mut self, path: impl AsRef<Path>) -> Result<&mut Self> {
+        self.push_jimage(path.as_ref().join("lib").join("modules"))
+    }
+
+    /// Sets the feature release of Java, e.g. 17, multi-release jars are read
+    /// for.
+    pub fn release(&mut self, release: u16) -> Result<&mut Self> {
+        self.release = Some(release);
+        #[cfg(feature = "jar")]
+        for entry in &mut self.entries {
+            if let Entry::Jar(_, jar) = entry {
+                jar.release(release)?;
+            }
+        }
+        self.cache.clear();
+        Ok(self)
+    }
+
+    /// Returns the class with the given internal name, e.g.
+    /// `java/lang/String`.
+    pub fn find(&mut self, name: &str) -> Result<Option<Arc<ClassFile>>> {
+        Ok(self
+            .lookup(name)?
+            .map(|(class_file, _)| Arc::clone(class_file)))
+    }
+
+    /// Returns the entry the class with the given internal name is provided
+    /// by.
+    pub fn source(&mut self, name: &str) -> Result<Option<&ClassSource>> {
+        Ok(self.lookup(name)?.map(|(_, source)| source))
+    }
+
+    fn lookup(&mut self, name: &str) -> Result<Option<&(Arc<ClassFile>, ClassSource)>> {
+        if !self.cache.contains_key(name) {
+            let mut class = None;
+            for entry in &mut self.entries {
+                if let Some((data, source)) = entry.read(name)? {
+                    class = Some((Arc::new(ClassFile::decode(&mut data.as_slice())?), source));
+                    break;
+                }
+            }
+            self.cache.insert(name.to_owned(), class);
+        }
+        Ok(self.cache[name].as_ref())
+    }
+}
+
+impl ClassSource {
+    /// Returns the path of the directory or file.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Directory(path) => path,
+            #[cfg(feature = "jar")]
+            Self::Jar(path) => path,
+            Self::JImage { path, .. } => path,
+        }
+    }
+}
@@ -0,0 +1,1276 @@
+use std::io::{Cursor, Seek, Write};
+
+use crate::{decode_with, Decode, Encode, Error, Result};
+
+pub mod builder;
+pub mod frame;
+
+/// A single JVM instruction.
+///
+/// Local variable indices are always 16-bit, the `wide` prefix is implied by
+/// indices and increments which do not fit into the short form. Decoded
+/// instructions keep an explicit prefix as [`Instruction::Wide`], so that they
+/// encode to the same bytes. Branch offsets are relative to the offset of the
+/// instruction itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Nop,
+    AconstNull,
+    IconstM1,
+    Iconst0,
+    Iconst1,
+    Iconst2,
+    Iconst3,
+    Iconst4,
+    Iconst5,
+    Lconst0,
+    Lconst1,
+    Fconst0,
+    Fconst1,
+    Fconst2,
+    Dconst0,
+    Dconst1,
+    Bipush(i8),
+    Sipush(i16),
+    Ldc(u8),
+    LdcW(u16),
+    Ldc2W(u16),
+    Iload(u16),
+    Lload(u16),
+    Fload(u16),
+    Dload(u16),
+    Aload(u16),
+    Iload0,
+    Iload1,
+    Iload2,
+    Iload3,
+    Lload0,
+    Lload1,
+    Lload2,
+    Lload3,
+    Fload0,
+    Fload1,
+    Fload2,
+    Fload3,
+    Dload0,
+    Dload1,
+    Dload2,
+    Dload3,
+    Aload0,
+    Aload1,
+    Aload2,
+    Aload3,
+    Iaload,
+    Laload,
+    Faload,
+    Daload,
+    Aaload,
+    Baload,
+    Caload,
+    Saload,
+    Istore(u16),
+    Lstore(u16),
+    Fstore(u16),
+    Dstore(u16),
+    Astore(u16),
+    Istore0,
+    Istore1,
+    Istore2,
+    Istore3,
+    Lstore0,
+    Lstore1,
+    Lstore2,
+    Lstore3,
+    Fstore0,
+    Fstore1,
+    Fstore2,
+    Fstore3,
+    Dstore0,
+    Dstore1,
+    Dstore2,
+    Dstore3,
+    Astore0,
+    Astore1,
+    Astore2,
+    Astore3,
+    Iastore,
+    Lastore,
+    Fastore,
+    Dastore,
+    Aastore,
+    Bastore,
+    Castore,
+    Sastore,
+    Pop,
+    Pop2,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    Swap,
+    Iadd,
+    Ladd,
+    Fadd,
+    Dadd,
+    Isub,
+    Lsub,
+    Fsub,
+    Dsub,
+    Imul,
+    Lmul,
+    Fmul,
+    Dmul,
+    Idiv,
+    Ldiv,
+    Fdiv,
+    Ddiv,
+    Irem,
+    Lrem,
+    Frem,
+    Drem,
+    Ineg,
+    Lneg,
+    Fneg,
+    Dneg,
+    Ishl,
+    Lshl,
+    Ishr,
+    Lshr,
+    Iushr,
+    Lushr,
+    Iand,
+    Land,
+    Ior,
+    Lor,
+    Ixor,
+    Lxor,
+    Iinc {
+        index: u16,
+        value: i16,
+    },
+    I2l,
+    I2f,
+    I2d,
+    L2i,
+    L2f,
+    L2d,
+    F2i,
+    F2l,
+    F2d,
+    D2i,
+    D2l,
+    D2f,
+    I2b,
+    I2c,
+    I2s,
+    Lcmp,
+    Fcmpl,
+    Fcmpg,
+    Dcmpl,
+    Dcmpg,
+    Ifeq(i16),
+    Ifne(i16),
+    Iflt(i16),
+    Ifge(i16),
+    Ifgt(i16),
+    Ifle(i16),
+    IfIcmpeq(i16),
+    IfIcmpne(i16),
+    IfIcmplt(i16),
+    IfIcmpge(i16),
+    IfIcmpgt(i16),
+    IfIcmple(i16),
+    IfAcmpeq(i16),
+    IfAcmpne(i16),
+    Goto(i16),
+    Jsr(i16),
+    Ret(u16),
+    Tableswitch {
+        default: i32,
+        low: i32,
+        offsets: Vec<i32>,
+    },
+    Lookupswitch {
+        default: i32,
+        pairs: Vec<(i32, i32)>,
+    },
+    Ireturn,
+    Lreturn,
+    Freturn,
+    Dreturn,
+    Areturn,
+    Return,
+    Getstatic(u16),
+    Putstatic(u16),
+    Getfield(u16),
+    Putfield(u16),
+    Invokevirtual(u16),
+    Invokespecial(u16),
+    Invokestatic(u16),
+    Invokeinterface {
+        index: u16,
+        count: u8,
+    },
+    Invokedynamic(u16),
+    New(u16),
+    Newarray(ArrayType),
+    Anewarray(u16),
+    Arraylength,
+    Athrow,
+    Checkcast(u16),
+    Instanceof(u16),
+    Monitorenter,
+    Monitorexit,
+    /// A load, store, `ret` or `iinc` with the `wide` prefix, even if its
+    /// operands fit into the short form.
+    Wide(Box<Instruction>),
+    Multianewarray {
+        index: u16,
+        dimensions: u8,
+    },
+    Ifnull(i16),
+    Ifnonnull(i16),
+    GotoW(i32),
+    JsrW(i32),
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Self::Nop => 0,
+            Self::AconstNull => 1,
+            Self::IconstM1 => 2,
+            Self::Iconst0 => 3,
+            Self::Iconst1 => 4,
+            Self::Iconst2 => 5,
+            Self::Iconst3 => 6,
+            Self::Iconst4 => 7,
+            Self::Iconst5 => 8,
+            Self::Lconst0 => 9,
+            Self::Lconst1 => 10,
+            Self::Fconst0 => 11,
+            Self::Fconst1 => 12,
+            Self::Fconst2 => 13,
+            Self::Dconst0 => 14,
+            Self::Dconst1 => 15,
+            Self::Bipush(_) => 16,
+            Self::Sipush(_) => 17,
+            Self::Ldc(_) => 18,
+            Self::LdcW(_) => 19,
+            Self::Ldc2W(_) => 20,
+            Self::Iload(_) => 21,
+            Self::Lload(_) => 22,
+            Self::Fload(_) => 23,
+            Self::Dload(_) => 24,
+            Self::Aload(_) => 25,
+            Self::Iload0 => 26,
+            Self::Iload1 => 27,
+            Self::Iload2 => 28,
+            Self::Iload3 => 29,
+            Self::Lload0 => 30,
+            Self::Lload1 => 31,
+            Self::Lload2 => 32,
+            Self::Lload3 => 33,
+            Self::Fload0 => 34,
+            Self::Fload1 => 35,
+            Self::Fload2 => 36,
+            Self::Fload3 => 37,
+            Self::Dload0 => 38,
+            Self::Dload1 => 39,
+            Self::Dload2 => 40,
+            Self::Dload3 => 41,
+            Self::Aload0 => 42,
+            Self::Aload1 => 43,
+            Self::Aload2 => 44,
+            Self::Aload3 => 45,
+            Self::Iaload => 46,
+            Self::Laload => 47,
+            Self::Faload => 48,
+            Self::Daload => 49,
+            Self::Aaload => 50,
+            Self::Baload => 51,
+            Self::Caload => 52,
+            Self::Saload => 53,
+            Self::Istore(_) => 54,
+            Self::Lstore(_) => 55,
+            Self::Fstore(_) => 56,
+            Self::Dstore(_) => 57,
+            Self::Astore(_) => 58,
+            Self::Istore0 => 59,
+            Self::Istore1 => 60,
+            Self::Istore2 => 61,
+            Self::Istore3 => 62,
+            Self::Lstore0 => 63,
+            Self::Lstore1 => 64,
+            Self::Lstore2 => 65,
+            Self::Lstore3 => 66,
+            Self::Fstore0 => 67,
+            Self::Fstore1 => 68,
+            Self::Fstore2 => 69,
+            Self::Fstore3 => 70,
+            Self::Dstore0 => 71,
+            Self::Dstore1 => 72,
+            Self::Dstore2 => 73,
+            Self::Dstore3 => 74,
+            Self::Astore0 => 75,
+            Self::Astore1 => 76,
+            Self::Astore2 => 77,
+            Self::Astore3 => 78,
+            Self::Iastore => 79,
+            Self::Lastore => 80,
+            Self::Fastore => 81,
+            Self::Dastore => 82,
+            Self::Aastore => 83,
+            Self::Bastore => 84,
+            Self::Castore => 85,
+            Self::Sastore => 86,
+            Self::Pop => 87,
+            Self::Pop2 => 88,
+            Self::Dup => 89,
+            Self::DupX1 => 90,
+            Self::DupX2 => 91,
+            Self::Dup2 => 92,
+            Self::Dup2X1 => 93,
+            Self::Dup2X2 => 94,
+            Self::Swap => 95,
+            Self::Iadd => 96,
+            Self::Ladd => 97,
+            Self::Fadd => 98,
+            Self::Dadd => 99,
+            Self::Isub => 100,
+            Self::Lsub => 101,
+            Self::Fsub => 102,
+            Self::Dsub => 103,
+            Self::Imul => 104,
+            Self::Lmul => 105,
+            Self::Fmul => 106,
+            Self::Dmul => 107,
+            Self::Idiv => 108,
+            Self::Ldiv => 109,
+            Self::Fdiv => 110,
+            Self::Ddiv => 111,
+            Self::Irem => 112,
+            Self::Lrem => 113,
+            Self::Frem => 114,
+            Self::Drem => 115,
+            Self::Ineg => 116,
+            Self::Lneg => 117,
+            Self::Fneg => 118,
+            Self::Dneg => 119,
+            Self::Ishl => 120,
+            Self::Lshl => 121,
+            Self::Ishr => 122,
+            Self::Lshr => 123,
+            Self::Iushr => 124,
+            Self::Lushr => 125,
+            Self::Iand => 126,
+            Self::Land => 127,
+            Self::Ior => 128,
+            Self::Lor => 129,
+            Self::Ixor => 130,
+            Self::Lxor => 131,
+            Self::Iinc { .. } => 132,
+            Self::I2l => 133,
+            Self::I2f => 134,
+            Self::I2d => 135,
+            Self::L2i => 136,
+            Self::L2f => 137,
+            Self::L2d => 138,
+            Self::F2i => 139,
+            Self::F2l => 140,
+            Self::F2d => 141,
+            Self::D2i => 142,
+            Self::D2l => 143,
+            Self::D2f => 144,
+            Self::I2b => 145,
+            Self::I2c => 146,
+            Self::I2s => 147,
+            Self::Lcmp => 148,
+            Self::Fcmpl => 149,
+            Self::Fcmpg => 150,
+            Self::Dcmpl => 151,
+            Self::Dcmpg => 152,
+            Self::Ifeq(_) => 153,
+            Self::Ifne(_) => 154,
+            Self::Iflt(_) => 155,
+            Self::Ifge(_) => 156,
+            Self::Ifgt(_) => 157,
+            Self::Ifle(_) => 158,
+            Self::IfIcmpeq(_) => 159,
+            Self::IfIcmpne(_) => 160,
+            Self::IfIcmplt(_) => 161,
+            Self::IfIcmpge(_) => 162,
+            Self::IfIcmpgt(_) => 163,
+            Self::IfIcmple(_) => 164,
+            Self::IfAcmpeq(_) => 165,
+            Self::IfAcmpne(_) => 166,
+            Self::Goto(_) => 167,
+            Self::Jsr(_) => 168,
+            Self::Ret(_) => 169,
+            Self::Tableswitch { .. } => 170,
+            Self::Lookupswitch { .. } => 171,
+            Self::Ireturn => 172,
+            Self::Lreturn => 173,
+            Self::Freturn => 174,
+            Self::Dreturn => 175,
+            Self::Areturn => 176,
+            Self::Return => 177,
+            Self::Getstatic(_) => 178,
+            Self::Putstatic(_) => 179,
+            Self::Getfield(_) => 180,
+            Self::Putfield(_) => 181,
+            Self::Invokevirtual(_) => 182,
+            Self::Invokespecial(_) => 183,
+            Self::Invokestatic(_) => 184,
+            Self::Invokeinterface { .. } => 185,
+            Self::Invokedynamic(_) => 186,
+            Self::New(_) => 187,
+            Self::Newarray(_) => 188,
+            Self::Anewarray(_) => 189,
+            Self::Arraylength => 190,
+            Self::Athrow => 191,
+            Self::Checkcast(_) => 192,
+            Self::Instanceof(_) => 193,
+            Self::Monitorenter => 194,
+            Self::Monitorexit => 195,
+            Self::Wide(_) => 196,
+            Self::Multianewarray { .. } => 197,
+            Self::Ifnull(_) => 198,
+            Self::Ifnonnull(_) => 199,
+            Self::GotoW(_) => 200,
+            Self::JsrW(_) => 201,
+        }
+    }
+
+    /// Returns the mnemonic of the opcode as used by the JVM specification,
+    /// e.g. `invokevirtual`.
+    pub fn mnemonic(&self) -> &'static str {
+        MNEMONICS[self.opcode() as usize]
+    }
+
+    /// Returns the length in bytes of this instruction when placed at
+    /// `offset`, which is only relevant for the padding of switches.
+    pub fn length(&self, offset: u32) -> u32 {
+        match self {
+            Self::Iload(index)
+            | Self::Lload(index)
+            | Self::Fload(index)
+            | Self::Dload(index)
+            | Self::Aload(index)
+            | Self::Istore(index)
+            | Self::Lstore(index)
+            | Self::Fstore(index)
+            | Self::Dstore(index)
+            | Self::Astore(index)
+            | Self::Ret(index) => {
+                if *index > u8::MAX as u16 {
+                    4
+                } else {
+                    2
+                }
+            }
+            Self::Iinc { index, value } => {
+                if *index > u8::MAX as u16 || i8::try_from(*value).is_err() {
+                    6
+                } else {
+                    3
+                }
+            }
+            Self::Bipush(_) | Self::Ldc(_) | Self::Newarray(_) => 2,
+            Self::Sipush(_)
+            | Self::Ifeq(_)
+            | Self::Ifne(_)
+            | Self::Iflt(_)
+            | Self::Ifge(_)
+            | Self::Ifgt(_)
+            | Self::Ifle(_)
+            | Self::IfIcmpeq(_)
+            | Self::IfIcmpne(_)
+            | Self::IfIcmplt(_)
+            | Self::IfIcmpge(_)
+            | Self::IfIcmpgt(_)
+            | Self::IfIcmple(_)
+            | Self::IfAcmpeq(_)
+            | Self::IfAcmpne(_)
+            | Self::Goto(_)
+            | Self::Jsr(_)
+            | Self::Ifnull(_)
+            | Self::Ifnonnull(_)
+            | Self::LdcW(_)
+            | Self::Ldc2W(_)
+            | Self::Getstatic(_)
+            | Self::Putstatic(_)
+            | Self::Getfield(_)
+            | Self::Putfield(_)
+            | Self::Invokevirtual(_)
+            | Self::Invokespecial(_)
+            | Self::Invokestatic(_)
+            | Self::New(_)
+            | Self::Anewarray(_)
+            | Self::Checkcast(_)
+            | Self::Instanceof(_) => 3,
+            Self::Multianewarray { .. } => 4,
+            Self::GotoW(_)
+            | Self::JsrW(_)
+            | Self::Invokeinterface { .. }
+            | Self::Invokedynamic(_) => 5,
+            Self::Wide(instruction) => match **instruction {
+                Self::Iinc { .. } => 6,
+                _ => 4,
+            },
+            Self::Tableswitch { offsets, .. } => {
+                1 + padding(offset) + 12 + 4 * offsets.len() as u32
+            }
+            Self::Lookupswitch { pairs, .. } => 1 + padding(offset) + 8 + 8 * pairs.len() as u32,
+            _ => 1,
+        }
+    }
+
+    /// Decodes the instruction at `offset`, which is needed to skip the
+    /// padding of switches.
+    pub fn decode(input: &mut &[u8], offset: u32) -> Result<Self> {
+        let opcode = u8::decode(input)?;
+        Ok(match opcode {
+            0 => Self::Nop,
+            1 => Self::AconstNull,
+            2 => Self::IconstM1,
+            3 => Self::Iconst0,
+            4 => Self::Iconst1,
+            5 => Self::Iconst2,
+            6 => Self::Iconst3,
+            7 => Self::Iconst4,
+            8 => Self::Iconst5,
+            9 => Self::Lconst0,
+            10 => Self::Lconst1,
+            11 => Self::Fconst0,
+            12 => Self::Fconst1,
+            13 => Self::Fconst2,
+            14 => Self::Dconst0,
+            15 => Self::Dconst1,
+            16 => Self::Bipush(Decode::decode(input)?),
+            17 => Self::Sipush(Decode::decode(input)?),
+            18 => Self::Ldc(Decode::decode(input)?),
+            19 => Self::LdcW(Decode::decode(input)?),
+            20 => Self::Ldc2W(Decode::decode(input)?),
+            21 => Self::Iload(u8::decode(input)? as u16),
+            22 => Self::Lload(u8::decode(input)? as u16),
+            23 => Self::Fload(u8::decode(input)? as u16),
+            24 => Self::Dload(u8::decode(input)? as u16),
+            25 => Self::Aload(u8::decode(input)? as u16),
+            26 => Self::Iload0,
+            27 => Self::Iload1,
+            28 => Self::Iload2,
+            29 => Self::Iload3,
+            30 => Self::Lload0,
+            31 => Self::Lload1,
+            32 => Self::Lload2,
+            33 => Self::Lload3,
+            34 => Self::Fload0,
+            35 => Self::Fload1,
+            36 => Self::Fload2,
+            37 => Self::Fload3,
+            38 => Self::Dload0,
+            39 => Self::Dload1,
+            40 => Self::Dload2,
+            41 => Self::Dload3,
+            42 => Self::Aload0,
+            43 => Self::Aload1,
+            44 => Self::Aload2,
+            45 => Self::Aload3,
+            46 => Self::Iaload,
+            47 => Self::Laload,
+            48 => Self::Faload,
+            49 => Self::Daload,
+            50 => Self::Aaload,
+            51 => Self::Baload,
+            52 => Self::Caload,
+            53 => Self::Saload,
+            54 => Self::Istore(u8::decode(input)? as u16),
+            55 => Self::Lstore(u8::decode(input)? as u16),
+            56 => Self::Fstore(u8::decode(input)? as u16),
+            57 => Self::Dstore(u8::decode(input)? as u16),
+            58 => Self::Astore(u8::decode(input)? as u16),
+            59 => Self::Istore0,
+            60 => Self::Istore1,
+            61 => Self::Istore2,
+            62 => Self::Istore3,
+            63 => Self::Lstore0,
+            64 => Self::Lstore1,
+            65 => Self::Lstore2,
+            66 => Self::Lstore3,
+            67 => Self::Fstore0,
+            68 => Self::Fstore1,
+            69 => Self::Fstore2,
+            70 => Self::Fstore3,
+            71 => Self::Dstore0,
+            72 => Self::Dstore1,
+            73 => Self::Dstore2,
+            74 => Self::Dstore3,
+            75 => Self::Astore0,
+            76 => Self::Astore1,
+            77 => Self::Astore2,
+            78 => Self::Astore3,
+            79 => Self::Iastore,
+            80 => Self::Lastore,
+            81 => Self::Fastore,
+            82 => Self::Dastore,
+            83 => Self::Aastore,
+            84 => Self::Bastore,
+            85 => Self::Castore,
+            86 => Self::Sastore,
+            87 => Self::Pop,
+            88 => Self::Pop2,
+            89 => Self::Dup,
+            90 => Self::DupX1,
+            91 => Self::DupX2,
+            92 => Self::Dup2,
+            93 => Self::Dup2X1,
+            94 => Self::Dup2X2,
+            95 => Self::Swap,
+            96 => Self::Iadd,
+            97 => Self::Ladd,
+            98 => Self::Fadd,
+            99 => Self::Dadd,
+            100 => Self::Isub,
+            101 => Self::Lsub,
+            102 => Self::Fsub,
+            103 => Self::Dsub,
+            104 => Self::Imul,
+            105 => Self::Lmul,
+            106 => Self::Fmul,
+            107 => Self::Dmul,
+            108 => Self::Idiv,
+            109 => Self::Ldiv,
+            110 => Self::Fdiv,
+            111 => Self::Ddiv,
+            112 => Self::Irem,
+            113 => Self::Lrem,
+            114 => Self::Frem,
+            115 => Self::Drem,
+            116 => Self::Ineg,
+            117 => Self::Lneg,
+            118 => Self::Fneg,
+            119 => Self::Dneg,
+            120 => Self::Ishl,
+            121 => Self::Lshl,
+            122 => Self::Ishr,
+            123 => Self::Lshr,
+            124 => Self::Iushr,
+            125 => Self::Lushr,
+            126 => Self::Iand,
+            127 => Self::Land,
+            128 => Self::Ior,
+            129 => Self::Lor,
+            130 => Self::Ixor,
+            131 => Self::Lxor,
+            132 => Self::Iinc {
+                index: u8::decode(input)? as u16,
+                value: i8::decode(input)? as i16,
+            },
+            133 => Self::I2l,
+            134 => Self::I2f,
+            135 => Self::I2d,
+            136 => Self::L2i,
+            137 => Self::L2f,
+            138 => Self::L2d,
+            139 => Self::F2i,
+            140 => Self::F2l,
+            141 => Self::F2d,
+            142 => Self::D2i,
+            143 => Self::D2l,
+            144 => Self::D2f,
+            145 => Self::I2b,
+            146 => Self::I2c,
+            147 => Self::I2s,
+            148 => Self::Lcmp,
+            149 => Self::Fcmpl,
+            150 => Self::Fcmpg,
+            151 => Self::Dcmpl,
+            152 => Self::Dcmpg,
+            153 => Self::Ifeq(Decode::decode(input)?),
+            154 => Self::Ifne(Decode::decode(input)?),
+            155 => Self::Iflt(Decode::decode(input)?),
+            156 => Self::Ifge(Decode::decode(input)?),
+            157 => Self::Ifgt(Decode::decode(input)?),
+            158 => Self::Ifle(Decode::decode(input)?),
+            159 => Self::IfIcmpeq(Decode::decode(input)?),
+            160 => Self::IfIcmpne(Decode::decode(input)?),
+            161 => Self::IfIcmplt(Decode::decode(input)?),
+            162 => Self::IfIcmpge(Decode::decode(input)?),
+            163 => Self::IfIcmpgt(Decode::decode(input)?),
+            164 => Self::IfIcmple(Decode::decode(input)?),
+            165 => Self::IfAcmpeq(Decode::decode(input)?),
+            166 => Self::IfAcmpne(Decode::decode(input)?),
+            167 => Self::Goto(Decode::decode(input)?),
+            168 => Self::Jsr(Decode::decode(input)?),
+            169 => Self::Ret(u8::decode(input)? as u16),
+            170 => {
+                for _ in 0..padding(offset) {
+                    u8::decode(input)?;
+                }
+                let default = Decode::decode(input)?;
+                let low = i32::decode(input)?;
+                let high = i32::decode(input)?;
+                if high < low {
+                    return Err(Error::InvalidInstruction { opcode, offset });
+                }
+                // the count is untrusted, each offset takes four bytes
+                let count = (high as i64 - low as i64 + 1) as usize;
+                let mut offsets = Vec::with_capacity(count.min(input.len() / 4));
+                for _ in low..=high {
+                    offsets.push(Decode::decode(input)?);
+                }
+                Self::Tableswitch {
+                    default,
+                    low,
+                    offsets,
+                }
+            }
+            171 => {
+                for _ in 0..padding(offset) {
+                    u8::decode(input)?;
+                }
+                let default = Decode::decode(input)?;
+                let npairs = i32::decode(input)?;
+                if npairs < 0 {
+                    return Err(Error::InvalidInstruction { opcode, offset });
+                }
+                let mut pairs = Vec::with_capacity((npairs as usize).min(input.len() / 8));
+                for _ in 0..npairs {
+                    pairs.push((Decode::decode(input)?, Decode::decode(input)?));
+                }
+                Self::Lookupswitch { default, pairs }
+            }
+            172 => Self::Ireturn,
+            173 => Self::Lreturn,
+            174 => Self::Freturn,
+            175 => Self::Dreturn,
+            176 => Self::Areturn,
+            177 => Self::Return,
+            178 => Self::Getstatic(Decode::decode(input)?),
+            179 => Self::Putstatic(Decode::decode(input)?),
+            180 => Self::Getfield(Decode::decode(input)?),
+            181 => Self::Putfield(Decode::decode(input)?),
+            182 => Self::Invokevirtual(Decode::decode(input)?),
+            183 => Self::Invokespecial(Decode::decode(input)?),
+            184 => Self::Invokestatic(Decode::decode(input)?),
+            185 => {
+                let index = Decode::decode(input)?;
+                let count = Decode::decode(input)?;
+                let _ = u8::decode(input)?;
+                Self::Invokeinterface { index, count }
+            }
+            186 => {
+                let index = Decode::decode(input)?;
+                let _ = u16::decode(input)?;
+                Self::Invokedynamic(index)
+            }
+            187 => Self::New(Decode::decode(input)?),
+            188 => {
+                let atype = u8::decode(input)?;
+                Self::Newarray(
+                    ArrayType::from_atype(atype)
+                        .ok_or(Error::InvalidInstruction { opcode, offset })?,
+                )
+            }
+            189 => Self::Anewarray(Decode::decode(input)?),
+            190 => Self::Arraylength,
+            191 => Self::Athrow,
+            192 => Self::Checkcast(Decode::decode(input)?),
+            193 => Self::Instanceof(Decode::decode(input)?),
+            194 => Self::Monitorenter,
+            195 => Self::Monitorexit,
+            197 => Self::Multianewarray {
+                index: Decode::decode(input)?,
+                dimensions: Decode::decode(input)?,
+            },
+            198 => Self::Ifnull(Decode::decode(input)?),
+            199 => Self::Ifnonnull(Decode::decode(input)?),
+            200 => Self::GotoW(Decode::decode(input)?),
+            201 => Self::JsrW(Decode::decode(input)?),
+            196 => {
+                let opcode = u8::decode(input)?;
+                Self::Wide(Box::new(match opcode {
+                    21 => Self::Iload(Decode::decode(input)?),
+                    22 => Self::Lload(Decode::decode(input)?),
+                    23 => Self::Fload(Decode::decode(input)?),
+                    24 => Self::Dload(Decode::decode(input)?),
+                    25 => Self::Aload(Decode::decode(input)?),
+                    54 => Self::Istore(Decode::decode(input)?),
+                    55 => Self::Lstore(Decode::decode(input)?),
+                    56 => Self::Fstore(Decode::decode(input)?),
+                    57 => Self::Dstore(Decode::decode(input)?),
+                    58 => Self::Astore(Decode::decode(input)?),
+                    169 => Self::Ret(Decode::decode(input)?),
+                    132 => Self::Iinc {
+                        index: Decode::decode(input)?,
+                        value: Decode::decode(input)?,
+                    },
+                    _ => return Err(Error::InvalidInstruction { opcode, offset }),
+                }))
+            }
+            _ => return Err(Error::InvalidInstruction { opcode, offset }),
+        })
+    }
+
+    /// Encodes the instruction at `offset`, which is needed to pad switches.
+    pub fn encode(&self, output: &mut (impl Write + Seek), offset: u32) -> Result<()> {
+        match self {
+            Self::Iload(index)
+            | Self::Lload(index)
+            | Self::Fload(index)
+            | Self::Dload(index)
+            | Self::Aload(index)
+            | Self::Istore(index)
+            | Self::Lstore(index)
+            | Self::Fstore(index)
+            | Self::Dstore(index)
+            | Self::Astore(index)
+            | Self::Ret(index) => {
+                if *index > u8::MAX as u16 {
+                    196u8.encode(output)?;
+                    self.opcode().encode(output)?;
+                    index.encode(output)?;
+                } else {
+                    self.opcode().encode(output)?;
+                    (*index as u8).encode(output)?;
+                }
+            }
+            Self::Iinc { index, value } => match (u8::try_from(*index), i8::try_from(*value)) {
+                (Ok(index), Ok(value)) => {
+                    self.opcode().encode(output)?;
+                    index.encode(output)?;
+                    value.encode(output)?;
+                }
+                _ => {
+                    196u8.encode(output)?;
+                    self.opcode().encode(output)?;
+                    index.encode(output)?;
+                    value.encode(output)?;
+                }
+            },
+            Self::Wide(instruction) => match **instruction {
+                Self::Iload(index)
+                | Self::Lload(index)
+                | Self::Fload(index)
+                | Self::Dload(index)
+                | Self::Aload(index)
+                | Self::Istore(index)
+                | Self::Lstore(index)
+                | Self::Fstore(index)
+                | Self::Dstore(index)
+                | Self::Astore(index)
+                | Self::Ret(index) => {
+                    self.opcode().encode(output)?;
+                    instruction.opcode().encode(output)?;
+                    index.encode(output)?;
+                }
+                Self::Iinc { index, value } => {
+                    self.opcode().encode(output)?;
+                    instruction.opcode().encode(output)?;
+                    index.encode(output)?;
+                    value.encode(output)?;
+                }
+                _ => {
+                    return Err(Error::InvalidInstruction {
+                        opcode: self.opcode(),
+                        offset,
+                    })
+                }
+            },
+            Self::Bipush(value) => {
+                self.opcode().encode(output)?;
+                value.encode(output)?;
+            }
+            Self::Ldc(index) => {
+                self.opcode().encode(output)?;
+                index.encode(output)?;
+            }
+            Self::Sipush(value)
+            | Self::Ifeq(value)
+            | Self::Ifne(value)
+            | Self::Iflt(value)
+            | Self::Ifge(value)
+            | Self::Ifgt(value)
+            | Self::Ifle(value)
+            | Self::IfIcmpeq(value)
+            | Self::IfIcmpne(value)
+            | Self::IfIcmplt(value)
+            | Self::IfIcmpge(value)
+            | Self::IfIcmpgt(value)
+            | Self::IfIcmple(value)
+            | Self::IfAcmpeq(value)
+            | Self::IfAcmpne(value)
+            | Self::Goto(value)
+            | Self::Jsr(value)
+            | Self::Ifnull(value)
+            | Self::Ifnonnull(value) => {
+                self.opcode().encode(output)?;
+                value.encode(output)?;
+            }
+            Self::LdcW(index)
+            | Self::Ldc2W(index)
+            | Self::Getstatic(index)
+            | Self::Putstatic(index)
+            | Self::Getfield(index)
+            | Self::Putfield(index)
+            | Self::Invokevirtual(index)
+            | Self::Invokespecial(index)
+            | Self::Invokestatic(index)
+            | Self::New(index)
+            | Self::Anewarray(index)
+            | Self::Checkcast(index)
+            | Self::Instanceof(index) => {
+                self.opcode().encode(output)?;
+                index.encode(output)?;
+            }
+            Self::GotoW(branch) | Self::JsrW(branch) => {
+                self.opcode().encode(output)?;
+                branch.encode(output)?;
+            }
+            Self::Tableswitch {
+                default,
+                low,
+                offsets,
+            } => {
+                // the high bound has to be representable and at least `low`
+                let high = i32::try_from(offsets.len())
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .and_then(|count| low.checked_add(count - 1))
+                    .ok_or(Error::InvalidInstruction {
+                        opcode: self.opcode(),
+                        offset,
+                    })?;
+                self.opcode().encode(output)?;
+                output.write_all(&[0; 3][..padding(offset) as usize])?;
+                default.encode(output)?;
+                low.encode(output)?;
+                high.encode(output)?;
+                for offset in offsets {
+                    offset.encode(output)?;
+                }
+            }
+            Self::Lookupswitch { default, pairs } => {
+                self.opcode().encode(output)?;
+                output.write_all(&[0; 3][..padding(offset) as usize])?;
+                default.encode(output)?;
+                (pairs.len() as i32).encode(output)?;
+                for (key, offset) in pairs {
+                    key.encode(output)?;
+                    offset.encode(output)?;
+                }
+            }
+            Self::Invokeinterface { index, count } => {
+                self.opcode().encode(output)?;
+                index.encode(output)?;
+                count.encode(output)?;
+                0u8.encode(output)?;
+            }
+            Self::Invokedynamic(index) => {
+                self.opcode().encode(output)?;
+                index.encode(output)?;
+                0u16.encode(output)?;
+            }
+            Self::Newarray(atype) => {
+                self.opcode().encode(output)?;
+                (*atype as u8).encode(output)?;
+            }
+            Self::Multianewarray { index, dimensions } => {
+                self.opcode().encode(output)?;
+                index.encode(output)?;
+                dimensions.encode(output)?;
+            }
+            _ => self.opcode().encode(output)?,
+        }
+        Ok(())
+    }
+}
+
+/// The mnemonics of all opcodes, including the `wide` prefix.
+const MNEMONICS: [&str; 202] = [
+    "nop",
+    "aconst_null",
+    "iconst_m1",
+    "iconst_0",
+    "iconst_1",
+    "iconst_2",
+    "iconst_3",
+    "iconst_4",
+    "iconst_5",
+    "lconst_0",
+    "lconst_1",
+    "fconst_0",
+    "fconst_1",
+    "fconst_2",
+    "dconst_0",
+    "dconst_1",
+    "bipush",
+    "sipush",
+    "ldc",
+    "ldc_w",
+    "ldc2_w",
+    "iload",
+    "lload",
+    "fload",
+    "dload",
+    "aload",
+    "iload_0",
+    "iload_1",
+    "iload_2",
+    "iload_3",
+    "lload_0",
+    "lload_1",
+    "lload_2",
+    "lload_3",
+    "fload_0",
+    "fload_1",
+    "fload_2",
+    "fload_3",
+    "dload_0",
+    "dload_1",
+    "dload_2",
+    "dload_3",
+    "aload_0",
+    "aload_1",
+    "aload_2",
+    "aload_3",
+    "iaload",
+    "laload",
+    "faload",
+    "daload",
+    "aaload",
+    "baload",
+    "caload",
+    "saload",
+    "istore",
+    "lstore",
+    "fstore",
+    "dstore",
+    "astore",
+    "istore_0",
+    "istore_1",
+    "istore_2",
+    "istore_3",
+    "lstore_0",
+    "lstore_1",
+    "lstore_2",
+    "lstore_3",
+    "fstore_0",
+    "fstore_1",
+    "fstore_2",
+    "fstore_3",
+    "dstore_0",
+    "dstore_1",
+    "dstore_2",
+    "dstore_3",
+    "astore_0",
+    "astore_1",
+    "astore_2",
+    "astore_3",
+    "iastore",
+    "lastore",
+    "fastore",
+    "dastore",
+    "aastore",
+    "bastore",
+    "castore",
+    "sastore",
+    "pop",
+    "pop2",
+    "dup",
+    "dup_x1",
+    "dup_x2",
+    "dup2",
+    "dup2_x1",
+    "dup2_x2",
+    "swap",
+    "iadd",
+    "ladd",
+    "fadd",
+    "dadd",
+    "isub",
+    "lsub",
+    "fsub",
+    "dsub",
+    "imul",
+    "lmul",
+    "fmul",
+    "dmul",
+    "idiv",
+    "ldiv",
+    "fdiv",
+    "ddiv",
+    "irem",
+    "lrem",
+    "frem",
+    "drem",
+    "ineg",
+    "lneg",
+    "fneg",
+    "dneg",
+    "ishl",
+    "lshl",
+    "ishr",
+    "lshr",
+    "iushr",
+    "lushr",
+    "iand",
+    "land",
+    "ior",
+    "lor",
+    "ixor",
+    "lxor",
+    "iinc",
+    "i2l",
+    "i2f",
+    "i2d",
+    "l2i",
+    "l2f",
+    "l2d",
+    "f2i",
+    "f2l",
+    "f2d",
+    "d2i",
+    "d2l",
+    "d2f",
+    "i2b",
+    "i2c",
+    "i2s",
+    "lcmp",
+    "fcmpl",
+    "fcmpg",
+    "dcmpl",
+    "dcmpg",
+    "ifeq",
+    "ifne",
+    "iflt",
+    "ifge",
+    "ifgt",
+    "ifle",
+    "if_icmpeq",
+    "if_icmpne",
+    "if_icmplt",
+    "if_icmpge",
+    "if_icmpgt",
+    "if_icmple",
+    "if_acmpeq",
+    "if_acmpne",
+    "goto",
+    "jsr",
+    "ret",
+    "tableswitch",
+    "lookupswitch",
+    "ireturn",
+    "lreturn",
+    "freturn",
+    "dreturn",
+    "areturn",
+    "return",
+    "getstatic",
+    "putstatic",
+    "getfield",
+    "putfield",
+    "invokevirtual",
+    "invokespecial",
+    "invokestatic",
+    "invokeinterface",
+    "invokedynamic",
+    "new",
+    "newarray",
+    "anewarray",
+    "arraylength",
+    "athrow",
+    "checkcast",
+    "instanceof",
+    "monitorenter",
+    "monitorexit",
+    "wide",
+    "multianewarray",
+    "ifnull",
+    "ifnonnull",
+    "goto_w",
+    "jsr_w",
+];
+
+/// Returns the number of padding bytes following a switch opcode at `offset`,
+/// which align the operands to a multiple of four.
+pub(crate) fn padding(offset: u32) -> u32 {
+    3 - offset % 4
+}
+
+/// The element type of `newarray`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayType {
+    Boolean = 4,
+    Char = 5,
+    Float = 6,
+    Double = 7,
+    Byte = 8,
+    Short = 9,
+    Int = 10,
+    Long = 11,
+}
+
+impl ArrayType {
+    pub fn from_atype(atype: u8) -> Option<Self> {
+        Some(match atype {
+            4 => Self::Boolean,
+            5 => Self::Char,
+            6 => Self::Float,
+            7 => Self::Double,
+            8 => Self::Byte,
+            9 => Self::Short,
+            10 => Self::Int,
+            11 => Self::Long,
+            _ => return None,
+        })
+    }
+
+    /// Returns the name of the element type as written in Java source.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Boolean => "boolean",
+            Self::Char => "char",
+            Self::Float => "float",
+            Self::Double => "double",
+            Self::Byte => "byte",
+            Self::Short => "short",
+            Self::Int => "int",
+            Self::Long => "long",
+        }
+    }
+}
+
+/// Returns the opcode of an instruction by its mnemonic, `wide` included.
+pub(crate) fn opcode(mnemonic: &str) -> Option<u8> {
+    MNEMONICS
+        .iter()
+        .position(|candidate| *candidate == mnemonic)
+        .map(|opcode| opcode as u8)
+}
+
+/// Decodes all instructions of a method's code together with their offsets.
+pub fn decode(code: &[u8]) -> Result<Vec<(u32, Instruction)>> {
+    let mut input = code;
+    let mut instructions = vec![];
+    while !input.is_empty() {
+        let offset = (code.len() - input.len()) as u32;
+        let instruction =
+            decode_with(code, &mut input, |input| Instruction::decode(input, offset))?;
+        instructions.push((offset, instruction));
+    }
+    Ok(instructions)
+}
+
+/// Encodes instructions into a method's code.
+pub fn encode<'a>(instructions: impl IntoIterator<Item = &'a Instruction>) -> Result<Vec<u8>> {
+    let mut output = Cursor::new(vec![]);
+    for instruction in instructions {
+        let offset = output.position() as u32;
+        instruction.encode(&mut output, offset)?;
+    }
+    Ok(output.into_inner())
+}
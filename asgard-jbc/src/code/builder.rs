@@ -0,0 +1,411 @@
+use std::{
+    io::Cursor,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    class::attribute::ExceptionTableEntry,
+    code::{padding, Instruction},
+    Error, Result,
+};
+
+/// A position in the code of a [`CodeBuilder`], which can be referenced before
+/// it is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label {
+    builder: usize,
+    index: usize,
+}
+
+/// Identifies code builders, so that labels of other builders are rejected.
+static NEXT_BUILDER: AtomicUsize = AtomicUsize::new(0);
+
+/// The instructions which branch to a single target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jump {
+    Ifeq,
+    Ifne,
+    Iflt,
+    Ifge,
+    Ifgt,
+    Ifle,
+    IfIcmpeq,
+    IfIcmpne,
+    IfIcmplt,
+    IfIcmpge,
+    IfIcmpgt,
+    IfIcmple,
+    IfAcmpeq,
+    IfAcmpne,
+    Goto,
+    Jsr,
+    Ifnull,
+    Ifnonnull,
+}
+
+impl Jump {
+    pub fn instruction(self, branch: i16) -> Instruction {
+        match self {
+            Self::Ifeq => Instruction::Ifeq(branch),
+            Self::Ifne => Instruction::Ifne(branch),
+            Self::Iflt => Instruction::Iflt(branch),
+            Self::Ifge => Instruction::Ifge(branch),
+            Self::Ifgt => Instruction::Ifgt(branch),
+            Self::Ifle => Instruction::Ifle(branch),
+            Self::IfIcmpeq => Instruction::IfIcmpeq(branch),
+            Self::IfIcmpne => Instruction::IfIcmpne(branch),
+            Self::IfIcmplt => Instruction::IfIcmplt(branch),
+            Self::IfIcmpge => Instruction::IfIcmpge(branch),
+            Self::IfIcmpgt => Instruction::IfIcmpgt(branch),
+            Self::IfIcmple => Instruction::IfIcmple(branch),
+            Self::IfAcmpeq => Instruction::IfAcmpeq(branch),
+            Self::IfAcmpne => Instruction::IfAcmpne(branch),
+            Self::Goto => Instruction::Goto(branch),
+            Self::Jsr => Instruction::Jsr(branch),
+            Self::Ifnull => Instruction::Ifnull(branch),
+            Self::Ifnonnull => Instruction::Ifnonnull(branch),
+        }
+    }
+
+    /// Returns the jump and the branch offset of an instruction, or `None` if
+    /// the instruction does not branch to a single target. `goto_w` and
+    /// `jsr_w` are returned as [`Jump::Goto`] and [`Jump::Jsr`].
+    pub fn from_instruction(instruction: &Instruction) -> Option<(Self, i32)> {
+        Some(match *instruction {
+            Instruction::Ifeq(branch) => (Self::Ifeq, branch as i32),
+            Instruction::Ifne(branch) => (Self::Ifne, branch as i32),
+            Instruction::Iflt(branch) => (Self::Iflt, branch as i32),
+            Instruction::Ifge(branch) => (Self::Ifge, branch as i32),
+            Instruction::Ifgt(branch) => (Self::Ifgt, branch as i32),
+            Instruction::Ifle(branch) => (Self::Ifle, branch as i32),
+            Instruction::IfIcmpeq(branch) => (Self::IfIcmpeq, branch as i32),
+            Instruction::IfIcmpne(branch) => (Self::IfIcmpne, branch as i32),
+            Instruction::IfIcmplt(branch) => (Self::IfIcmplt, branch as i32),
+            Instruction::IfIcmpge(branch) => (Self::IfIcmpge, branch as i32),
+            Instruction::IfIcmpgt(branch) => (Self::IfIcmpgt, branch as i32),
+            Instruction::IfIcmple(branch) => (Self::IfIcmple, branch as i32),
+            Instruction::IfAcmpeq(branch) => (Self::IfAcmpeq, branch as i32),
+            Instruction::IfAcmpne(branch) => (Self::IfAcmpne, branch as i32),
+            Instruction::Goto(branch) => (Self::Goto, branch as i32),
+            Instruction::Jsr(branch) => (Self::Jsr, branch as i32),
+            Instruction::Ifnull(branch) => (Self::Ifnull, branch as i32),
+            Instruction::Ifnonnull(branch) => (Self::Ifnonnull, branch as i32),
+            Instruction::GotoW(branch) => (Self::Goto, branch),
+            Instruction::JsrW(branch) => (Self::Jsr, branch),
+            _ => return None,
+        })
+    }
+
+    /// Returns the jump with the negated condition, or `None` for
+    /// unconditional jumps.
+    pub fn inverse(self) -> Option<Self> {
+        Some(match self {
+            Self::Ifeq => Self::Ifne,
+            Self::Ifne => Self::Ifeq,
+            Self::Iflt => Self::Ifge,
+            Self::Ifge => Self::Iflt,
+            Self::Ifgt => Self::Ifle,
+            Self::Ifle => Self::Ifgt,
+            Self::IfIcmpeq => Self::IfIcmpne,
+            Self::IfIcmpne => Self::IfIcmpeq,
+            Self::IfIcmplt => Self::IfIcmpge,
+            Self::IfIcmpge => Self::IfIcmplt,
+            Self::IfIcmpgt => Self::IfIcmple,
+            Self::IfIcmple => Self::IfIcmpgt,
+            Self::IfAcmpeq => Self::IfAcmpne,
+            Self::IfAcmpne => Self::IfAcmpeq,
+            Self::Ifnull => Self::Ifnonnull,
+            Self::Ifnonnull => Self::Ifnull,
+            Self::Goto | Self::Jsr => return None,
+        })
+    }
+
+    /// Returns the length of the jump, which is longer if the target is out of
+    /// range of a 16-bit offset.
+    fn length(self, wide: bool) -> u32 {
+        match (self, wide) {
+            (_, false) => 3,
+            (Self::Goto | Self::Jsr, true) => 5,
+            // inverted condition jumping over a goto_w
+            (_, true) => 8,
+        }
+    }
+}
+
+enum Item {
+    Instruction(Instruction),
+    Jump {
+        jump: Jump,
+        target: Label,
+    },
+    Tableswitch {
+        default: Label,
+        low: i32,
+        targets: Vec<Label>,
+    },
+    Lookupswitch {
+        default: Label,
+        pairs: Vec<(i32, Label)>,
+    },
+}
+
+impl Item {
+    fn length(&self, offset: u32, wide: bool) -> u32 {
+        match self {
+            Self::Instruction(instruction) => instruction.length(offset),
+            Self::Jump { jump, .. } => jump.length(wide),
+            Self::Tableswitch { targets, .. } => {
+                1 + padding(offset) + 12 + 4 * targets.len() as u32
+            }
+            Self::Lookupswitch { pairs, .. } => 1 + padding(offset) + 8 + 8 * pairs.len() as u32,
+        }
+    }
+}
+
+/// The code of a method as assembled by a [`CodeBuilder`].
+#[derive(Debug, Clone)]
+pub struct AssembledCode {
+    pub code: Vec<u8>,
+    pub exception_table: Vec<ExceptionTableEntry>,
+    builder: usize,
+    label_offsets: Vec<Option<u32>>,
+}
+
+impl AssembledCode {
+    /// Returns the offset a label has been placed at, `None` for labels of
+    /// other builders.
+    pub fn offset(&self, label: Label) -> Option<u32> {
+        if label.builder != self.builder {
+            return None;
+        }
+        self.label_offsets[label.index]
+    }
+}
+
+/// Assembles the code of a method from instructions which reference labels
+/// instead of offsets.
+///
+/// Branch offsets are resolved when building, jumps which are out of range of
+/// a 16-bit offset are replaced with `goto_w` and `jsr_w`, conditional jumps
+/// by jumping over a `goto_w` with the inverted condition.
+pub struct CodeBuilder {
+    id: usize,
+    items: Vec<Item>,
+    /// Index of the item each label has been placed before.
+    labels: Vec<Option<usize>>,
+    exception_table: Vec<(Label, Label, Label, u16)>,
+    /// The error of the first invalid placement of a label, reported when
+    /// building.
+    misplaced_label: Option<fn() -> Error>,
+}
+
+impl Default for CodeBuilder {
+    fn default() -> Self {
+        Self {
+            id: NEXT_BUILDER.fetch_add(1, Ordering::Relaxed),
+            items: vec![],
+            labels: vec![],
+            exception_table: vec![],
+            misplaced_label: None,
+        }
+    }
+}
+
+impl CodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label {
+            builder: self.id,
+            index: self.labels.len() - 1,
+        }
+    }
+
+    /// Places a label before the next instruction. Placing a label twice, or
+    /// one of another builder, fails when building.
+    pub fn place_label(&mut self, label: Label) -> &mut Self {
+        let placed = match self.label(label) {
+            Ok(None) => {
+                self.labels[label.index] = Some(self.items.len());
+                return self;
+            }
+            Ok(Some(_)) => || Error::DuplicateLabel,
+            Err(_) => || Error::ForeignLabel,
+        };
+        self.misplaced_label.get_or_insert(placed);
+        self
+    }
+
+    /// Appends an instruction, branch offsets of which are taken as is.
+    pub fn instruction(&mut self, instruction: Instruction) -> &mut Self {
+        self.items.push(Item::Instruction(instruction));
+        self
+    }
+
+    pub fn jump(&mut self, jump: Jump, target: Label) -> &mut Self {
+        self.items.push(Item::Jump { jump, target });
+        self
+    }
+
+    pub fn tableswitch(&mut self, default: Label, low: i32, targets: Vec<Label>) -> &mut Self {
+        self.items.push(Item::Tableswitch {
+            default,
+            low,
+            targets,
+        });
+        self
+    }
+
+    pub fn lookupswitch(&mut self, default: Label, mut pairs: Vec<(i32, Label)>) -> &mut Self {
+        // keys have to be sorted in increasing order
+        pairs.sort_by_key(|(key, _)| *key);
+        self.items.push(Item::Lookupswitch { default, pairs });
+        self
+    }
+
+    /// Adds an exception handler for the code from `start` (inclusive) to
+    /// `end` (exclusive), `catch_type` being the constant pool index of the
+    /// caught class or 0 for any.
+    pub fn exception_handler(
+        &mut self,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_type: u16,
+    ) -> &mut Self {
+        self.exception_table.push((start, end, handler, catch_type));
+        self
+    }
+
+    pub fn build(&self) -> Result<AssembledCode> {
+        if let Some(error) = self.misplaced_label {
+            return Err(error());
+        }
+        // jumps only ever get widened, therefore this terminates
+        let mut wide = vec![false; self.items.len()];
+        let offsets = loop {
+            let offsets = self.layout(&wide);
+            let mut changed = false;
+            for (index, item) in self.items.iter().enumerate() {
+                if let Item::Jump { target, .. } = item {
+                    if !wide[index] {
+                        let branch = self.offset(&offsets, *target)? as i64 - offsets[index] as i64;
+                        if i16::try_from(branch).is_err() {
+                            wide[index] = true;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if !changed {
+                break offsets;
+            }
+        };
+        let length = offsets[self.items.len()];
+        if length > u16::MAX as u32 {
+            return Err(Error::CodeTooLarge(length));
+        }
+
+        let mut output = Cursor::new(Vec::with_capacity(length as usize));
+        for (index, item) in self.items.iter().enumerate() {
+            let offset = offsets[index];
+            let branch = |target: Label| -> Result<i32> {
+                Ok(self.offset(&offsets, target)? as i32 - offset as i32)
+            };
+            match item {
+                Item::Instruction(instruction) => instruction.encode(&mut output, offset)?,
+                Item::Jump { jump, target } => match (wide[index], jump.inverse()) {
+                    (false, _) => jump
+                        .instruction(branch(*target)? as i16)
+                        .encode(&mut output, offset)?,
+                    (true, None) => {
+                        let instruction = if *jump == Jump::Goto {
+                            Instruction::GotoW(branch(*target)?)
+                        } else {
+                            Instruction::JsrW(branch(*target)?)
+                        };
+                        instruction.encode(&mut output, offset)?;
+                    }
+                    (true, Some(inverse)) => {
+                        inverse.instruction(8).encode(&mut output, offset)?;
+                        Instruction::GotoW(branch(*target)? - 3).encode(&mut output, offset + 3)?;
+                    }
+                },
+                Item::Tableswitch {
+                    default,
+                    low,
+                    targets,
+                } => Instruction::Tableswitch {
+                    default: branch(*default)?,
+                    low: *low,
+                    offsets: targets
+                        .iter()
+                        .map(|target| branch(*target))
+                        .collect::<Result<_>>()?,
+                }
+                .encode(&mut output, offset)?,
+                Item::Lookupswitch { default, pairs } => Instruction::Lookupswitch {
+                    default: branch(*default)?,
+                    pairs: pairs
+                        .iter()
+                        .map(|(key, target)| Ok((*key, branch(*target)?)))
+                        .collect::<Result<_>>()?,
+                }
+                .encode(&mut output, offset)?,
+            }
+        }
+
+        let exception_table = self
+            .exception_table
+            .iter()
+            .map(|(start, end, handler, catch_type)| {
+                Ok(ExceptionTableEntry {
+                    start_pc: self.offset(&offsets, *start)? as u16,
+                    end_pc: self.offset(&offsets, *end)? as u16,
+                    handler_pc: self.offset(&offsets, *handler)? as u16,
+                    catch_type: *catch_type,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let label_offsets = self
+            .labels
+            .iter()
+            .map(|index| index.map(|index| offsets[index]))
+            .collect();
+        Ok(AssembledCode {
+            code: output.into_inner(),
+            exception_table,
+            builder: self.id,
+            label_offsets,
+        })
+    }
+
+    /// Returns the offset of each item, followed by the total length.
+    fn layout(&self, wide: &[bool]) -> Vec<u32> {
+        let mut offsets = Vec::with_capacity(self.items.len() + 1);
+        let mut offset = 0;
+        for (item, wide) in self.items.iter().zip(wide) {
+            offsets.push(offset);
+            offset += item.length(offset, *wide);
+        }
+        offsets.push(offset);
+        offsets
+    }
+
+    fn offset(&self, offsets: &[u32], label: Label) -> Result<u32> {
+        self.label(label)?
+            .map(|index| offsets[index])
+            .ok_or(Error::UnplacedLabel)
+    }
+
+    /// Returns the index of the item a label has been placed before.
+    fn label(&self, label: Label) -> Result<Option<usize>> {
+        if label.builder != self.id {
+            return Err(Error::ForeignLabel);
+        }
+        Ok(self.labels[label.index])
+    }
+}
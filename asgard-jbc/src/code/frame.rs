@@ -0,0 +1,1065 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    class::{
+        attribute::{CodeAttribute, StackMapFrame, StackMapTableAttribute, VerificationTypeInfo},
+        ConstantLookup, ConstantPool, ConstantPoolInfo, MethodAccessFlags, MethodInfo,
+    },
+    code::{ArrayType, Instruction},
+    descriptor::{FieldType, MethodDescriptor},
+    Error, Result,
+};
+
+/// The type of a local variable or operand stack entry as seen by the
+/// verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    /// A class given by its internal name, or an array given by its
+    /// descriptor.
+    Object(String),
+    /// An object created by the `new` instruction at the given offset, the
+    /// constructor of which has not been invoked yet.
+    Uninitialized(u32),
+}
+
+impl VerificationType {
+    pub fn from_field_type(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Byte
+            | FieldType::Char
+            | FieldType::Int
+            | FieldType::Short
+            | FieldType::Boolean => Self::Integer,
+            FieldType::Float => Self::Float,
+            FieldType::Long => Self::Long,
+            FieldType::Double => Self::Double,
+            FieldType::Object(class_name) => Self::Object(class_name.clone()),
+            FieldType::Array(_) => Self::Object(field_type.to_string()),
+            FieldType::Void => Self::Top,
+        }
+    }
+
+    /// Returns whether values of this type take up two local variables or
+    /// operand stack entries.
+    pub fn is_wide(&self) -> bool {
+        matches!(self, Self::Long | Self::Double)
+    }
+}
+
+/// The types of the local variables and operand stack entries at an offset,
+/// as written to the `StackMapTable` attribute: `Long` and `Double` are only
+/// listed once, and trailing `Top` locals are omitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub offset: u32,
+    pub locals: Vec<VerificationType>,
+    pub stack: Vec<VerificationType>,
+}
+
+#[derive(Debug)]
+pub struct Analysis {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    /// Frames at all offsets which require one, in increasing order.
+    pub frames: Vec<Frame>,
+    /// Locals of the implicit initial frame, as given by the descriptor.
+    initial_locals: Vec<VerificationType>,
+}
+
+impl Analysis {
+    /// Builds the `StackMapTable` attribute of the frames, using the most
+    /// compact frame types. `class_index` returns the index of the constant
+    /// pool `Class` entry for a class name or array descriptor.
+    pub fn stack_map_table(
+        &self,
+        mut class_index: impl FnMut(&str) -> Result<u16>,
+    ) -> Result<StackMapTableAttribute> {
+        let mut verification_type_info = |r#type: &VerificationType| -> Result<_> {
+            Ok(match r#type {
+                VerificationType::Top => VerificationTypeInfo::Top,
+                VerificationType::Integer => VerificationTypeInfo::Integer,
+                VerificationType::Float => VerificationTypeInfo::Float,
+                VerificationType::Long => VerificationTypeInfo::Long,
+                VerificationType::Double => VerificationTypeInfo::Double,
+                VerificationType::Null => VerificationTypeInfo::Null,
+                VerificationType::UninitializedThis => VerificationTypeInfo::UninitializedThis,
+                VerificationType::Object(class_name) => VerificationTypeInfo::Object {
+                    cpool_index: class_index(class_name)?,
+                },
+                VerificationType::Uninitialized(offset) => VerificationTypeInfo::Uninitialized {
+                    offset: *offset as u16,
+                },
+            })
+        };
+
+        let mut entries = Vec::with_capacity(self.frames.len());
+        let mut previous_offset = None;
+        let mut previous_locals = &self.initial_locals;
+        for frame in &self.frames {
+            let offset_delta = match previous_offset {
+                Some(previous_offset) => frame.offset - previous_offset - 1,
+                None => frame.offset,
+            } as u16;
+            let locals = &frame.locals;
+            let entry = if frame.stack.is_empty() && locals == previous_locals {
+                if offset_delta < 64 {
+                    StackMapFrame::Same { offset_delta }
+                } else {
+                    StackMapFrame::SameExtended { offset_delta }
+                }
+            } else if frame.stack.len() == 1 && locals == previous_locals {
+                let stack = verification_type_info(&frame.stack[0])?;
+                if offset_delta < 64 {
+                    StackMapFrame::SameLocals1StackItem {
+                        offset_delta,
+                        stack,
+                    }
+                } else {
+                    StackMapFrame::SameLocals1StackItemExtended {
+                        offset_delta,
+                        stack,
+                    }
+                }
+            } else if frame.stack.is_empty()
+                && locals.len() < previous_locals.len()
+                && previous_locals.len() - locals.len() <= 3
+                && previous_locals.starts_with(locals)
+            {
+                StackMapFrame::Chop {
+                    k: (previous_locals.len() - locals.len()) as u8,
+                    offset_delta,
+                }
+            } else if frame.stack.is_empty()
+                && locals.len() > previous_locals.len()
+                && locals.len() - previous_locals.len() <= 3
+                && locals.starts_with(previous_locals)
+            {
+                StackMapFrame::Append {
+                    offset_delta,
+                    locals: locals[previous_locals.len()..]
+                        .iter()
+                        .map(&mut verification_type_info)
+                        .collect::<Result<_>>()?,
+                }
+            } else {
+                StackMapFrame::Full {
+                    offset_delta,
+                    locals: locals
+                        .iter()
+                        .map(&mut verification_type_info)
+                        .collect::<Result<_>>()?,
+                    stack: frame
+                        .stack
+                        .iter()
+                        .map(&mut verification_type_info)
+                        .collect::<Result<_>>()?,
+                }
+            };
+            entries.push(entry);
+            previous_offset = Some(frame.offset);
+            previous_locals = locals;
+        }
+        Ok(StackMapTableAttribute(entries))
+    }
+}
+
+/// Provides the class hierarchy needed to merge reference types.
+pub trait ClassHierarchy {
+    /// Returns the internal name of the most specific common super class of
+    /// two classes, `java/lang/Object` if either is an interface.
+    fn common_super_class(&self, a: &str, b: &str) -> String;
+}
+
+impl<F: Fn(&str, &str) -> String> ClassHierarchy for F {
+    fn common_super_class(&self, a: &str, b: &str) -> String {
+        self(a, b)
+    }
+}
+
+/// Computes `max_stack`, `max_locals` and the stack map frames of a method of
+/// the class `class_name` by data-flow analysis.
+///
+/// Subroutines (`jsr` and `ret`) are not supported, as they are not allowed in
+/// class files which require stack map frames.
+///
+/// Unreachable code fails with [`Error::Verification`] if it needs a frame,
+/// e.g. following a `goto` or `return`, as its types are unknown. Compilers
+/// emitting dead code have to remove it, or replace it with `nop`s ending in
+/// an `athrow`.
+pub fn analyze(
+    class_name: &str,
+    method: &MethodInfo,
+    code: &CodeAttribute,
+    constant_pool: &ConstantPool,
+    hierarchy: &(impl ClassHierarchy + ?Sized),
+) -> Result<Analysis> {
+    let name = method.name(constant_pool)?;
+    let descriptor = method.descriptor(constant_pool)?;
+    // the `wide` prefix does not matter to the verifier
+    let instructions: Vec<_> = code
+        .instructions()?
+        .into_iter()
+        .map(|(offset, instruction)| match instruction {
+            Instruction::Wide(instruction) => (offset, *instruction),
+            instruction => (offset, instruction),
+        })
+        .collect();
+
+    let mut locals = vec![];
+    if !method.access_flags.contains(MethodAccessFlags::STATIC) {
+        locals.push(if name == "<init>" && class_name != "java/lang/Object" {
+            VerificationType::UninitializedThis
+        } else {
+            VerificationType::Object(class_name.to_owned())
+        });
+    }
+    for parameter_type in &descriptor.parameter_types {
+        let parameter_type = VerificationType::from_field_type(parameter_type);
+        let wide = parameter_type.is_wide();
+        locals.push(parameter_type);
+        if wide {
+            locals.push(VerificationType::Top);
+        }
+    }
+    let max_locals = instructions
+        .iter()
+        .filter_map(|(_, instruction)| match instruction {
+            Instruction::Iinc { index, .. } => Some(index + 1),
+            _ => local_access(instruction).map(|(kind, index, _)| index + kind.size()),
+        })
+        .chain([locals.len() as u16])
+        .max()
+        .unwrap_or_default();
+    let initial_locals = compact(&locals);
+    locals.resize(max_locals as usize, VerificationType::Top);
+
+    let analyzer = Analyzer {
+        class_name,
+        constant_pool,
+        hierarchy,
+        instructions: &instructions,
+    };
+    let mut states = vec![None; instructions.len()];
+    let mut worklist = vec![];
+    if !instructions.is_empty() {
+        states[0] = Some(State {
+            locals,
+            stack: vec![],
+        });
+        worklist.push(0);
+    }
+    let mut frame_indices = BTreeSet::new();
+    for handler in &code.exception_table {
+        frame_indices.insert(analyzer.index(handler.handler_pc as u32)?);
+    }
+    let mut max_stack = 0;
+    while let Some(index) = worklist.pop() {
+        let (offset, instruction) = &instructions[index];
+        let mut state: State = states[index].clone().unwrap();
+
+        for handler in &code.exception_table {
+            if (handler.start_pc as u32..handler.end_pc as u32).contains(offset) {
+                let catch_type = if handler.catch_type == 0 {
+                    "java/lang/Throwable"
+                } else {
+                    constant_pool.class_name(handler.catch_type)?
+                };
+                let handler_state = State {
+                    locals: state.locals.clone(),
+                    stack: vec![VerificationType::Object(catch_type.to_owned())],
+                };
+                let handler_index = analyzer.index(handler.handler_pc as u32)?;
+                if analyzer.merge(
+                    handler.handler_pc as u32,
+                    &mut states[handler_index],
+                    &handler_state,
+                )? {
+                    worklist.push(handler_index);
+                }
+                max_stack = max_stack.max(1);
+            }
+        }
+
+        let successors = analyzer.execute(*offset, instruction, &mut state)?;
+        max_stack = max_stack.max(state.stack.len());
+        let mut successor_indices = vec![];
+        if successors.fall_through {
+            if index + 1 == instructions.len() {
+                return Err(verification_error(
+                    *offset,
+                    "falling off the end of the code",
+                ));
+            }
+            successor_indices.push(index + 1);
+        } else if index + 1 < instructions.len() {
+            frame_indices.insert(index + 1);
+        }
+        for branch in successors.branches {
+            let target = analyzer.index((*offset as i64 + branch as i64) as u32)?;
+            frame_indices.insert(target);
+            successor_indices.push(target);
+        }
+        for successor_index in successor_indices {
+            if analyzer.merge(
+                instructions[successor_index].0,
+                &mut states[successor_index],
+                &state,
+            )? {
+                worklist.push(successor_index);
+            }
+        }
+    }
+
+    let frames = frame_indices
+        .into_iter()
+        .map(|index| {
+            let offset = instructions[index].0;
+            let state = states[index]
+                .as_ref()
+                .ok_or_else(|| verification_error(offset, "unreachable code"))?;
+            let mut locals = compact(&state.locals);
+            while locals.last() == Some(&VerificationType::Top) {
+                locals.pop();
+            }
+            Ok(Frame {
+                offset,
+                locals,
+                stack: compact(&state.stack),
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Analysis {
+        max_stack: max_stack as u16,
+        max_locals,
+        frames,
+        initial_locals,
+    })
+}
+
+fn verification_error(offset: u32, reason: &'static str) -> Error {
+    Error::Verification { offset, reason }
+}
+
+/// Lists `Long` and `Double` only once, dropping the following `Top`.
+fn compact(types: &[VerificationType]) -> Vec<VerificationType> {
+    let mut compacted = vec![];
+    let mut types = types.iter();
+    while let Some(r#type) = types.next() {
+        if r#type.is_wide() {
+            types.next();
+        }
+        compacted.push(r#type.clone());
+    }
+    compacted
+}
+
+/// The types of the local variables and operand stack entries, with `Long`
+/// and `Double` taking up two entries, the second being `Top`.
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    locals: Vec<VerificationType>,
+    stack: Vec<VerificationType>,
+}
+
+impl State {
+    fn push(&mut self, r#type: VerificationType) {
+        let wide = r#type.is_wide();
+        self.stack.push(r#type);
+        if wide {
+            self.stack.push(VerificationType::Top);
+        }
+    }
+
+    fn pop(&mut self, offset: u32, length: usize) -> Result<()> {
+        if self.stack.len() < length {
+            return Err(verification_error(offset, "operand stack underflow"));
+        }
+        self.stack.truncate(self.stack.len() - length);
+        Ok(())
+    }
+
+    fn pop_reference(&mut self, offset: u32) -> Result<VerificationType> {
+        match self.stack.pop() {
+            Some(VerificationType::Top) => Err(verification_error(
+                offset,
+                "expected reference on the operand stack",
+            )),
+            Some(r#type) => Ok(r#type),
+            None => Err(verification_error(offset, "operand stack underflow")),
+        }
+    }
+
+    fn set_local(&mut self, offset: u32, index: u16, r#type: VerificationType) -> Result<()> {
+        let index = index as usize;
+        let wide = r#type.is_wide();
+        if index + wide as usize >= self.locals.len() {
+            return Err(verification_error(offset, "local variable out of range"));
+        }
+        // overwriting the second half of a long or double invalidates it
+        if index > 0 && self.locals[index - 1].is_wide() {
+            self.locals[index - 1] = VerificationType::Top;
+        }
+        self.locals[index] = r#type;
+        if wide {
+            self.locals[index + 1] = VerificationType::Top;
+        }
+        Ok(())
+    }
+}
+
+struct Successors {
+    fall_through: bool,
+    branches: Vec<i32>,
+}
+
+impl Successors {
+    fn next() -> Self {
+        Self {
+            fall_through: true,
+            branches: vec![],
+        }
+    }
+
+    fn none() -> Self {
+        Self {
+            fall_through: false,
+            branches: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Integer,
+    Float,
+    Long,
+    Double,
+    Reference,
+}
+
+impl Kind {
+    fn size(self) -> u16 {
+        match self {
+            Self::Long | Self::Double => 2,
+            _ => 1,
+        }
+    }
+
+    fn verification_type(self) -> VerificationType {
+        match self {
+            Self::Integer => VerificationType::Integer,
+            Self::Float => VerificationType::Float,
+            Self::Long => VerificationType::Long,
+            Self::Double => VerificationType::Double,
+            Self::Reference => unreachable!(),
+        }
+    }
+}
+
+/// Returns the kind and index of the local variable accessed by a load or
+/// store instruction, and whether it is a store.
+fn local_access(instruction: &Instruction) -> Option<(Kind, u16, bool)> {
+    Some(match *instruction {
+        Instruction::Iload(index) => (Kind::Integer, index, false),
+        Instruction::Lload(index) => (Kind::Long, index, false),
+        Instruction::Fload(index) => (Kind::Float, index, false),
+        Instruction::Dload(index) => (Kind::Double, index, false),
+        Instruction::Aload(index) => (Kind::Reference, index, false),
+        Instruction::Iload0 => (Kind::Integer, 0, false),
+        Instruction::Iload1 => (Kind::Integer, 1, false),
+        Instruction::Iload2 => (Kind::Integer, 2, false),
+        Instruction::Iload3 => (Kind::Integer, 3, false),
+        Instruction::Lload0 => (Kind::Long, 0, false),
+        Instruction::Lload1 => (Kind::Long, 1, false),
+        Instruction::Lload2 => (Kind::Long, 2, false),
+        Instruction::Lload3 => (Kind::Long, 3, false),
+        Instruction::Fload0 => (Kind::Float, 0, false),
+        Instruction::Fload1 => (Kind::Float, 1, false),
+        Instruction::Fload2 => (Kind::Float, 2, false),
+        Instruction::Fload3 => (Kind::Float, 3, false),
+        Instruction::Dload0 => (Kind::Double, 0, false),
+        Instruction::Dload1 => (Kind::Double, 1, false),
+        Instruction::Dload2 => (Kind::Double, 2, false),
+        Instruction::Dload3 => (Kind::Double, 3, false),
+        Instruction::Aload0 => (Kind::Reference, 0, false),
+        Instruction::Aload1 => (Kind::Reference, 1, false),
+        Instruction::Aload2 => (Kind::Reference, 2, false),
+        Instruction::Aload3 => (Kind::Reference, 3, false),
+        Instruction::Istore(index) => (Kind::Integer, index, true),
+        Instruction::Lstore(index) => (Kind::Long, index, true),
+        Instruction::Fstore(index) => (Kind::Float, index, true),
+        Instruction::Dstore(index) => (Kind::Double, index, true),
+        Instruction::Astore(index) => (Kind::Reference, index, true),
+        Instruction::Istore0 => (Kind::Integer, 0, true),
+        Instruction::Istore1 => (Kind::Integer, 1, true),
+        Instruction::Istore2 => (Kind::Integer, 2, true),
+        Instruction::Istore3 => (Kind::Integer, 3, true),
+        Instruction::Lstore0 => (Kind::Long, 0, true),
+        Instruction::Lstore1 => (Kind::Long, 1, true),
+        Instruction::Lstore2 => (Kind::Long, 2, true),
+        Instruction::Lstore3 => (Kind::Long, 3, true),
+        Instruction::Fstore0 => (Kind::Float, 0, true),
+        Instruction::Fstore1 => (Kind::Float, 1, true),
+        Instruction::Fstore2 => (Kind::Float, 2, true),
+        Instruction::Fstore3 => (Kind::Float, 3, true),
+        Instruction::Dstore0 => (Kind::Double, 0, true),
+        Instruction::Dstore1 => (Kind::Double, 1, true),
+        Instruction::Dstore2 => (Kind::Double, 2, true),
+        Instruction::Dstore3 => (Kind::Double, 3, true),
+        Instruction::Astore0 => (Kind::Reference, 0, true),
+        Instruction::Astore1 => (Kind::Reference, 1, true),
+        Instruction::Astore2 => (Kind::Reference, 2, true),
+        Instruction::Astore3 => (Kind::Reference, 3, true),
+        _ => return None,
+    })
+}
+
+struct Analyzer<'a, H: ?Sized> {
+    class_name: &'a str,
+    constant_pool: &'a ConstantPool,
+    hierarchy: &'a H,
+    instructions: &'a [(u32, Instruction)],
+}
+
+impl<H: ClassHierarchy + ?Sized> Analyzer<'_, H> {
+    fn index(&self, offset: u32) -> Result<usize> {
+        self.instructions
+            .binary_search_by_key(&offset, |(offset, _)| *offset)
+            .map_err(|_| verification_error(offset, "branch into the middle of an instruction"))
+    }
+
+    /// Merges `state` into the state of the instruction at `offset`, returning
+    /// whether it changed.
+    fn merge(&self, offset: u32, into: &mut Option<State>, state: &State) -> Result<bool> {
+        let Some(into) = into else {
+            *into = Some(state.clone());
+            return Ok(true);
+        };
+        if into.stack.len() != state.stack.len() {
+            return Err(verification_error(
+                offset,
+                "inconsistent operand stack height",
+            ));
+        }
+        let mut changed = false;
+        for (into, r#type) in into.locals.iter_mut().zip(&state.locals) {
+            let merged = self.merge_type(into, r#type);
+            if merged != *into {
+                *into = merged;
+                changed = true;
+            }
+        }
+        for (into, r#type) in into.stack.iter_mut().zip(&state.stack) {
+            let merged = self.merge_type(into, r#type);
+            if merged != *into {
+                if merged == VerificationType::Top {
+                    return Err(verification_error(
+                        offset,
+                        "incompatible operand stack types",
+                    ));
+                }
+                *into = merged;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    fn merge_type(&self, a: &VerificationType, b: &VerificationType) -> VerificationType {
+        match (a, b) {
+            _ if a == b => a.clone(),
+            (VerificationType::Null, VerificationType::Object(_)) => b.clone(),
+            (VerificationType::Object(_), VerificationType::Null) => a.clone(),
+            (VerificationType::Object(a), VerificationType::Object(b)) => {
+                VerificationType::Object(self.common_super_type(a, b))
+            }
+            _ => VerificationType::Top,
+        }
+    }
+
+    fn common_super_type(&self, a: &str, b: &str) -> String {
+        match (a.strip_prefix('['), b.strip_prefix('[')) {
+            (Some(a), Some(b)) => {
+                let component_name = |descriptor: &str| -> Option<String> {
+                    match descriptor.as_bytes()[0] {
+                        b'L' => Some(descriptor[1..descriptor.len() - 1].to_owned()),
+                        b'[' => Some(descriptor.to_owned()),
+                        _ => None,
+                    }
+                };
+                match (component_name(a), component_name(b)) {
+                    (Some(a), Some(b)) => {
+                        let component_name = self.common_super_type(&a, &b);
+                        if component_name.starts_with('[') {
+                            format!("[{component_name}")
+                        } else {
+                            format!("[L{component_name};")
+                        }
+                    }
+                    _ => "java/lang/Object".to_owned(),
+                }
+            }
+            (None, None) => self.hierarchy.common_super_class(a, b),
+            _ => "java/lang/Object".to_owned(),
+        }
+    }
+
+    fn constant_type(&self, index: u16) -> Result<VerificationType> {
+        let object = |class_name: &str| VerificationType::Object(class_name.to_owned());
+        Ok(
+            match self
+                .constant_pool
+                .get(index)
+                .ok_or(Error::InvalidConstantPoolIndex(index))?
+            {
+                ConstantPoolInfo::Integer(_) => VerificationType::Integer,
+                ConstantPoolInfo::Float(_) => VerificationType::Float,
+                ConstantPoolInfo::Long(_) => VerificationType::Long,
+                ConstantPoolInfo::Double(_) => VerificationType::Double,
+                ConstantPoolInfo::Class { .. } => object("java/lang/Class"),
+                ConstantPoolInfo::String { .. } => object("java/lang/String"),
+                ConstantPoolInfo::MethodHandle { .. } => object("java/lang/invoke/MethodHandle"),
+                ConstantPoolInfo::MethodType { .. } => object("java/lang/invoke/MethodType"),
+                ConstantPoolInfo::Dynamic {
+                    name_and_type_index,
+                    ..
+                } => {
+                    let (_, descriptor) = self.constant_pool.name_and_type(*name_and_type_index)?;
+                    VerificationType::from_field_type(&descriptor.parse()?)
+                }
+                _ => {
+                    return Err(Error::UnexpectedConstant {
+                        index,
+                        expected: "loadable constant",
+                    })
+                }
+            },
+        )
+    }
+
+    fn field_type(&self, index: u16) -> Result<VerificationType> {
+        let (_, _, descriptor) = self.constant_pool.member_ref(index)?;
+        Ok(VerificationType::from_field_type(&descriptor.parse()?))
+    }
+
+    /// Simulates the execution of an instruction, returning its successors.
+    fn execute(
+        &self,
+        offset: u32,
+        instruction: &Instruction,
+        state: &mut State,
+    ) -> Result<Successors> {
+        use VerificationType::{Double, Float, Integer, Long, Null, Object};
+
+        if let Some((kind, index, store)) = local_access(instruction) {
+            if store {
+                let r#type = match kind {
+                    Kind::Reference => state.pop_reference(offset)?,
+                    _ => {
+                        let r#type = kind.verification_type();
+                        state.pop(offset, if r#type.is_wide() { 2 } else { 1 })?;
+                        r#type
+                    }
+                };
+                state.set_local(offset, index, r#type)?;
+            } else {
+                let r#type = match kind {
+                    Kind::Reference => {
+                        state.locals.get(index as usize).cloned().ok_or_else(|| {
+                            verification_error(offset, "local variable out of range")
+                        })?
+                    }
+                    _ => kind.verification_type(),
+                };
+                state.push(r#type);
+            }
+            return Ok(Successors::next());
+        }
+
+        let object = |class_name: &str| Object(class_name.to_owned());
+        match instruction {
+            Instruction::Nop => {}
+            Instruction::AconstNull => state.push(Null),
+            Instruction::IconstM1
+            | Instruction::Iconst0
+            | Instruction::Iconst1
+            | Instruction::Iconst2
+            | Instruction::Iconst3
+            | Instruction::Iconst4
+            | Instruction::Iconst5
+            | Instruction::Bipush(_)
+            | Instruction::Sipush(_) => state.push(Integer),
+            Instruction::Lconst0 | Instruction::Lconst1 => state.push(Long),
+            Instruction::Fconst0 | Instruction::Fconst1 | Instruction::Fconst2 => state.push(Float),
+            Instruction::Dconst0 | Instruction::Dconst1 => state.push(Double),
+            Instruction::Ldc(index) => state.push(self.constant_type(*index as u16)?),
+            Instruction::LdcW(index) | Instruction::Ldc2W(index) => {
+                state.push(self.constant_type(*index)?)
+            }
+            Instruction::Iaload
+            | Instruction::Baload
+            | Instruction::Caload
+            | Instruction::Saload => {
+                state.pop(offset, 2)?;
+                state.push(Integer);
+            }
+            Instruction::Laload => {
+                state.pop(offset, 2)?;
+                state.push(Long);
+            }
+            Instruction::Faload => {
+                state.pop(offset, 2)?;
+                state.push(Float);
+            }
+            Instruction::Daload => {
+                state.pop(offset, 2)?;
+                state.push(Double);
+            }
+            Instruction::Aaload => {
+                state.pop(offset, 1)?;
+                let component_type = match state.pop_reference(offset)? {
+                    Object(descriptor) => match descriptor.strip_prefix("[L") {
+                        Some(class_name) => object(&class_name[..class_name.len() - 1]),
+                        None => match descriptor.strip_prefix('[') {
+                            Some(descriptor) => object(descriptor),
+                            None => {
+                                return Err(verification_error(offset, "expected array"));
+                            }
+                        },
+                    },
+                    _ => Null,
+                };
+                state.push(component_type);
+            }
+            Instruction::Iastore
+            | Instruction::Fastore
+            | Instruction::Aastore
+            | Instruction::Bastore
+            | Instruction::Castore
+            | Instruction::Sastore => state.pop(offset, 3)?,
+            Instruction::Lastore | Instruction::Dastore => state.pop(offset, 4)?,
+            Instruction::Pop => state.pop(offset, 1)?,
+            Instruction::Pop2 => state.pop(offset, 2)?,
+            Instruction::Dup
+            | Instruction::DupX1
+            | Instruction::DupX2
+            | Instruction::Dup2
+            | Instruction::Dup2X1
+            | Instruction::Dup2X2
+            | Instruction::Swap => {
+                // (number of entries to duplicate, depth to insert them at)
+                let (length, depth) = match instruction {
+                    Instruction::Dup => (1, 1),
+                    Instruction::DupX1 => (1, 2),
+                    Instruction::DupX2 => (1, 3),
+                    Instruction::Dup2 => (2, 2),
+                    Instruction::Dup2X1 => (2, 3),
+                    Instruction::Dup2X2 => (2, 4),
+                    _ => (0, 2),
+                };
+                if state.stack.len() < depth {
+                    return Err(verification_error(offset, "operand stack underflow"));
+                }
+                let top = state.stack.len() - length.max(1);
+                if length == 0 {
+                    state.stack.swap(top, top - 1);
+                } else {
+                    let values = state.stack[top..].to_vec();
+                    let at = state.stack.len() - depth;
+                    state.stack.splice(at..at, values);
+                }
+            }
+            Instruction::Iadd
+            | Instruction::Isub
+            | Instruction::Imul
+            | Instruction::Idiv
+            | Instruction::Irem
+            | Instruction::Ishl
+            | Instruction::Ishr
+            | Instruction::Iushr
+            | Instruction::Iand
+            | Instruction::Ior
+            | Instruction::Ixor
+            | Instruction::Fcmpl
+            | Instruction::Fcmpg => {
+                state.pop(offset, 2)?;
+                state.push(Integer);
+            }
+            Instruction::Fadd
+            | Instruction::Fsub
+            | Instruction::Fmul
+            | Instruction::Fdiv
+            | Instruction::Frem => {
+                state.pop(offset, 2)?;
+                state.push(Float);
+            }
+            Instruction::Ladd
+            | Instruction::Lsub
+            | Instruction::Lmul
+            | Instruction::Ldiv
+            | Instruction::Lrem
+            | Instruction::Land
+            | Instruction::Lor
+            | Instruction::Lxor => {
+                state.pop(offset, 4)?;
+                state.push(Long);
+            }
+            Instruction::Lshl | Instruction::Lshr | Instruction::Lushr => {
+                state.pop(offset, 3)?;
+                state.push(Long);
+            }
+            Instruction::Dadd
+            | Instruction::Dsub
+            | Instruction::Dmul
+            | Instruction::Ddiv
+            | Instruction::Drem => {
+                state.pop(offset, 4)?;
+                state.push(Double);
+            }
+            Instruction::Lcmp | Instruction::Dcmpl | Instruction::Dcmpg => {
+                state.pop(offset, 4)?;
+                state.push(Integer);
+            }
+            Instruction::Ineg | Instruction::I2b | Instruction::I2c | Instruction::I2s => {
+                state.pop(offset, 1)?;
+                state.push(Integer);
+            }
+            Instruction::Fneg | Instruction::I2f => {
+                state.pop(offset, 1)?;
+                state.push(Float);
+            }
+            Instruction::Lneg | Instruction::D2l => {
+                state.pop(offset, 2)?;
+                state.push(Long);
+            }
+            Instruction::Dneg | Instruction::L2d => {
+                state.pop(offset, 2)?;
+                state.push(Double);
+            }
+            Instruction::I2l | Instruction::F2l => {
+                state.pop(offset, 1)?;
+                state.push(Long);
+            }
+            Instruction::I2d | Instruction::F2d => {
+                state.pop(offset, 1)?;
+                state.push(Double);
+            }
+            Instruction::L2i | Instruction::D2i => {
+                state.pop(offset, 2)?;
+                state.push(Integer);
+            }
+            Instruction::L2f | Instruction::D2f => {
+                state.pop(offset, 2)?;
+                state.push(Float);
+            }
+            Instruction::F2i => {
+                state.pop(offset, 1)?;
+                state.push(Integer);
+            }
+            Instruction::Iinc { index, .. } => state.set_local(offset, *index, Integer)?,
+            Instruction::Ifeq(branch)
+            | Instruction::Ifne(branch)
+            | Instruction::Iflt(branch)
+            | Instruction::Ifge(branch)
+            | Instruction::Ifgt(branch)
+            | Instruction::Ifle(branch)
+            | Instruction::Ifnull(branch)
+            | Instruction::Ifnonnull(branch) => {
+                state.pop(offset, 1)?;
+                return Ok(Successors {
+                    fall_through: true,
+                    branches: vec![*branch as i32],
+                });
+            }
+            Instruction::IfIcmpeq(branch)
+            | Instruction::IfIcmpne(branch)
+            | Instruction::IfIcmplt(branch)
+            | Instruction::IfIcmpge(branch)
+            | Instruction::IfIcmpgt(branch)
+            | Instruction::IfIcmple(branch)
+            | Instruction::IfAcmpeq(branch)
+            | Instruction::IfAcmpne(branch) => {
+                state.pop(offset, 2)?;
+                return Ok(Successors {
+                    fall_through: true,
+                    branches: vec![*branch as i32],
+                });
+            }
+            Instruction::Goto(branch) => {
+                return Ok(Successors {
+                    fall_through: false,
+                    branches: vec![*branch as i32],
+                })
+            }
+            Instruction::GotoW(branch) => {
+                return Ok(Successors {
+                    fall_through: false,
+                    branches: vec![*branch],
+                })
+            }
+            Instruction::Jsr(_) | Instruction::JsrW(_) | Instruction::Ret(_) => {
+                return Err(verification_error(offset, "subroutines are not supported"));
+            }
+            Instruction::Tableswitch {
+                default, offsets, ..
+            } => {
+                state.pop(offset, 1)?;
+                return Ok(Successors {
+                    fall_through: false,
+                    branches: [*default].into_iter().chain(offsets.clone()).collect(),
+                });
+            }
+            Instruction::Lookupswitch { default, pairs } => {
+                state.pop(offset, 1)?;
+                return Ok(Successors {
+                    fall_through: false,
+                    branches: [*default]
+                        .into_iter()
+                        .chain(pairs.iter().map(|(_, branch)| *branch))
+                        .collect(),
+                });
+            }
+            Instruction::Ireturn | Instruction::Freturn | Instruction::Areturn => {
+                state.pop(offset, 1)?;
+                return Ok(Successors::none());
+            }
+            Instruction::Lreturn | Instruction::Dreturn => {
+                state.pop(offset, 2)?;
+                return Ok(Successors::none());
+            }
+            Instruction::Return => return Ok(Successors::none()),
+            Instruction::Athrow => {
+                state.pop(offset, 1)?;
+                return Ok(Successors::none());
+            }
+            Instruction::Getstatic(index) => state.push(self.field_type(*index)?),
+            Instruction::Putstatic(index) => {
+                let r#type = self.field_type(*index)?;
+                state.pop(offset, if r#type.is_wide() { 2 } else { 1 })?;
+            }
+            Instruction::Getfield(index) => {
+                state.pop(offset, 1)?;
+                state.push(self.field_type(*index)?);
+            }
+            Instruction::Putfield(index) => {
+                let r#type = self.field_type(*index)?;
+                state.pop(offset, if r#type.is_wide() { 3 } else { 2 })?;
+            }
+            Instruction::Invokevirtual(index)
+            | Instruction::Invokespecial(index)
+            | Instruction::Invokestatic(index)
+            | Instruction::Invokeinterface { index, .. }
+            | Instruction::Invokedynamic(index) => {
+                let (name, descriptor) = match instruction {
+                    Instruction::Invokedynamic(_) => match self.constant_pool.get(*index) {
+                        Some(ConstantPoolInfo::InvokeDynamic {
+                            name_and_type_index,
+                            ..
+                        }) => self.constant_pool.name_and_type(*name_and_type_index)?,
+                        _ => {
+                            return Err(Error::UnexpectedConstant {
+                                index: *index,
+                                expected: "InvokeDynamic",
+                            })
+                        }
+                    },
+                    _ => {
+                        let (_, name, descriptor) = self.constant_pool.member_ref(*index)?;
+                        (name, descriptor)
+                    }
+                };
+                let descriptor: MethodDescriptor = descriptor.parse()?;
+                let parameters_length = descriptor
+                    .parameter_types
+                    .iter()
+                    .map(|parameter_type| if parameter_type.is_wide() { 2 } else { 1 })
+                    .sum();
+                state.pop(offset, parameters_length)?;
+                if !matches!(
+                    instruction,
+                    Instruction::Invokestatic(_) | Instruction::Invokedynamic(_)
+                ) {
+                    let receiver = state.pop_reference(offset)?;
+                    if name == "<init>" {
+                        let initialized = match receiver {
+                            VerificationType::UninitializedThis => object(self.class_name),
+                            VerificationType::Uninitialized(new_offset) => {
+                                match &self.instructions[self.index(new_offset)?].1 {
+                                    Instruction::New(index) => {
+                                        object(self.constant_pool.class_name(*index)?)
+                                    }
+                                    _ => {
+                                        return Err(verification_error(
+                                            offset,
+                                            "uninitialized object not created by new",
+                                        ))
+                                    }
+                                }
+                            }
+                            _ => {
+                                return Err(verification_error(
+                                    offset,
+                                    "constructor invoked on initialized object",
+                                ))
+                            }
+                        };
+                        for r#type in state.locals.iter_mut().chain(state.stack.iter_mut()) {
+                            if *r#type == receiver {
+                                *r#type = initialized.clone();
+                            }
+                        }
+                    }
+                }
+                if !matches!(descriptor.return_type, FieldType::Void) {
+                    state.push(VerificationType::from_field_type(&descriptor.return_type));
+                }
+            }
+            Instruction::New(_) => state.push(VerificationType::Uninitialized(offset)),
+            Instruction::Newarray(atype) => {
+                state.pop(offset, 1)?;
+                let descriptor = match atype {
+                    ArrayType::Boolean => "[Z",
+                    ArrayType::Char => "[C",
+                    ArrayType::Float => "[F",
+                    ArrayType::Double => "[D",
+                    ArrayType::Byte => "[B",
+                    ArrayType::Short => "[S",
+                    ArrayType::Int => "[I",
+                    ArrayType::Long => "[J",
+                };
+                state.push(object(descriptor));
+            }
+            Instruction::Anewarray(index) => {
+                state.pop(offset, 1)?;
+                let class_name = self.constant_pool.class_name(*index)?;
+                state.push(if class_name.starts_with('[') {
+                    Object(format!("[{class_name}"))
+                } else {
+                    Object(format!("[L{class_name};"))
+                });
+            }
+            Instruction::Arraylength | Instruction::Instanceof(_) => {
+                state.pop(offset, 1)?;
+                state.push(Integer);
+            }
+            Instruction::Checkcast(index) => {
+                state.pop(offset, 1)?;
+                state.push(object(self.constant_pool.class_name(*index)?));
+            }
+            Instruction::Monitorenter | Instruction::Monitorexit => state.pop(offset, 1)?,
+            Instruction::Multianewarray { index, dimensions } => {
+                state.pop(offset, *dimensions as usize)?;
+                state.push(object(self.constant_pool.class_name(*index)?));
+            }
+            _ => unreachable!("loads and stores are handled above"),
+        }
+        Ok(Successors::next())
+    }
+}
@@ -1,8 +1,11 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use crate::{Error, Result};
 
-#[derive(Debug)]
+/// The maximum number of array dimensions a descriptor may have.
+pub const MAX_ARRAY_DIMENSIONS: usize = 255;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum FieldType {
     Byte,
     Char,
@@ -10,69 +13,199 @@ pub enum FieldType {
     Float,
     Int,
     Long,
+    /// A class type, given by its internal name, e.g. `java/lang/String`.
     Object(String),
     Short,
     Boolean,
     Array(Box<Self>),
+    /// Only valid as the return type of a [`MethodDescriptor`].
     Void,
 }
 
 impl FieldType {
-    fn from_str_internal(s: &mut &str) -> Result<Self> {
-        let field_type = s.chars().next().unwrap();
-        *s = &s[1..];
-        Ok(match field_type {
-            'B' => Self::Byte,
-            'C' => Self::Char,
-            'D' => Self::Double,
-            'F' => Self::Float,
-            'I' => Self::Int,
-            'J' => Self::Long,
-            'L' => {
-                let class_name_end = s.find(';').unwrap();
-                *s = &s[class_name_end + 1..];
-                let class_name = &s[..class_name_end];
-                Self::Object(class_name.to_owned())
-            }
-            'S' => Self::Short,
-            'Z' => Self::Boolean,
-            '[' => Self::Array(Box::new(s.parse()?)),
-            'V' => Self::Void,
-            _ => todo!(),
-        })
+    /// Returns whether values of this type take two local variable slots and
+    /// operand stack entries.
+    pub fn is_wide(&self) -> bool {
+        matches!(self, Self::Long | Self::Double)
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Byte => f.write_str("B"),
+            Self::Char => f.write_str("C"),
+            Self::Double => f.write_str("D"),
+            Self::Float => f.write_str("F"),
+            Self::Int => f.write_str("I"),
+            Self::Long => f.write_str("J"),
+            Self::Object(class_name) => write!(f, "L{class_name};"),
+            Self::Short => f.write_str("S"),
+            Self::Boolean => f.write_str("Z"),
+            Self::Array(component_type) => write!(f, "[{component_type}"),
+            Self::Void => f.write_str("V"),
+        }
     }
 }
 
 impl FromStr for FieldType {
     type Err = Error;
 
-    fn from_str(mut s: &str) -> Result<Self> {
-        Self::from_str_internal(&mut s)
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s);
+        let field_type = parser.field_type()?;
+        parser.end()?;
+        Ok(field_type)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MethodDescriptor {
     pub parameter_types: Vec<FieldType>,
+    /// [`FieldType::Void`] if the method does not return a value.
     pub return_type: FieldType,
 }
 
+impl fmt::Display for MethodDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(")?;
+        for parameter_type in &self.parameter_types {
+            write!(f, "{parameter_type}")?;
+        }
+        write!(f, "){}", self.return_type)
+    }
+}
+
 impl FromStr for MethodDescriptor {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let parameter_descriptor_begin = s.find('(').unwrap();
-        let parameter_descriptor_end = s.find(')').unwrap();
-        let mut parameter_descriptor = &s[parameter_descriptor_begin + 1..parameter_descriptor_end];
+        let mut parser = Parser::new(s);
+        parser.expect('(', "expected '('")?;
         let mut parameter_types = vec![];
-        while !parameter_descriptor.is_empty() {
-            parameter_types.push(FieldType::from_str_internal(&mut parameter_descriptor)?);
+        while parser.peek() != Some(')') {
+            parameter_types.push(parser.field_type()?);
         }
-        let return_descriptor = &s[parameter_descriptor_end + 1..];
-        let return_type = return_descriptor.parse()?;
+        parser.expect(')', "expected ')'")?;
+        let return_type = if parser.peek() == Some('V') {
+            parser.next();
+            FieldType::Void
+        } else {
+            parser.field_type()?
+        };
+        parser.end()?;
         Ok(Self {
             parameter_types,
             return_type,
         })
     }
 }
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    fn error(&self, reason: &'static str) -> Error {
+        Error::InvalidDescriptor {
+            position: self.position,
+            reason,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        self.position += char.len_utf8();
+        Some(char)
+    }
+
+    fn expect(&mut self, expected: char, reason: &'static str) -> Result<()> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(reason));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn end(&self) -> Result<()> {
+        match self.peek() {
+            Some(_) => Err(self.error("trailing characters")),
+            None => Ok(()),
+        }
+    }
+
+    fn field_type(&mut self) -> Result<FieldType> {
+        let begin = self.position;
+        let mut dimensions = 0;
+        while self.peek() == Some('[') {
+            self.next();
+            dimensions += 1;
+        }
+        if dimensions > MAX_ARRAY_DIMENSIONS {
+            return Err(Error::InvalidDescriptor {
+                position: begin,
+                reason: "more than 255 array dimensions",
+            });
+        }
+
+        let mut field_type = match self.peek() {
+            Some('B') => FieldType::Byte,
+            Some('C') => FieldType::Char,
+            Some('D') => FieldType::Double,
+            Some('F') => FieldType::Float,
+            Some('I') => FieldType::Int,
+            Some('J') => FieldType::Long,
+            Some('S') => FieldType::Short,
+            Some('Z') => FieldType::Boolean,
+            Some('L') => {
+                self.next();
+                FieldType::Object(self.class_name()?.to_owned())
+            }
+            Some('V') => return Err(self.error("void outside of return type")),
+            Some(_) => return Err(self.error("unknown type")),
+            None => return Err(self.error("unexpected end")),
+        };
+        if !matches!(field_type, FieldType::Object(_)) {
+            self.next();
+        }
+        for _ in 0..dimensions {
+            field_type = FieldType::Array(Box::new(field_type));
+        }
+        Ok(field_type)
+    }
+
+    /// Parses an internal class name terminated by `;`.
+    fn class_name(&mut self) -> Result<&'a str> {
+        let begin = self.position;
+        let mut segment_begin = begin;
+        loop {
+            match self.peek() {
+                Some(';') | Some('/') if self.position == segment_begin => {
+                    return Err(self.error("empty class name"))
+                }
+                Some(';') => break,
+                Some('/') => {
+                    self.next();
+                    segment_begin = self.position;
+                }
+                Some('.' | '[') => return Err(self.error("invalid character in class name")),
+                Some(_) => {
+                    self.next();
+                }
+                None => return Err(self.error("expected ';'")),
+            }
+        }
+        let class_name = &self.input[begin..self.position];
+        self.next();
+        Ok(class_name)
+    }
+}
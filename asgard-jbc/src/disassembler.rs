@@ -0,0 +1,1295 @@
+//! Listing of class files in the manner of `javap -c -p -l -s`, or `javap -v`
+//! in verbose mode.
+//!
+//! Names are printed in their internal form, types as descriptors and branch
+//! targets as labels numbered in the order of their offsets, which makes the
+//! output stable and suitable for diffing.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Write},
+};
+
+use bitflags::Flags;
+
+use crate::{
+    class::{
+        annotation::{Annotation, TargetInfo, TypeAnnotation, TypePathKind, Value},
+        attribute::{
+            Attribute, CodeAttribute, StackMapFrame, StackMapTableAttribute, VerificationTypeInfo,
+        },
+        AttributeInfo, ClassAccessFlags, ClassFile, ConstantLookup, ConstantPool, ConstantPoolInfo,
+    },
+    code::Instruction,
+    mutf8::JavaString,
+    Result,
+};
+
+/// Disassembles a class file, see [`Disassembler`].
+pub fn disassemble(class_file: &ClassFile) -> Result<String> {
+    Disassembler::new().disassemble(class_file)
+}
+
+/// Prints class files in a human-readable form.
+#[derive(Debug, Clone, Default)]
+pub struct Disassembler {
+    verbose: bool,
+}
+
+impl Disassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Additionally prints the version, the constant pool, access flags,
+    /// constant pool indices of operands, instruction offsets, sizes of code
+    /// and stack map frames.
+    pub fn verbose(&mut self, verbose: bool) -> &mut Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn disassemble(&self, class_file: &ClassFile) -> Result<String> {
+        let mut printer = Printer {
+            verbose: self.verbose,
+            constant_pool: &class_file.constant_pool,
+            output: String::new(),
+        };
+        printer.class_file(class_file)?;
+        Ok(printer.output)
+    }
+}
+
+struct Printer<'a> {
+    verbose: bool,
+    constant_pool: &'a ConstantPool,
+    output: String,
+}
+
+impl Printer<'_> {
+    fn line(&mut self, indent: usize, line: impl Display) {
+        // writing to a string never fails
+        let _ = writeln!(self.output, "{:indent$}{line}", "", indent = indent * 2);
+    }
+
+    fn class_file(&mut self, class_file: &ClassFile) -> Result<()> {
+        let access_flags = class_file.access_flags;
+        let kind = if access_flags.contains(ClassAccessFlags::MODULE) {
+            "module"
+        } else if access_flags.contains(ClassAccessFlags::ANNOTATION) {
+            "@interface"
+        } else if access_flags.contains(ClassAccessFlags::INTERFACE) {
+            "interface"
+        } else if access_flags.contains(ClassAccessFlags::ENUM) {
+            "enum"
+        } else {
+            "class"
+        };
+        let mut header = modifiers(
+            &access_flags,
+            &["SUPER", "INTERFACE", "ANNOTATION", "ENUM", "MODULE"],
+        );
+        if access_flags.contains(ClassAccessFlags::INTERFACE) {
+            header.retain(|modifier| modifier != "abstract");
+        }
+        header.push(kind.to_owned());
+        header.push(class_file.name()?.to_owned());
+        if let Some(super_name) = class_file.super_name()? {
+            header.push(format!("extends {super_name}"));
+        }
+        let interface_names = class_file.interface_names()?;
+        if !interface_names.is_empty() {
+            header.push(format!("implements {}", interface_names.join(", ")));
+        }
+        self.line(0, header.join(" "));
+
+        if self.verbose {
+            self.line(1, format!("minor version: {}", class_file.minor_version));
+            self.line(1, format!("major version: {}", class_file.major_version));
+            self.line(1, format!("flags: {}", flags(&access_flags)));
+            self.line(1, format!("this_class: #{}", class_file.this_class));
+            self.line(1, format!("super_class: #{}", class_file.super_class));
+            self.line(
+                1,
+                format!(
+                    "interfaces: {}, fields: {}, methods: {}, attributes: {}",
+                    class_file.interfaces.len(),
+                    class_file.fields.len(),
+                    class_file.methods.len(),
+                    class_file.attributes.len()
+                ),
+            );
+            self.line(0, "Constant pool:");
+            for (index, constant) in self.constant_pool.iter() {
+                let line = self.constant_pool_entry(index, constant)?;
+                self.line(1, line);
+            }
+        }
+
+        self.line(0, "{");
+        let mut first = true;
+        for field in &class_file.fields {
+            if !first {
+                self.line(0, "");
+            }
+            first = false;
+            let mut declaration = modifiers(&field.access_flags, &[]);
+            declaration.push(field.name(self.constant_pool)?.to_owned());
+            declaration.push(self.constant_pool.utf8(field.descriptor_index)?.to_owned());
+            self.line(1, declaration.join(" "));
+            if self.verbose {
+                self.line(2, format!("flags: {}", flags(&field.access_flags)));
+            }
+            self.attributes(2, &field.attributes)?;
+        }
+        for method in &class_file.methods {
+            if !first {
+                self.line(0, "");
+            }
+            first = false;
+            let mut declaration = modifiers(&method.access_flags, &[]);
+            declaration.push(format!(
+                "{}{}",
+                method.name(self.constant_pool)?,
+                self.constant_pool.utf8(method.descriptor_index)?
+            ));
+            self.line(1, declaration.join(" "));
+            if self.verbose {
+                self.line(2, format!("flags: {}", flags(&method.access_flags)));
+            }
+            self.attributes(2, &method.attributes)?;
+        }
+        self.line(0, "}");
+        self.attributes(0, &class_file.attributes)
+    }
+
+    fn constant_pool_entry(&self, index: u16, constant: &ConstantPoolInfo) -> Result<String> {
+        let (tag, operands) = match constant {
+            ConstantPoolInfo::Utf8(value) => ("Utf8", quote_java(value)),
+            ConstantPoolInfo::Integer(value) => ("Integer", value.to_string()),
+            ConstantPoolInfo::Float(value) => ("Float", format!("{value:?}f")),
+            ConstantPoolInfo::Long(value) => ("Long", format!("{value}l")),
+            ConstantPoolInfo::Double(value) => ("Double", format!("{value:?}d")),
+            ConstantPoolInfo::Class { name_index } => ("Class", format!("#{name_index}")),
+            ConstantPoolInfo::String { string_index } => ("String", format!("#{string_index}")),
+            ConstantPoolInfo::Fieldref {
+                class_index,
+                name_and_type_index,
+            } => ("Fieldref", format!("#{class_index}.#{name_and_type_index}")),
+            ConstantPoolInfo::Methodref {
+                class_index,
+                name_and_type_index,
+            } => (
+                "Methodref",
+                format!("#{class_index}.#{name_and_type_index}"),
+            ),
+            ConstantPoolInfo::InterfaceMethodref {
+                class_index,
+                name_and_type_index,
+            } => (
+                "InterfaceMethodref",
+                format!("#{class_index}.#{name_and_type_index}"),
+            ),
+            ConstantPoolInfo::NameAndType {
+                name_index,
+                descriptor_index,
+            } => ("NameAndType", format!("#{name_index}:#{descriptor_index}")),
+            ConstantPoolInfo::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => (
+                "MethodHandle",
+                format!("{reference_kind}:#{reference_index}"),
+            ),
+            ConstantPoolInfo::MethodType { descriptor_index } => {
+                ("MethodType", format!("#{descriptor_index}"))
+            }
+            ConstantPoolInfo::Dynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => (
+                "Dynamic",
+                format!("#{bootstrap_method_attr_index}:#{name_and_type_index}"),
+            ),
+            ConstantPoolInfo::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => (
+                "InvokeDynamic",
+                format!("#{bootstrap_method_attr_index}:#{name_and_type_index}"),
+            ),
+            ConstantPoolInfo::Module { name_index } => ("Module", format!("#{name_index}")),
+            ConstantPoolInfo::Package { name_index } => ("Package", format!("#{name_index}")),
+        };
+        let entry = format!("{:>5} = {tag:<18} {operands}", format!("#{index}"));
+        Ok(match constant {
+            ConstantPoolInfo::Utf8(_)
+            | ConstantPoolInfo::Integer(_)
+            | ConstantPoolInfo::Float(_)
+            | ConstantPoolInfo::Long(_)
+            | ConstantPoolInfo::Double(_) => entry,
+            _ => format!("{entry:<48} // {}", self.constant_value(index)?),
+        })
+    }
+
+    /// Returns the resolved value of a constant pool entry.
+    fn constant_value(&self, index: u16) -> Result<String> {
+        let constant_pool = self.constant_pool;
+        let constant = match constant_pool.get(index) {
+            Some(constant) => constant,
+            None => return Ok(format!("<invalid #{index}>")),
+        };
+        Ok(match constant {
+            ConstantPoolInfo::Utf8(value) => quote_java(value),
+            ConstantPoolInfo::Integer(value) => value.to_string(),
+            ConstantPoolInfo::Float(value) => format!("{value:?}f"),
+            ConstantPoolInfo::Long(value) => format!("{value}l"),
+            ConstantPoolInfo::Double(value) => format!("{value:?}d"),
+            ConstantPoolInfo::Class { .. } => constant_pool.class_name(index)?.to_owned(),
+            ConstantPoolInfo::String { string_index } => {
+                quote_java(constant_pool.java_string(*string_index)?)
+            }
+            ConstantPoolInfo::Fieldref { .. }
+            | ConstantPoolInfo::Methodref { .. }
+            | ConstantPoolInfo::InterfaceMethodref { .. } => {
+                let (class_name, name, descriptor) = constant_pool.member_ref(index)?;
+                format!("{class_name}.{name}:{descriptor}")
+            }
+            ConstantPoolInfo::NameAndType { .. } => {
+                let (name, descriptor) = constant_pool.name_and_type(index)?;
+                format!("{name}:{descriptor}")
+            }
+            ConstantPoolInfo::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => format!(
+                "{} {}",
+                reference_kind_name(*reference_kind),
+                self.constant_value(*reference_index)?
+            ),
+            ConstantPoolInfo::MethodType { descriptor_index } => {
+                constant_pool.utf8(*descriptor_index)?.to_owned()
+            }
+            ConstantPoolInfo::Dynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            }
+            | ConstantPoolInfo::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => {
+                let (name, descriptor) = constant_pool.name_and_type(*name_and_type_index)?;
+                format!("#{bootstrap_method_attr_index}:{name}:{descriptor}")
+            }
+            ConstantPoolInfo::Module { .. } => constant_pool.module_name(index)?.to_owned(),
+            ConstantPoolInfo::Package { .. } => constant_pool.package_name(index)?.to_owned(),
+        })
+    }
+
+    /// Returns the operand referencing a constant pool entry, prefixed by the
+    /// kind of entry, e.g. `Method java/lang/Object.<init>:()V`, and by its
+    /// index in verbose mode.
+    fn constant(&self, index: u16) -> Result<String> {
+        let kind = match self.constant_pool.get(index) {
+            Some(ConstantPoolInfo::Utf8(_)) => "Utf8",
+            Some(ConstantPoolInfo::Integer(_)) => "int",
+            Some(ConstantPoolInfo::Float(_)) => "float",
+            Some(ConstantPoolInfo::Long(_)) => "long",
+            Some(ConstantPoolInfo::Double(_)) => "double",
+            Some(ConstantPoolInfo::Class { .. }) => "class",
+            Some(ConstantPoolInfo::String { .. }) => "String",
+            Some(ConstantPoolInfo::Fieldref { .. }) => "Field",
+            Some(ConstantPoolInfo::Methodref { .. }) => "Method",
+            Some(ConstantPoolInfo::InterfaceMethodref { .. }) => "InterfaceMethod",
+            Some(ConstantPoolInfo::NameAndType { .. }) => "NameAndType",
+            Some(ConstantPoolInfo::MethodHandle { .. }) => "MethodHandle",
+            Some(ConstantPoolInfo::MethodType { .. }) => "MethodType",
+            Some(ConstantPoolInfo::Dynamic { .. }) => "Dynamic",
+            Some(ConstantPoolInfo::InvokeDynamic { .. }) => "InvokeDynamic",
+            Some(ConstantPoolInfo::Module { .. }) => "Module",
+            Some(ConstantPoolInfo::Package { .. }) => "Package",
+            None => return Ok(format!("#{index}")),
+        };
+        let value = self.constant_value(index)?;
+        Ok(if self.verbose {
+            format!("#{index} // {kind} {value}")
+        } else {
+            format!("{kind} {value}")
+        })
+    }
+
+    fn attributes(&mut self, indent: usize, attributes: &[AttributeInfo]) -> Result<()> {
+        self.attributes_with_labels(indent, attributes, &Labels::default())
+    }
+
+    fn attributes_with_labels(
+        &mut self,
+        indent: usize,
+        attributes: &[AttributeInfo],
+        labels: &Labels,
+    ) -> Result<()> {
+        for attribute in attributes {
+            let attribute = Attribute::decode(attribute, self.constant_pool)?;
+            self.attribute(indent, &attribute, labels)?;
+        }
+        Ok(())
+    }
+
+    fn attribute(&mut self, indent: usize, attribute: &Attribute, labels: &Labels) -> Result<()> {
+        let constant_pool = self.constant_pool;
+        let name = attribute.name();
+        match attribute {
+            Attribute::ConstantValue(attribute) => {
+                let value = self.constant(attribute.constantvalue_index)?;
+                self.line(indent, format!("{name}: {value}"));
+            }
+            Attribute::Code(attribute) => self.code(indent, attribute)?,
+            Attribute::StackMapTable(attribute) => {
+                if self.verbose {
+                    self.stack_map_table(indent, attribute, labels)?;
+                }
+            }
+            Attribute::Exceptions(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for index in &attribute.0 {
+                    self.line(indent + 1, constant_pool.class_name(*index)?);
+                }
+            }
+            Attribute::InnerClasses(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for inner_class in &attribute.0 {
+                    let mut line = modifiers(&inner_class.inner_class_access_flags, &[]);
+                    line.push(
+                        constant_pool
+                            .class_name(inner_class.inner_class_info_index)?
+                            .to_owned(),
+                    );
+                    if inner_class.outer_class_info_index != 0 {
+                        line.push(format!(
+                            "of {}",
+                            constant_pool.class_name(inner_class.outer_class_info_index)?
+                        ));
+                    }
+                    if inner_class.inner_name_index != 0 {
+                        line.push(format!(
+                            "as {}",
+                            constant_pool.utf8(inner_class.inner_name_index)?
+                        ));
+                    }
+                    self.line(indent + 1, line.join(" "));
+                }
+            }
+            Attribute::EnclosingMethod(attribute) => {
+                let class_name = constant_pool.class_name(attribute.class_index)?;
+                self.line(
+                    indent,
+                    if attribute.method_index == 0 {
+                        format!("{name}: {class_name}")
+                    } else {
+                        let (method_name, descriptor) =
+                            constant_pool.name_and_type(attribute.method_index)?;
+                        format!("{name}: {class_name}.{method_name}:{descriptor}")
+                    },
+                );
+            }
+            Attribute::Synthetic | Attribute::Deprecated => {
+                self.line(indent, format!("{name}: true"));
+            }
+            Attribute::Signature(attribute) => {
+                let signature = constant_pool.utf8(attribute.signature_index)?;
+                self.line(indent, format!("{name}: {signature}"));
+            }
+            Attribute::SourceFile(attribute) => {
+                let source_file = quote(constant_pool.utf8(attribute.sourcefile_index)?);
+                self.line(indent, format!("{name}: {source_file}"));
+            }
+            Attribute::SourceDebugExtension(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for line in String::from_utf8_lossy(&attribute.0).lines() {
+                    self.line(indent + 1, line);
+                }
+            }
+            Attribute::LineNumberTable(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for line_number in &attribute.0 {
+                    self.line(
+                        indent + 1,
+                        format!(
+                            "line {}: {}",
+                            line_number.line_number,
+                            labels.name(line_number.start_pc as u32)
+                        ),
+                    );
+                }
+            }
+            Attribute::LocalVariableTable(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for local_variable in &attribute.0 {
+                    let start = local_variable.start_pc as u32;
+                    self.line(
+                        indent + 1,
+                        format!(
+                            "{}: {} {} from {} to {}",
+                            local_variable.index,
+                            constant_pool.utf8(local_variable.name_index)?,
+                            constant_pool.utf8(local_variable.descriptor_index)?,
+                            labels.name(start),
+                            labels.name(start + local_variable.length as u32)
+                        ),
+                    );
+                }
+            }
+            Attribute::LocalVariableTypeTable(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for local_variable in &attribute.0 {
+                    let start = local_variable.start_pc as u32;
+                    self.line(
+                        indent + 1,
+                        format!(
+                            "{}: {} {} from {} to {}",
+                            local_variable.index,
+                            constant_pool.utf8(local_variable.name_index)?,
+                            constant_pool.utf8(local_variable.signature_index)?,
+                            labels.name(start),
+                            labels.name(start + local_variable.length as u32)
+                        ),
+                    );
+                }
+            }
+            Attribute::RuntimeVisibleAnnotations(attribute)
+            | Attribute::RuntimeInvisibleAnnotations(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for annotation in &attribute.0 {
+                    let annotation = self.annotation(annotation)?;
+                    self.line(indent + 1, annotation);
+                }
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(attribute)
+            | Attribute::RuntimeInvisibleParameterAnnotations(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for (index, annotations) in attribute.0.iter().enumerate() {
+                    self.line(indent + 1, format!("parameter {index}:"));
+                    for annotation in annotations {
+                        let annotation = self.annotation(annotation)?;
+                        self.line(indent + 2, annotation);
+                    }
+                }
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(attribute)
+            | Attribute::RuntimeInvisibleTypeAnnotations(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for type_annotation in &attribute.0 {
+                    let type_annotation = self.type_annotation(type_annotation, labels)?;
+                    self.line(indent + 1, type_annotation);
+                }
+            }
+            Attribute::AnnotationDefault(attribute) => {
+                let value = self.value(&attribute.0.resolve(constant_pool)?)?;
+                self.line(indent, format!("{name}: {value}"));
+            }
+            Attribute::BootstrapMethods(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for (index, bootstrap_method) in attribute.0.iter().enumerate() {
+                    let method_handle = self.constant(bootstrap_method.bootstrap_method_ref)?;
+                    self.line(indent + 1, format!("{index}: {method_handle}"));
+                    for argument in &bootstrap_method.bootstrap_arguments {
+                        let argument = self.constant(*argument)?;
+                        self.line(indent + 2, argument);
+                    }
+                }
+            }
+            Attribute::MethodParameters(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for parameter in &attribute.0 {
+                    let mut line = modifiers(&parameter.access_flags, &[]);
+                    line.push(if parameter.name_index == 0 {
+                        "<no name>".to_owned()
+                    } else {
+                        constant_pool.utf8(parameter.name_index)?.to_owned()
+                    });
+                    self.line(indent + 1, line.join(" "));
+                }
+            }
+            Attribute::Module(attribute) => {
+                let mut line = modifiers(&attribute.module_flags, &[]);
+                line.push(
+                    constant_pool
+                        .module_name(attribute.module_name_index)?
+                        .to_owned(),
+                );
+                if attribute.module_version_index != 0 {
+                    line.push(
+                        constant_pool
+                            .utf8(attribute.module_version_index)?
+                            .to_owned(),
+                    );
+                }
+                self.line(indent, format!("{name}: {}", line.join(" ")));
+                for requires in &attribute.requires {
+                    let mut line = vec!["requires".to_owned()];
+                    line.extend(
+                        modifiers(&requires.requires_flags, &[])
+                            .into_iter()
+                            .map(|modifier| modifier.replace("static_phase", "static")),
+                    );
+                    line.push(
+                        constant_pool
+                            .module_name(requires.requires_index)?
+                            .to_owned(),
+                    );
+                    if requires.requires_version_index != 0 {
+                        line.push(
+                            constant_pool
+                                .utf8(requires.requires_version_index)?
+                                .to_owned(),
+                        );
+                    }
+                    self.line(indent + 1, line.join(" "));
+                }
+                for exports in &attribute.exports {
+                    let mut line = vec!["exports".to_owned()];
+                    line.extend(modifiers(&exports.exports_flags, &[]));
+                    line.push(
+                        constant_pool
+                            .package_name(exports.exports_index)?
+                            .to_owned(),
+                    );
+                    self.line(indent + 1, line.join(" "));
+                    for index in &exports.exports_to_index {
+                        let module = constant_pool.module_name(*index)?;
+                        self.line(indent + 2, format!("to {module}"));
+                    }
+                }
+                for opens in &attribute.opens {
+                    let mut line = vec!["opens".to_owned()];
+                    line.extend(modifiers(&opens.opens_flags, &[]));
+                    line.push(constant_pool.package_name(opens.opens_index)?.to_owned());
+                    self.line(indent + 1, line.join(" "));
+                    for index in &opens.opens_to_index {
+                        let module = constant_pool.module_name(*index)?;
+                        self.line(indent + 2, format!("to {module}"));
+                    }
+                }
+                for index in &attribute.uses_index {
+                    let service = constant_pool.class_name(*index)?;
+                    self.line(indent + 1, format!("uses {service}"));
+                }
+                for provides in &attribute.provides {
+                    let service = constant_pool.class_name(provides.provides_index)?;
+                    self.line(indent + 1, format!("provides {service}"));
+                    for index in &provides.provides_with_index {
+                        let provider = constant_pool.class_name(*index)?;
+                        self.line(indent + 2, format!("with {provider}"));
+                    }
+                }
+            }
+            Attribute::ModulePackages(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for index in &attribute.0 {
+                    self.line(indent + 1, constant_pool.package_name(*index)?);
+                }
+            }
+            Attribute::ModuleMainClass(attribute) => {
+                let main_class = constant_pool.class_name(attribute.main_class_index)?;
+                self.line(indent, format!("{name}: {main_class}"));
+            }
+            Attribute::NestHost(attribute) => {
+                let host_class = constant_pool.class_name(attribute.host_class_index)?;
+                self.line(indent, format!("{name}: {host_class}"));
+            }
+            Attribute::NestMembers(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for index in &attribute.0 {
+                    self.line(indent + 1, constant_pool.class_name(*index)?);
+                }
+            }
+            Attribute::Record(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for component in &attribute.0 {
+                    self.line(
+                        indent + 1,
+                        format!(
+                            "{} {}",
+                            constant_pool.utf8(component.name_index)?,
+                            constant_pool.utf8(component.descriptor_index)?
+                        ),
+                    );
+                    self.attributes(indent + 2, &component.attributes)?;
+                }
+            }
+            Attribute::PermittedSubclasses(attribute) => {
+                self.line(indent, format!("{name}:"));
+                for index in &attribute.0 {
+                    self.line(indent + 1, constant_pool.class_name(*index)?);
+                }
+            }
+            Attribute::Custom(_) => self.line(indent, format!("{name}:")),
+            Attribute::Unknown { info, .. } => {
+                self.line(indent, format!("{name}: {} bytes", info.len()));
+                if self.verbose {
+                    for chunk in info.chunks(16) {
+                        let bytes = chunk
+                            .iter()
+                            .map(|byte| format!("{byte:02x}"))
+                            .collect::<Vec<_>>();
+                        self.line(indent + 1, bytes.join(" "));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn code(&mut self, indent: usize, code: &CodeAttribute) -> Result<()> {
+        let instructions = code.instructions()?;
+        let attributes = code
+            .attributes
+            .iter()
+            .map(|attribute| Attribute::decode(attribute, self.constant_pool))
+            .collect::<Result<Vec<_>>>()?;
+
+        let offsets = referenced_offsets(&instructions, code, &attributes, self.verbose);
+        let labels = Labels(
+            offsets
+                .into_iter()
+                .enumerate()
+                .map(|(index, offset)| (offset, index))
+                .collect(),
+        );
+
+        self.line(indent, "Code:");
+        if self.verbose {
+            self.line(
+                indent + 1,
+                format!(
+                    "stack={}, locals={}, length={}",
+                    code.max_stack,
+                    code.max_locals,
+                    code.code.len()
+                ),
+            );
+        }
+        let mut placed = labels.0.range(..).peekable();
+        for (offset, instruction) in &instructions {
+            while let Some((_, index)) = placed.next_if(|(label, _)| *label <= offset) {
+                self.line(indent + 1, format!("L{index}:"));
+            }
+            let mut lines = self.instruction(*offset, instruction, &labels)?.into_iter();
+            let first = lines.next().unwrap_or_default();
+            self.line(
+                indent + 2,
+                if self.verbose {
+                    format!("{offset:>4}: {first}")
+                } else {
+                    first
+                },
+            );
+            for line in lines {
+                self.line(indent + if self.verbose { 5 } else { 3 }, line);
+            }
+        }
+        for (_, index) in placed {
+            self.line(indent + 1, format!("L{index}:"));
+        }
+
+        if !code.exception_table.is_empty() {
+            self.line(indent + 1, "Exception table:");
+            for entry in &code.exception_table {
+                let catch_type = if entry.catch_type == 0 {
+                    "any".to_owned()
+                } else {
+                    self.constant_pool.class_name(entry.catch_type)?.to_owned()
+                };
+                self.line(
+                    indent + 2,
+                    format!(
+                        "from {} to {} target {} type {catch_type}",
+                        labels.name(entry.start_pc as u32),
+                        labels.name(entry.end_pc as u32),
+                        labels.name(entry.handler_pc as u32)
+                    ),
+                );
+            }
+        }
+        for attribute in &attributes {
+            self.attribute(indent + 1, attribute, &labels)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the lines of an instruction, more than one for switches.
+    fn instruction(
+        &self,
+        offset: u32,
+        instruction: &Instruction,
+        labels: &Labels,
+    ) -> Result<Vec<String>> {
+        let mnemonic = instruction.mnemonic();
+        let target = |delta: i32| labels.name(offset.wrapping_add_signed(delta));
+        Ok(vec![match instruction {
+            Instruction::Bipush(value) => format!("{mnemonic} {value}"),
+            Instruction::Sipush(value) => format!("{mnemonic} {value}"),
+            Instruction::Ldc(index) => format!("{mnemonic} {}", self.constant(*index as u16)?),
+            Instruction::LdcW(index)
+            | Instruction::Ldc2W(index)
+            | Instruction::Getstatic(index)
+            | Instruction::Putstatic(index)
+            | Instruction::Getfield(index)
+            | Instruction::Putfield(index)
+            | Instruction::Invokevirtual(index)
+            | Instruction::Invokespecial(index)
+            | Instruction::Invokestatic(index)
+            | Instruction::Invokedynamic(index)
+            | Instruction::New(index)
+            | Instruction::Anewarray(index)
+            | Instruction::Checkcast(index)
+            | Instruction::Instanceof(index) => format!("{mnemonic} {}", self.constant(*index)?),
+            Instruction::Invokeinterface { index, count } => {
+                let constant = self.constant(*index)?;
+                if self.verbose {
+                    // keeps the comment at the end
+                    match constant.split_once(" // ") {
+                        Some((index, comment)) => {
+                            format!("{mnemonic} {index}, {count} // {comment}")
+                        }
+                        None => format!("{mnemonic} {constant}, {count}"),
+                    }
+                } else {
+                    format!("{mnemonic} {constant}")
+                }
+            }
+            Instruction::Multianewarray { index, dimensions } => {
+                let constant = self.constant(*index)?;
+                match constant.split_once(" // ") {
+                    Some((index, comment)) => {
+                        format!("{mnemonic} {index}, {dimensions} // {comment}")
+                    }
+                    None => format!("{mnemonic} {constant}, {dimensions}"),
+                }
+            }
+            Instruction::Iload(index)
+            | Instruction::Lload(index)
+            | Instruction::Fload(index)
+            | Instruction::Dload(index)
+            | Instruction::Aload(index)
+            | Instruction::Istore(index)
+            | Instruction::Lstore(index)
+            | Instruction::Fstore(index)
+            | Instruction::Dstore(index)
+            | Instruction::Astore(index)
+            | Instruction::Ret(index) => format!("{mnemonic} {index}"),
+            Instruction::Iinc { index, value } => format!("{mnemonic} {index}, {value}"),
+            Instruction::Wide(instruction) => {
+                // suffixes the mnemonic like javap, e.g. `iload_w 300`
+                let line = self.instruction(offset, instruction, labels)?.remove(0);
+                let mnemonic = instruction.mnemonic();
+                format!("{mnemonic}_w{}", &line[mnemonic.len()..])
+            }
+            Instruction::Ifeq(delta)
+            | Instruction::Ifne(delta)
+            | Instruction::Iflt(delta)
+            | Instruction::Ifge(delta)
+            | Instruction::Ifgt(delta)
+            | Instruction::Ifle(delta)
+            | Instruction::IfIcmpeq(delta)
+            | Instruction::IfIcmpne(delta)
+            | Instruction::IfIcmplt(delta)
+            | Instruction::IfIcmpge(delta)
+            | Instruction::IfIcmpgt(delta)
+            | Instruction::IfIcmple(delta)
+            | Instruction::IfAcmpeq(delta)
+            | Instruction::IfAcmpne(delta)
+            | Instruction::Goto(delta)
+            | Instruction::Jsr(delta)
+            | Instruction::Ifnull(delta)
+            | Instruction::Ifnonnull(delta) => format!("{mnemonic} {}", target(*delta as i32)),
+            Instruction::GotoW(delta) | Instruction::JsrW(delta) => {
+                format!("{mnemonic} {}", target(*delta))
+            }
+            Instruction::Newarray(array_type) => format!("{mnemonic} {}", array_type.name()),
+            Instruction::Tableswitch {
+                default,
+                low,
+                offsets,
+            } => {
+                let mut lines = vec![format!("{mnemonic} {{")];
+                for (key, delta) in (*low..).zip(offsets) {
+                    lines.push(format!("{key}: {}", target(*delta)));
+                }
+                lines.push(format!("default: {}", target(*default)));
+                lines.push("}".to_owned());
+                return Ok(lines);
+            }
+            Instruction::Lookupswitch { default, pairs } => {
+                let mut lines = vec![format!("{mnemonic} {{")];
+                for (key, delta) in pairs {
+                    lines.push(format!("{key}: {}", target(*delta)));
+                }
+                lines.push(format!("default: {}", target(*default)));
+                lines.push("}".to_owned());
+                return Ok(lines);
+            }
+            _ => mnemonic.to_owned(),
+        }])
+    }
+
+    fn stack_map_table(
+        &mut self,
+        indent: usize,
+        stack_map_table: &StackMapTableAttribute,
+        labels: &Labels,
+    ) -> Result<()> {
+        self.line(indent, "StackMapTable:");
+        for (offset, frame) in frame_offsets(stack_map_table) {
+            let kind = match frame {
+                StackMapFrame::Same { .. } => "same".to_owned(),
+                StackMapFrame::SameLocals1StackItem { .. } => "same_locals_1_stack_item".to_owned(),
+                StackMapFrame::SameLocals1StackItemExtended { .. } => {
+                    "same_locals_1_stack_item_extended".to_owned()
+                }
+                StackMapFrame::Chop { k, .. } => format!("chop {k}"),
+                StackMapFrame::SameExtended { .. } => "same_extended".to_owned(),
+                StackMapFrame::Append { .. } => "append".to_owned(),
+                StackMapFrame::Full { .. } => "full".to_owned(),
+            };
+            let mut line = format!("{}: {kind}", labels.name(offset));
+            let (locals, stack) = frame_types(frame);
+            let types = |types: &[VerificationTypeInfo]| -> Result<String> {
+                Ok(types
+                    .iter()
+                    .map(|verification_type| self.verification_type(verification_type, labels))
+                    .collect::<Result<Vec<_>>>()?
+                    .join(", "))
+            };
+            if matches!(
+                frame,
+                StackMapFrame::Append { .. } | StackMapFrame::Full { .. }
+            ) {
+                line.push_str(&format!(" locals=[{}]", types(locals)?));
+            }
+            if !stack.is_empty() || matches!(frame, StackMapFrame::Full { .. }) {
+                line.push_str(&format!(" stack=[{}]", types(stack)?));
+            }
+            self.line(indent + 1, line);
+        }
+        Ok(())
+    }
+
+    fn verification_type(
+        &self,
+        verification_type: &VerificationTypeInfo,
+        labels: &Labels,
+    ) -> Result<String> {
+        Ok(match verification_type {
+            VerificationTypeInfo::Top => "top".to_owned(),
+            VerificationTypeInfo::Integer => "int".to_owned(),
+            VerificationTypeInfo::Float => "float".to_owned(),
+            VerificationTypeInfo::Double => "double".to_owned(),
+            VerificationTypeInfo::Long => "long".to_owned(),
+            VerificationTypeInfo::Null => "null".to_owned(),
+            VerificationTypeInfo::UninitializedThis => "uninitialized_this".to_owned(),
+            VerificationTypeInfo::Object { cpool_index } => {
+                self.constant_pool.class_name(*cpool_index)?.to_owned()
+            }
+            VerificationTypeInfo::Uninitialized { offset } => {
+                format!("uninitialized {}", labels.name(*offset as u32))
+            }
+        })
+    }
+
+    fn annotation(&self, annotation: &Annotation) -> Result<String> {
+        let mut elements = vec![];
+        for pair in &annotation.element_value_pairs {
+            elements.push(format!(
+                "{}={}",
+                self.constant_pool.utf8(pair.element_name_index)?,
+                self.value(&pair.value.resolve(self.constant_pool)?)?
+            ));
+        }
+        Ok(format!(
+            "@{}({})",
+            annotation.type_descriptor(self.constant_pool)?,
+            elements.join(", ")
+        ))
+    }
+
+    fn value(&self, value: &Value) -> Result<String> {
+        Ok(match value {
+            Value::Byte(value) => format!("(byte) {value}"),
+            Value::Char(value) => match char::from_u32(*value as u32) {
+                Some(char) if !char.is_control() && char != '\'' && char != '\\' => {
+                    format!("'{char}'")
+                }
+                _ => format!("'\\u{value:04x}'"),
+            },
+            Value::Double(value) => format!("{value:?}d"),
+            Value::Float(value) => format!("{value:?}f"),
+            Value::Int(value) => value.to_string(),
+            Value::Long(value) => format!("{value}l"),
+            Value::Short(value) => format!("(short) {value}"),
+            Value::Boolean(value) => value.to_string(),
+            Value::String(value) => quote(value),
+            Value::Enum {
+                type_descriptor,
+                name,
+            } => format!("{type_descriptor}.{name}"),
+            Value::Class(descriptor) => format!("{descriptor}.class"),
+            Value::Annotation(annotation) => self.annotation(annotation)?,
+            Value::Array(values) => format!(
+                "{{{}}}",
+                values
+                    .iter()
+                    .map(|value| self.value(value))
+                    .collect::<Result<Vec<_>>>()?
+                    .join(", ")
+            ),
+        })
+    }
+
+    fn type_annotation(&self, type_annotation: &TypeAnnotation, labels: &Labels) -> Result<String> {
+        let annotation = self.annotation(&Annotation {
+            type_index: type_annotation.type_index,
+            element_value_pairs: type_annotation.element_value_pairs.clone(),
+        })?;
+        let target = match &type_annotation.target_info {
+            TargetInfo::ClassTypeParameter {
+                type_parameter_index,
+            }
+            | TargetInfo::MethodTypeParameter {
+                type_parameter_index,
+            } => format!(" type_parameter={type_parameter_index}"),
+            TargetInfo::ClassExtends { supertype_index } => format!(" supertype={supertype_index}"),
+            TargetInfo::ClassTypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            }
+            | TargetInfo::MethodTypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            } => format!(" type_parameter={type_parameter_index} bound={bound_index}"),
+            TargetInfo::Field | TargetInfo::MethodReturn | TargetInfo::MethodReceiver => {
+                String::new()
+            }
+            TargetInfo::MethodFormalParameter {
+                formal_parameter_index,
+            } => format!(" parameter={formal_parameter_index}"),
+            TargetInfo::Throws { throws_type_index } => format!(" throws={throws_type_index}"),
+            TargetInfo::LocalVariable(targets) | TargetInfo::ResourceVariable(targets) => format!(
+                " locals=[{}]",
+                targets
+                    .iter()
+                    .map(|target| {
+                        let start = target.start_pc as u32;
+                        format!(
+                            "{} from {} to {}",
+                            target.index,
+                            labels.name(start),
+                            labels.name(start + target.length as u32)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TargetInfo::ExceptionParameter {
+                exception_table_index,
+            } => format!(" exception_table={exception_table_index}"),
+            TargetInfo::Instanceof { offset }
+            | TargetInfo::New { offset }
+            | TargetInfo::ConstructorReference { offset }
+            | TargetInfo::MethodReference { offset } => {
+                format!(" at {}", labels.name(*offset as u32))
+            }
+            TargetInfo::Cast {
+                offset,
+                type_argument_index,
+            }
+            | TargetInfo::ConstructorInvocationTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | TargetInfo::MethodInvocationTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | TargetInfo::ConstructorReferenceTypeArgument {
+                offset,
+                type_argument_index,
+            }
+            | TargetInfo::MethodReferenceTypeArgument {
+                offset,
+                type_argument_index,
+            } => format!(
+                " at {} type_argument={type_argument_index}",
+                labels.name(*offset as u32)
+            ),
+        };
+        let path = if type_annotation.target_path.0.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " path=[{}]",
+                type_annotation
+                    .target_path
+                    .0
+                    .iter()
+                    .map(|entry| match entry.type_path_kind {
+                        TypePathKind::Array => "ARRAY".to_owned(),
+                        TypePathKind::Nested => "INNER_TYPE".to_owned(),
+                        TypePathKind::WildcardBound => "WILDCARD".to_owned(),
+                        TypePathKind::TypeArgument => {
+                            format!("TYPE_ARGUMENT({})", entry.type_argument_index)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+        Ok(format!(
+            "{annotation} {}{target}{path}",
+            target_type_name(type_annotation.target_info.target_type())
+        ))
+    }
+}
+
+/// Names of the offsets referenced in a method's code, numbered in the order
+/// of the offsets.
+#[derive(Default)]
+pub(crate) struct Labels(pub(crate) BTreeMap<u32, usize>);
+
+impl Labels {
+    pub(crate) fn name(&self, offset: u32) -> String {
+        match self.0.get(&offset) {
+            Some(index) => format!("L{index}"),
+            None => format!("@{offset}"),
+        }
+    }
+}
+
+/// Collects the offsets referenced by instructions, the exception table and
+/// the attributes of a method's code, including those of stack map frames if
+/// `frames` is set.
+pub(crate) fn referenced_offsets(
+    instructions: &[(u32, Instruction)],
+    code: &CodeAttribute,
+    attributes: &[Attribute],
+    frames: bool,
+) -> BTreeSet<u32> {
+    let mut offsets = BTreeSet::new();
+    for (offset, instruction) in instructions {
+        offsets.extend(branch_targets(*offset, instruction));
+    }
+    for entry in &code.exception_table {
+        offsets.extend([entry.start_pc, entry.end_pc, entry.handler_pc].map(u32::from));
+    }
+    for attribute in attributes {
+        match attribute {
+            Attribute::LineNumberTable(attribute) => {
+                offsets.extend(attribute.0.iter().map(|line| line.start_pc as u32));
+            }
+            Attribute::LocalVariableTable(attribute) => {
+                for local_variable in &attribute.0 {
+                    let start = local_variable.start_pc as u32;
+                    offsets.extend([start, start + local_variable.length as u32]);
+                }
+            }
+            Attribute::LocalVariableTypeTable(attribute) => {
+                for local_variable in &attribute.0 {
+                    let start = local_variable.start_pc as u32;
+                    offsets.extend([start, start + local_variable.length as u32]);
+                }
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(attribute)
+            | Attribute::RuntimeInvisibleTypeAnnotations(attribute) => {
+                for type_annotation in &attribute.0 {
+                    let target_info = &type_annotation.target_info;
+                    if let TargetInfo::LocalVariable(targets)
+                    | TargetInfo::ResourceVariable(targets) = target_info
+                    {
+                        for target in targets {
+                            let start = target.start_pc as u32;
+                            offsets.extend([start, start + target.length as u32]);
+                        }
+                    }
+                    offsets.extend(target_info.offset().map(u32::from));
+                }
+            }
+            Attribute::StackMapTable(attribute) if frames => {
+                for (offset, frame) in frame_offsets(attribute) {
+                    offsets.insert(offset);
+                    let (locals, stack) = frame_types(frame);
+                    for verification_type in locals.iter().chain(stack) {
+                        if let VerificationTypeInfo::Uninitialized { offset } = verification_type {
+                            offsets.insert(*offset as u32);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    offsets
+}
+
+fn branch_targets(offset: u32, instruction: &Instruction) -> Vec<u32> {
+    let target = |delta: i32| offset.wrapping_add_signed(delta);
+    match instruction {
+        Instruction::Ifeq(delta)
+        | Instruction::Ifne(delta)
+        | Instruction::Iflt(delta)
+        | Instruction::Ifge(delta)
+        | Instruction::Ifgt(delta)
+        | Instruction::Ifle(delta)
+        | Instruction::IfIcmpeq(delta)
+        | Instruction::IfIcmpne(delta)
+        | Instruction::IfIcmplt(delta)
+        | Instruction::IfIcmpge(delta)
+        | Instruction::IfIcmpgt(delta)
+        | Instruction::IfIcmple(delta)
+        | Instruction::IfAcmpeq(delta)
+        | Instruction::IfAcmpne(delta)
+        | Instruction::Goto(delta)
+        | Instruction::Jsr(delta)
+        | Instruction::Ifnull(delta)
+        | Instruction::Ifnonnull(delta) => vec![target(*delta as i32)],
+        Instruction::GotoW(delta) | Instruction::JsrW(delta) => vec![target(*delta)],
+        Instruction::Tableswitch {
+            default, offsets, ..
+        } => std::iter::once(default)
+            .chain(offsets)
+            .map(|delta| target(*delta))
+            .collect(),
+        Instruction::Lookupswitch { default, pairs } => std::iter::once(default)
+            .chain(pairs.iter().map(|(_, delta)| delta))
+            .map(|delta| target(*delta))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns the frames of a stack map table with their absolute offsets.
+pub(crate) fn frame_offsets(
+    stack_map_table: &StackMapTableAttribute,
+) -> Vec<(u32, &StackMapFrame)> {
+    let mut offset = None;
+    stack_map_table
+        .0
+        .iter()
+        .map(|frame| {
+            let frame_offset = match offset {
+                Some(offset) => offset + frame.offset_delta() as u32 + 1,
+                None => frame.offset_delta() as u32,
+            };
+            offset = Some(frame_offset);
+            (frame_offset, frame)
+        })
+        .collect()
+}
+
+fn frame_types(frame: &StackMapFrame) -> (&[VerificationTypeInfo], &[VerificationTypeInfo]) {
+    match frame {
+        StackMapFrame::SameLocals1StackItem { stack, .. }
+        | StackMapFrame::SameLocals1StackItemExtended { stack, .. } => {
+            (&[], std::slice::from_ref(stack))
+        }
+        StackMapFrame::Append { locals, .. } => (locals, &[]),
+        StackMapFrame::Full { locals, stack, .. } => (locals, stack),
+        _ => (&[], &[]),
+    }
+}
+
+/// Returns the lowercase names of the set flags, except for the given ones.
+fn modifiers<F: Flags>(flags: &F, except: &[&str]) -> Vec<String> {
+    flags
+        .iter_names()
+        .filter(|(name, _)| !except.contains(name))
+        .map(|(name, _)| name.to_lowercase())
+        .collect()
+}
+
+/// Returns the flags as hexadecimal number followed by their names, e.g.
+/// `(0x0021) ACC_PUBLIC, ACC_SUPER`.
+fn flags<F: Flags<Bits = u16>>(flags: &F) -> String {
+    let names = flags
+        .iter_names()
+        .map(|(name, _)| format!("ACC_{name}"))
+        .collect::<Vec<_>>();
+    format!("({:#06x}) {}", flags.bits(), names.join(", "))
+        .trim_end()
+        .to_owned()
+}
+
+pub(crate) fn reference_kind_name(reference_kind: u8) -> String {
+    match reference_kind {
+        1 => "REF_getField".to_owned(),
+        2 => "REF_getStatic".to_owned(),
+        3 => "REF_putField".to_owned(),
+        4 => "REF_putStatic".to_owned(),
+        5 => "REF_invokeVirtual".to_owned(),
+        6 => "REF_invokeStatic".to_owned(),
+        7 => "REF_invokeSpecial".to_owned(),
+        8 => "REF_newInvokeSpecial".to_owned(),
+        9 => "REF_invokeInterface".to_owned(),
+        _ => format!("REF_{reference_kind}"),
+    }
+}
+
+pub(crate) fn target_type_name(target_type: u8) -> String {
+    match target_type {
+        0x00 => "CLASS_TYPE_PARAMETER".to_owned(),
+        0x01 => "METHOD_TYPE_PARAMETER".to_owned(),
+        0x10 => "CLASS_EXTENDS".to_owned(),
+        0x11 => "CLASS_TYPE_PARAMETER_BOUND".to_owned(),
+        0x12 => "METHOD_TYPE_PARAMETER_BOUND".to_owned(),
+        0x13 => "FIELD".to_owned(),
+        0x14 => "METHOD_RETURN".to_owned(),
+        0x15 => "METHOD_RECEIVER".to_owned(),
+        0x16 => "METHOD_FORMAL_PARAMETER".to_owned(),
+        0x17 => "THROWS".to_owned(),
+        0x40 => "LOCAL_VARIABLE".to_owned(),
+        0x41 => "RESOURCE_VARIABLE".to_owned(),
+        0x42 => "EXCEPTION_PARAMETER".to_owned(),
+        0x43 => "INSTANCEOF".to_owned(),
+        0x44 => "NEW".to_owned(),
+        0x45 => "CONSTRUCTOR_REFERENCE".to_owned(),
+        0x46 => "METHOD_REFERENCE".to_owned(),
+        0x47 => "CAST".to_owned(),
+        0x48 => "CONSTRUCTOR_INVOCATION_TYPE_ARGUMENT".to_owned(),
+        0x49 => "METHOD_INVOCATION_TYPE_ARGUMENT".to_owned(),
+        0x4A => "CONSTRUCTOR_REFERENCE_TYPE_ARGUMENT".to_owned(),
+        0x4B => "METHOD_REFERENCE_TYPE_ARGUMENT".to_owned(),
+        _ => format!("{target_type:#04x}"),
+    }
+}
+
+/// Quotes a string, escaping quotes, backslashes and control characters.
+pub(crate) fn quote(value: &str) -> String {
+    quote_code_points(value.chars().map(u32::from))
+}
+
+/// Quotes a Java string, escaping unpaired surrogates like control characters.
+pub(crate) fn quote_java(value: &JavaString) -> String {
+    quote_code_points(value.code_points())
+}
+
+fn quote_code_points(code_points: impl Iterator<Item = u32>) -> String {
+    let mut quoted = String::from('"');
+    for code_point in code_points {
+        let Some(char) = char::from_u32(code_point) else {
+            let _ = write!(quoted, "\\u{code_point:04x}");
+            continue;
+        };
+        match char {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            char if char.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", char as u32);
+            }
+            char => quoted.push(char),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,534 @@
+//! Graph of the super classes and interfaces of a set of classes, with subtype
+//! queries and resolution of members as specified in JVMS §5.4.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    class::{ClassAccessFlags, ClassFile, FieldAccessFlags, MethodAccessFlags},
+    class_path::ClassPath,
+    code::frame::ClassHierarchy,
+    Result,
+};
+
+/// The classes of a [`Hierarchy`], reduced to their names, super types and
+/// members.
+#[derive(Debug, Clone)]
+pub struct ClassNode {
+    pub name: String,
+    pub access_flags: ClassAccessFlags,
+    pub super_name: Option<String>,
+    pub interfaces: Vec<String>,
+    pub fields: Vec<FieldNode>,
+    pub methods: Vec<MethodNode>,
+}
+
+impl ClassNode {
+    pub fn new(class_file: &ClassFile) -> Result<Self> {
+        let constant_pool = &class_file.constant_pool;
+        Ok(Self {
+            name: class_file.name()?.to_owned(),
+            access_flags: class_file.access_flags,
+            super_name: class_file.super_name()?.map(str::to_owned),
+            interfaces: class_file
+                .interface_names()?
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            fields: class_file
+                .fields
+                .iter()
+                .map(|field| {
+                    Ok(FieldNode {
+                        name: field.name(constant_pool)?.to_owned(),
+                        descriptor: constant_pool.utf8(field.descriptor_index)?.to_owned(),
+                        access_flags: field.access_flags,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            methods: class_file
+                .methods
+                .iter()
+                .map(|method| {
+                    Ok(MethodNode {
+                        name: method.name(constant_pool)?.to_owned(),
+                        descriptor: constant_pool.utf8(method.descriptor_index)?.to_owned(),
+                        access_flags: method.access_flags,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn is_interface(&self) -> bool {
+        self.access_flags.contains(ClassAccessFlags::INTERFACE)
+    }
+
+    /// Returns the internal name of the package, empty for the unnamed
+    /// package.
+    pub fn package(&self) -> &str {
+        package(&self.name)
+    }
+
+    pub fn field(&self, name: &str, descriptor: &str) -> Option<&FieldNode> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name && field.descriptor == descriptor)
+    }
+
+    pub fn method(&self, name: &str, descriptor: &str) -> Option<&MethodNode> {
+        self.methods
+            .iter()
+            .find(|method| method.name == name && method.descriptor == descriptor)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldNode {
+    pub name: String,
+    pub descriptor: String,
+    pub access_flags: FieldAccessFlags,
+}
+
+#[derive(Debug, Clone)]
+pub struct MethodNode {
+    pub name: String,
+    pub descriptor: String,
+    pub access_flags: MethodAccessFlags,
+}
+
+impl MethodNode {
+    fn is_private(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::PRIVATE)
+    }
+
+    fn is_static(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::STATIC)
+    }
+
+    fn is_abstract(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::ABSTRACT)
+    }
+}
+
+/// A method or field together with the class declaring it.
+pub type Member<'a, T> = (&'a ClassNode, &'a T);
+
+/// The class hierarchy of a set of classes, which do not have to be
+/// complete: super types missing from the hierarchy end the search in the
+/// queries below.
+#[derive(Debug, Clone, Default)]
+pub struct Hierarchy {
+    classes: HashMap<String, ClassNode>,
+}
+
+impl Hierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a class, replacing the class of the same name.
+    pub fn insert(&mut self, class_file: &ClassFile) -> Result<&mut Self> {
+        let class = ClassNode::new(class_file)?;
+        self.classes.insert(class.name.clone(), class);
+        Ok(self)
+    }
+
+    /// Adds the class with the given internal name and all of its super types
+    /// not yet present from the class path, returns if the class has been
+    /// found.
+    pub fn load(&mut self, class_path: &mut ClassPath, name: &str) -> Result<bool> {
+        let mut pending = vec![name.to_owned()];
+        while let Some(name) = pending.pop() {
+            if self.classes.contains_key(&name) {
+                continue;
+            }
+            let Some(class_file) = class_path.find(&name)? else {
+                continue;
+            };
+            let class = ClassNode::new(&class_file)?;
+            pending.extend(class.super_name.iter().cloned());
+            pending.extend(class.interfaces.iter().cloned());
+            self.classes.insert(name, class);
+        }
+        Ok(self.classes.contains_key(name))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ClassNode> {
+        self.classes.get(name)
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &ClassNode> {
+        self.classes.values()
+    }
+
+    /// Returns the super classes of a class, starting with the direct super
+    /// class. Cyclic hierarchies, which the JVM rejects, end before the first
+    /// repeated class.
+    pub fn super_classes<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a ClassNode> {
+        let mut class = self.get(name);
+        let mut visited = class
+            .map(|class| class.name.as_str())
+            .into_iter()
+            .collect::<HashSet<_>>();
+        std::iter::from_fn(move || {
+            class = self
+                .get(class?.super_name.as_deref()?)
+                .filter(|super_class| visited.insert(&super_class.name));
+            class
+        })
+    }
+
+    /// Returns all super interfaces of a class, including the ones of its
+    /// super classes and super interfaces, each once.
+    pub fn interfaces<'a>(&'a self, name: &str) -> Vec<&'a ClassNode> {
+        let mut interfaces = vec![];
+        let mut visited = HashSet::new();
+        let mut pending = vec![];
+        for class in self.get(name).into_iter().chain(self.super_classes(name)) {
+            pending.extend(class.interfaces.iter().rev());
+            while let Some(interface) = pending.pop() {
+                if !visited.insert(interface.as_str()) {
+                    continue;
+                }
+                if let Some(interface) = self.get(interface) {
+                    interfaces.push(interface);
+                    pending.extend(interface.interfaces.iter().rev());
+                }
+            }
+        }
+        interfaces
+    }
+
+    /// Returns if values of type `a` are assignable to type `b`, both given as
+    /// internal names, or as descriptors for array types.
+    pub fn is_subtype(&self, a: &str, b: &str) -> bool {
+        if a == b {
+            return true;
+        }
+        match (a.strip_prefix('['), b.strip_prefix('[')) {
+            (Some(a), Some(b)) => match (component(a), component(b)) {
+                (Some(a), Some(b)) => self.is_subtype(a, b),
+                _ => false,
+            },
+            (Some(_), None) => matches!(
+                b,
+                "java/lang/Object" | "java/lang/Cloneable" | "java/io/Serializable"
+            ),
+            (None, Some(_)) => false,
+            (None, None) => {
+                b == "java/lang/Object"
+                    || self.super_classes(a).any(|class| class.name == b)
+                    || self.interfaces(a).iter().any(|class| class.name == b)
+            }
+        }
+    }
+
+    /// Returns the methods a class inherits from its super types, excluding
+    /// private methods, constructors, methods it declares itself and methods
+    /// of other packages it cannot access.
+    ///
+    /// Of the methods of super interfaces the maximally-specific ones are
+    /// returned, preferring default methods.
+    pub fn inherited_methods<'a>(&'a self, name: &str) -> Vec<Member<'a, MethodNode>> {
+        let Some(class) = self.get(name) else {
+            return vec![];
+        };
+        let mut signatures = class
+            .methods
+            .iter()
+            .map(|method| (method.name.as_str(), method.descriptor.as_str()))
+            .collect::<HashSet<_>>();
+        let mut methods = vec![];
+        for super_class in self.super_classes(name) {
+            for method in &super_class.methods {
+                if method.is_private()
+                    || method.name.starts_with('<')
+                    || (!method
+                        .access_flags
+                        .intersects(MethodAccessFlags::PUBLIC | MethodAccessFlags::PROTECTED)
+                        && super_class.package() != class.package())
+                {
+                    continue;
+                }
+                if signatures.insert((&method.name, &method.descriptor)) {
+                    methods.push((super_class, method));
+                }
+            }
+        }
+        for interface in self.interfaces(name) {
+            for method in &interface.methods {
+                if method.is_private()
+                    || method.is_static()
+                    || !signatures.insert((&method.name, &method.descriptor))
+                {
+                    continue;
+                }
+                let candidates = self.maximally_specific(class, &method.name, &method.descriptor);
+                methods.extend(
+                    candidates
+                        .iter()
+                        .find(|(_, method)| !method.is_abstract())
+                        .or(candidates.first()),
+                );
+            }
+        }
+        methods
+    }
+
+    /// Resolves a method referenced by a `Methodref`, JVMS §5.4.3.3. Returns
+    /// `None` if the class is an interface or the method is not found.
+    pub fn resolve_method<'a>(
+        &'a self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<Member<'a, MethodNode>> {
+        let class = self.get(class)?;
+        if class.is_interface() {
+            return None;
+        }
+        for super_class in std::iter::once(class).chain(self.super_classes(&class.name)) {
+            if let Some(method) = signature_polymorphic(super_class, name) {
+                return Some((super_class, method));
+            }
+            if let Some(method) = super_class.method(name, descriptor) {
+                return Some((super_class, method));
+            }
+        }
+        self.resolve_in_interfaces(class, name, descriptor)
+    }
+
+    /// Resolves a method referenced by an `InterfaceMethodref`, JVMS §5.4.3.4.
+    /// Returns `None` if the class is not an interface or the method is not
+    /// found.
+    pub fn resolve_interface_method<'a>(
+        &'a self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<Member<'a, MethodNode>> {
+        let class = self.get(class)?;
+        if !class.is_interface() {
+            return None;
+        }
+        if let Some(method) = class.method(name, descriptor) {
+            return Some((class, method));
+        }
+        if let Some(object) = self.get("java/lang/Object") {
+            if let Some(method) = object.method(name, descriptor).filter(|method| {
+                method.access_flags.contains(MethodAccessFlags::PUBLIC) && !method.is_static()
+            }) {
+                return Some((object, method));
+            }
+        }
+        self.resolve_in_interfaces(class, name, descriptor)
+    }
+
+    /// Resolves a field referenced by a `Fieldref`, JVMS §5.4.3.2, looking in
+    /// the class, then its super interfaces and then its super class.
+    pub fn resolve_field<'a>(
+        &'a self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<Member<'a, FieldNode>> {
+        self.resolve_field_in(class, name, descriptor, &mut HashSet::new())
+    }
+
+    /// Resolves a field, skipping the classes searched already, which also
+    /// ends the search in cyclic hierarchies.
+    fn resolve_field_in<'a>(
+        &'a self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+        visited: &mut HashSet<&'a str>,
+    ) -> Option<Member<'a, FieldNode>> {
+        let class = self.get(class)?;
+        if !visited.insert(&class.name) {
+            return None;
+        }
+        if let Some(field) = class.field(name, descriptor) {
+            return Some((class, field));
+        }
+        class
+            .interfaces
+            .iter()
+            .chain(&class.super_name)
+            .find_map(|super_type| self.resolve_field_in(super_type, name, descriptor, visited))
+    }
+
+    /// Selects the method invoked by `invokevirtual` or `invokeinterface` on
+    /// an instance of the given class for a resolved method, JVMS §5.4.6.
+    /// Returns `None` if there is no or no unique non-abstract method.
+    pub fn select_method<'a>(
+        &'a self,
+        class: &str,
+        resolved: Member<'a, MethodNode>,
+    ) -> Option<Member<'a, MethodNode>> {
+        let (_, resolved_method) = resolved;
+        if resolved_method.is_private() {
+            return Some(resolved);
+        }
+        let class = self.get(class)?;
+        for super_class in std::iter::once(class).chain(self.super_classes(&class.name)) {
+            if let Some(method) = super_class
+                .method(&resolved_method.name, &resolved_method.descriptor)
+                .filter(|method| !method.is_static())
+            {
+                if self.can_override((super_class, method), resolved) {
+                    return Some((super_class, method));
+                }
+            }
+        }
+        let candidates =
+            self.maximally_specific(class, &resolved_method.name, &resolved_method.descriptor);
+        let mut concrete = candidates
+            .into_iter()
+            .filter(|(_, method)| !method.is_abstract());
+        match (concrete.next(), concrete.next()) {
+            (Some(method), None) => Some(method),
+            _ => None,
+        }
+    }
+
+    /// Returns if a method can override another method, JVMS §5.4.5, which
+    /// for package-private methods of other packages requires an overriding
+    /// method in between.
+    fn can_override(&self, method: Member<MethodNode>, other: Member<MethodNode>) -> bool {
+        let ((class, method), (other_class, other_method)) = (method, other);
+        if class.name == other_class.name {
+            return true;
+        }
+        if method.is_private() {
+            return false;
+        }
+        if other_method
+            .access_flags
+            .intersects(MethodAccessFlags::PUBLIC | MethodAccessFlags::PROTECTED)
+            || class.package() == other_class.package()
+        {
+            return true;
+        }
+        // look for an overriding method in the super classes up to the other
+        // class, which cyclic hierarchies may never reach
+        let super_classes = self.super_classes(&class.name).collect::<Vec<_>>();
+        let Some(end) = super_classes
+            .iter()
+            .position(|super_class| super_class.name == other_class.name)
+        else {
+            return false;
+        };
+        super_classes[..end].iter().any(|super_class| {
+            super_class
+                .method(&method.name, &method.descriptor)
+                .filter(|super_method| !super_method.is_private())
+                .is_some_and(|super_method| {
+                    self.can_override((class, method), (super_class, super_method))
+                        && self.can_override((super_class, super_method), other)
+                })
+        })
+    }
+
+    /// Resolves a method in the super interfaces, preferring the only
+    /// non-abstract maximally-specific method, and any other otherwise.
+    fn resolve_in_interfaces<'a>(
+        &'a self,
+        class: &ClassNode,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<Member<'a, MethodNode>> {
+        let candidates = self.maximally_specific(class, name, descriptor);
+        let mut concrete = candidates
+            .iter()
+            .filter(|(_, method)| !method.is_abstract());
+        if let (Some(method), None) = (concrete.next(), concrete.next()) {
+            return Some(*method);
+        }
+        candidates.first().copied()
+    }
+
+    /// Returns the non-private, non-static methods of the super interfaces of
+    /// a class with the given name and descriptor that are not declared by a
+    /// sub interface of the one declaring them, too.
+    fn maximally_specific<'a>(
+        &'a self,
+        class: &ClassNode,
+        name: &str,
+        descriptor: &str,
+    ) -> Vec<Member<'a, MethodNode>> {
+        let candidates = self
+            .interfaces(&class.name)
+            .into_iter()
+            .filter_map(|interface| {
+                interface
+                    .method(name, descriptor)
+                    .filter(|method| !method.is_private() && !method.is_static())
+                    .map(|method| (interface, method))
+            })
+            .collect::<Vec<_>>();
+        candidates
+            .iter()
+            .filter(|(interface, _)| {
+                !candidates.iter().any(|(other, _)| {
+                    other.name != interface.name && self.is_subtype(&other.name, &interface.name)
+                })
+            })
+            .copied()
+            .collect()
+    }
+}
+
+impl ClassHierarchy for Hierarchy {
+    fn common_super_class(&self, a: &str, b: &str) -> String {
+        let object = "java/lang/Object".to_owned();
+        if [a, b]
+            .iter()
+            .any(|name| self.get(name).is_none_or(ClassNode::is_interface))
+        {
+            return object;
+        }
+        if self.is_subtype(b, a) {
+            return a.to_owned();
+        }
+        self.super_classes(a)
+            .find(|super_class| self.is_subtype(b, &super_class.name))
+            .map_or(object, |super_class| super_class.name.clone())
+    }
+}
+
+/// Returns the signature polymorphic method with the given name, JVMS §2.9.3,
+/// which matches any descriptor.
+fn signature_polymorphic<'a>(class: &'a ClassNode, name: &str) -> Option<&'a MethodNode> {
+    if !matches!(
+        class.name.as_str(),
+        "java/lang/invoke/MethodHandle" | "java/lang/invoke/VarHandle"
+    ) {
+        return None;
+    }
+    let mut methods = class.methods.iter().filter(|method| method.name == name);
+    match (methods.next(), methods.next()) {
+        (Some(method), None)
+            if method.descriptor.starts_with("([Ljava/lang/Object;)")
+                && method
+                    .access_flags
+                    .contains(MethodAccessFlags::VARARGS | MethodAccessFlags::NATIVE) =>
+        {
+            Some(method)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the internal name of the component type of an array type, given
+/// without the leading `[`, `None` for primitive types.
+fn component(descriptor: &str) -> Option<&str> {
+    if descriptor.starts_with('[') {
+        return Some(descriptor);
+    }
+    descriptor.strip_prefix('L')?.strip_suffix(';')
+}
+
+fn package(name: &str) -> &str {
+    name.rsplit_once('/').map_or("", |(package, _)| package)
+}
@@ -0,0 +1,345 @@
+//! Reading of jar files, enabled by the `jar` feature.
+
+use std::{
+    collections::{btree_map, BTreeMap},
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    class::{borrowed::ClassFileRef, ClassFile},
+    Decode, Error, Result,
+};
+
+const MANIFEST: &str = "META-INF/MANIFEST.MF";
+const VERSIONS: &str = "META-INF/versions/";
+
+/// A jar file, the entries of which are read on request.
+///
+/// Multi-release jars are read as for the release set with
+/// [`release`](Self::release): an entry in `META-INF/versions/N/` takes the
+/// place of the base entry for the highest `N` not above the release. By
+/// default, only the base entries are read.
+pub struct Jar<R> {
+    archive: ZipArchive<R>,
+    manifest: Option<Manifest>,
+    release: Option<u16>,
+    /// Indices of the entries in the archive by their name, after resolving
+    /// versioned entries.
+    entries: BTreeMap<String, usize>,
+}
+
+impl Jar<File> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> Jar<R> {
+    pub fn new(reader: R) -> Result<Self> {
+        let mut archive = ZipArchive::new(reader)?;
+        let manifest = match archive.by_name(MANIFEST) {
+            Ok(mut file) => {
+                let mut input = vec![];
+                file.read_to_end(&mut input)?;
+                Some(Manifest::parse(&input)?)
+            }
+            Err(zip::result::ZipError::FileNotFound) => None,
+            Err(error) => return Err(error.into()),
+        };
+        let mut jar = Self {
+            archive,
+            manifest,
+            release: None,
+            entries: BTreeMap::new(),
+        };
+        jar.index()?;
+        Ok(jar)
+    }
+
+    pub fn manifest(&self) -> Option<&Manifest> {
+        self.manifest.as_ref()
+    }
+
+    /// Sets the feature release of Java, e.g. 17, entries of multi-release
+    /// jars are resolved for.
+    pub fn release(&mut self, release: u16) -> Result<&mut Self> {
+        self.release = Some(release);
+        self.index()?;
+        Ok(self)
+    }
+
+    fn index(&mut self) -> Result<()> {
+        let release = self.release.filter(|_| {
+            self.manifest
+                .as_ref()
+                .is_some_and(Manifest::is_multi_release)
+        });
+        // the version of each entry, 0 for base entries
+        let mut entries = BTreeMap::<String, (u16, usize)>::new();
+        for index in 0..self.archive.len() {
+            let file = self.archive.by_index_raw(index)?;
+            if file.is_dir() {
+                continue;
+            }
+            let (version, name) = match (release, versioned(file.name())) {
+                (Some(release), Some((version, name))) => {
+                    // versioned entries are only defined from Java 9 on
+                    if version < 9 || version > release {
+                        continue;
+                    }
+                    (version, name)
+                }
+                _ => (0, file.name()),
+            };
+            match entries.get_mut(name) {
+                Some(entry) if entry.0 < version => *entry = (version, index),
+                Some(_) => {}
+                None => {
+                    entries.insert(name.to_owned(), (version, index));
+                }
+            }
+        }
+        self.entries = entries
+            .into_iter()
+            .map(|(name, (_, index))| (name, index))
+            .collect();
+        Ok(())
+    }
+
+    /// Returns the names of all entries, in lexicographical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Returns the internal names of all classes, in lexicographical order.
+    pub fn class_names(&self) -> impl Iterator<Item = &str> {
+        self.names().filter_map(class_name)
+    }
+
+    pub fn entry(&mut self, name: &str) -> Result<Option<JarEntry>> {
+        match self.entries.get(name) {
+            Some(index) => read(&mut self.archive, name, *index).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads and decodes the class with the given internal name, e.g.
+    /// `java/lang/String`.
+    pub fn class(&mut self, class_name: &str) -> Result<Option<ClassFile>> {
+        match self.entry(&format!("{class_name}.class"))? {
+            Some(entry) => entry.decode().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns an iterator reading the entries one by one.
+    pub fn entries(&mut self) -> Entries<'_, R> {
+        Entries {
+            archive: &mut self.archive,
+            entries: self.entries.iter(),
+        }
+    }
+}
+
+/// Splits the name of an entry in `META-INF/versions/N/` into `N` and the name
+/// of the base entry.
+fn versioned(name: &str) -> Option<(u16, &str)> {
+    let (version, name) = name.strip_prefix(VERSIONS)?.split_once('/')?;
+    Some((version.parse().ok()?, name))
+}
+
+/// Returns the internal class name of class files outside of `META-INF`,
+/// including `module-info`.
+fn class_name(name: &str) -> Option<&str> {
+    if name.starts_with("META-INF/") {
+        return None;
+    }
+    name.strip_suffix(".class")
+}
+
+fn read<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str, index: usize) -> Result<JarEntry> {
+    let mut file = archive.by_index(index)?;
+    // the size is declared by the archive, it is not trusted for allocating
+    let mut data = vec![];
+    file.read_to_end(&mut data)?;
+    Ok(JarEntry {
+        name: name.to_owned(),
+        data,
+    })
+}
+
+/// Iterator over the entries of a [`Jar`].
+pub struct Entries<'a, R> {
+    archive: &'a mut ZipArchive<R>,
+    entries: btree_map::Iter<'a, String, usize>,
+}
+
+impl<R: Read + Seek> Iterator for Entries<'_, R> {
+    type Item = Result<JarEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (name, index) = self.entries.next()?;
+        Some(read(self.archive, name, *index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+/// An entry of a [`Jar`], named as the base entry if it has been resolved
+/// from `META-INF/versions/N/`.
+#[derive(Debug, Clone)]
+pub struct JarEntry {
+    name: String,
+    data: Vec<u8>,
+}
+
+impl JarEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the internal class name if the entry is a class file.
+    pub fn class_name(&self) -> Option<&str> {
+        class_name(&self.name)
+    }
+
+    pub fn decode(&self) -> Result<ClassFile> {
+        ClassFile::decode(&mut self.data.as_slice())
+    }
+
+    pub fn decode_ref(&self) -> Result<ClassFileRef<'_>> {
+        ClassFileRef::decode(&mut self.data.as_slice())
+    }
+}
+
+/// The manifest of a jar file, `META-INF/MANIFEST.MF`. Attribute names are
+/// compared case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    pub main_attributes: Vec<(String, String)>,
+    /// The per-entry sections, by the value of their `Name` attribute.
+    pub entries: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Manifest {
+    /// Parses a manifest, joining continuation lines.
+    pub fn parse(input: &[u8]) -> Result<Self> {
+        let mut sections = vec![];
+        let mut attributes: Vec<(String, Vec<u8>)> = vec![];
+        let mut first_line = 1;
+        for (line_number, line) in lines(input).enumerate() {
+            let line_number = line_number + 1;
+            if line.is_empty() {
+                if !attributes.is_empty() {
+                    sections.push((first_line, std::mem::take(&mut attributes)));
+                }
+                first_line = line_number + 1;
+            } else if let Some(continuation) = line.strip_prefix(b" ") {
+                match attributes.last_mut() {
+                    Some((_, value)) => value.extend_from_slice(continuation),
+                    None => return Err(Error::InvalidManifest { line: line_number }),
+                }
+            } else {
+                let separator = line
+                    .windows(2)
+                    .position(|separator| separator == b": ")
+                    .ok_or(Error::InvalidManifest { line: line_number })?;
+                let name = std::str::from_utf8(&line[..separator])
+                    .ok()
+                    .filter(|name| {
+                        !name.is_empty()
+                            && name
+                                .bytes()
+                                .all(|char| char.is_ascii_alphanumeric() || b"-_".contains(&char))
+                    })
+                    .ok_or(Error::InvalidManifest { line: line_number })?;
+                attributes.push((name.to_owned(), line[separator + 2..].to_vec()));
+            }
+        }
+        if !attributes.is_empty() {
+            sections.push((first_line, attributes));
+        }
+
+        let mut manifest = Self::default();
+        for (index, (line, attributes)) in sections.into_iter().enumerate() {
+            let attributes = attributes
+                .into_iter()
+                .map(|(name, value)| {
+                    Ok((
+                        name,
+                        String::from_utf8(value).map_err(|_| Error::InvalidManifest { line })?,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if index == 0 {
+                manifest.main_attributes = attributes;
+            } else {
+                // per-entry sections start with their name
+                match attributes.split_first() {
+                    Some(((name, value), attributes)) if name.eq_ignore_ascii_case("Name") => {
+                        manifest.entries.push((value.clone(), attributes.to_vec()))
+                    }
+                    _ => return Err(Error::InvalidManifest { line }),
+                }
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn main_attribute(&self, name: &str) -> Option<&str> {
+        attribute(&self.main_attributes, name)
+    }
+
+    pub fn entry_attribute(&self, entry: &str, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry_name, _)| entry_name == entry)
+            .and_then(|(_, attributes)| attribute(attributes, name))
+    }
+
+    /// Returns the binary name of the main class, e.g. `com.example.Main`.
+    pub fn main_class(&self) -> Option<&str> {
+        self.main_attribute("Main-Class")
+    }
+
+    pub fn is_multi_release(&self) -> bool {
+        self.main_attribute("Multi-Release")
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+    }
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(attribute_name, _)| attribute_name.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Splits the input into lines ending with CR LF, LF or CR.
+fn lines(mut input: &[u8]) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        if input.is_empty() {
+            return None;
+        }
+        let end = input
+            .iter()
+            .position(|byte| matches!(byte, b'\r' | b'\n'))
+            .unwrap_or(input.len());
+        let line = &input[..end];
+        input = match &input[end..] {
+            [b'\r', b'\n', rest @ ..] | [b'\r' | b'\n', rest @ ..] => rest,
+            rest => rest,
+        };
+        Some(line)
+    })
+}
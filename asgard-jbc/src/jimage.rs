@@ -0,0 +1,521 @@
+//! Reading of the runtime image of JDK 9 and later, `lib/modules`, in the
+//! jimage format.
+
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    vec,
+};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian, WriteBytesExt};
+
+use crate::{
+    class::{borrowed::ClassFileRef, ClassFile},
+    Decode, Error, Result,
+};
+
+const MAGIC: u32 = 0xCAFEDADA;
+const MAJOR_VERSION: u16 = 1;
+const HEADER_SIZE: usize = 7 * 4;
+const HASH_MULTIPLIER: u32 = 0x01000193;
+
+const ATTRIBUTE_END: u8 = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+
+const COMPRESSED_MAGIC: u32 = 0xCAFEFAFA;
+const COMPRESSED_HEADER_SIZE: usize = 4 + 8 + 8 + 4 + 4 + 1;
+
+/// A jimage file, the index of which is kept in memory while the resources
+/// are read on request.
+///
+/// Resources are named `/<module>/<path>`, e.g.
+/// `/java.base/java/lang/String.class`. Resources compressed by `jlink
+/// --compress` are decompressed when read.
+pub struct JImage<R> {
+    reader: R,
+    /// Whether the image has been written on a big-endian platform, all
+    /// numbers of the index are stored in the byte order of the platform.
+    big_endian: bool,
+    redirect: Vec<i32>,
+    offsets: Vec<u32>,
+    locations: Vec<u8>,
+    strings: Vec<u8>,
+    /// Offset of the resources, following the index.
+    index_size: u64,
+    /// Length of the image, which resources have to fit into.
+    length: u64,
+}
+
+impl JImage<File> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> JImage<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let big_endian = match LittleEndian::read_u32(&header) {
+            MAGIC => false,
+            _ if BigEndian::read_u32(&header) == MAGIC => true,
+            _ => return Err(Error::InvalidImage("bad magic")),
+        };
+        let mut header = header
+            .chunks_exact(4)
+            .map(|value| read_u32(big_endian, value) as usize)
+            .skip(1);
+        let mut next = || header.next().unwrap();
+        if next() >> 16 != MAJOR_VERSION as usize {
+            return Err(Error::InvalidImage("unsupported version"));
+        }
+        let _flags = next();
+        let _resource_count = next();
+        let table_length = next();
+        let locations_size = next();
+        let strings_size = next();
+
+        // the sizes are untrusted, and checked against the length of the
+        // image before allocating the index
+        let length = reader.seek(SeekFrom::End(0))?;
+        let index_size = (table_length as u64 * 8)
+            .checked_add(locations_size as u64)
+            .and_then(|size| size.checked_add(strings_size as u64))
+            .filter(|size| HEADER_SIZE as u64 + size <= length)
+            .ok_or(Error::InvalidImage("index exceeds image"))? as usize;
+        reader.seek(SeekFrom::Start(HEADER_SIZE as u64))?;
+        let mut index = vec![0; index_size];
+        reader.read_exact(&mut index)?;
+        let (redirect, index) = index.split_at(table_length * 4);
+        let (offsets, index) = index.split_at(table_length * 4);
+        let (locations, strings) = index.split_at(locations_size);
+        Ok(Self {
+            reader,
+            big_endian,
+            redirect: redirect
+                .chunks_exact(4)
+                .map(|value| read_u32(big_endian, value) as i32)
+                .collect(),
+            offsets: offsets
+                .chunks_exact(4)
+                .map(|value| read_u32(big_endian, value))
+                .collect(),
+            locations: locations.to_vec(),
+            strings: strings.to_vec(),
+            index_size: (HEADER_SIZE + index_size) as u64,
+            length,
+        })
+    }
+
+    /// Reads the resource with the given name, e.g.
+    /// `/java.base/java/lang/String.class`.
+    pub fn resource(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        match self.location(name)? {
+            Some(location) => self.read(&location).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the name of the module containing the package with the given
+    /// internal name, e.g. `java/lang`.
+    pub fn package_module(&mut self, package: &str) -> Result<Option<String>> {
+        // lists the modules containing the package, each as a flag telling
+        // if the package is empty in the module and the offset of its name
+        let Some(modules) = self.resource(&format!("/packages/{}", package.replace('/', ".")))?
+        else {
+            return Ok(None);
+        };
+        for module in modules.chunks_exact(8) {
+            if read_u32(self.big_endian, &module[..4]) == 0 {
+                let name = self.string(read_u32(self.big_endian, &module[4..]) as u64)?;
+                return Ok(Some(name.to_owned()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads and decodes the class with the given internal name, e.g.
+    /// `java/lang/String`, from the module containing its package.
+    pub fn class(&mut self, class_name: &str) -> Result<Option<ClassFile>> {
+        let package = class_name
+            .rsplit_once('/')
+            .map_or("", |(package, _)| package);
+        let Some(module) = self.package_module(package)? else {
+            return Ok(None);
+        };
+        match self.resource(&format!("/{module}/{class_name}.class"))? {
+            Some(data) => ClassFile::decode(&mut data.as_slice()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns an iterator reading the resources one by one, ordered by module
+    /// and path.
+    pub fn entries(&mut self) -> Result<Entries<'_, R>> {
+        let mut entries = vec![];
+        for offset in &self.offsets {
+            let location = Location::decode(
+                self.locations
+                    .get(*offset as usize..)
+                    .ok_or(Error::InvalidImage("location offset out of bounds"))?,
+            )?;
+            // skips the directories of the virtual file system, `/modules/`
+            // and `/packages/`, as well as the resources outside of modules
+            let module = self.string(location.attributes[ATTRIBUTE_MODULE])?;
+            if matches!(module, "" | "modules" | "packages") {
+                continue;
+            }
+            let name = self.location_name(&location)?;
+            let path = name[module.len() + 2..].to_owned();
+            entries.push((module.to_owned(), path, location));
+        }
+        entries.sort_unstable_by(|(module, path, _), (other_module, other_path, _)| {
+            (module, path).cmp(&(other_module, other_path))
+        });
+        Ok(Entries {
+            jimage: self,
+            entries: entries.into_iter(),
+        })
+    }
+
+    /// Looks up the location of a resource by the hash of its name, verifying
+    /// that the location found is named the same.
+    fn location(&self, name: &str) -> Result<Option<Location>> {
+        let length = self.redirect.len() as u32;
+        if length == 0 {
+            return Ok(None);
+        }
+        let index = match self.redirect[(hash(name, HASH_MULTIPLIER) % length) as usize] {
+            0 => return Ok(None),
+            // redirects to the index directly
+            index if index < 0 => (-1 - index) as u32,
+            // seeds the hash resolving the collision
+            seed => hash(name, seed as u32) % length,
+        };
+        let Some(offset) = self.offsets.get(index as usize) else {
+            return Err(Error::InvalidImage("location index out of bounds"));
+        };
+        let location = Location::decode(
+            self.locations
+                .get(*offset as usize..)
+                .ok_or(Error::InvalidImage("location offset out of bounds"))?,
+        )?;
+        Ok((self.location_name(&location)? == name).then_some(location))
+    }
+
+    fn location_name(&self, location: &Location) -> Result<String> {
+        let mut name = String::new();
+        let module = self.string(location.attributes[ATTRIBUTE_MODULE])?;
+        if !module.is_empty() {
+            name.push('/');
+            name.push_str(module);
+            name.push('/');
+        }
+        let parent = self.string(location.attributes[ATTRIBUTE_PARENT])?;
+        if !parent.is_empty() {
+            name.push_str(parent);
+            name.push('/');
+        }
+        name.push_str(self.string(location.attributes[ATTRIBUTE_BASE])?);
+        let extension = self.string(location.attributes[ATTRIBUTE_EXTENSION])?;
+        if !extension.is_empty() {
+            name.push('.');
+            name.push_str(extension);
+        }
+        Ok(name)
+    }
+
+    fn string(&self, offset: u64) -> Result<&str> {
+        std::str::from_utf8(self.string_bytes(offset)?)
+            .map_err(|_| Error::InvalidImage("invalid string"))
+    }
+
+    /// Returns the null-terminated, modified UTF-8 encoded string at the given
+    /// offset of the strings table.
+    fn string_bytes(&self, offset: u64) -> Result<&[u8]> {
+        let string = self
+            .strings
+            .get(offset as usize..)
+            .ok_or(Error::InvalidImage("string offset out of bounds"))?;
+        let end = string
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or(Error::InvalidImage("unterminated string"))?;
+        Ok(&string[..end])
+    }
+
+    fn read(&mut self, location: &Location) -> Result<Vec<u8>> {
+        let compressed_size = location.attributes[ATTRIBUTE_COMPRESSED];
+        let size = if compressed_size != 0 {
+            compressed_size
+        } else {
+            location.attributes[ATTRIBUTE_UNCOMPRESSED]
+        };
+        let offset = self
+            .index_size
+            .checked_add(location.attributes[ATTRIBUTE_OFFSET])
+            .filter(|offset| {
+                offset
+                    .checked_add(size)
+                    .is_some_and(|end| end <= self.length)
+            })
+            .ok_or(Error::InvalidImage("resource exceeds image"))?;
+        let mut data = vec![0; size as usize];
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.reader.read_exact(&mut data)?;
+        if compressed_size != 0 {
+            data = self.decompress(data)?;
+        }
+        Ok(data)
+    }
+
+    /// Decompresses a resource, which might have been compressed several
+    /// times, each prefixed with a header naming the decompressor.
+    fn decompress(&self, mut data: Vec<u8>) -> Result<Vec<u8>> {
+        while data.len() >= COMPRESSED_HEADER_SIZE
+            && read_u32(self.big_endian, &data) == COMPRESSED_MAGIC
+        {
+            let header = &data[..COMPRESSED_HEADER_SIZE];
+            let uncompressed_size = read_u64(self.big_endian, &header[12..]) as usize;
+            let decompressor = self.string(read_u32(self.big_endian, &header[20..]) as u64)?;
+            let input = &data[COMPRESSED_HEADER_SIZE..];
+            data = match decompressor {
+                "zip" => miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(
+                    input,
+                    uncompressed_size,
+                )
+                .map_err(|_| Error::InvalidImage("invalid zip compressed resource"))?,
+                "compact-cp" => self.expand_strings(input)?,
+                _ => return Err(Error::InvalidImage("unknown decompressor")),
+            };
+            if data.len() != uncompressed_size {
+                return Err(Error::InvalidImage("invalid uncompressed size"));
+            }
+        }
+        Ok(data)
+    }
+
+    /// Reverts the string sharing of `jlink --compress=1`, which moves the
+    /// UTF-8 constants of class files into the strings table of the image,
+    /// and replaces descriptors by a descriptor stripped of class names
+    /// followed by the package and simple name of each class.
+    fn expand_strings(&self, mut input: &[u8]) -> Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(input.len() * 2);
+        // magic and version
+        output.extend_from_slice(split(&mut input, 8)?);
+        let constant_pool_count = split(&mut input, 2)?;
+        output.extend_from_slice(constant_pool_count);
+        let constant_pool_count = BigEndian::read_u16(constant_pool_count);
+        let mut index = 1;
+        while index < constant_pool_count {
+            let tag = split(&mut input, 1)?[0];
+            match tag {
+                // shared string
+                23 => {
+                    let string = self.string_bytes(read_compressed_int(&mut input)? as u64)?;
+                    write_utf8(&mut output, string)?;
+                }
+                // shared descriptor
+                25 => {
+                    let descriptor = self.string_bytes(read_compressed_int(&mut input)? as u64)?;
+                    let length = read_compressed_int(&mut input)? as usize;
+                    let mut names = split(&mut input, length)?;
+                    let mut string = Vec::with_capacity(descriptor.len() * 2);
+                    for &byte in descriptor {
+                        string.push(byte);
+                        if byte == b'L' {
+                            let package =
+                                self.string_bytes(read_compressed_int(&mut names)? as u64)?;
+                            if !package.is_empty() {
+                                string.extend_from_slice(package);
+                                string.push(b'/');
+                            }
+                            string.extend_from_slice(
+                                self.string_bytes(read_compressed_int(&mut names)? as u64)?,
+                            );
+                        }
+                    }
+                    write_utf8(&mut output, &string)?;
+                }
+                _ => {
+                    let size = match tag {
+                        1 => 2 + BigEndian::read_u16(split(&mut { input }, 2)?) as usize,
+                        5 | 6 => 8,
+                        3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => 4,
+                        15 => 3,
+                        7 | 8 | 16 | 19 | 20 => 2,
+                        _ => return Err(Error::InvalidImage("invalid shared constant pool")),
+                    };
+                    output.push(tag);
+                    output.extend_from_slice(split(&mut input, size)?);
+                    // longs and doubles take two entries
+                    if matches!(tag, 5 | 6) {
+                        index += 1;
+                    }
+                }
+            }
+            index += 1;
+        }
+        output.extend_from_slice(input);
+        Ok(output)
+    }
+}
+
+/// Iterator over the resources of a [`JImage`].
+pub struct Entries<'a, R> {
+    jimage: &'a mut JImage<R>,
+    entries: vec::IntoIter<(String, String, Location)>,
+}
+
+impl<R: Read + Seek> Iterator for Entries<'_, R> {
+    type Item = Result<JImageEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (module, path, location) = self.entries.next()?;
+        Some(
+            self.jimage
+                .read(&location)
+                .map(|data| JImageEntry { module, path, data }),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+/// A resource of a [`JImage`].
+#[derive(Debug, Clone)]
+pub struct JImageEntry {
+    module: String,
+    path: String,
+    data: Vec<u8>,
+}
+
+impl JImageEntry {
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    /// Returns the path of the resource inside of its module, e.g.
+    /// `java/lang/String.class`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the internal class name if the resource is a class file,
+    /// including `module-info`.
+    pub fn class_name(&self) -> Option<&str> {
+        self.path.strip_suffix(".class")
+    }
+
+    pub fn decode(&self) -> Result<ClassFile> {
+        ClassFile::decode(&mut self.data.as_slice())
+    }
+
+    pub fn decode_ref(&self) -> Result<ClassFileRef<'_>> {
+        ClassFileRef::decode(&mut self.data.as_slice())
+    }
+}
+
+/// The attributes of a resource, indexed by their kind.
+struct Location {
+    attributes: [u64; 8],
+}
+
+impl Location {
+    /// Decodes the attributes, each a byte holding the kind in the upper five
+    /// bits and the length of the following big-endian value minus one in the
+    /// lower three bits, up to the end attribute.
+    fn decode(mut input: &[u8]) -> Result<Self> {
+        let mut attributes = [0; 8];
+        loop {
+            let Some((&byte, rest)) = input.split_first() else {
+                return Err(Error::InvalidImage("unterminated location"));
+            };
+            let kind = byte >> 3;
+            if kind == ATTRIBUTE_END {
+                break;
+            }
+            let length = (byte & 0x7) as usize + 1;
+            if kind as usize >= attributes.len() || rest.len() < length {
+                return Err(Error::InvalidImage("invalid location attribute"));
+            }
+            attributes[kind as usize] = rest[..length]
+                .iter()
+                .fold(0, |value, &byte| value << 8 | byte as u64);
+            input = &rest[length..];
+        }
+        Ok(Self { attributes })
+    }
+}
+
+fn read_u32(big_endian: bool, input: &[u8]) -> u32 {
+    if big_endian {
+        BigEndian::read_u32(input)
+    } else {
+        LittleEndian::read_u32(input)
+    }
+}
+
+fn read_u64(big_endian: bool, input: &[u8]) -> u64 {
+    if big_endian {
+        BigEndian::read_u64(input)
+    } else {
+        LittleEndian::read_u64(input)
+    }
+}
+
+fn split<'a>(input: &mut &'a [u8], length: usize) -> Result<&'a [u8]> {
+    crate::split_off(input, length)
+        .map_err(|_| Error::InvalidImage("truncated compressed resource"))
+}
+
+/// Reads an integer of the string sharing, either four bytes or, if the
+/// highest bit is set, one to three bytes with the length in the next two bits
+/// and the value in the remaining bits.
+fn read_compressed_int(input: &mut &[u8]) -> Result<u32> {
+    let header = split(input, 1)?[0];
+    let (length, value) = if header & 0x80 != 0 {
+        ((header >> 5 & 0x3) as usize, (header & 0x1F) as u32)
+    } else {
+        (4, header as u32)
+    };
+    if length == 0 {
+        return Err(Error::InvalidImage("invalid compressed integer"));
+    }
+    Ok(split(input, length - 1)?
+        .iter()
+        .fold(value, |value, &byte| value << 8 | byte as u32))
+}
+
+/// Writes a UTF-8 constant of a class file.
+fn write_utf8(output: &mut Vec<u8>, string: &[u8]) -> Result<()> {
+    output.push(1);
+    output.write_u16::<BigEndian>(
+        string
+            .len()
+            .try_into()
+            .map_err(|_| Error::InvalidImage("shared string too long"))?,
+    )?;
+    output.extend_from_slice(string);
+    Ok(())
+}
+
+/// Hashes the UTF-8 encoded name as the index of the image is built with.
+fn hash(name: &str, seed: u32) -> u32 {
+    name.bytes().fold(seed, |hash, byte| {
+        hash.wrapping_mul(HASH_MULTIPLIER) ^ byte as u32
+    }) & 0x7FFFFFFF
+}
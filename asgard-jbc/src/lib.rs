@@ -1,15 +1,199 @@
-use std::io::{Seek, Write};
+use std::io::{ErrorKind, Seek, Write};
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use thiserror::Error;
 
+pub mod assembly;
 pub mod class;
+pub mod class_path;
+pub mod code;
 pub mod descriptor;
+pub mod disassembler;
+pub mod hierarchy;
+#[cfg(feature = "jar")]
+pub mod jar;
+pub mod jimage;
+pub mod mutf8;
+pub mod signature;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("IO error")]
-    Io(#[from] std::io::Error),
+    Io(#[source] std::io::Error),
+    #[error("Bad magic {0:#010X}, expected 0xCAFEBABE")]
+    BadMagic(u32),
+    #[error("Unknown constant tag {tag} at offset {offset}")]
+    UnknownConstantTag { tag: u8, offset: usize },
+    #[error("Truncated input at offset {offset}")]
+    TruncatedInput { offset: usize },
+    #[error("Invalid UTF-8 at offset {offset}")]
+    InvalidUtf8 { offset: usize },
+    #[error("Utf8 constant at index {0} contains unpaired surrogates")]
+    UnpairedSurrogate(u16),
+    #[error("Invalid constant pool index {0}")]
+    InvalidConstantPoolIndex(u16),
+    #[error("Unexpected constant at index {index}, expected {expected}")]
+    UnexpectedConstant { index: u16, expected: &'static str },
+    #[error("Invalid instruction {opcode:#04X} at offset {offset}")]
+    InvalidInstruction { opcode: u8, offset: u32 },
+    #[error("Label has not been placed")]
+    UnplacedLabel,
+    #[error("Label has been placed more than once")]
+    DuplicateLabel,
+    #[error("Label belongs to another code builder")]
+    ForeignLabel,
+    #[error("Code too large, {0} bytes exceed the limit of 65535")]
+    CodeTooLarge(u32),
+    #[error("Constant pool too large, exceeding the limit of 65535 entries")]
+    ConstantPoolTooLarge,
+    #[error("Too many {0}, exceeding the limit of 65535")]
+    TooMany(&'static str),
+    #[error("Utf8 constant too long, {0} bytes exceed the limit of 65535")]
+    Utf8TooLong(usize),
+    #[error("Unknown stack map frame type {frame_type} at offset {offset}")]
+    UnknownFrameType { frame_type: u8, offset: usize },
+    #[error("Invalid stack map frame: {0}")]
+    InvalidFrame(&'static str),
+    #[error("Unknown verification type {tag} at offset {offset}")]
+    UnknownVerificationType { tag: u8, offset: usize },
+    #[error("Verification failed at offset {offset}: {reason}")]
+    Verification { offset: u32, reason: &'static str },
+    #[error("Unknown element value tag {tag} at offset {offset}")]
+    UnknownElementValueTag { tag: u8, offset: usize },
+    #[error("Element value nested too deeply at offset {offset}")]
+    ElementValueTooDeep { offset: usize },
+    #[error("Unknown type annotation target type {target_type:#04X} at offset {offset}")]
+    UnknownTargetType { target_type: u8, offset: usize },
+    #[error("Unknown type path kind {type_path_kind} at offset {offset}")]
+    UnknownTypePathKind { type_path_kind: u8, offset: usize },
+    #[error("Missing attribute {0}")]
+    MissingAttribute(&'static str),
+    #[error("Invalid length of attribute {0}")]
+    InvalidAttributeLength(String),
+    #[error("Invalid descriptor at position {position}: {reason}")]
+    InvalidDescriptor {
+        position: usize,
+        reason: &'static str,
+    },
+    #[error("Invalid signature at position {position}: {reason}")]
+    InvalidSignature {
+        position: usize,
+        reason: &'static str,
+    },
+    #[error("Invalid assembly at line {line}: {reason}")]
+    InvalidAssembly { line: usize, reason: String },
+    #[error("Invalid jimage: {0}")]
+    InvalidImage(&'static str),
+    #[cfg(feature = "jar")]
+    #[error("Invalid manifest at line {line}")]
+    InvalidManifest { line: usize },
+    #[cfg(feature = "jar")]
+    #[error("Zip error")]
+    Zip(#[from] zip::result::ZipError),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        if value.kind() == ErrorKind::UnexpectedEof {
+            // the failing read leaves the input at its end, which
+            // `decode_with` points the error at
+            Self::TruncatedInput { offset: 0 }
+        } else {
+            Self::Io(value)
+        }
+    }
+}
+
+impl Error {
+    /// Shifts the offset of this error by `base`, used to make errors of
+    /// nested structures relative to the enclosing input.
+    pub(crate) fn offset_by(self, base: usize) -> Self {
+        match self {
+            Self::UnknownConstantTag { tag, offset } => Self::UnknownConstantTag {
+                tag,
+                offset: base + offset,
+            },
+            Self::UnknownFrameType { frame_type, offset } => Self::UnknownFrameType {
+                frame_type,
+                offset: base + offset,
+            },
+            Self::UnknownVerificationType { tag, offset } => Self::UnknownVerificationType {
+                tag,
+                offset: base + offset,
+            },
+            Self::UnknownElementValueTag { tag, offset } => Self::UnknownElementValueTag {
+                tag,
+                offset: base + offset,
+            },
+            Self::ElementValueTooDeep { offset } => Self::ElementValueTooDeep {
+                offset: base + offset,
+            },
+            Self::UnknownTargetType {
+                target_type,
+                offset,
+            } => Self::UnknownTargetType {
+                target_type,
+                offset: base + offset,
+            },
+            Self::UnknownTypePathKind {
+                type_path_kind,
+                offset,
+            } => Self::UnknownTypePathKind {
+                type_path_kind,
+                offset: base + offset,
+            },
+            Self::TruncatedInput { offset } => Self::TruncatedInput {
+                offset: base + offset,
+            },
+            Self::InvalidUtf8 { offset } => Self::InvalidUtf8 {
+                offset: base + offset,
+            },
+            _ => self,
+        }
+    }
+}
+
+/// Converts the length of a table to the `u16` preceding it, failing if there
+/// are too many `entries`.
+pub(crate) fn count(length: usize, entries: &'static str) -> Result<u16> {
+    u16::try_from(length).map_err(|_| Error::TooMany(entries))
+}
+
+/// Decodes a nested structure starting at the current position of `input`,
+/// making the offsets of its errors relative to `begin`.
+pub(crate) fn decode_nested<T: Decode>(begin: &[u8], input: &mut &[u8]) -> Result<T> {
+    decode_with(begin, input, T::decode)
+}
+
+/// Like [`decode_nested`], decoding the structure with `decode`, as needed for
+/// the borrowed structures which cannot implement [`Decode`].
+///
+/// Reads running out of bytes leave `input` at its end, so truncation errors
+/// are pointed at the position `input` stopped at.
+pub(crate) fn decode_with<'a, T>(
+    begin: &[u8],
+    input: &mut &'a [u8],
+    decode: impl FnOnce(&mut &'a [u8]) -> Result<T>,
+) -> Result<T> {
+    let offset = begin.len() - input.len();
+    decode(input).map_err(|error| match error {
+        Error::TruncatedInput { .. } => Error::TruncatedInput {
+            offset: begin.len() - input.len(),
+        },
+        error => error.offset_by(offset),
+    })
+}
+
+/// Splits off the next `length` bytes of `input` without copying them,
+/// leaving `input` at its end if it is too short like failing reads do.
+pub(crate) fn split_off<'a>(input: &mut &'a [u8], length: usize) -> Result<&'a [u8]> {
+    if input.len() < length {
+        *input = &input[input.len()..];
+        return Err(Error::TruncatedInput { offset: 0 });
+    }
+    let (bytes, rest) = input.split_at(length);
+    *input = rest;
+    Ok(bytes)
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -35,6 +219,19 @@ impl Decode for u8 {
     }
 }
 
+impl Encode for i8 {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        output.write_i8(*self)?;
+        Ok(())
+    }
+}
+
+impl Decode for i8 {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Ok(input.read_i8()?)
+    }
+}
+
 impl Encode for u16 {
     fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
         output.write_u16::<BigEndian>(*self)?;
@@ -48,6 +245,19 @@ impl Decode for u16 {
     }
 }
 
+impl Encode for i16 {
+    fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
+        output.write_i16::<BigEndian>(*self)?;
+        Ok(())
+    }
+}
+
+impl Decode for i16 {
+    fn decode(input: &mut &[u8]) -> Result<Self> {
+        Ok(input.read_i16::<BigEndian>()?)
+    }
+}
+
 impl Encode for u32 {
     fn encode(&self, output: &mut (impl Write + Seek)) -> Result<()> {
         output.write_u32::<BigEndian>(*self)?;
@@ -0,0 +1,282 @@
+//! Modified UTF-8, the string encoding used by `CONSTANT_Utf8` entries and
+//! JNI.
+//!
+//! It differs from UTF-8 in that NUL is encoded as the two bytes `0xC0 0x80`,
+//! and supplementary characters are encoded as their UTF-16 surrogate pair,
+//! each surrogate taking three bytes. As Java strings are sequences of UTF-16
+//! code units, surrogates can also appear unpaired, which [`JavaString`] can
+//! represent unlike [`str`].
+
+use std::{borrow::Cow, fmt};
+
+use crate::{Error, Result};
+
+/// A Java string, which unlike [`str`] can contain unpaired surrogates.
+///
+/// It is stored as WTF-8, which is UTF-8 extended by encoding unpaired
+/// surrogates like any other code point, so that valid strings can be
+/// borrowed as [`str`].
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JavaString(Vec<u8>);
+
+impl JavaString {
+    /// Returns the string if it contains no unpaired surrogates.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    /// Returns the string with unpaired surrogates replaced by U+FFFD.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        match self.as_str() {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(
+                self.code_points()
+                    .map(|code_point| char::from_u32(code_point).unwrap_or('\u{FFFD}'))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns the code points of the string, including unpaired surrogates.
+    pub fn code_points(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut bytes = self.0.iter();
+        std::iter::from_fn(move || {
+            let byte = *bytes.next()? as u32;
+            let (length, mut code_point) = match byte {
+                0x00..=0x7F => (0, byte),
+                0xC0..=0xDF => (1, byte & 0x1F),
+                0xE0..=0xEF => (2, byte & 0x0F),
+                _ => (3, byte & 0x07),
+            };
+            for _ in 0..length {
+                code_point = code_point << 6 | (*bytes.next()? & 0x3F) as u32;
+            }
+            Some(code_point)
+        })
+    }
+
+    /// Appends a code point, which may be a surrogate, joining it with a
+    /// preceding high surrogate to a supplementary character.
+    pub fn push(&mut self, code_point: u32) {
+        let high = match self.0.as_slice() {
+            [.., 0xED, second @ 0xA0..=0xAF, third] if (0xDC00..=0xDFFF).contains(&code_point) => {
+                Some(0xD000 | (*second as u32 & 0x3F) << 6 | (*third as u32 & 0x3F))
+            }
+            _ => None,
+        };
+        let char = match high {
+            Some(high) => {
+                self.0.truncate(self.0.len() - 3);
+                char::from_u32(0x10000 + ((high - 0xD800) << 10) + (code_point - 0xDC00))
+            }
+            None => char::from_u32(code_point),
+        };
+        match char {
+            Some(char) => self
+                .0
+                .extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes()),
+            None => push_unit(code_point as u16, &mut self.0),
+        }
+    }
+}
+
+impl From<&str> for JavaString {
+    fn from(value: &str) -> Self {
+        Self(value.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for JavaString {
+    fn from(value: String) -> Self {
+        Self(value.into_bytes())
+    }
+}
+
+impl PartialEq<str> for JavaString {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl fmt::Debug for JavaString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for code_point in self.code_points() {
+            match char::from_u32(code_point) {
+                Some(char) => write!(f, "{}", char.escape_debug())?,
+                None => write!(f, "\\u{{{code_point:x}}}")?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+impl fmt::Display for JavaString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_lossy())
+    }
+}
+
+/// Encodes a string as modified UTF-8, borrowing it if the encodings are
+/// identical.
+pub fn encode(value: &str) -> Cow<'_, [u8]> {
+    encode_wtf8(value.as_bytes())
+}
+
+/// Encodes a Java string as modified UTF-8, borrowing it if the encodings
+/// are identical.
+pub fn encode_java(value: &JavaString) -> Cow<'_, [u8]> {
+    encode_wtf8(&value.0)
+}
+
+fn encode_wtf8(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if !bytes.iter().any(|byte| *byte == 0 || *byte >= 0xF0) {
+        return Cow::Borrowed(bytes);
+    }
+
+    let mut encoded = Vec::with_capacity(bytes.len() + bytes.len() / 2);
+    let mut position = 0;
+    while position < bytes.len() {
+        match bytes[position] {
+            0 => {
+                encoded.extend_from_slice(&[0xC0, 0x80]);
+                position += 1;
+            }
+            0xF0.. => {
+                let char = std::str::from_utf8(&bytes[position..position + 4])
+                    .ok()
+                    .and_then(|char| char.chars().next())
+                    .unwrap_or_default();
+                for unit in char.encode_utf16(&mut [0; 2]) {
+                    push_unit(*unit, &mut encoded);
+                }
+                position += 4;
+            }
+            byte => {
+                encoded.push(byte);
+                position += 1;
+            }
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+fn push_unit(unit: u16, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&[
+        0xE0 | (unit >> 12) as u8,
+        0x80 | (unit >> 6 & 0x3F) as u8,
+        0x80 | (unit & 0x3F) as u8,
+    ]);
+}
+
+/// Decodes a modified UTF-8 string, borrowing it if the encodings are
+/// identical.
+///
+/// Byte sequences which are not in their shortest form (other than the
+/// encoding of NUL) are rejected, as are the four byte sequences of UTF-8, so
+/// that decoding and encoding round-trips. Unpaired surrogates cannot be
+/// represented as [`str`] and are rejected as well, see [`decode_java`] to
+/// keep them.
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    match decode_wtf8(bytes)? {
+        Cow::Borrowed(value) => std::str::from_utf8(value).ok().map(Cow::Borrowed),
+        Cow::Owned(value) => String::from_utf8(value).ok().map(Cow::Owned),
+    }
+    .ok_or_else(|| Error::InvalidUtf8 {
+        offset: unpaired_surrogate(bytes),
+    })
+}
+
+/// Decodes a modified UTF-8 string, keeping unpaired surrogates.
+pub fn decode_java(bytes: &[u8]) -> Result<JavaString> {
+    Ok(JavaString(decode_wtf8(bytes)?.into_owned()))
+}
+
+/// Decodes modified UTF-8 to WTF-8, borrowing it if the encodings are
+/// identical, which they are unless NUL or surrogate pairs occur.
+pub(crate) fn decode_wtf8(bytes: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if !bytes.iter().any(|byte| *byte == 0 || *byte >= 0xF0) && std::str::from_utf8(bytes).is_ok() {
+        return Ok(Cow::Borrowed(bytes));
+    }
+
+    let mut value = Vec::with_capacity(bytes.len());
+    let mut changed = false;
+    let mut position = 0;
+    while position < bytes.len() {
+        let (unit, length) = decode_unit(bytes, position)?;
+        if let 0xD800..=0xDBFF = unit {
+            if let Ok((low_unit @ 0xDC00..=0xDFFF, low_length)) =
+                decode_unit(bytes, position + length)
+            {
+                let code_point =
+                    0x10000 + ((unit as u32 - 0xD800) << 10) + (low_unit as u32 - 0xDC00);
+                let char = char::from_u32(code_point).unwrap_or_default();
+                value.extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes());
+                changed = true;
+                position += length + low_length;
+                continue;
+            }
+        }
+        if unit == 0 {
+            value.push(0);
+            changed = true;
+        } else {
+            value.extend_from_slice(&bytes[position..position + length]);
+        }
+        position += length;
+    }
+    Ok(if changed {
+        Cow::Owned(value)
+    } else {
+        Cow::Borrowed(bytes)
+    })
+}
+
+/// Returns the offset of the first unpaired surrogate of a valid modified
+/// UTF-8 string.
+fn unpaired_surrogate(bytes: &[u8]) -> usize {
+    let mut position = 0;
+    while let Ok((unit, length)) = decode_unit(bytes, position) {
+        match unit {
+            0xD800..=0xDBFF => match decode_unit(bytes, position + length) {
+                Ok((0xDC00..=0xDFFF, low_length)) => position += low_length,
+                _ => break,
+            },
+            0xDC00..=0xDFFF => break,
+            _ => {}
+        }
+        position += length;
+    }
+    position
+}
+
+/// Decodes the UTF-16 code unit at `position`, returning it together with the
+/// length of its encoding.
+fn decode_unit(bytes: &[u8], position: usize) -> Result<(u16, usize)> {
+    let error = Error::InvalidUtf8 { offset: position };
+    let continuation = |index: usize| match bytes.get(position + index) {
+        Some(byte @ 0x80..=0xBF) => Ok((byte & 0x3F) as u16),
+        _ => Err(Error::InvalidUtf8 { offset: position }),
+    };
+    match *bytes
+        .get(position)
+        .ok_or(Error::InvalidUtf8 { offset: position })?
+    {
+        byte @ 0x01..=0x7F => Ok((byte as u16, 1)),
+        byte @ 0xC0..=0xDF => {
+            let unit = (byte as u16 & 0x1F) << 6 | continuation(1)?;
+            if unit != 0 && unit < 0x80 {
+                return Err(error);
+            }
+            Ok((unit, 2))
+        }
+        byte @ 0xE0..=0xEF => {
+            let unit = (byte as u16 & 0x0F) << 12 | continuation(1)? << 6 | continuation(2)?;
+            if unit < 0x800 {
+                return Err(error);
+            }
+            Ok((unit, 3))
+        }
+        _ => Err(error),
+    }
+}
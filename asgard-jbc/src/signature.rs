@@ -0,0 +1,514 @@
+use std::{fmt, str::FromStr};
+
+use crate::{descriptor::MAX_ARRAY_DIMENSIONS, Error, Result};
+
+/// The maximum depth of nested type arguments, which bounds the recursion of
+/// the parser.
+pub const MAX_TYPE_ARGUMENT_DEPTH: usize = 255;
+
+/// A type in a generic signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeSignature {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+    Class(ClassTypeSignature),
+    TypeVariable(String),
+    Array(Box<Self>),
+    Void,
+}
+
+impl TypeSignature {
+    /// Returns whether this is a class, type variable or array type.
+    pub fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            Self::Class(_) | Self::TypeVariable(_) | Self::Array(_)
+        )
+    }
+}
+
+impl fmt::Display for TypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Byte => f.write_str("B"),
+            Self::Char => f.write_str("C"),
+            Self::Double => f.write_str("D"),
+            Self::Float => f.write_str("F"),
+            Self::Int => f.write_str("I"),
+            Self::Long => f.write_str("J"),
+            Self::Short => f.write_str("S"),
+            Self::Boolean => f.write_str("Z"),
+            Self::Class(class_type) => class_type.fmt(f),
+            Self::TypeVariable(name) => write!(f, "T{name};"),
+            Self::Array(component_type) => write!(f, "[{component_type}"),
+            Self::Void => f.write_str("V"),
+        }
+    }
+}
+
+impl FromStr for TypeSignature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s);
+        let type_signature = parser.reference_type()?;
+        parser.end()?;
+        Ok(type_signature)
+    }
+}
+
+/// A possibly parameterized class type, e.g. `Ljava/util/Map<TK;TV;>.Entry;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassTypeSignature {
+    /// The package in internal form, e.g. `java/util`, empty for the unnamed
+    /// package.
+    pub package: String,
+    /// The outermost class followed by the inner classes, each with their
+    /// type arguments.
+    pub classes: Vec<SimpleClassTypeSignature>,
+}
+
+impl ClassTypeSignature {
+    /// Returns the internal name of the erased class.
+    pub fn name(&self) -> String {
+        let mut name = self.package.clone();
+        for (index, class) in self.classes.iter().enumerate() {
+            match index {
+                0 if name.is_empty() => {}
+                0 => name.push('/'),
+                _ => name.push('$'),
+            }
+            name.push_str(&class.name);
+        }
+        name
+    }
+}
+
+impl fmt::Display for ClassTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("L")?;
+        if !self.package.is_empty() {
+            write!(f, "{}/", self.package)?;
+        }
+        for (index, class) in self.classes.iter().enumerate() {
+            if index != 0 {
+                f.write_str(".")?;
+            }
+            class.fmt(f)?;
+        }
+        f.write_str(";")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleClassTypeSignature {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+impl fmt::Display for SimpleClassTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.type_arguments.is_empty() {
+            f.write_str("<")?;
+            for type_argument in &self.type_arguments {
+                type_argument.fmt(f)?;
+            }
+            f.write_str(">")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeArgument {
+    /// `*`, an unbounded wildcard.
+    Any,
+    Exact(TypeSignature),
+    /// `+`, a wildcard with an upper bound.
+    Extends(TypeSignature),
+    /// `-`, a wildcard with a lower bound.
+    Super(TypeSignature),
+}
+
+impl fmt::Display for TypeArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("*"),
+            Self::Exact(type_signature) => type_signature.fmt(f),
+            Self::Extends(type_signature) => write!(f, "+{type_signature}"),
+            Self::Super(type_signature) => write!(f, "-{type_signature}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParameter {
+    pub name: String,
+    /// The class bound, which may be absent if there are interface bounds.
+    pub class_bound: Option<TypeSignature>,
+    pub interface_bounds: Vec<TypeSignature>,
+}
+
+impl fmt::Display for TypeParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.name)?;
+        if let Some(class_bound) = &self.class_bound {
+            class_bound.fmt(f)?;
+        }
+        for interface_bound in &self.interface_bounds {
+            write!(f, ":{interface_bound}")?;
+        }
+        Ok(())
+    }
+}
+
+fn fmt_type_parameters(
+    type_parameters: &[TypeParameter],
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    if !type_parameters.is_empty() {
+        f.write_str("<")?;
+        for type_parameter in type_parameters {
+            write!(f, "{type_parameter}")?;
+        }
+        f.write_str(">")?;
+    }
+    Ok(())
+}
+
+/// The generic signature of a class, e.g.
+/// `<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/List<TE;>;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub super_class: ClassTypeSignature,
+    pub super_interfaces: Vec<ClassTypeSignature>,
+}
+
+impl fmt::Display for ClassSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_type_parameters(&self.type_parameters, f)?;
+        self.super_class.fmt(f)?;
+        for super_interface in &self.super_interfaces {
+            super_interface.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ClassSignature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s);
+        let type_parameters = parser.type_parameters()?;
+        let super_class = parser.class_type()?;
+        let mut super_interfaces = vec![];
+        while parser.peek().is_some() {
+            super_interfaces.push(parser.class_type()?);
+        }
+        Ok(Self {
+            type_parameters,
+            super_class,
+            super_interfaces,
+        })
+    }
+}
+
+/// The generic signature of a method, e.g.
+/// `<T:Ljava/lang/Object;>([TT;)Ljava/util/List<TT;>;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameter_types: Vec<TypeSignature>,
+    pub return_type: TypeSignature,
+    /// Class types or type variables.
+    pub throws: Vec<TypeSignature>,
+}
+
+impl fmt::Display for MethodSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_type_parameters(&self.type_parameters, f)?;
+        f.write_str("(")?;
+        for parameter_type in &self.parameter_types {
+            parameter_type.fmt(f)?;
+        }
+        write!(f, "){}", self.return_type)?;
+        for throws in &self.throws {
+            write!(f, "^{throws}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MethodSignature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s);
+        let type_parameters = parser.type_parameters()?;
+        parser.expect('(')?;
+        let mut parameter_types = vec![];
+        while parser.peek() != Some(')') {
+            parameter_types.push(parser.java_type()?);
+        }
+        parser.expect(')')?;
+        let return_type = if parser.peek() == Some('V') {
+            parser.next();
+            TypeSignature::Void
+        } else {
+            parser.java_type()?
+        };
+        let mut throws = vec![];
+        while parser.peek().is_some() {
+            parser.expect('^')?;
+            throws.push(match parser.peek() {
+                Some('T') => parser.reference_type()?,
+                _ => TypeSignature::Class(parser.class_type()?),
+            });
+        }
+        Ok(Self {
+            type_parameters,
+            parameter_types,
+            return_type,
+            throws,
+        })
+    }
+}
+
+/// The generic signature of a field, which is a class, type variable or array
+/// type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSignature(pub TypeSignature);
+
+impl fmt::Display for FieldSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for FieldSignature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse().map(Self)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    /// Depth of the type arguments being parsed.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn error(&self, reason: &'static str) -> Error {
+        Error::InvalidSignature {
+            position: self.position,
+            reason,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        self.position += char.len_utf8();
+        Some(char)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(match expected {
+                '(' => "expected '('",
+                ')' => "expected ')'",
+                ';' => "expected ';'",
+                ':' => "expected ':'",
+                '>' => "expected '>'",
+                '^' => "expected '^'",
+                _ => "unexpected character",
+            }));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn end(&self) -> Result<()> {
+        match self.peek() {
+            Some(_) => Err(self.error("trailing characters")),
+            None => Ok(()),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<&'a str> {
+        let begin = self.position;
+        while let Some(char) = self.peek() {
+            if matches!(char, '.' | ';' | '[' | '/' | '<' | '>' | ':') {
+                break;
+            }
+            self.next();
+        }
+        if self.position == begin {
+            return Err(self.error("expected identifier"));
+        }
+        Ok(&self.input[begin..self.position])
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>> {
+        let mut type_parameters = vec![];
+        if self.peek() != Some('<') {
+            return Ok(type_parameters);
+        }
+        self.next();
+        loop {
+            let name = self.identifier()?.to_owned();
+            self.expect(':')?;
+            let class_bound = match self.peek() {
+                Some('L' | 'T' | '[') => Some(self.reference_type()?),
+                _ => None,
+            };
+            let mut interface_bounds = vec![];
+            while self.peek() == Some(':') {
+                self.next();
+                interface_bounds.push(self.reference_type()?);
+            }
+            type_parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+            if self.peek() == Some('>') {
+                self.next();
+                return Ok(type_parameters);
+            }
+        }
+    }
+
+    fn java_type(&mut self) -> Result<TypeSignature> {
+        let type_signature = match self.peek() {
+            Some('B') => TypeSignature::Byte,
+            Some('C') => TypeSignature::Char,
+            Some('D') => TypeSignature::Double,
+            Some('F') => TypeSignature::Float,
+            Some('I') => TypeSignature::Int,
+            Some('J') => TypeSignature::Long,
+            Some('S') => TypeSignature::Short,
+            Some('Z') => TypeSignature::Boolean,
+            _ => return self.reference_type(),
+        };
+        self.next();
+        Ok(type_signature)
+    }
+
+    fn reference_type(&mut self) -> Result<TypeSignature> {
+        match self.peek() {
+            Some('L') => Ok(TypeSignature::Class(self.class_type()?)),
+            Some('T') => {
+                self.next();
+                let name = self.identifier()?.to_owned();
+                self.expect(';')?;
+                Ok(TypeSignature::TypeVariable(name))
+            }
+            Some('[') => {
+                let begin = self.position;
+                let mut dimensions = 0;
+                while self.peek() == Some('[') {
+                    self.next();
+                    dimensions += 1;
+                }
+                if dimensions > MAX_ARRAY_DIMENSIONS {
+                    return Err(Error::InvalidSignature {
+                        position: begin,
+                        reason: "more than 255 array dimensions",
+                    });
+                }
+                let mut type_signature = self.java_type()?;
+                for _ in 0..dimensions {
+                    type_signature = TypeSignature::Array(Box::new(type_signature));
+                }
+                Ok(type_signature)
+            }
+            _ => Err(self.error("expected reference type")),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature> {
+        if self.peek() != Some('L') {
+            return Err(self.error("expected class type"));
+        }
+        self.next();
+        let mut package = String::new();
+        let mut name = self.identifier()?;
+        while self.peek() == Some('/') {
+            self.next();
+            if !package.is_empty() {
+                package.push('/');
+            }
+            package.push_str(name);
+            name = self.identifier()?;
+        }
+        let mut classes = vec![self.simple_class_type(name)?];
+        while self.peek() == Some('.') {
+            self.next();
+            let name = self.identifier()?;
+            classes.push(self.simple_class_type(name)?);
+        }
+        self.expect(';')?;
+        Ok(ClassTypeSignature { package, classes })
+    }
+
+    fn simple_class_type(&mut self, name: &str) -> Result<SimpleClassTypeSignature> {
+        let mut type_arguments = vec![];
+        if self.peek() == Some('<') {
+            if self.depth == MAX_TYPE_ARGUMENT_DEPTH {
+                return Err(self.error("type arguments nested too deeply"));
+            }
+            self.depth += 1;
+            self.next();
+            loop {
+                type_arguments.push(match self.peek() {
+                    Some('*') => {
+                        self.next();
+                        TypeArgument::Any
+                    }
+                    Some('+') => {
+                        self.next();
+                        TypeArgument::Extends(self.reference_type()?)
+                    }
+                    Some('-') => {
+                        self.next();
+                        TypeArgument::Super(self.reference_type()?)
+                    }
+                    _ => TypeArgument::Exact(self.reference_type()?),
+                });
+                if self.peek() == Some('>') {
+                    self.next();
+                    break;
+                }
+            }
+            self.depth -= 1;
+        }
+        Ok(SimpleClassTypeSignature {
+            name: name.to_owned(),
+            type_arguments,
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use std::io::Cursor;
+
+use asgard_jbc::{
+    class::{
+        annotation::{
+            Annotation, ElementValue, ElementValuePair, TypeAnnotation, TypePathEntry,
+            TypePathKind, Value, MAX_ELEMENT_VALUE_DEPTH,
+        },
+        attribute::{AnnotationsAttribute, Attribute},
+        builder::ClassBuilder,
+    },
+    Decode, Encode, Error,
+};
+
+fn encode(value: &impl Encode) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    value.encode(&mut output).unwrap();
+    output.into_inner()
+}
+
+/// Returns the bytes of the target info of each target type.
+fn target_infos() -> Vec<(u8, Vec<u8>)> {
+    let mut target_infos = vec![];
+    for target_type in [0x00, 0x01, 0x16] {
+        target_infos.push((target_type, vec![2]));
+    }
+    for target_type in [0x10, 0x17, 0x42, 0x43, 0x44, 0x45, 0x46] {
+        target_infos.push((target_type, vec![0, 3]));
+    }
+    for target_type in [0x11, 0x12] {
+        target_infos.push((target_type, vec![1, 2]));
+    }
+    for target_type in [0x13, 0x14, 0x15] {
+        target_infos.push((target_type, vec![]));
+    }
+    for target_type in [0x40, 0x41] {
+        target_infos.push((target_type, vec![0, 1, 0, 4, 0, 8, 0, 2]));
+    }
+    for target_type in 0x47..=0x4B {
+        target_infos.push((target_type, vec![0, 5, 1]));
+    }
+    target_infos
+}
+
+#[test]
+fn round_trip_all_target_types() {
+    for (target_type, target_info) in target_infos() {
+        let mut bytes = vec![target_type];
+        bytes.extend(target_info);
+        // a path of one entry per kind, then the type and no elements
+        bytes.extend([4, 0, 0, 1, 0, 2, 0, 3, 1, 0, 9, 0, 0]);
+        let type_annotation = TypeAnnotation::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(type_annotation.target_info.target_type(), target_type);
+        assert_eq!(type_annotation.type_index, 9);
+        assert_eq!(encode(&type_annotation), bytes, "{target_type:#04x}");
+    }
+}
+
+#[test]
+fn reject_unknown_target_types() {
+    let bytes = [0x20, 0, 0, 0, 1, 0, 0];
+    assert!(matches!(
+        TypeAnnotation::decode(&mut bytes.as_slice()),
+        Err(Error::UnknownTargetType {
+            target_type: 0x20,
+            offset: 0
+        })
+    ));
+}
+
+#[test]
+fn keep_type_argument_indices_of_other_path_kinds() {
+    // javac writes 0, but others are kept to encode the same bytes
+    let bytes = [0x13, 2, 0, 7, 3, 1, 0, 9, 0, 0];
+    let type_annotation = TypeAnnotation::decode(&mut bytes.as_slice()).unwrap();
+    assert_eq!(
+        type_annotation.target_path.0,
+        [
+            TypePathEntry {
+                type_path_kind: TypePathKind::Array,
+                type_argument_index: 7,
+            },
+            TypePathEntry::type_argument(1),
+        ]
+    );
+    assert_eq!(encode(&type_annotation), bytes);
+}
+
+/// Returns an element value of `depth` nested arrays around a string.
+fn nested_arrays(depth: usize) -> Vec<u8> {
+    let mut bytes = vec![];
+    for _ in 0..depth {
+        bytes.extend([b'[', 0, 1]);
+    }
+    bytes.extend([b's', 0, 1]);
+    bytes
+}
+
+#[test]
+fn limit_element_value_depth() {
+    let bytes = nested_arrays(MAX_ELEMENT_VALUE_DEPTH);
+    let value = ElementValue::decode(&mut bytes.as_slice()).unwrap();
+    assert_eq!(encode(&value), bytes);
+
+    let bytes = nested_arrays(MAX_ELEMENT_VALUE_DEPTH + 1);
+    assert!(matches!(
+        ElementValue::decode(&mut bytes.as_slice()),
+        Err(Error::ElementValueTooDeep { offset }) if offset == 3 * (MAX_ELEMENT_VALUE_DEPTH + 1)
+    ));
+
+    // annotations count towards the depth like arrays
+    let mut bytes = vec![0, 1, 0, 1, 0, 2];
+    for _ in 0..=MAX_ELEMENT_VALUE_DEPTH {
+        bytes.extend([b'@', 0, 1, 0, 1, 0, 2]);
+    }
+    bytes.extend([b'Z', 0, 3]);
+    assert!(matches!(
+        Annotation::decode(&mut bytes.as_slice()),
+        Err(Error::ElementValueTooDeep { .. })
+    ));
+}
+
+#[test]
+fn query_class_annotations() {
+    let mut builder = ClassBuilder::new("example/Annotated");
+    let constant_pool = builder.constant_pool();
+    let retention = Annotation {
+        type_index: constant_pool
+            .utf8("Ljava/lang/annotation/Retention;")
+            .unwrap(),
+        element_value_pairs: vec![ElementValuePair {
+            element_name_index: constant_pool.utf8("value").unwrap(),
+            value: ElementValue::Enum {
+                type_name_index: constant_pool
+                    .utf8("Ljava/lang/annotation/RetentionPolicy;")
+                    .unwrap(),
+                const_name_index: constant_pool.utf8("RUNTIME").unwrap(),
+            },
+        }],
+    };
+    let target = Annotation {
+        type_index: constant_pool.utf8("Lexample/Target;").unwrap(),
+        element_value_pairs: vec![ElementValuePair {
+            element_name_index: constant_pool.utf8("values").unwrap(),
+            value: ElementValue::Array(vec![
+                ElementValue::Int(constant_pool.integer(7).unwrap()),
+                ElementValue::Boolean(constant_pool.integer(1).unwrap()),
+                ElementValue::Class(constant_pool.utf8("[I").unwrap()),
+                ElementValue::Annotation(retention.clone()),
+            ]),
+        }],
+    };
+    builder
+        .attribute(&Attribute::RuntimeVisibleAnnotations(AnnotationsAttribute(
+            vec![retention.clone()],
+        )))
+        .unwrap()
+        .attribute(&Attribute::RuntimeInvisibleAnnotations(
+            AnnotationsAttribute(vec![target]),
+        ))
+        .unwrap();
+    let class_file = builder.build().unwrap();
+    let constant_pool = &class_file.constant_pool;
+
+    // visible and invisible annotations together
+    assert_eq!(class_file.annotations().unwrap().len(), 2);
+    assert!(class_file
+        .annotation("Ljava/lang/Deprecated;")
+        .unwrap()
+        .is_none());
+
+    let annotation = class_file
+        .annotation("Ljava/lang/annotation/Retention;")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        annotation.type_descriptor(constant_pool).unwrap(),
+        "Ljava/lang/annotation/Retention;"
+    );
+    assert_eq!(
+        annotation.value(constant_pool, "value").unwrap(),
+        Some(Value::Enum {
+            type_descriptor: "Ljava/lang/annotation/RetentionPolicy;",
+            name: "RUNTIME",
+        })
+    );
+    assert_eq!(annotation.value(constant_pool, "missing").unwrap(), None);
+
+    let annotation = class_file.annotation("Lexample/Target;").unwrap().unwrap();
+    assert_eq!(
+        annotation.value(constant_pool, "values").unwrap(),
+        Some(Value::Array(vec![
+            Value::Int(7),
+            Value::Boolean(true),
+            Value::Class("[I"),
+            Value::Annotation(&retention),
+        ]))
+    );
+}
@@ -0,0 +1,122 @@
+use std::io::Cursor;
+
+use asgard_jbc::{
+    assembly::{self, Printer},
+    class::{
+        attribute::{Attribute, LineNumber},
+        ClassAccessFlags, ClassFile, ConstantLookup, MethodAccessFlags,
+    },
+    code::Instruction,
+    Decode, Encode,
+};
+
+mod common;
+
+const HELLO: &str = r#"
+.version 52 0
+.class public super Hello
+.super java/lang/Object
+
+.method public static main ([Ljava/lang/String;)V {
+  .code stack 2 locals 1 {
+      getstatic Field java/lang/System out Ljava/io/PrintStream;
+      ldc String "Hello, world!"
+      invokevirtual Method java/io/PrintStream println (Ljava/lang/String;)V
+    L0:
+      return
+    .linenumbertable {
+      L0 3
+    }
+  }
+}
+"#;
+
+fn encode(class_file: &ClassFile) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    class_file.encode(&mut output).unwrap();
+    output.into_inner()
+}
+
+#[test]
+fn round_trip_runtime_classes_with_constant_pool() {
+    let mut printer = Printer::new();
+    printer.constant_pool(true);
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        let assembly = printer
+            .print(&class_file)
+            .unwrap_or_else(|error| panic!("{path}: {error}"));
+        let parsed = assembly::parse(&assembly).unwrap_or_else(|error| panic!("{path}: {error}"));
+        assert!(encode(&parsed) == data, "{path}");
+    }
+}
+
+#[test]
+fn round_trip_runtime_classes_without_constant_pool() {
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        let assembly = assembly::print(&class_file).unwrap();
+        let parsed = assembly::parse(&assembly).unwrap_or_else(|error| panic!("{path}: {error}"));
+        // only the order of the constant pool differs
+        assert_eq!(assembly::print(&parsed).unwrap(), assembly, "{path}");
+    }
+}
+
+#[test]
+fn parse_hand_written_class() {
+    let class_file = assembly::parse(HELLO).unwrap();
+    let constant_pool = &class_file.constant_pool;
+    assert_eq!(class_file.major_version, 52);
+    assert_eq!(
+        class_file.access_flags,
+        ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER
+    );
+    assert_eq!(class_file.name().unwrap(), "Hello");
+    assert_eq!(class_file.super_name().unwrap(), Some("java/lang/Object"));
+    assert!(class_file.fields.is_empty());
+
+    let [method] = class_file.methods.as_slice() else {
+        panic!("expected one method");
+    };
+    assert_eq!(
+        method.access_flags,
+        MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC
+    );
+    assert_eq!(method.name(constant_pool).unwrap(), "main");
+    let code = method.code(constant_pool).unwrap().unwrap();
+    assert_eq!((code.max_stack, code.max_locals), (2, 1));
+    let instructions = code.instructions().unwrap();
+    let [(0, Instruction::Getstatic(field)), (3, Instruction::Ldc(string)), (5, Instruction::Invokevirtual(method)), (8, Instruction::Return)] =
+        instructions.as_slice()
+    else {
+        panic!("unexpected instructions {instructions:?}");
+    };
+    assert_eq!(
+        constant_pool.member_ref(*field).unwrap(),
+        ("java/lang/System", "out", "Ljava/io/PrintStream;")
+    );
+    assert_eq!(
+        constant_pool.string(*string as u16).unwrap(),
+        "Hello, world!"
+    );
+    assert_eq!(
+        constant_pool.member_ref(*method).unwrap(),
+        ("java/io/PrintStream", "println", "(Ljava/lang/String;)V")
+    );
+
+    let [attribute] = code.attributes.as_slice() else {
+        panic!("expected one code attribute");
+    };
+    let Attribute::LineNumberTable(line_numbers) =
+        Attribute::decode(attribute, constant_pool).unwrap()
+    else {
+        panic!("expected a LineNumberTable");
+    };
+    let [LineNumber {
+        start_pc: 8,
+        line_number: 3,
+    }] = line_numbers.0.as_slice()
+    else {
+        panic!("unexpected line numbers {:?}", line_numbers.0);
+    };
+}
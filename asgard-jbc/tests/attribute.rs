@@ -0,0 +1,150 @@
+use asgard_jbc::{
+    class::{
+        attribute::{Attribute, AttributeRegistry, CustomAttribute, SourceFileAttribute},
+        AttributeInfo, ClassFile, ConstantPool, ConstantPoolInfo,
+    },
+    Decode, Error, Result,
+};
+
+mod common;
+
+/// Decodes `attributes` and checks that they encode to the same bytes.
+fn round_trip(attributes: &[AttributeInfo], constant_pool: &ConstantPool, path: &str) {
+    for attribute in attributes {
+        let name = constant_pool.utf8(attribute.attribute_name_index).unwrap();
+        let decoded = Attribute::decode(attribute, constant_pool)
+            .unwrap_or_else(|error| panic!("{path} {name}: {error}"));
+        // the JDK adds attributes of its own, e.g. ModuleHashes
+        if let Attribute::Unknown { .. } = decoded {
+            assert!(name.starts_with("Module"), "{path} {name}");
+        }
+        assert_eq!(decoded.name(), name, "{path}");
+        assert!(decoded.info().unwrap() == attribute.info, "{path} {name}");
+        if let Attribute::Code(code) = decoded {
+            round_trip(&code.attributes, constant_pool, path);
+        }
+    }
+}
+
+#[test]
+fn round_trip_runtime_attributes() {
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        let constant_pool = &class_file.constant_pool;
+        round_trip(&class_file.attributes, constant_pool, &path);
+        for field in &class_file.fields {
+            round_trip(&field.attributes, constant_pool, &path);
+        }
+        for method in &class_file.methods {
+            round_trip(&method.attributes, constant_pool, &path);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Checksum(u32);
+
+impl CustomAttribute for Checksum {
+    fn name(&self) -> &str {
+        "Checksum"
+    }
+
+    fn info(&self) -> Result<Vec<u8>> {
+        Ok(self.0.to_be_bytes().to_vec())
+    }
+}
+
+fn checksum(info: &[u8], _: &ConstantPool) -> Result<Checksum> {
+    u32::decode(&mut &info[..]).map(Checksum)
+}
+
+fn constant_pool() -> ConstantPool {
+    let mut constant_pool = ConstantPool::new();
+    constant_pool.push(ConstantPoolInfo::Utf8("Checksum".into()));
+    constant_pool.push(ConstantPoolInfo::Utf8("SourceFile".into()));
+    constant_pool
+}
+
+#[test]
+fn keep_unknown_attributes() {
+    let attribute = AttributeInfo {
+        attribute_name_index: 1,
+        info: vec![1, 2, 3],
+    };
+    let decoded = Attribute::decode(&attribute, &constant_pool()).unwrap();
+    let Attribute::Unknown { name, info } = &decoded else {
+        panic!("expected an unknown attribute, got {decoded:?}");
+    };
+    assert_eq!(name, "Checksum");
+    assert_eq!(info, &[1, 2, 3]);
+    assert_eq!(decoded.info().unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn decode_registered_attributes() {
+    let constant_pool = constant_pool();
+    let mut registry = AttributeRegistry::new();
+    registry.register("Checksum", checksum);
+    let attribute = AttributeInfo {
+        attribute_name_index: 1,
+        info: vec![0xCA, 0xFE, 0xBA, 0xBE],
+    };
+    let decoded = registry.decode(&attribute, &constant_pool).unwrap();
+    let Attribute::Custom(custom) = &decoded else {
+        panic!("expected a custom attribute, got {decoded:?}");
+    };
+    assert_eq!(custom.downcast_ref::<Checksum>().unwrap().0, 0xCAFEBABE);
+    assert_eq!(decoded.name(), "Checksum");
+    assert!(decoded.info().unwrap() == attribute.info);
+
+    let mut constant_pool = constant_pool;
+    let length = constant_pool.len();
+    let encoded = decoded.to_info(&mut constant_pool).unwrap();
+    assert_eq!(encoded.attribute_name_index, 1);
+    assert_eq!(constant_pool.len(), length);
+
+    // errors of the decoder are passed on
+    let attribute = AttributeInfo {
+        attribute_name_index: 1,
+        info: vec![0xCA, 0xFE],
+    };
+    assert!(registry.decode(&attribute, &constant_pool).is_err());
+}
+
+#[test]
+fn prefer_registered_decoders_over_standard_ones() {
+    let constant_pool = constant_pool();
+    let attribute = AttributeInfo {
+        attribute_name_index: 2,
+        info: vec![0, 1],
+    };
+    let Attribute::SourceFile(SourceFileAttribute { sourcefile_index }) =
+        Attribute::decode(&attribute, &constant_pool).unwrap()
+    else {
+        panic!("expected a SourceFile attribute");
+    };
+    assert_eq!(sourcefile_index, 1);
+
+    let mut registry = AttributeRegistry::new();
+    registry.register("SourceFile", checksum);
+    let attribute = AttributeInfo {
+        attribute_name_index: 2,
+        info: vec![0, 0, 0, 1],
+    };
+    let decoded = registry.decode(&attribute, &constant_pool).unwrap();
+    assert!(
+        matches!(&decoded, Attribute::Custom(custom) if custom.downcast_ref::<Checksum>().is_some())
+    );
+}
+
+#[test]
+fn reject_standard_attributes_with_trailing_bytes() {
+    let attribute = AttributeInfo {
+        attribute_name_index: 2,
+        info: vec![0, 1, 0],
+    };
+    assert!(matches!(
+        Attribute::decode(&attribute, &constant_pool()),
+        Err(Error::InvalidAttributeLength(name)) if name == "SourceFile"
+    ));
+}
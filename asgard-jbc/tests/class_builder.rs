@@ -0,0 +1,137 @@
+use std::io::Cursor;
+
+use asgard_jbc::{
+    class::{
+        annotation::Annotation,
+        attribute::{
+            AnnotationsAttribute, Attribute, ConstantValueAttribute, ExceptionsAttribute,
+            SignatureAttribute,
+        },
+        builder::ClassBuilder,
+        AttributeInfo, ClassFile, ConstantLookup, ConstantPool, FieldAccessFlags,
+        MethodAccessFlags,
+    },
+    code::Instruction,
+    Decode, Encode,
+};
+
+/// Returns the names of `attributes`.
+fn names<'a>(attributes: &[AttributeInfo], constant_pool: &'a ConstantPool) -> Vec<&'a str> {
+    attributes
+        .iter()
+        .map(|attribute| constant_pool.utf8(attribute.attribute_name_index).unwrap())
+        .collect()
+}
+
+fn decode(attribute: &AttributeInfo, constant_pool: &ConstantPool) -> Attribute {
+    Attribute::decode(attribute, constant_pool).unwrap()
+}
+
+#[test]
+fn build_fields_and_methods_with_attributes() {
+    let mut builder = ClassBuilder::new("example/Members");
+    let constant_pool = builder.constant_pool();
+    let constantvalue_index = constant_pool.integer(42).unwrap();
+    let signature_index = constant_pool.utf8("Ljava/util/List<TT;>;").unwrap();
+    let io_exception = constant_pool.class("java/io/IOException").unwrap();
+    let deprecated = Annotation {
+        type_index: constant_pool.utf8("Ljava/lang/Deprecated;").unwrap(),
+        element_value_pairs: vec![],
+    };
+    builder
+        .field(
+            FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+            "ANSWER",
+            "I",
+            &[Attribute::ConstantValue(ConstantValueAttribute {
+                constantvalue_index,
+            })],
+        )
+        .unwrap()
+        .field(
+            FieldAccessFlags::PRIVATE,
+            "values",
+            "Ljava/util/List;",
+            &[Attribute::Signature(SignatureAttribute { signature_index })],
+        )
+        .unwrap()
+        .method(
+            MethodAccessFlags::PUBLIC,
+            "close",
+            "()V",
+            &[
+                Attribute::Exceptions(ExceptionsAttribute(vec![io_exception])),
+                Attribute::RuntimeVisibleAnnotations(AnnotationsAttribute(vec![deprecated])),
+            ],
+            |code, _| {
+                code.instruction(Instruction::Return);
+                Ok(())
+            },
+        )
+        .unwrap()
+        .declare_method(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT,
+            "read",
+            "()I",
+            &[Attribute::Exceptions(ExceptionsAttribute(vec![
+                io_exception,
+            ]))],
+        )
+        .unwrap();
+
+    // decode the encoded class to check that the attributes are well-formed
+    let mut output = Cursor::new(Vec::new());
+    builder.build().unwrap().encode(&mut output).unwrap();
+    let class_file = ClassFile::decode(&mut output.into_inner().as_slice()).unwrap();
+    let constant_pool = &class_file.constant_pool;
+
+    let [answer, values] = class_file.fields.as_slice() else {
+        panic!("expected two fields");
+    };
+    let [constant_value] = answer.attributes.as_slice() else {
+        panic!("expected a single attribute");
+    };
+    let Attribute::ConstantValue(constant_value) = decode(constant_value, constant_pool) else {
+        panic!("expected a ConstantValue");
+    };
+    assert_eq!(
+        constant_pool
+            .integer(constant_value.constantvalue_index)
+            .unwrap(),
+        42
+    );
+    assert_eq!(names(&values.attributes, constant_pool), ["Signature"]);
+    let Attribute::Signature(signature) = decode(&values.attributes[0], constant_pool) else {
+        panic!("expected a Signature");
+    };
+    assert_eq!(
+        constant_pool.utf8(signature.signature_index).unwrap(),
+        "Ljava/util/List<TT;>;"
+    );
+
+    let [close, read] = class_file.methods.as_slice() else {
+        panic!("expected two methods");
+    };
+    // the code comes first
+    assert_eq!(
+        names(&close.attributes, constant_pool),
+        ["Code", "Exceptions", "RuntimeVisibleAnnotations"]
+    );
+    assert!(close.code(constant_pool).unwrap().is_some());
+    assert!(close
+        .annotation(constant_pool, "Ljava/lang/Deprecated;")
+        .unwrap()
+        .is_some());
+    assert_eq!(names(&read.attributes, constant_pool), ["Exceptions"]);
+    assert!(read.code(constant_pool).unwrap().is_none());
+    let Attribute::Exceptions(exceptions) = decode(&read.attributes[0], constant_pool) else {
+        panic!("expected Exceptions");
+    };
+    let [exception] = exceptions.0.as_slice() else {
+        panic!("expected a single exception");
+    };
+    assert_eq!(
+        constant_pool.class_name(*exception).unwrap(),
+        "java/io/IOException"
+    );
+}
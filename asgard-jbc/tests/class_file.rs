@@ -0,0 +1,129 @@
+use asgard_jbc::{
+    class::{builder::ClassBuilder, ClassFile, FieldAccessFlags, MethodAccessFlags},
+    descriptor::{FieldType, MethodDescriptor},
+    Decode, Error,
+};
+
+mod common;
+
+fn class_file() -> ClassFile {
+    let mut builder = ClassBuilder::new("example/Resolved");
+    builder
+        .super_class(Some("java/util/AbstractList"))
+        .interface("java/util/RandomAccess")
+        .interface("java/io/Serializable")
+        .field(
+            FieldAccessFlags::PRIVATE,
+            "values",
+            "[Ljava/lang/Object;",
+            &[],
+        )
+        .unwrap()
+        .declare_method(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT,
+            "get",
+            "(I)Ljava/lang/Object;",
+            &[],
+        )
+        .unwrap();
+    builder.build().unwrap()
+}
+
+#[test]
+fn resolve_names_and_descriptors() {
+    let class_file = class_file();
+    let constant_pool = &class_file.constant_pool;
+    assert_eq!(class_file.name().unwrap(), "example/Resolved");
+    assert_eq!(
+        class_file.super_name().unwrap(),
+        Some("java/util/AbstractList")
+    );
+    assert_eq!(
+        class_file.interface_names().unwrap(),
+        ["java/util/RandomAccess", "java/io/Serializable"]
+    );
+
+    let field = &class_file.fields[0];
+    assert_eq!(field.name(constant_pool).unwrap(), "values");
+    assert_eq!(
+        field.field_type(constant_pool).unwrap(),
+        FieldType::Array(Box::new(FieldType::Object("java/lang/Object".to_owned())))
+    );
+
+    let method = &class_file.methods[0];
+    assert_eq!(method.name(constant_pool).unwrap(), "get");
+    assert_eq!(
+        method.descriptor(constant_pool).unwrap(),
+        MethodDescriptor {
+            parameter_types: vec![FieldType::Int],
+            return_type: FieldType::Object("java/lang/Object".to_owned()),
+        }
+    );
+}
+
+#[test]
+fn resolve_runtime_classes() {
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        let name = class_file.name().unwrap();
+        if path == "module-info.class" {
+            assert_eq!(name, "module-info");
+            assert_eq!(class_file.super_name().unwrap(), None);
+        } else {
+            assert_eq!(format!("{name}.class"), path);
+        }
+        if path == "java/lang/Object.class" {
+            assert_eq!(class_file.super_name().unwrap(), None);
+        }
+        class_file.interface_names().unwrap();
+    }
+}
+
+#[test]
+fn reject_wrong_typed_indices() {
+    let mut class_file = class_file();
+    let field = &class_file.fields[0];
+    let name_index = field.name_index;
+    let descriptor_index = field.descriptor_index;
+
+    // a Utf8 entry where a Class entry is expected
+    class_file.this_class = name_index;
+    assert!(matches!(
+        class_file.name(),
+        Err(Error::UnexpectedConstant { index, expected: "Class" }) if index == name_index
+    ));
+    class_file.super_class = name_index;
+    assert!(matches!(
+        class_file.super_name(),
+        Err(Error::UnexpectedConstant { .. })
+    ));
+    class_file.interfaces.push(0);
+    assert!(matches!(
+        class_file.interface_names(),
+        Err(Error::InvalidConstantPoolIndex(0))
+    ));
+
+    // a Class entry where a Utf8 entry is expected
+    let class_index = class_file.interfaces[0];
+    let constant_pool = &class_file.constant_pool;
+    let field = &mut class_file.fields[0];
+    field.name_index = class_index;
+    assert!(matches!(
+        field.name(constant_pool),
+        Err(Error::UnexpectedConstant {
+            expected: "Utf8",
+            ..
+        })
+    ));
+    let method = &mut class_file.methods[0];
+    method.descriptor_index = descriptor_index;
+    assert!(matches!(
+        method.descriptor(constant_pool),
+        Err(Error::InvalidDescriptor { position: 0, .. })
+    ));
+    method.descriptor_index = constant_pool.len() + 1;
+    assert!(matches!(
+        method.descriptor(constant_pool),
+        Err(Error::InvalidConstantPoolIndex(_))
+    ));
+}
@@ -0,0 +1,190 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use asgard_jbc::{
+    class::builder::ClassBuilder,
+    class_path::{ClassPath, ClassSource},
+    Encode, Error,
+};
+
+mod common;
+
+/// Returns an empty temporary directory unique to the test.
+fn temp_dir(test: &str) -> PathBuf {
+    let directory =
+        std::env::temp_dir().join(format!("asgard-class-path-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Returns an empty class file with the given internal name.
+fn class(name: &str) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    ClassBuilder::new(name)
+        .build()
+        .unwrap()
+        .encode(&mut output)
+        .unwrap();
+    output.into_inner()
+}
+
+fn write_class(directory: &Path, name: &str) {
+    let path = directory.join(format!("{name}.class"));
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, class(name)).unwrap();
+}
+
+#[test]
+fn skip_missing_entries() {
+    let directory = temp_dir("missing");
+    write_class(&directory, "example/Present");
+
+    let missing = directory.join("missing.jar");
+    let mut class_path =
+        ClassPath::parse(std::env::join_paths([&missing, &directory]).unwrap()).unwrap();
+    class_path.push(directory.join("missing")).unwrap();
+    let source = class_path.source("example/Present").unwrap().cloned();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(source.unwrap().path(), directory);
+    assert!(class_path.find("example/Missing").unwrap().is_none());
+}
+
+#[test]
+fn cache_classes_and_their_absence() {
+    let first = temp_dir("cache-first");
+    let second = temp_dir("cache-second");
+    write_class(&first, "example/Cached");
+    let mut class_path = ClassPath::new();
+    class_path.push_directory(&first);
+
+    let cached = class_path.find("example/Cached").unwrap().unwrap();
+    assert_eq!(cached.name().unwrap(), "example/Cached");
+    std::fs::remove_file(first.join("example/Cached.class")).unwrap();
+    assert!(Arc::ptr_eq(
+        &class_path.find("example/Cached").unwrap().unwrap(),
+        &cached
+    ));
+
+    assert!(class_path.find("example/Late").unwrap().is_none());
+    write_class(&first, "example/Late");
+    assert!(class_path.find("example/Late").unwrap().is_none());
+    // adding entries forgets absent classes, but keeps found ones
+    write_class(&second, "example/Cached");
+    class_path.push_directory(&second);
+    let source = class_path.source("example/Late").unwrap().cloned();
+    let cached_again = class_path.find("example/Cached").unwrap().unwrap();
+    std::fs::remove_dir_all(&first).unwrap();
+    std::fs::remove_dir_all(&second).unwrap();
+    assert_eq!(source, Some(ClassSource::Directory(first)));
+    assert!(Arc::ptr_eq(&cached_again, &cached));
+}
+
+#[test]
+fn reject_files_shorter_than_a_magic() {
+    let directory = temp_dir("short");
+    let path = directory.join("short.jar");
+    std::fs::write(&path, b"PK").unwrap();
+    let result = ClassPath::new().push(&path).map(|_| ());
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert!(matches!(result, Err(Error::TruncatedInput { offset: 2 })));
+}
+
+#[test]
+fn find_classes_in_the_runtime_image() {
+    let Some(java_home) = common::java_home() else {
+        eprintln!("no JDK found, skipping runtime image");
+        return;
+    };
+    let modules = java_home.join("lib").join("modules");
+    if !modules.is_file() {
+        eprintln!("no runtime image at {}, skipping it", modules.display());
+        return;
+    }
+    let directory = temp_dir("jimage");
+    // earlier entries take precedence over the runtime image
+    write_class(&directory, "java/lang/Runnable");
+    let mut class_path = ClassPath::new();
+    class_path
+        .push(&directory)
+        .unwrap()
+        .push_java_home(&java_home)
+        .unwrap();
+
+    let string = class_path.find("java/lang/String").unwrap().unwrap();
+    assert_eq!(string.super_name().unwrap(), Some("java/lang/Object"));
+    assert_eq!(
+        class_path.source("java/lang/String").unwrap(),
+        Some(&ClassSource::JImage {
+            path: modules.clone(),
+            module: "java.base".to_owned(),
+        })
+    );
+    assert_eq!(
+        class_path
+            .source("java/sql/Connection")
+            .unwrap()
+            .and_then(|source| match source {
+                ClassSource::JImage { module, .. } => Some(module.as_str()),
+                _ => None,
+            }),
+        Some("java.sql")
+    );
+    let runnable = class_path.source("java/lang/Runnable").unwrap().cloned();
+    // classes of unknown packages and missing classes of known ones
+    assert!(class_path.find("example/Missing").unwrap().is_none());
+    assert!(class_path.find("java/lang/Missing").unwrap().is_none());
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(runnable, Some(ClassSource::Directory(directory)));
+}
+
+#[cfg(feature = "jar")]
+#[test]
+fn find_classes_in_multi_release_jars() {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    let directory = temp_dir("jar");
+    let path = directory.join("example.jar");
+    let mut writer = ZipWriter::new(std::fs::File::create(&path).unwrap());
+    for (name, data) in [
+        (
+            "META-INF/MANIFEST.MF",
+            b"Manifest-Version: 1.0\r\nMulti-Release: true\r\n\r\n".to_vec(),
+        ),
+        ("example/Base.class", class("example/Base")),
+        (
+            "META-INF/versions/11/example/Base.class",
+            class("example/Base11"),
+        ),
+    ] {
+        writer.start_file(name, FileOptions::default()).unwrap();
+        writer.write_all(&data).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let mut class_path = ClassPath::new();
+    // jars are recognized by their magic
+    class_path.push(&path).unwrap();
+    let base = class_path.find("example/Base").unwrap().unwrap();
+    assert_eq!(base.name().unwrap(), "example/Base");
+    assert_eq!(
+        class_path.source("example/Base").unwrap(),
+        Some(&ClassSource::Jar(path.clone()))
+    );
+    // setting the release drops the cached classes
+    class_path.release(17).unwrap();
+    let versioned = class_path.find("example/Base").unwrap().unwrap();
+    assert_eq!(versioned.name().unwrap(), "example/Base11");
+
+    // jars pushed later are read for the release as well
+    let mut class_path = ClassPath::new();
+    class_path.release(9).unwrap().push_jar(&path).unwrap();
+    let base = class_path.find("example/Base").unwrap().unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(base.name().unwrap(), "example/Base");
+}
@@ -0,0 +1,112 @@
+use asgard_jbc::{
+    class::{
+        attribute::AttributeRegistry, AttributeInfo, ClassFile, ConstantPool, ConstantPoolInfo,
+    },
+    code::{self, Instruction},
+    Decode, Error,
+};
+
+mod common;
+
+#[test]
+fn round_trip_runtime_method_bodies() {
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        for method in &class_file.methods {
+            let Some(code) = method.code(&class_file.constant_pool).unwrap() else {
+                continue;
+            };
+            let instructions = code::decode(&code.code).unwrap();
+            let encoded = code::encode(instructions.iter().map(|(_, instruction)| instruction));
+            assert!(encoded.unwrap() == code.code, "{path}");
+        }
+    }
+}
+
+/// Returns a switch at offset 0, padded to the default offset.
+fn switch(opcode: u8, operands: &[i32]) -> Vec<u8> {
+    let mut code = vec![opcode, 0, 0, 0];
+    for operand in operands {
+        code.extend(operand.to_be_bytes());
+    }
+    code
+}
+
+#[test]
+fn reject_oversized_switches() {
+    for code in [
+        switch(170, &[0, 0, i32::MAX]),
+        switch(170, &[0, i32::MIN, i32::MAX]),
+        switch(171, &[0, i32::MAX]),
+    ] {
+        assert!(matches!(
+            Instruction::decode(&mut code.as_slice(), 0),
+            Err(Error::TruncatedInput { .. })
+        ));
+    }
+}
+
+#[test]
+fn reject_inverted_tableswitch() {
+    let code = switch(170, &[0, 1, 0]);
+    assert!(matches!(
+        Instruction::decode(&mut code.as_slice(), 0),
+        Err(Error::InvalidInstruction { opcode: 170, .. })
+    ));
+}
+
+#[test]
+fn reject_unrepresentable_tableswitch() {
+    for (low, offsets) in [(i32::MAX, vec![0, 0]), (0, vec![])] {
+        let tableswitch = Instruction::Tableswitch {
+            default: 0,
+            low,
+            offsets,
+        };
+        assert!(matches!(
+            code::encode([&tableswitch]),
+            Err(Error::InvalidInstruction { opcode: 170, .. })
+        ));
+    }
+}
+
+#[test]
+fn keep_unnecessary_wide_prefixes() {
+    // wide iinc 1 1, wide iload 2, return
+    let code = [196, 132, 0, 1, 0, 1, 196, 21, 0, 2, 177];
+    let instructions = code::decode(&code).unwrap();
+    assert_eq!(
+        instructions,
+        [
+            (
+                0,
+                Instruction::Wide(Box::new(Instruction::Iinc { index: 1, value: 1 }))
+            ),
+            (6, Instruction::Wide(Box::new(Instruction::Iload(2)))),
+            (10, Instruction::Return),
+        ]
+    );
+    let encoded = code::encode(instructions.iter().map(|(_, instruction)| instruction));
+    assert_eq!(encoded.unwrap(), code);
+}
+
+#[test]
+fn reject_wide_prefix_of_other_instructions() {
+    assert!(matches!(
+        code::encode([&Instruction::Wide(Box::new(Instruction::Nop))]),
+        Err(Error::InvalidInstruction { opcode: 196, .. })
+    ));
+}
+
+#[test]
+fn reject_oversized_code_length() {
+    let mut constant_pool = ConstantPool::new();
+    let attribute = AttributeInfo {
+        attribute_name_index: constant_pool.push(ConstantPoolInfo::Utf8("Code".into())),
+        info: vec![0, 1, 0, 1, 0xFF, 0xFF, 0xFF, 0xFF, 177],
+    };
+    assert!(matches!(
+        AttributeRegistry::new().decode(&attribute, &constant_pool),
+        Err(Error::TruncatedInput { offset: 9 })
+    ));
+}
@@ -0,0 +1,113 @@
+use asgard_jbc::{
+    code::{
+        self,
+        builder::{CodeBuilder, Jump},
+        Instruction,
+    },
+    Error,
+};
+
+/// Number of `nop`s which put a label out of range of a 16-bit offset.
+const FAR: u32 = 40000;
+
+#[test]
+fn keep_short_jumps() {
+    let mut builder = CodeBuilder::new();
+    let target = builder.new_label();
+    builder
+        .jump(Jump::Ifeq, target)
+        .instruction(Instruction::Nop)
+        .place_label(target)
+        .instruction(Instruction::Return);
+    let assembled = builder.build().unwrap();
+    assert_eq!(
+        code::decode(&assembled.code).unwrap(),
+        [
+            (0, Instruction::Ifeq(4)),
+            (3, Instruction::Nop),
+            (4, Instruction::Return),
+        ]
+    );
+    assert_eq!(assembled.offset(target), Some(4));
+}
+
+#[test]
+fn widen_far_gotos() {
+    let mut builder = CodeBuilder::new();
+    let target = builder.new_label();
+    builder.jump(Jump::Goto, target);
+    for _ in 0..FAR {
+        builder.instruction(Instruction::Nop);
+    }
+    builder.place_label(target).instruction(Instruction::Return);
+    let assembled = builder.build().unwrap();
+    let instructions = code::decode(&assembled.code).unwrap();
+    assert_eq!(instructions[0], (0, Instruction::GotoW(5 + FAR as i32)));
+    assert_eq!(assembled.offset(target), Some(5 + FAR));
+}
+
+#[test]
+fn widen_far_conditionals_into_inverted_jumps_over_goto_w() {
+    let mut builder = CodeBuilder::new();
+    let start = builder.new_label();
+    let end = builder.new_label();
+    builder.place_label(start).jump(Jump::IfIcmplt, end);
+    for _ in 0..FAR {
+        builder.instruction(Instruction::Nop);
+    }
+    builder
+        .jump(Jump::Ifnull, start)
+        .place_label(end)
+        .instruction(Instruction::Return);
+    let assembled = builder.build().unwrap();
+    let instructions = code::decode(&assembled.code).unwrap();
+    let end_offset = 8 + FAR + 8;
+    assert_eq!(instructions[0], (0, Instruction::IfIcmpge(8)));
+    assert_eq!(
+        instructions[1],
+        (3, Instruction::GotoW(end_offset as i32 - 3))
+    );
+    // backward jumps are widened the same way
+    let backward = 8 + FAR;
+    assert_eq!(
+        instructions[instructions.len() - 3],
+        (backward, Instruction::Ifnonnull(8))
+    );
+    assert_eq!(
+        instructions[instructions.len() - 2],
+        (backward + 3, Instruction::GotoW(-(backward as i32 + 3)))
+    );
+    assert_eq!(assembled.offset(end), Some(end_offset));
+}
+
+#[test]
+fn reject_misplaced_labels() {
+    let mut builder = CodeBuilder::new();
+    let label = builder.new_label();
+    builder
+        .place_label(label)
+        .instruction(Instruction::Nop)
+        .place_label(label);
+    assert!(matches!(builder.build(), Err(Error::DuplicateLabel)));
+
+    let mut other = CodeBuilder::new();
+    let foreign = other.new_label();
+    let mut builder = CodeBuilder::new();
+    builder
+        .place_label(foreign)
+        .instruction(Instruction::Return);
+    assert!(matches!(builder.build(), Err(Error::ForeignLabel)));
+
+    let mut builder = CodeBuilder::new();
+    builder
+        .jump(Jump::Goto, foreign)
+        .instruction(Instruction::Return);
+    assert!(matches!(builder.build(), Err(Error::ForeignLabel)));
+
+    let mut builder = CodeBuilder::new();
+    let unplaced = builder.new_label();
+    builder
+        .jump(Jump::Goto, unplaced)
+        .instruction(Instruction::Return);
+    assert!(matches!(builder.build(), Err(Error::UnplacedLabel)));
+}
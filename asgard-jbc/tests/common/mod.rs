@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests.
+
+// not every test uses every helper
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use asgard_jbc::jimage::JImage;
+
+/// Returns the class files of the runtime image of the JDK at `JAVA_HOME`, or
+/// of the `java` on the `PATH`, together with their paths. Tests depending on
+/// it pass trivially if there is none.
+pub fn runtime_classes() -> Vec<(String, Vec<u8>)> {
+    let Some(path) = java_home().map(|java_home| java_home.join("lib").join("modules")) else {
+        eprintln!("no JDK found, skipping runtime image");
+        return vec![];
+    };
+    let Ok(mut image) = JImage::open(&path) else {
+        eprintln!("no runtime image at {}, skipping it", path.display());
+        return vec![];
+    };
+    image
+        .entries()
+        .unwrap()
+        .map(Result::unwrap)
+        .filter(|entry| entry.path().ends_with(".class"))
+        .map(|entry| (entry.path().to_owned(), entry.data().to_vec()))
+        .collect()
+}
+
+/// Returns the JDK at `JAVA_HOME`, or of the `java` on the `PATH`.
+pub fn java_home() -> Option<PathBuf> {
+    if let Some(java_home) = std::env::var_os("JAVA_HOME") {
+        return Some(java_home.into());
+    }
+    let java = std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|path| path.join("java"))
+        .find(|java| java.is_file())?;
+    Some(java.canonicalize().ok()?.parent()?.parent()?.to_owned())
+}
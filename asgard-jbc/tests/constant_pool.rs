@@ -0,0 +1,169 @@
+use std::io::Cursor;
+
+use asgard_jbc::{
+    class::{
+        borrowed::ClassFileRef,
+        builder::{ClassBuilder, ConstantPoolBuilder},
+        ClassFile, ConstantLookup, ConstantPool, ConstantPoolInfo,
+    },
+    Decode, Encode, Error,
+};
+
+#[test]
+fn long_and_double_take_two_slots() {
+    let mut data = vec![0, 7];
+    data.push(5);
+    data.extend(0x0102030405060708i64.to_be_bytes());
+    data.push(6);
+    data.extend(2.5f64.to_be_bytes());
+    data.extend([1, 0, 16]);
+    data.extend(b"java/lang/Object");
+    data.extend([7, 0, 5]);
+
+    let constant_pool = ConstantPool::decode(&mut data.as_slice()).unwrap();
+    assert_eq!(constant_pool.len(), 6);
+    assert_eq!(constant_pool.long(1).unwrap(), 0x0102030405060708);
+    assert!(constant_pool.get(2).is_none());
+    assert!(matches!(
+        constant_pool.utf8(2),
+        Err(Error::InvalidConstantPoolIndex(2))
+    ));
+    assert_eq!(constant_pool.double(3).unwrap(), 2.5);
+    assert!(constant_pool.get(4).is_none());
+    assert_eq!(constant_pool.utf8(5).unwrap(), "java/lang/Object");
+    assert_eq!(constant_pool.class_name(6).unwrap(), "java/lang/Object");
+    assert_eq!(
+        constant_pool
+            .iter()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>(),
+        [1, 3, 5, 6]
+    );
+
+    let mut output = Cursor::new(Vec::new());
+    constant_pool.encode(&mut output).unwrap();
+    assert_eq!(output.into_inner(), data);
+}
+
+#[test]
+fn push_reserves_the_second_slot() {
+    let mut constant_pool = ConstantPool::new();
+    assert_eq!(constant_pool.push(ConstantPoolInfo::Double(1.0)), 1);
+    assert_eq!(constant_pool.push(ConstantPoolInfo::Integer(2)), 3);
+    assert_eq!(constant_pool.len(), 3);
+}
+
+#[test]
+fn builder_reuses_equal_entries() {
+    let mut builder = ConstantPoolBuilder::new();
+    let class = builder.class("java/lang/Object").unwrap();
+    assert_eq!(builder.utf8("java/lang/Object").unwrap(), 1);
+    assert_eq!(builder.class("java/lang/Object").unwrap(), class);
+    assert_eq!(builder.string("java/lang/Object").unwrap(), 3);
+    assert_eq!(builder.long(7).unwrap(), 4);
+    assert_eq!(builder.long(7).unwrap(), 4);
+    assert_eq!(builder.integer(7).unwrap(), 6);
+    // floating-point constants are compared by their bits
+    assert_eq!(builder.double(0.0).unwrap(), 7);
+    assert_eq!(builder.double(-0.0).unwrap(), 9);
+    assert_eq!(builder.double(0.0).unwrap(), 7);
+    assert_eq!(builder.build().len(), 10);
+}
+
+#[test]
+fn builder_limits_the_number_of_entries() {
+    let mut builder = ConstantPoolBuilder::new();
+    for value in 0..u16::MAX as i32 - 2 {
+        builder.integer(value).unwrap();
+    }
+    // a Long or Double needs two of the remaining slots
+    assert!(matches!(builder.long(0), Err(Error::ConstantPoolTooLarge)));
+    assert_eq!(builder.integer(-1).unwrap(), u16::MAX - 1);
+    assert!(matches!(
+        builder.integer(-2),
+        Err(Error::ConstantPoolTooLarge)
+    ));
+    // existing entries are still found
+    assert_eq!(builder.integer(0).unwrap(), 1);
+}
+
+#[test]
+fn builder_limits_the_encoded_length_of_utf8() {
+    let mut builder = ConstantPoolBuilder::new();
+    assert!(builder.utf8(&"a".repeat(65535)).is_ok());
+    assert!(matches!(
+        builder.utf8(&"a".repeat(65536)),
+        Err(Error::Utf8TooLong(65536))
+    ));
+    // NUL takes two bytes in modified UTF-8
+    assert!(matches!(
+        builder.utf8(&"\0".repeat(32768)),
+        Err(Error::Utf8TooLong(65536))
+    ));
+    assert!(matches!(
+        builder.string(&"\0".repeat(32768)),
+        Err(Error::Utf8TooLong(65536))
+    ));
+}
+
+/// Resolves the entries allocated by [`owned_and_borrowed_lookups_agree`].
+fn resolve(constants: &impl ConstantLookup) -> (i64, f32, &str, &str, (&str, &str, &str), &str) {
+    (
+        constants.long(1).unwrap(),
+        constants.float(3).unwrap(),
+        constants.string(5).unwrap(),
+        constants.class_name(7).unwrap(),
+        constants.member_ref(11).unwrap(),
+        constants.package_name(13).unwrap(),
+    )
+}
+
+#[test]
+fn owned_and_borrowed_lookups_agree() {
+    let mut builder = ClassBuilder::new("example/Constants");
+    let constant_pool = builder.constant_pool();
+    assert_eq!(constant_pool.long(-1).unwrap(), 1);
+    assert_eq!(constant_pool.float(0.5).unwrap(), 3);
+    assert_eq!(constant_pool.string("value").unwrap(), 5);
+    assert_eq!(constant_pool.class("java/lang/Thread").unwrap(), 7);
+    assert_eq!(
+        constant_pool
+            .method_ref("java/lang/Thread", "run", "()V")
+            .unwrap(),
+        11
+    );
+    assert_eq!(constant_pool.package("java/lang").unwrap(), 13);
+    let mut output = Cursor::new(Vec::new());
+    builder.build().unwrap().encode(&mut output).unwrap();
+    let data = output.into_inner();
+
+    let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+    let class_file_ref = ClassFileRef::decode(&mut data.as_slice()).unwrap();
+    let resolved = resolve(&class_file.constant_pool);
+    assert_eq!(
+        resolved,
+        (
+            -1,
+            0.5,
+            "value",
+            "java/lang/Thread",
+            ("java/lang/Thread", "run", "()V"),
+            "java/lang",
+        )
+    );
+    assert_eq!(resolve(&class_file_ref.constant_pool), resolved);
+
+    // Utf8 entries are not resolved as other types in either representation
+    for constants in [
+        &class_file.constant_pool as &dyn ConstantLookup,
+        &class_file_ref.constant_pool,
+    ] {
+        assert!(matches!(
+            constants.class_name(4),
+            Err(Error::UnexpectedConstant {
+                index: 4,
+                expected: "Class"
+            })
+        ));
+    }
+}
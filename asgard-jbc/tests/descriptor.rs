@@ -0,0 +1,108 @@
+use asgard_jbc::{
+    class::ClassFile,
+    descriptor::{FieldType, MethodDescriptor},
+    Decode, Error,
+};
+
+mod common;
+
+#[test]
+fn round_trip_runtime_descriptors() {
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        let constant_pool = &class_file.constant_pool;
+        for field in &class_file.fields {
+            let descriptor = constant_pool.utf8(field.descriptor_index).unwrap();
+            let field_type = field
+                .field_type(constant_pool)
+                .unwrap_or_else(|error| panic!("{path} {descriptor}: {error}"));
+            assert_eq!(field_type.to_string(), descriptor, "{path}");
+        }
+        for method in &class_file.methods {
+            let descriptor = constant_pool.utf8(method.descriptor_index).unwrap();
+            let parsed = method
+                .descriptor(constant_pool)
+                .unwrap_or_else(|error| panic!("{path} {descriptor}: {error}"));
+            assert_eq!(parsed.to_string(), descriptor, "{path}");
+        }
+    }
+}
+
+#[test]
+fn parse_method_descriptors() {
+    let descriptor = "(I[[Ljava/lang/String;J)Ljava/util/List;";
+    let parsed: MethodDescriptor = descriptor.parse().unwrap();
+    assert_eq!(
+        parsed.parameter_types,
+        [
+            FieldType::Int,
+            FieldType::Array(Box::new(FieldType::Array(Box::new(FieldType::Object(
+                "java/lang/String".to_owned()
+            ))))),
+            FieldType::Long,
+        ]
+    );
+    assert_eq!(
+        parsed.return_type,
+        FieldType::Object("java/util/List".to_owned())
+    );
+    assert_eq!(parsed.to_string(), descriptor);
+
+    let parsed: MethodDescriptor = "()V".parse().unwrap();
+    assert!(parsed.parameter_types.is_empty());
+    assert_eq!(parsed.return_type, FieldType::Void);
+}
+
+#[test]
+fn limit_array_dimensions() {
+    let descriptor = format!("{}I", "[".repeat(255));
+    assert!(descriptor.parse::<FieldType>().is_ok());
+    let descriptor = format!("(J{}I)V", "[".repeat(256));
+    assert!(matches!(
+        descriptor.parse::<MethodDescriptor>(),
+        Err(Error::InvalidDescriptor { position: 2, .. })
+    ));
+}
+
+#[test]
+fn report_field_type_error_positions() {
+    for (descriptor, position) in [
+        ("", 0),
+        ("X", 0),
+        ("V", 0),
+        ("[V", 1),
+        ("II", 1),
+        ("Ljava/lang/String", 17),
+        ("L;", 1),
+        ("Ljava//String;", 6),
+        ("Ljava.lang.String;", 5),
+    ] {
+        assert!(
+            matches!(
+                descriptor.parse::<FieldType>(),
+                Err(Error::InvalidDescriptor { position: actual, .. }) if actual == position
+            ),
+            "{descriptor}"
+        );
+    }
+}
+
+#[test]
+fn report_method_descriptor_error_positions() {
+    for (descriptor, position) in [
+        ("", 0),
+        ("I)V", 0),
+        ("(I", 2),
+        ("(V)V", 1),
+        ("(I)", 3),
+        ("(I)VV", 4),
+    ] {
+        assert!(
+            matches!(
+                descriptor.parse::<MethodDescriptor>(),
+                Err(Error::InvalidDescriptor { position: actual, .. }) if actual == position
+            ),
+            "{descriptor}"
+        );
+    }
+}
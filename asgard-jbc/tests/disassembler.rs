@@ -0,0 +1,127 @@
+use asgard_jbc::{
+    class::{
+        builder::ClassBuilder, ClassFile, ConstantPoolInfo, FieldAccessFlags, MethodAccessFlags,
+    },
+    code::{builder::Jump, Instruction},
+    disassembler::{self, Disassembler},
+    mutf8::JavaString,
+    Decode,
+};
+
+mod common;
+
+/// Returns a class returning a string constant which ends in an unpaired
+/// surrogate.
+fn greeter() -> ClassFile {
+    let mut builder = ClassBuilder::new("example/Greeter");
+    builder
+        .field(FieldAccessFlags::PRIVATE, "count", "I", &[])
+        .unwrap()
+        .method(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            "greeting",
+            "(Z)Ljava/lang/String;",
+            &[],
+            |code, constant_pool| {
+                let mut value = JavaString::from("\"hi\"\n");
+                value.push(0xD800);
+                let string_index = constant_pool.push(ConstantPoolInfo::Utf8(value))?;
+                let string = constant_pool.push(ConstantPoolInfo::String { string_index })?;
+                let otherwise = code.new_label();
+                code.instruction(Instruction::Iload(0))
+                    .jump(Jump::Ifeq, otherwise)
+                    .instruction(Instruction::Ldc(string as u8))
+                    .instruction(Instruction::Areturn)
+                    .place_label(otherwise)
+                    .instruction(Instruction::AconstNull)
+                    .instruction(Instruction::Areturn);
+                Ok(())
+            },
+        )
+        .unwrap();
+    builder.build().unwrap()
+}
+
+#[test]
+fn disassemble_greeter() {
+    assert_eq!(
+        disassembler::disassemble(&greeter()).unwrap(),
+        r#"public class example/Greeter extends java/lang/Object
+{
+  private count I
+
+  public static greeting(Z)Ljava/lang/String;
+    Code:
+        iload 0
+        ifeq L0
+        ldc String "\"hi\"\n\ud800"
+        areturn
+      L0:
+        aconst_null
+        areturn
+}
+"#
+    );
+}
+
+#[test]
+fn disassemble_greeter_verbosely() {
+    assert_eq!(
+        Disassembler::new()
+            .verbose(true)
+            .disassemble(&greeter())
+            .unwrap(),
+        r#"public class example/Greeter extends java/lang/Object
+  minor version: 0
+  major version: 52
+  flags: (0x0021) ACC_PUBLIC, ACC_SUPER
+  this_class: #4
+  super_class: #6
+  interfaces: 0, fields: 1, methods: 1, attributes: 0
+Constant pool:
+     #1 = Utf8               "\"hi\"\n\ud800"
+     #2 = String             #1                    // "\"hi\"\n\ud800"
+     #3 = Utf8               "example/Greeter"
+     #4 = Class              #3                    // example/Greeter
+     #5 = Utf8               "java/lang/Object"
+     #6 = Class              #5                    // java/lang/Object
+     #7 = Utf8               "count"
+     #8 = Utf8               "I"
+     #9 = Utf8               "greeting"
+    #10 = Utf8               "(Z)Ljava/lang/String;"
+    #11 = Utf8               "StackMapTable"
+    #12 = Utf8               "Code"
+{
+  private count I
+    flags: (0x0002) ACC_PRIVATE
+
+  public static greeting(Z)Ljava/lang/String;
+    flags: (0x0009) ACC_PUBLIC, ACC_STATIC
+    Code:
+      stack=1, locals=1, length=10
+           0: iload 0
+           2: ifeq L0
+           5: ldc #2 // String "\"hi\"\n\ud800"
+           7: areturn
+      L0:
+           8: aconst_null
+           9: areturn
+      StackMapTable:
+        L0: same
+}
+"#
+    );
+}
+
+#[test]
+fn disassemble_runtime_classes() {
+    let mut verbose = Disassembler::new();
+    verbose.verbose(true);
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        disassembler::disassemble(&class_file).unwrap_or_else(|error| panic!("{path}: {error}"));
+        verbose
+            .disassemble(&class_file)
+            .unwrap_or_else(|error| panic!("{path}: {error}"));
+    }
+}
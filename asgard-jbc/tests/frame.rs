@@ -0,0 +1,150 @@
+use asgard_jbc::{
+    class::{
+        attribute::{Attribute, StackMapFrame, VerificationTypeInfo},
+        builder::ClassBuilder,
+        ClassFile, ConstantLookup, ConstantPool, MethodAccessFlags,
+    },
+    code::{builder::Jump, frame, Instruction},
+    Decode, Error,
+};
+
+mod common;
+
+fn object(_: &str, _: &str) -> String {
+    "java/lang/Object".to_owned()
+}
+
+#[test]
+fn compute_the_maxima_of_javac() {
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        let constant_pool = &class_file.constant_pool;
+        let class_name = constant_pool.class_name(class_file.this_class).unwrap();
+        for method in &class_file.methods {
+            let Some(code) = method.code(constant_pool).unwrap() else {
+                continue;
+            };
+            let name = method.name(constant_pool).unwrap();
+            let analysis = frame::analyze(class_name, method, &code, constant_pool, &object)
+                .unwrap_or_else(|error| panic!("{path} {name}: {error}"));
+            assert_eq!(analysis.max_stack, code.max_stack, "{path} {name}");
+            // javac reserves slots for some locals which are never accessed
+            assert!(analysis.max_locals <= code.max_locals, "{path} {name}");
+        }
+    }
+}
+
+/// Returns the class name of a verification type, or `None` if it is not an
+/// object.
+fn class_name<'a>(constant_pool: &'a ConstantPool, info: &VerificationTypeInfo) -> Option<&'a str> {
+    match info {
+        VerificationTypeInfo::Object { cpool_index } => {
+            Some(constant_pool.class_name(*cpool_index).unwrap())
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn emit_frames_at_merges_and_handlers() {
+    let mut builder = ClassBuilder::new("example/Frames");
+    builder
+        .method(
+            MethodAccessFlags::STATIC,
+            "divide",
+            "(ILjava/lang/String;)I",
+            &[],
+            |code, constant_pool| {
+                let (otherwise, merge, start, end, handler) = (
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                );
+                code.instruction(Instruction::Iload(0))
+                    .jump(Jump::Ifeq, otherwise)
+                    .instruction(Instruction::Aload(1))
+                    .jump(Jump::Goto, merge)
+                    .place_label(otherwise)
+                    .instruction(Instruction::AconstNull)
+                    .place_label(merge)
+                    .instruction(Instruction::Pop)
+                    .place_label(start)
+                    .instruction(Instruction::Iload(0))
+                    .instruction(Instruction::Iconst1)
+                    .instruction(Instruction::Idiv)
+                    .instruction(Instruction::Istore(2))
+                    .place_label(end)
+                    .instruction(Instruction::Iload(2))
+                    .instruction(Instruction::Ireturn)
+                    .place_label(handler)
+                    .instruction(Instruction::Astore(3))
+                    .instruction(Instruction::Iconst0)
+                    .instruction(Instruction::Ireturn)
+                    .exception_handler(
+                        start,
+                        end,
+                        handler,
+                        constant_pool.class("java/lang/ArithmeticException")?,
+                    );
+                Ok(())
+            },
+        )
+        .unwrap();
+    let class_file = builder.build().unwrap();
+    let constant_pool = &class_file.constant_pool;
+    let code = class_file.methods[0].code(constant_pool).unwrap().unwrap();
+    assert_eq!((code.max_stack, code.max_locals), (2, 4));
+
+    let [attribute] = code.attributes.as_slice() else {
+        panic!("expected a single attribute");
+    };
+    let Attribute::StackMapTable(stack_map_table) =
+        Attribute::decode(attribute, constant_pool).unwrap()
+    else {
+        panic!("expected a StackMapTable");
+    };
+    let frames = stack_map_table.0;
+    assert_eq!(frames.len(), 3);
+    // the branch target keeps the locals of the method
+    assert_eq!(frames[0], StackMapFrame::Same { offset_delta: 10 });
+    // null merged with a string
+    let StackMapFrame::SameLocals1StackItem {
+        offset_delta: 0,
+        stack,
+    } = &frames[1]
+    else {
+        panic!("unexpected frame {:?}", frames[1]);
+    };
+    assert_eq!(class_name(constant_pool, stack), Some("java/lang/String"));
+    // the handler sees the locals before `istore_2`, with the exception on the
+    // stack
+    let StackMapFrame::SameLocals1StackItem {
+        offset_delta: 9,
+        stack,
+    } = &frames[2]
+    else {
+        panic!("unexpected frame {:?}", frames[2]);
+    };
+    assert_eq!(
+        class_name(constant_pool, stack),
+        Some("java/lang/ArithmeticException")
+    );
+}
+
+#[test]
+fn reject_unreachable_code() {
+    let mut builder = ClassBuilder::new("example/Unreachable");
+    builder
+        .method(MethodAccessFlags::STATIC, "run", "()V", &[], |code, _| {
+            code.instruction(Instruction::Return)
+                .instruction(Instruction::Return);
+            Ok(())
+        })
+        .unwrap();
+    assert!(matches!(
+        builder.build(),
+        Err(Error::Verification { offset: 1, .. })
+    ));
+}
@@ -0,0 +1,383 @@
+use asgard_jbc::{
+    class::{builder::ClassBuilder, ClassAccessFlags, FieldAccessFlags, MethodAccessFlags},
+    code::{frame::ClassHierarchy, Instruction},
+    hierarchy::{ClassNode, Hierarchy, Member, MethodNode},
+};
+
+const INTERFACE: ClassAccessFlags = ClassAccessFlags::PUBLIC
+    .union(ClassAccessFlags::INTERFACE)
+    .union(ClassAccessFlags::ABSTRACT);
+
+/// Describes a class of a test hierarchy.
+struct Class<'a> {
+    name: &'a str,
+    access_flags: ClassAccessFlags,
+    super_class: Option<&'a str>,
+    interfaces: &'a [&'a str],
+    /// The names of methods of descriptor `()V`, declared without code if
+    /// they are abstract.
+    methods: &'a [(MethodAccessFlags, &'a str)],
+    /// The names of fields of descriptor `I`.
+    fields: &'a [&'a str],
+}
+
+impl Default for Class<'_> {
+    fn default() -> Self {
+        Self {
+            name: "",
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            super_class: Some("java/lang/Object"),
+            interfaces: &[],
+            methods: &[],
+            fields: &[],
+        }
+    }
+}
+
+fn hierarchy(classes: &[Class]) -> Hierarchy {
+    let mut hierarchy = Hierarchy::new();
+    for class in classes {
+        let mut builder = ClassBuilder::new(class.name);
+        builder
+            .access_flags(class.access_flags)
+            .super_class(class.super_class);
+        for interface in class.interfaces {
+            builder.interface(*interface);
+        }
+        for (access_flags, name) in class.methods {
+            if access_flags.contains(MethodAccessFlags::ABSTRACT) {
+                builder
+                    .declare_method(*access_flags, *name, "()V", &[])
+                    .unwrap();
+            } else {
+                builder
+                    .method(*access_flags, *name, "()V", &[], |code, _| {
+                        code.instruction(Instruction::Return);
+                        Ok(())
+                    })
+                    .unwrap();
+            }
+        }
+        for name in class.fields {
+            builder
+                .field(
+                    FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC,
+                    *name,
+                    "I",
+                    &[],
+                )
+                .unwrap();
+        }
+        hierarchy.insert(&builder.build().unwrap()).unwrap();
+    }
+    hierarchy
+}
+
+fn object() -> Class<'static> {
+    Class {
+        name: "java/lang/Object",
+        super_class: None,
+        ..Class::default()
+    }
+}
+
+fn names<'a>(classes: impl IntoIterator<Item = &'a ClassNode>) -> Vec<&'a str> {
+    classes
+        .into_iter()
+        .map(|class| class.name.as_str())
+        .collect()
+}
+
+/// Returns the name of the class declaring a method.
+fn declaring_class<'a>(member: Option<Member<'a, MethodNode>>) -> Option<&'a str> {
+    member.map(|(class, _)| class.name.as_str())
+}
+
+#[test]
+fn query_subtypes() {
+    let hierarchy = hierarchy(&[
+        object(),
+        Class {
+            name: "a/J",
+            access_flags: INTERFACE,
+            ..Class::default()
+        },
+        Class {
+            name: "a/I",
+            access_flags: INTERFACE,
+            interfaces: &["a/J"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/Base",
+            interfaces: &["a/I"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/Derived",
+            super_class: Some("a/Base"),
+            ..Class::default()
+        },
+        Class {
+            name: "a/Other",
+            super_class: Some("a/Base"),
+            ..Class::default()
+        },
+    ]);
+    assert_eq!(
+        names(hierarchy.super_classes("a/Derived")),
+        ["a/Base", "java/lang/Object"]
+    );
+    assert_eq!(names(hierarchy.interfaces("a/Derived")), ["a/I", "a/J"]);
+
+    for (a, b, expected) in [
+        ("a/Derived", "a/Base", true),
+        ("a/Derived", "a/J", true),
+        ("a/Base", "a/Derived", false),
+        ("a/J", "a/I", false),
+        // classes missing from the hierarchy are only subtypes of Object
+        ("a/Missing", "java/lang/Object", true),
+        ("a/Missing", "a/Base", false),
+        ("[La/Derived;", "[La/I;", true),
+        ("[La/I;", "[La/Derived;", false),
+        ("[[La/Derived;", "[Ljava/lang/Object;", true),
+        ("[[I", "[Ljava/lang/Cloneable;", true),
+        ("[I", "[J", false),
+        ("[I", "java/io/Serializable", true),
+        ("[La/Base;", "a/Base", false),
+    ] {
+        assert_eq!(hierarchy.is_subtype(a, b), expected, "{a} {b}");
+    }
+
+    assert_eq!(
+        hierarchy.common_super_class("a/Derived", "a/Other"),
+        "a/Base"
+    );
+    assert_eq!(
+        hierarchy.common_super_class("a/Base", "a/Derived"),
+        "a/Base"
+    );
+    assert_eq!(
+        hierarchy.common_super_class("a/Derived", "a/I"),
+        "java/lang/Object"
+    );
+}
+
+#[test]
+fn end_queries_in_cyclic_hierarchies() {
+    let hierarchy = hierarchy(&[
+        object(),
+        Class {
+            name: "a/A",
+            super_class: Some("a/B"),
+            interfaces: &["a/X"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/B",
+            super_class: Some("a/A"),
+            methods: &[(MethodAccessFlags::empty(), "m")],
+            ..Class::default()
+        },
+        Class {
+            name: "a/X",
+            access_flags: INTERFACE,
+            interfaces: &["a/Y"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/Y",
+            access_flags: INTERFACE,
+            interfaces: &["a/X"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/C",
+            ..Class::default()
+        },
+    ]);
+    assert_eq!(names(hierarchy.super_classes("a/A")), ["a/B"]);
+    assert_eq!(names(hierarchy.interfaces("a/A")), ["a/X", "a/Y"]);
+    assert!(hierarchy.is_subtype("a/A", "a/B"));
+    assert!(!hierarchy.is_subtype("a/A", "a/C"));
+    assert_eq!(
+        hierarchy.common_super_class("a/A", "a/C"),
+        "java/lang/Object"
+    );
+    assert!(hierarchy.resolve_field("a/A", "missing", "I").is_none());
+    assert!(hierarchy.resolve_field("a/X", "missing", "I").is_none());
+    assert!(hierarchy.resolve_method("a/A", "missing", "()V").is_none());
+    let resolved = hierarchy.resolve_method("a/A", "m", "()V");
+    assert_eq!(declaring_class(resolved), Some("a/B"));
+    assert_eq!(
+        declaring_class(hierarchy.select_method("a/A", resolved.unwrap())),
+        Some("a/B")
+    );
+}
+
+#[test]
+fn resolve_fields_in_interfaces_before_super_classes() {
+    let hierarchy = hierarchy(&[
+        object(),
+        Class {
+            name: "a/I",
+            access_flags: INTERFACE,
+            fields: &["value"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/Base",
+            fields: &["value", "base"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/Derived",
+            super_class: Some("a/Base"),
+            interfaces: &["a/I"],
+            ..Class::default()
+        },
+    ]);
+    let declaring_class = |name| {
+        hierarchy
+            .resolve_field("a/Derived", name, "I")
+            .map(|(class, _)| class.name.as_str())
+    };
+    assert_eq!(declaring_class("value"), Some("a/I"));
+    assert_eq!(declaring_class("base"), Some("a/Base"));
+    assert_eq!(declaring_class("missing"), None);
+}
+
+#[test]
+fn select_maximally_specific_methods() {
+    let abstract_method = (MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT, "m");
+    let default_method = (MethodAccessFlags::PUBLIC, "m");
+    let interface = |name, interfaces, methods| Class {
+        name,
+        access_flags: INTERFACE,
+        interfaces,
+        methods,
+        ..Class::default()
+    };
+    let hierarchy = hierarchy(&[
+        object(),
+        interface("a/Top", &[], &[default_method]),
+        interface("a/Sub", &["a/Top"], &[default_method]),
+        interface("a/Other", &[], &[default_method]),
+        interface("a/Abstract", &[], &[abstract_method]),
+        Class {
+            name: "a/Specific",
+            interfaces: &["a/Top", "a/Sub"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/Ambiguous",
+            interfaces: &["a/Sub", "a/Other"],
+            ..Class::default()
+        },
+        Class {
+            name: "a/Concrete",
+            interfaces: &["a/Abstract", "a/Top"],
+            ..Class::default()
+        },
+    ]);
+
+    // the default method of the sub interface overrides the other
+    let resolved = hierarchy.resolve_method("a/Specific", "m", "()V").unwrap();
+    assert_eq!(resolved.0.name, "a/Sub");
+    assert_eq!(
+        declaring_class(hierarchy.select_method("a/Specific", resolved)),
+        Some("a/Sub")
+    );
+    let inherited = hierarchy.inherited_methods("a/Specific");
+    assert_eq!(
+        inherited
+            .iter()
+            .map(|(class, method)| (class.name.as_str(), method.name.as_str()))
+            .collect::<Vec<_>>(),
+        [("a/Sub", "m")]
+    );
+
+    // two unrelated default methods conflict
+    let resolved = hierarchy.resolve_method("a/Ambiguous", "m", "()V").unwrap();
+    assert!(hierarchy.select_method("a/Ambiguous", resolved).is_none());
+
+    // the only default method is preferred over abstract ones
+    let resolved = hierarchy.resolve_method("a/Concrete", "m", "()V").unwrap();
+    assert_eq!(resolved.0.name, "a/Top");
+    assert_eq!(
+        declaring_class(hierarchy.select_method("a/Concrete", resolved)),
+        Some("a/Top")
+    );
+    let resolved = hierarchy
+        .resolve_interface_method("a/Abstract", "m", "()V")
+        .unwrap();
+    assert_eq!(
+        declaring_class(hierarchy.select_method("a/Concrete", resolved)),
+        Some("a/Top")
+    );
+}
+
+#[test]
+fn override_package_private_methods_only_within_the_package() {
+    let package_private = MethodAccessFlags::empty();
+    let hierarchy = hierarchy(&[
+        object(),
+        Class {
+            name: "p/A",
+            methods: &[
+                (package_private, "m"),
+                (MethodAccessFlags::PUBLIC, "n"),
+                (MethodAccessFlags::PRIVATE, "o"),
+            ],
+            ..Class::default()
+        },
+        Class {
+            name: "q/B",
+            super_class: Some("p/A"),
+            methods: &[
+                (package_private, "m"),
+                (MethodAccessFlags::PUBLIC, "n"),
+                (MethodAccessFlags::PUBLIC, "o"),
+            ],
+            ..Class::default()
+        },
+        Class {
+            name: "p/C",
+            super_class: Some("q/B"),
+            methods: &[(package_private, "m")],
+            ..Class::default()
+        },
+        Class {
+            name: "q/D",
+            super_class: Some("p/C"),
+            methods: &[(MethodAccessFlags::PUBLIC, "m")],
+            ..Class::default()
+        },
+        Class {
+            name: "p/E",
+            super_class: Some("p/A"),
+            methods: &[(MethodAccessFlags::PUBLIC, "m")],
+            ..Class::default()
+        },
+        Class {
+            name: "q/F",
+            super_class: Some("p/E"),
+            methods: &[(MethodAccessFlags::PUBLIC, "m")],
+            ..Class::default()
+        },
+    ]);
+    let select = |class, name| {
+        let resolved = hierarchy.resolve_method("p/A", name, "()V").unwrap();
+        declaring_class(hierarchy.select_method(class, resolved))
+    };
+    // q/B.m is in another package than p/A.m
+    assert_eq!(select("q/B", "m"), Some("p/A"));
+    assert_eq!(select("p/C", "m"), Some("p/C"));
+    // q/D.m overrides neither p/C.m nor p/A.m, as both are of another package
+    assert_eq!(select("q/D", "m"), Some("p/C"));
+    // q/F.m overrides the public p/E.m, which overrides p/A.m
+    assert_eq!(select("q/F", "m"), Some("q/F"));
+    assert_eq!(select("q/B", "n"), Some("q/B"));
+    // private methods are invoked as resolved
+    assert_eq!(select("q/B", "o"), Some("p/A"));
+}
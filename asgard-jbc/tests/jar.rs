@@ -0,0 +1,162 @@
+use std::io::{Cursor, Write};
+
+use asgard_jbc::{
+    jar::{Jar, Manifest},
+    Error,
+};
+use zip::{write::FileOptions, ZipWriter};
+
+const MULTI_RELEASE: &[u8] = b"Manifest-Version: 1.0\r\nMulti-Release: true\r\n\r\n";
+
+fn write_jar(entries: &[(&str, &[u8])]) -> Jar<Cursor<Vec<u8>>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in entries {
+        writer.start_file(*name, FileOptions::default()).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    Jar::new(writer.finish().unwrap()).unwrap()
+}
+
+fn data(jar: &mut Jar<Cursor<Vec<u8>>>, name: &str) -> Option<Vec<u8>> {
+    jar.entry(name).unwrap().map(|entry| entry.data().to_vec())
+}
+
+fn versioned_jar(manifest: &[u8]) -> Jar<Cursor<Vec<u8>>> {
+    write_jar(&[
+        ("META-INF/MANIFEST.MF", manifest),
+        ("a/A.class", &b"base"[..]),
+        ("a/C.class", b"base"),
+        ("META-INF/versions/8/a/A.class", b"8"),
+        ("META-INF/versions/9/a/A.class", b"9"),
+        ("META-INF/versions/11/a/A.class", b"11"),
+        ("META-INF/versions/17/a/B.class", b"17"),
+    ])
+}
+
+#[test]
+fn read_base_entries_by_default() {
+    let mut jar = versioned_jar(MULTI_RELEASE);
+    assert!(jar.manifest().unwrap().is_multi_release());
+    assert_eq!(data(&mut jar, "a/A.class").unwrap(), b"base");
+    assert_eq!(data(&mut jar, "a/B.class"), None);
+    // versioned entries are plain entries of META-INF
+    assert_eq!(
+        data(&mut jar, "META-INF/versions/9/a/A.class").unwrap(),
+        b"9"
+    );
+    assert_eq!(jar.class_names().collect::<Vec<_>>(), ["a/A", "a/C"]);
+}
+
+#[test]
+fn resolve_versioned_entries_up_to_the_release() {
+    let mut jar = versioned_jar(MULTI_RELEASE);
+    for (release, a, b) in [
+        // versioned entries are only defined from Java 9 on
+        (8, &b"base"[..], None),
+        (10, b"9", None),
+        (11, b"11", None),
+        (17, b"11", Some(&b"17"[..])),
+    ] {
+        jar.release(release).unwrap();
+        assert_eq!(data(&mut jar, "a/A.class").unwrap(), a, "{release}");
+        assert_eq!(data(&mut jar, "a/B.class").as_deref(), b, "{release}");
+        // entries without versions fall back to the base entry
+        assert_eq!(data(&mut jar, "a/C.class").unwrap(), b"base", "{release}");
+    }
+
+    // resolved entries are named as the base entry
+    let entry = jar.entry("a/A.class").unwrap().unwrap();
+    assert_eq!(entry.name(), "a/A.class");
+    assert_eq!(entry.class_name(), Some("a/A"));
+    assert_eq!(
+        jar.entries()
+            .map(|entry| entry.unwrap().name().to_owned())
+            .collect::<Vec<_>>(),
+        [
+            "META-INF/MANIFEST.MF",
+            "a/A.class",
+            "a/B.class",
+            "a/C.class"
+        ]
+    );
+}
+
+#[test]
+fn ignore_versions_unless_multi_release() {
+    let mut jar = versioned_jar(b"Manifest-Version: 1.0\r\n\r\n");
+    jar.release(17).unwrap();
+    assert_eq!(data(&mut jar, "a/A.class").unwrap(), b"base");
+    assert_eq!(data(&mut jar, "a/B.class"), None);
+
+    let mut unversioned = write_jar(&[
+        ("a/A.class", &b"base"[..]),
+        ("META-INF/versions/9/a/A.class", b"9"),
+    ]);
+    assert!(unversioned.manifest().is_none());
+    unversioned.release(17).unwrap();
+    assert_eq!(data(&mut unversioned, "a/A.class").unwrap(), b"base");
+}
+
+#[test]
+fn parse_manifests() {
+    let manifest = Manifest::parse(
+        b"Manifest-Version: 1.0\r\n\
+          Main-Class: com.example.\r\n \
+          Main\r\n\
+          Created-By: 17\n\
+          \n\
+          Name: com/example/Main.class\n\
+          SHA-256-Digest: abc\n \
+          def\n\
+          \n\
+          \n\
+          name: com/example/\r\
+          Sealed: true",
+    )
+    .unwrap();
+    assert_eq!(
+        manifest.main_attributes,
+        [
+            ("Manifest-Version".to_owned(), "1.0".to_owned()),
+            ("Main-Class".to_owned(), "com.example.Main".to_owned()),
+            ("Created-By".to_owned(), "17".to_owned()),
+        ]
+    );
+    assert_eq!(manifest.main_class(), Some("com.example.Main"));
+    // attribute names are case-insensitive
+    assert_eq!(manifest.main_attribute("created-by"), Some("17"));
+    assert!(!manifest.is_multi_release());
+    assert_eq!(manifest.entries.len(), 2);
+    assert_eq!(
+        manifest.entry_attribute("com/example/Main.class", "sha-256-digest"),
+        Some("abcdef")
+    );
+    assert_eq!(
+        manifest.entry_attribute("com/example/", "Sealed"),
+        Some("true")
+    );
+    assert_eq!(manifest.entry_attribute("com/example/", "Missing"), None);
+}
+
+#[test]
+fn report_invalid_manifest_lines() {
+    for (input, line) in [
+        // a continuation without an attribute
+        (&b" continued\n"[..], 1),
+        (b"Manifest-Version: 1.0\nMain-Class\n", 2),
+        (b"Manifest-Version: 1.0\nMain Class: Main\n", 2),
+        (b"Manifest-Version: 1.0\n: Main\n", 2),
+        // sections after the main section must start with a name
+        (b"Manifest-Version: 1.0\n\nSealed: true\n", 3),
+        (b"Manifest-Version: \xFF\n", 1),
+    ] {
+        assert!(
+            matches!(
+                Manifest::parse(input),
+                Err(Error::InvalidManifest { line: actual }) if actual == line
+            ),
+            "{}",
+            String::from_utf8_lossy(input)
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::io::Cursor;
+
+use asgard_jbc::{
+    class::{builder::ClassBuilder, ClassFile, ConstantLookup, ConstantPoolInfo, FieldAccessFlags},
+    jimage::JImage,
+    Decode, Encode, Error,
+};
+
+const HASH_MULTIPLIER: u32 = 0x01000193;
+
+fn hash(name: &str, seed: u32) -> u32 {
+    name.bytes().fold(seed, |hash, byte| {
+        hash.wrapping_mul(HASH_MULTIPLIER) ^ byte as u32
+    }) & 0x7FFFFFFF
+}
+
+/// Builds a little-endian jimage.
+#[derive(Default)]
+struct ImageBuilder {
+    strings: Vec<u8>,
+    resources: Vec<(String, [u64; 8])>,
+    data: Vec<u8>,
+}
+
+impl ImageBuilder {
+    fn new() -> Self {
+        Self {
+            strings: vec![0],
+            ..Self::default()
+        }
+    }
+
+    /// Appends a string to the strings table, returning its offset.
+    fn string(&mut self, value: &[u8]) -> u32 {
+        if value.is_empty() {
+            return 0;
+        }
+        let offset = self.strings.len() as u32;
+        self.strings.extend_from_slice(value);
+        self.strings.push(0);
+        offset
+    }
+
+    /// Adds a resource named `/<module>/<parent>/<base>.<extension>`, whose
+    /// data might be compressed.
+    fn resource(&mut self, name: &str, data: &[u8], uncompressed_size: Option<usize>) {
+        let (module, path) = name[1..].split_once('/').unwrap();
+        let (parent, file) = path.rsplit_once('/').unwrap_or(("", path));
+        let (base, extension) = file.rsplit_once('.').unwrap_or((file, ""));
+        let mut attributes = [0; 8];
+        attributes[1] = self.string(module.as_bytes()) as u64;
+        attributes[2] = self.string(parent.as_bytes()) as u64;
+        attributes[3] = self.string(base.as_bytes()) as u64;
+        attributes[4] = self.string(extension.as_bytes()) as u64;
+        attributes[5] = self.data.len() as u64;
+        match uncompressed_size {
+            Some(uncompressed_size) => {
+                attributes[6] = data.len() as u64;
+                attributes[7] = uncompressed_size as u64;
+            }
+            None => attributes[7] = data.len() as u64,
+        }
+        self.data.extend_from_slice(data);
+        self.resources.push((name.to_owned(), attributes));
+    }
+
+    /// Adds a resource compressed by the given decompressor.
+    fn compressed(&mut self, name: &str, decompressor: &str, data: &[u8], uncompressed: &[u8]) {
+        let decompressor = self.string(decompressor.as_bytes());
+        let mut compressed = Vec::new();
+        compressed.extend(0xCAFEFAFAu32.to_le_bytes());
+        compressed.extend((data.len() as u64).to_le_bytes());
+        compressed.extend((uncompressed.len() as u64).to_le_bytes());
+        compressed.extend(decompressor.to_le_bytes());
+        compressed.extend(0u32.to_le_bytes());
+        compressed.push(1);
+        compressed.extend_from_slice(data);
+        self.resource(name, &compressed, Some(uncompressed.len()));
+    }
+
+    fn build(self) -> Vec<u8> {
+        let mut locations = vec![];
+        let mut location_offsets = vec![];
+        for (_, attributes) in &self.resources {
+            location_offsets.push(locations.len() as u32);
+            for (kind, value) in attributes.iter().enumerate().skip(1) {
+                if *value == 0 {
+                    continue;
+                }
+                let bytes = value.to_be_bytes();
+                let skip = bytes.iter().take_while(|byte| **byte == 0).count().min(7);
+                locations.push((kind as u8) << 3 | (7 - skip) as u8);
+                locations.extend_from_slice(&bytes[skip..]);
+            }
+            locations.push(0);
+        }
+
+        // builds the perfect hash as jlink does, colliding names get a seed
+        // placing them into free slots, the others redirect to a free slot
+        let length = self.resources.len();
+        let mut buckets = vec![vec![]; length];
+        for (index, (name, _)) in self.resources.iter().enumerate() {
+            buckets[(hash(name, HASH_MULTIPLIER) % length as u32) as usize].push(index);
+        }
+        let mut redirect = vec![0i32; length];
+        let mut offsets = vec![None; length];
+        let mut order = (0..length).collect::<Vec<_>>();
+        order.sort_by_key(|bucket| std::cmp::Reverse(buckets[*bucket].len()));
+        for bucket in order {
+            match buckets[bucket][..] {
+                [] => {}
+                [index] => {
+                    let slot = offsets.iter().position(Option::is_none).unwrap();
+                    redirect[bucket] = -1 - slot as i32;
+                    offsets[slot] = Some(location_offsets[index]);
+                }
+                ref indices => {
+                    let slots = |seed| {
+                        indices
+                            .iter()
+                            .map(|index| {
+                                let name = &self.resources[*index].0;
+                                (hash(name, seed) % length as u32) as usize
+                            })
+                            .collect::<Vec<_>>()
+                    };
+                    let seed = (1..0x10000)
+                        .find(|seed| {
+                            let mut slots = slots(*seed);
+                            let free = slots.iter().all(|slot| offsets[*slot].is_none());
+                            slots.sort_unstable();
+                            slots.dedup();
+                            free && slots.len() == indices.len()
+                        })
+                        .expect("no seed separates the colliding names");
+                    redirect[bucket] = seed as i32;
+                    for (index, slot) in indices.iter().zip(slots(seed)) {
+                        offsets[slot] = Some(location_offsets[*index]);
+                    }
+                }
+            }
+        }
+
+        let mut image = Vec::new();
+        for value in [
+            0xCAFEDADA,
+            1 << 16,
+            0,
+            self.resources.len() as u32,
+            length as u32,
+            locations.len() as u32,
+            self.strings.len() as u32,
+        ] {
+            image.extend(value.to_le_bytes());
+        }
+        for value in redirect {
+            image.extend(value.to_le_bytes());
+        }
+        for value in offsets {
+            image.extend(value.unwrap().to_le_bytes());
+        }
+        image.extend(locations);
+        image.extend(self.strings);
+        image.extend(self.data);
+        image
+    }
+}
+
+fn class(name: &str) -> Vec<u8> {
+    let mut class = ClassBuilder::new(name);
+    class
+        .field(FieldAccessFlags::PUBLIC, "value", "Ljava/lang/String;", &[])
+        .unwrap();
+    let mut output = Cursor::new(Vec::new());
+    class.build().unwrap().encode(&mut output).unwrap();
+    output.into_inner()
+}
+
+/// Writes an integer as the three byte form of the string sharing.
+fn compressed_int(output: &mut Vec<u8>, value: u32) {
+    output.extend([0xE0 | (value >> 16) as u8, (value >> 8) as u8, value as u8]);
+}
+
+/// Shares the UTF-8 constants of a class file as `jlink --compress=1` does,
+/// splitting class names out of `Ljava/lang/String;`.
+fn share_strings(image: &mut ImageBuilder, data: &[u8]) -> Vec<u8> {
+    let class_file = ClassFile::decode(&mut &data[..]).unwrap();
+    let mut constant_pool = Cursor::new(Vec::new());
+    class_file.constant_pool.encode(&mut constant_pool).unwrap();
+    let rest = &data[8 + constant_pool.get_ref().len()..];
+
+    let mut output = data[..10].to_vec();
+    for (_, info) in class_file.constant_pool.iter() {
+        match info {
+            ConstantPoolInfo::Utf8(value) if *value == *"Ljava/lang/String;" => {
+                output.push(25);
+                compressed_int(&mut output, image.string(b"L;"));
+                let mut names = vec![];
+                compressed_int(&mut names, image.string(b"java/lang"));
+                compressed_int(&mut names, image.string(b"String"));
+                compressed_int(&mut output, names.len() as u32);
+                output.extend(names);
+            }
+            ConstantPoolInfo::Utf8(value) => {
+                output.push(23);
+                let offset = image.string(value.as_str().unwrap().as_bytes());
+                compressed_int(&mut output, offset);
+            }
+            info => {
+                let mut encoded = Cursor::new(Vec::new());
+                info.encode(&mut encoded).unwrap();
+                output.extend(encoded.into_inner());
+            }
+        }
+    }
+    output.extend_from_slice(rest);
+    output
+}
+
+#[test]
+fn read_plain_resources() {
+    let data = class("example/Plain");
+    let mut image = ImageBuilder::new();
+    image.resource("/example.module/example/Plain.class", &data, None);
+    image.resource("/example.module/example/data.txt", b"plain", None);
+    let module = image.string(b"example.module");
+    let mut package = 0u32.to_le_bytes().to_vec();
+    package.extend(module.to_le_bytes());
+    image.resource("/packages/example", &package, None);
+
+    let mut image = JImage::new(Cursor::new(image.build())).unwrap();
+    assert_eq!(
+        image.resource("/example.module/example/data.txt").unwrap(),
+        Some(b"plain".to_vec())
+    );
+    assert_eq!(image.resource("/example.module/missing").unwrap(), None);
+    assert_eq!(
+        image.package_module("example").unwrap().as_deref(),
+        Some("example.module")
+    );
+    let class_file = image.class("example/Plain").unwrap().unwrap();
+    assert_eq!(
+        class_file
+            .constant_pool
+            .class_name(class_file.this_class)
+            .unwrap(),
+        "example/Plain"
+    );
+
+    let entries = image
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (entry.path().to_owned(), entry.data().to_vec())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [
+            ("example/Plain.class".to_owned(), data),
+            ("example/data.txt".to_owned(), b"plain".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn read_compressed_resources() {
+    let data = class("example/Compressed");
+    let mut image = ImageBuilder::new();
+    let zipped = miniz_oxide::deflate::compress_to_vec_zlib(&data, 6);
+    image.compressed("/example/example/Zip.class", "zip", &zipped, &data);
+    let shared = share_strings(&mut image, &data);
+    image.compressed(
+        "/example/example/Strings.class",
+        "compact-cp",
+        &shared,
+        &data,
+    );
+
+    let mut image = JImage::new(Cursor::new(image.build())).unwrap();
+    assert_eq!(
+        image.resource("/example/example/Zip.class").unwrap(),
+        Some(data.clone())
+    );
+    assert_eq!(
+        image.resource("/example/example/Strings.class").unwrap(),
+        Some(data)
+    );
+}
+
+#[test]
+fn reject_index_exceeding_image() {
+    let mut header = Vec::new();
+    for value in [0xCAFEDADA, 1 << 16, 0, 0, u32::MAX, u32::MAX, u32::MAX] {
+        header.extend(value.to_le_bytes());
+    }
+    assert!(matches!(
+        JImage::new(Cursor::new(header)),
+        Err(Error::InvalidImage(_))
+    ));
+}
+
+#[test]
+fn reject_resource_exceeding_image() {
+    let mut image = ImageBuilder::new();
+    image.resource("/example/data.txt", b"data", None);
+    image.resources[0].1[7] = u32::MAX as u64 * 16;
+    let mut image = JImage::new(Cursor::new(image.build())).unwrap();
+    assert!(matches!(
+        image.resource("/example/data.txt"),
+        Err(Error::InvalidImage(_))
+    ));
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use asgard_jbc::{
+    class::{attribute::RequiresFlags, module::ModuleDescriptor, ClassFile},
+    Decode, Error,
+};
+
+mod common;
+
+/// Returns the modules of the runtime image by name.
+fn runtime_modules() -> HashMap<String, ModuleDescriptor> {
+    common::runtime_classes()
+        .into_iter()
+        .filter(|(path, _)| path == "module-info.class")
+        .map(|(path, data)| {
+            let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+            let module = ModuleDescriptor::from_class_file(&class_file)
+                .unwrap_or_else(|error| panic!("{path}: {error}"));
+            (module.name.clone(), module)
+        })
+        .collect()
+}
+
+#[test]
+fn read_runtime_modules() {
+    let modules = runtime_modules();
+    let Some(java_base) = modules.get("java.base") else {
+        return;
+    };
+    assert!(!java_base.is_open());
+    assert!(java_base.requires.is_empty());
+    assert!(java_base.version.is_some());
+    assert!(java_base.is_exported("java/lang"));
+    assert!(java_base
+        .packages
+        .iter()
+        .any(|package| package == "java/lang"));
+    assert!(java_base
+        .packages
+        .iter()
+        .any(|package| package == "jdk/internal/misc"));
+    assert!(java_base.main_class.is_none());
+
+    // exported to some modules only
+    assert!(!java_base.is_exported("jdk/internal/misc"));
+    assert!(java_base.is_exported_to("jdk/internal/misc", "java.logging"));
+    assert!(!java_base.is_exported_to("jdk/internal/misc", "java.compiler"));
+    assert!(!java_base.is_exported_to("jdk/internal/foo", "java.logging"));
+
+    assert!(java_base
+        .uses
+        .iter()
+        .any(|service| service == "java/nio/file/spi/FileSystemProvider"));
+    let provides = java_base
+        .provides
+        .iter()
+        .find(|provides| provides.service == "java/nio/file/spi/FileSystemProvider")
+        .unwrap();
+    assert_eq!(
+        provides.providers,
+        ["jdk/internal/jrtfs/JrtFileSystemProvider"]
+    );
+
+    let java_sql = &modules["java.sql"];
+    for name in ["java.logging", "java.transaction.xa", "java.xml"] {
+        let requires = java_sql
+            .requires
+            .iter()
+            .find(|requires| requires.name == name)
+            .unwrap();
+        assert!(requires.flags.contains(RequiresFlags::TRANSITIVE), "{name}");
+    }
+    let requires = java_sql
+        .requires
+        .iter()
+        .find(|requires| requires.name == "java.base")
+        .unwrap();
+    assert!(requires.flags.contains(RequiresFlags::MANDATED));
+    assert!(java_sql.is_exported("java/sql"));
+}
+
+#[test]
+fn require_the_module_attribute() {
+    let (_, data) = common::runtime_classes()
+        .into_iter()
+        .find(|(path, _)| path == "java/lang/Object.class")
+        .unwrap_or_default();
+    if data.is_empty() {
+        return;
+    }
+    let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+    assert!(matches!(
+        ModuleDescriptor::from_class_file(&class_file),
+        Err(Error::MissingAttribute("Module"))
+    ));
+}
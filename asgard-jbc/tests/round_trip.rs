@@ -0,0 +1,108 @@
+use std::io::Cursor;
+
+use asgard_jbc::{
+    class::{
+        borrowed::ClassFileRef, builder::ClassBuilder, ClassFile, ConstantPoolInfo,
+        FieldAccessFlags, MethodAccessFlags,
+    },
+    code::Instruction,
+    mutf8, Decode, Encode, Error,
+};
+
+mod common;
+
+fn encode(class_file: &ClassFile) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    class_file.encode(&mut output).unwrap();
+    output.into_inner()
+}
+
+fn assert_round_trip(path: &str, data: &[u8]) {
+    let class_file = ClassFile::decode(&mut &data[..]).unwrap();
+    assert!(encode(&class_file) == data, "{path}");
+    let class_file = ClassFileRef::decode(&mut &data[..]).unwrap();
+    assert!(
+        encode(&class_file.to_class_file()) == data,
+        "{path} borrowed"
+    );
+}
+
+#[test]
+fn round_trip_runtime_classes() {
+    for (path, data) in common::runtime_classes() {
+        assert_round_trip(&path, &data);
+    }
+}
+
+#[test]
+fn round_trip_modified_utf8() {
+    let mut class_file = ClassBuilder::new("Strings").build().unwrap();
+    for bytes in [
+        &b"\xC0\x80"[..],
+        b"\xED\xA0\xBD\xED\xB8\x80",
+        b"lone \xED\xA0\x80 high",
+        b"lone \xED\xB0\x80 low",
+    ] {
+        let value = mutf8::decode_java(bytes).unwrap();
+        class_file.constant_pool.push(ConstantPoolInfo::Utf8(value));
+    }
+    let data = encode(&class_file);
+    assert_round_trip("Strings", &data);
+}
+
+#[test]
+fn reject_four_byte_utf8() {
+    assert!(mutf8::decode_java(b"\xF0\x9F\x98\x80").is_err());
+    assert!(mutf8::decode("\u{1F600}".as_bytes()).is_err());
+}
+
+#[test]
+fn report_truncation_offsets() {
+    let mut class = ClassBuilder::new("Truncated");
+    class
+        .field(FieldAccessFlags::PRIVATE, "value", "I", &[])
+        .unwrap();
+    class
+        .method(MethodAccessFlags::PUBLIC, "run", "()V", &[], |code, _| {
+            code.instruction(Instruction::Return);
+            Ok(())
+        })
+        .unwrap();
+    let data = encode(&class.build().unwrap());
+    for length in 0..data.len() {
+        let input = &data[..length];
+        assert!(
+            matches!(
+                ClassFile::decode(&mut &input[..]),
+                Err(Error::TruncatedInput { offset }) if offset == length
+            ),
+            "{length}"
+        );
+        assert!(
+            matches!(
+                ClassFileRef::decode(&mut &input[..]),
+                Err(Error::TruncatedInput { offset }) if offset == length
+            ),
+            "{length}"
+        );
+    }
+}
+
+#[test]
+fn reject_oversized_tables() {
+    let mut class_file = ClassBuilder::new("Oversized").build().unwrap();
+    class_file
+        .constant_pool
+        .push(ConstantPoolInfo::Utf8("a".repeat(65536).into()));
+    assert!(matches!(
+        class_file.encode(&mut Cursor::new(Vec::new())),
+        Err(Error::Utf8TooLong(65536))
+    ));
+
+    let mut class_file = ClassBuilder::new("Oversized").build().unwrap();
+    class_file.interfaces = vec![class_file.this_class; 65536];
+    assert!(matches!(
+        class_file.encode(&mut Cursor::new(Vec::new())),
+        Err(Error::TooMany("interfaces"))
+    ));
+}
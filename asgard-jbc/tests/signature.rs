@@ -0,0 +1,136 @@
+use std::{fmt::Display, str::FromStr};
+
+use asgard_jbc::{
+    class::{attribute::Attribute, AttributeInfo, ClassFile, ConstantPool},
+    signature::{
+        ClassSignature, ClassTypeSignature, FieldSignature, MethodSignature,
+        SimpleClassTypeSignature, TypeArgument, TypeSignature,
+    },
+    Decode, Error,
+};
+
+mod common;
+
+/// Parses and prints the signature among `attributes`, if any.
+fn round_trip<T>(attributes: &[AttributeInfo], constant_pool: &ConstantPool, path: &str)
+where
+    T: FromStr<Err = Error> + Display,
+{
+    for attribute in attributes {
+        if constant_pool.utf8(attribute.attribute_name_index).unwrap() != "Signature" {
+            continue;
+        }
+        let Attribute::Signature(signature) = Attribute::decode(attribute, constant_pool).unwrap()
+        else {
+            unreachable!();
+        };
+        let signature = constant_pool.utf8(signature.signature_index).unwrap();
+        let parsed = signature
+            .parse::<T>()
+            .unwrap_or_else(|error| panic!("{path} {signature}: {error}"));
+        assert_eq!(parsed.to_string(), signature, "{path}");
+    }
+}
+
+#[test]
+fn round_trip_runtime_signatures() {
+    for (path, data) in common::runtime_classes() {
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        let constant_pool = &class_file.constant_pool;
+        round_trip::<ClassSignature>(&class_file.attributes, constant_pool, &path);
+        for field in &class_file.fields {
+            round_trip::<FieldSignature>(&field.attributes, constant_pool, &path);
+        }
+        for method in &class_file.methods {
+            round_trip::<MethodSignature>(&method.attributes, constant_pool, &path);
+        }
+    }
+}
+
+#[test]
+fn parse_inner_classes_and_wildcards() {
+    let signature: FieldSignature = "Ljava/util/Map<+TK;*>.Entry<-[I>;".parse().unwrap();
+    assert_eq!(
+        signature.0,
+        TypeSignature::Class(ClassTypeSignature {
+            package: "java/util".to_owned(),
+            classes: vec![
+                SimpleClassTypeSignature {
+                    name: "Map".to_owned(),
+                    type_arguments: vec![
+                        TypeArgument::Extends(TypeSignature::TypeVariable("K".to_owned())),
+                        TypeArgument::Any,
+                    ],
+                },
+                SimpleClassTypeSignature {
+                    name: "Entry".to_owned(),
+                    type_arguments: vec![TypeArgument::Super(TypeSignature::Array(Box::new(
+                        TypeSignature::Int
+                    )))],
+                },
+            ],
+        })
+    );
+    let TypeSignature::Class(class_type) = &signature.0 else {
+        unreachable!();
+    };
+    assert_eq!(class_type.name(), "java/util/Map$Entry");
+}
+
+#[test]
+fn parse_method_signatures() {
+    let signature =
+        "<T:Ljava/lang/Object;:Ljava/lang/Comparable<-TT;>;>([TT;J)V^TE;^Ljava/io/IOException;";
+    let parsed: MethodSignature = signature.parse().unwrap();
+    assert_eq!(parsed.type_parameters.len(), 1);
+    assert_eq!(parsed.type_parameters[0].interface_bounds.len(), 1);
+    assert_eq!(
+        parsed.parameter_types,
+        [
+            TypeSignature::Array(Box::new(TypeSignature::TypeVariable("T".to_owned()))),
+            TypeSignature::Long,
+        ]
+    );
+    assert_eq!(parsed.return_type, TypeSignature::Void);
+    assert_eq!(parsed.throws.len(), 2);
+    assert_eq!(parsed.to_string(), signature);
+}
+
+#[test]
+fn limit_array_dimensions() {
+    let signature = format!("{}I", "[".repeat(255));
+    assert!(signature.parse::<TypeSignature>().is_ok());
+    let signature = format!("Ljava/util/List<{}I>;", "[".repeat(256));
+    assert!(matches!(
+        signature.parse::<TypeSignature>(),
+        Err(Error::InvalidSignature { position: 16, .. })
+    ));
+}
+
+#[test]
+fn limit_type_argument_depth() {
+    let nested = |depth: usize| format!("{}La;{}", "La<".repeat(depth), ">;".repeat(depth));
+    assert!(nested(255).parse::<TypeSignature>().is_ok());
+    assert!(matches!(
+        nested(256).parse::<TypeSignature>(),
+        Err(Error::InvalidSignature { position: 767, .. })
+    ));
+}
+
+#[test]
+fn report_error_positions() {
+    for (signature, position) in [
+        ("Ljava/util/List<TE;", 19),
+        ("Ljava/lang/String", 17),
+        ("TT", 2),
+        ("I", 0),
+    ] {
+        assert!(
+            matches!(
+                signature.parse::<TypeSignature>(),
+                Err(Error::InvalidSignature { position: actual, .. }) if actual == position
+            ),
+            "{signature}"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::Cursor;
+
+use asgard_jbc::{
+    class::attribute::{StackMapFrame, VerificationTypeInfo},
+    Decode, Encode, Error,
+};
+
+fn encode(frame: &StackMapFrame) -> asgard_jbc::Result<Vec<u8>> {
+    let mut output = Cursor::new(Vec::new());
+    frame.encode(&mut output)?;
+    Ok(output.into_inner())
+}
+
+#[test]
+fn reject_out_of_range_frames() {
+    for frame in [
+        StackMapFrame::Same { offset_delta: 64 },
+        StackMapFrame::SameLocals1StackItem {
+            offset_delta: 200,
+            stack: VerificationTypeInfo::Integer,
+        },
+        StackMapFrame::Chop {
+            k: 0,
+            offset_delta: 0,
+        },
+        StackMapFrame::Chop {
+            k: 4,
+            offset_delta: 0,
+        },
+        StackMapFrame::Append {
+            offset_delta: 0,
+            locals: vec![],
+        },
+        StackMapFrame::Append {
+            offset_delta: 0,
+            locals: vec![VerificationTypeInfo::Integer; 4],
+        },
+    ] {
+        assert!(
+            matches!(encode(&frame), Err(Error::InvalidFrame(_))),
+            "{frame:?}"
+        );
+    }
+}
+
+#[test]
+fn round_trip_boundary_frames() {
+    for frame in [
+        StackMapFrame::Same { offset_delta: 63 },
+        StackMapFrame::SameLocals1StackItem {
+            offset_delta: 63,
+            stack: VerificationTypeInfo::Integer,
+        },
+        StackMapFrame::Chop {
+            k: 3,
+            offset_delta: 0,
+        },
+        StackMapFrame::Append {
+            offset_delta: 0,
+            locals: vec![VerificationTypeInfo::Integer; 3],
+        },
+    ] {
+        let bytes = encode(&frame).unwrap();
+        assert_eq!(StackMapFrame::decode(&mut bytes.as_slice()).unwrap(), frame);
+    }
+}
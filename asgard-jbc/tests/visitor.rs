@@ -0,0 +1,178 @@
+use std::io::Cursor;
+
+use asgard_jbc::{
+    class::{
+        builder::{ClassBuilder, ConstantPoolBuilder},
+        visitor::{ClassReader, ClassVisitor, ClassWriter, MethodVisitor},
+        ClassFile, MethodAccessFlags,
+    },
+    code::{
+        builder::{Jump, Label},
+        Instruction,
+    },
+    Decode, Encode, Result,
+};
+
+mod common;
+
+fn encode(class_file: &ClassFile) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    class_file.encode(&mut output).unwrap();
+    output.into_inner()
+}
+
+#[test]
+fn copy_unchanged_methods_byte_for_byte() {
+    for (path, data) in common::runtime_classes() {
+        let mut writer = ClassWriter::new();
+        ClassReader::new(&data)
+            .unwrap()
+            .accept(&mut writer)
+            .unwrap();
+        let class_file = writer.into_class_file().unwrap();
+        assert!(encode(&class_file) == data, "{path}");
+    }
+}
+
+/// Forwards the class, but takes no method unchanged, so that all code is
+/// rewritten by the writer.
+struct Rewrite<'a>(&'a mut dyn ClassVisitor);
+
+impl ClassVisitor for Rewrite<'_> {
+    fn delegate(&mut self) -> Option<&mut dyn ClassVisitor> {
+        Some(self.0)
+    }
+
+    fn visit_method(
+        &mut self,
+        constant_pool: &mut ConstantPoolBuilder,
+        access_flags: MethodAccessFlags,
+        name_index: u16,
+        descriptor_index: u16,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        let method_visitor =
+            self.0
+                .visit_method(constant_pool, access_flags, name_index, descriptor_index)?;
+        Ok(method_visitor
+            .map(|delegate| Box::new(RewriteMethod(delegate)) as Box<dyn MethodVisitor + '_>))
+    }
+}
+
+struct RewriteMethod<'a>(Box<dyn MethodVisitor + 'a>);
+
+impl MethodVisitor for RewriteMethod<'_> {
+    fn delegate(&mut self) -> Option<&mut dyn MethodVisitor> {
+        Some(&mut *self.0)
+    }
+
+    fn visit_code(&mut self, constant_pool: &mut ConstantPoolBuilder) -> Result<()> {
+        self.0.visit_code(constant_pool)?;
+        // labels allocated by adapters come from the writer
+        let label = self.new_label();
+        self.visit_label(constant_pool, label)
+    }
+}
+
+#[test]
+fn rewrite_runtime_classes() {
+    for (path, data) in common::runtime_classes() {
+        let mut writer = ClassWriter::new();
+        ClassReader::new(&data)
+            .unwrap()
+            .accept(&mut Rewrite(&mut writer))
+            .unwrap_or_else(|error| panic!("{path}: {error}"));
+        let rewritten = writer.into_class_file().unwrap();
+        let class_file = ClassFile::decode(&mut data.as_slice()).unwrap();
+        assert_eq!(rewritten.methods.len(), class_file.methods.len(), "{path}");
+        for (rewritten_method, method) in rewritten.methods.iter().zip(&class_file.methods) {
+            let name = method.name(&class_file.constant_pool).unwrap();
+            let code = method.code(&class_file.constant_pool).unwrap();
+            let rewritten_code = rewritten_method.code(&rewritten.constant_pool).unwrap();
+            let (Some(code), Some(rewritten_code)) = (code, rewritten_code) else {
+                assert_eq!(
+                    rewritten_method.attributes.len(),
+                    method.attributes.len(),
+                    "{path} {name}"
+                );
+                continue;
+            };
+            // javac never needs wide jumps, so the instructions keep their
+            // lengths and the code its bytes
+            assert!(rewritten_code.code == code.code, "{path} {name}");
+            assert_eq!(
+                format!("{:?}", rewritten_code.exception_table),
+                format!("{:?}", code.exception_table),
+                "{path} {name}"
+            );
+            assert_eq!(rewritten_code.max_stack, code.max_stack, "{path} {name}");
+        }
+    }
+}
+
+/// Counts the instructions of a class without writing it.
+#[derive(Default)]
+struct Counter {
+    instructions: usize,
+    jumps: usize,
+}
+
+impl ClassVisitor for Counter {
+    fn visit_method(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        _access_flags: MethodAccessFlags,
+        _name_index: u16,
+        _descriptor_index: u16,
+    ) -> Result<Option<Box<dyn MethodVisitor + '_>>> {
+        Ok(Some(Box::new(MethodCounter(self))))
+    }
+}
+
+struct MethodCounter<'a>(&'a mut Counter);
+
+impl MethodVisitor for MethodCounter<'_> {
+    fn visit_instruction(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        _instruction: Instruction,
+    ) -> Result<()> {
+        self.0.instructions += 1;
+        Ok(())
+    }
+
+    fn visit_jump(
+        &mut self,
+        _constant_pool: &mut ConstantPoolBuilder,
+        _jump: Jump,
+        _target: Label,
+    ) -> Result<()> {
+        self.0.jumps += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn visit_code_without_delegate() {
+    let mut builder = ClassBuilder::new("example/Counted");
+    builder
+        .method(MethodAccessFlags::STATIC, "sign", "(I)I", &[], |code, _| {
+            let negative = code.new_label();
+            code.instruction(Instruction::Iload(0))
+                .jump(Jump::Iflt, negative)
+                .instruction(Instruction::Iconst1)
+                .instruction(Instruction::Ireturn)
+                .place_label(negative)
+                .instruction(Instruction::IconstM1)
+                .instruction(Instruction::Ireturn);
+            Ok(())
+        })
+        .unwrap();
+    let data = encode(&builder.build().unwrap());
+    // the labels of the branch targets come from the default `new_label`
+    let mut counter = Counter::default();
+    ClassReader::new(&data)
+        .unwrap()
+        .accept(&mut counter)
+        .unwrap();
+    assert_eq!((counter.instructions, counter.jumps), (5, 1));
+}
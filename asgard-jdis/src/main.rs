@@ -0,0 +1,114 @@
+use std::{error::Error, path::Path, process::ExitCode, sync::Arc};
+
+use asgard_jbc::{class::ClassFile, class_path::ClassPath, disassembler::Disassembler, Decode};
+
+const USAGE: &str = "Usage: asgard-jdis [-v] [-cp <class path>] <class>...";
+
+fn main() -> ExitCode {
+    let mut disassembler = Disassembler::new();
+    let mut class_path = None;
+    let mut classes = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" | "-verbose" => {
+                disassembler.verbose(true);
+            }
+            "-cp" | "-classpath" | "--class-path" => match args.next() {
+                Some(path) => class_path = Some(path),
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "-help" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => classes.push(arg),
+        }
+    }
+    if classes.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    // only opened once a class is looked up by name
+    let mut opened = None;
+    let mut exit_code = ExitCode::SUCCESS;
+    for class in classes {
+        match read(&mut opened, class_path.as_deref(), &class).and_then(|class_file| {
+            disassembler
+                .disassemble(&class_file)
+                .map_err(|error| describe(&error))
+        }) {
+            Ok(output) => print!("{output}"),
+            Err(error) => {
+                eprintln!("error: {class}: {error}");
+                exit_code = ExitCode::FAILURE;
+            }
+        }
+    }
+    exit_code
+}
+
+/// Opens the given class path, followed by the runtime image of the running
+/// JDK if `JAVA_HOME` is set, warning if it has none.
+fn open_class_path(class_path: Option<&str>) -> Result<ClassPath, String> {
+    let mut opened = ClassPath::new();
+    if let Some(class_path) = class_path {
+        for path in std::env::split_paths(class_path) {
+            opened
+                .push(&path)
+                .map_err(|error| format!("{}: {}", path.display(), describe(&error)))?;
+        }
+    }
+    if let Some(java_home) = std::env::var_os("JAVA_HOME") {
+        let modules = Path::new(&java_home).join("lib").join("modules");
+        if modules.is_file() {
+            opened
+                .push_jimage(&modules)
+                .map_err(|error| format!("{}: {}", modules.display(), describe(&error)))?;
+        } else {
+            eprintln!(
+                "warning: {}: no runtime image, classes of the JDK cannot be found",
+                modules.display()
+            );
+        }
+    }
+    Ok(opened)
+}
+
+/// Reads a class file by its path, or looks up a class by its binary or
+/// internal name, opening the class path on the first lookup.
+fn read(
+    opened: &mut Option<ClassPath>,
+    class_path: Option<&str>,
+    class: &str,
+) -> Result<Arc<ClassFile>, String> {
+    if class.ends_with(".class") {
+        let input = std::fs::read(class).map_err(|error| describe(&error))?;
+        return ClassFile::decode(&mut input.as_slice())
+            .map(Arc::new)
+            .map_err(|error| describe(&error));
+    }
+    if opened.is_none() {
+        *opened = Some(open_class_path(class_path)?);
+    }
+    match opened.as_mut().unwrap().find(&class.replace('.', "/")) {
+        Ok(Some(class_file)) => Ok(class_file),
+        Ok(None) => Err("class not found".to_owned()),
+        Err(error) => Err(describe(&error)),
+    }
+}
+
+/// Formats an error followed by the chain of its causes.
+fn describe(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(&format!(": {error}"));
+        source = error.source();
+    }
+    message
+}
@@ -1,39 +1,27 @@
-use asgard_jbc::{AccessFlags, ClassFile, ConstantPoolInfo, Decode, MethodDescriptor};
+use asgard_jbc::{
+    class::{ClassFile, MethodAccessFlags},
+    descriptor::FieldType,
+    Decode,
+};
 
 fn main() {
     let input = std::fs::read("/home/valaphee/Documents/asgard-example/build/classes/java/main/com/valaphee/asgard/example/CallMe.class").unwrap();
     let class_file = ClassFile::decode(&mut input.as_ref()).unwrap();
 
-    let class_name: &String = match &class_file.constant_pool[(class_file.this_class - 1) as usize]
-    {
-        ConstantPoolInfo::Class { name_index } => {
-            match &class_file.constant_pool[(name_index - 1) as usize] {
-                ConstantPoolInfo::Utf8(value) => value,
-                _ => todo!(),
-            }
-        }
-        _ => todo!(),
-    };
+    let class_name = class_file.name().unwrap();
     let class_name_without_path = class_name.rsplit("/").next().unwrap();
 
     let mut methods = vec![];
     let mut overridable_methods = vec![];
     let mut overridable_method_bodies = vec![];
     for method in &class_file.methods {
-        if !method.access_flags.contains(AccessFlags::PUBLIC) {
+        if !method.access_flags.contains(MethodAccessFlags::PUBLIC) {
             continue;
         }
 
-        let method_name = match &class_file.constant_pool[(method.name_index - 1) as usize] {
-            ConstantPoolInfo::Utf8(value) => value,
-            _ => todo!(),
-        };
-        let method_descriptor_raw =
-            match &class_file.constant_pool[(method.descriptor_index - 1) as usize] {
-                ConstantPoolInfo::Utf8(value) => value,
-                _ => todo!(),
-            };
-        let method_descriptor: MethodDescriptor = method_descriptor_raw.parse().unwrap();
+        let method_name = method.name(&class_file.constant_pool).unwrap();
+        let method_descriptor = method.descriptor(&class_file.constant_pool).unwrap();
+        let method_descriptor_raw = method_descriptor.to_string();
 
         if method_name == "<init>" {
             methods.push(format!(
@@ -49,53 +37,36 @@ r#"fn new() -> Self {{
             ));
         } else {
             let rust_type = match method_descriptor.return_type {
-                asgard_jbc::FieldType::Byte => "i8",
-                asgard_jbc::FieldType::Char => "u16",
-                asgard_jbc::FieldType::Double => "f64",
-                asgard_jbc::FieldType::Float => "f32",
-                asgard_jbc::FieldType::Int => "i32",
-                asgard_jbc::FieldType::Long => "i64",
-                asgard_jbc::FieldType::Object(_) => todo!(),
-                asgard_jbc::FieldType::Short => "i16",
-                asgard_jbc::FieldType::Boolean => "bool",
-                asgard_jbc::FieldType::Array(_) => todo!(),
-                asgard_jbc::FieldType::Void => "()",
+                FieldType::Byte => "i8",
+                FieldType::Char => "u16",
+                FieldType::Double => "f64",
+                FieldType::Float => "f32",
+                FieldType::Int => "i32",
+                FieldType::Long => "i64",
+                FieldType::Object(_) => todo!(),
+                FieldType::Short => "i16",
+                FieldType::Boolean => "bool",
+                FieldType::Array(_) => todo!(),
+                FieldType::Void => "()",
             };
             let rust_conv = match method_descriptor.return_type {
-                asgard_jbc::FieldType::Boolean => " != 0",
+                FieldType::Boolean => " != 0",
                 _ => "",
             };
             let method_call = match method_descriptor.return_type {
-                asgard_jbc::FieldType::Byte => "CallByteMethodA",
-                asgard_jbc::FieldType::Char => "CallCharMethodA",
-                asgard_jbc::FieldType::Double => "CallDoubleMethodA",
-                asgard_jbc::FieldType::Float => "CallFloatMethodA",
-                asgard_jbc::FieldType::Int => "CallIntMethodA",
-                asgard_jbc::FieldType::Long => "CallLongMethodA",
-                asgard_jbc::FieldType::Object(_) => todo!(),
-                asgard_jbc::FieldType::Short => "CallShortMethodA",
-                asgard_jbc::FieldType::Boolean => "CallBooleanMethodA",
-                asgard_jbc::FieldType::Array(_) => todo!(),
-                asgard_jbc::FieldType::Void => "CallVoidMethodA",
+                FieldType::Byte => "CallByteMethodA",
+                FieldType::Char => "CallCharMethodA",
+                FieldType::Double => "CallDoubleMethodA",
+                FieldType::Float => "CallFloatMethodA",
+                FieldType::Int => "CallIntMethodA",
+                FieldType::Long => "CallLongMethodA",
+                FieldType::Object(_) => todo!(),
+                FieldType::Short => "CallShortMethodA",
+                FieldType::Boolean => "CallBooleanMethodA",
+                FieldType::Array(_) => todo!(),
+                FieldType::Void => "CallVoidMethodA",
             };
 
-            method_descriptor
-                .parameter_types
-                .iter()
-                .map(|parameter_type| match parameter_type {
-                    asgard_jbc::FieldType::Byte => "i8",
-                    asgard_jbc::FieldType::Char => "u16",
-                    asgard_jbc::FieldType::Double => "f64",
-                    asgard_jbc::FieldType::Float => "f32",
-                    asgard_jbc::FieldType::Int => "i32",
-                    asgard_jbc::FieldType::Long => "i64",
-                    asgard_jbc::FieldType::Object(_) => todo!(),
-                    asgard_jbc::FieldType::Short => "i16",
-                    asgard_jbc::FieldType::Boolean => "bool",
-                    asgard_jbc::FieldType::Array(_) => todo!(),
-                    _ => unreachable!(),
-                });
-
             overridable_methods.push(format!(
                 r#"fn {method_name}(&self) -> {rust_type} {{
         self.object().{method_name}()
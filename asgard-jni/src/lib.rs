@@ -1,4 +1,9 @@
-use std::cell::Cell;
+use std::{
+    cell::Cell,
+    ffi::{CStr, CString},
+};
+
+use asgard_jbc::mutf8::{self, JavaString};
 
 pub use ffi::*;
 
@@ -15,3 +20,16 @@ mod ffi {
 thread_local! {
     static ENV: Cell<*mut JNIEnv> = Cell::new(std::ptr::null_mut());
 }
+
+/// Converts a string to the NUL-terminated modified UTF-8 expected by
+/// `NewStringUTF` and the lookup functions.
+pub fn to_modified_utf8(value: &str) -> CString {
+    // NUL is encoded as two non-zero bytes in modified UTF-8
+    unsafe { CString::from_vec_unchecked(mutf8::encode(value).into_owned()) }
+}
+
+/// Converts the NUL-terminated modified UTF-8 returned by `GetStringUTFChars`,
+/// keeping the unpaired surrogates Java strings can contain.
+pub fn from_modified_utf8(value: &CStr) -> asgard_jbc::Result<JavaString> {
+    mutf8::decode_java(value.to_bytes())
+}